@@ -1,8 +1,20 @@
-use crate::unpack::unpack_context;
+use crate::unpack::{unpack_context, unpack_context_detached, unpack_context_from_bytes};
 use crate::config::Config;
-use crate_spec::error::Result;
-use crate_spec::utils::context::PackageContext;
-use crate_spec::utils::file_ops::{validate_input_file, ensure_output_dir, write_file, write_text_file, read_file};
+use crate_spec::error::{CrateSpecError, Result};
+use crate_spec::locale::{lang, tr, Lang};
+use crate_spec::network::{digest_to_hex_string, PkiClient};
+use crate_spec::utils::batch::{expand_inputs, run_batch, BatchFormat, BatchProgress};
+use crate_spec::utils::context::{PackageContext, SIGTYPE};
+use crate_spec::utils::decode::dump_sections;
+use crate_spec::utils::metadata::MetadataFormat;
+use crate_spec::utils::policy::Policy;
+use crate_spec::utils::file_ops::{validate_input_file, ensure_output_dir, replace_file, write_file, read_file, is_stdin_path, read_stdin};
+use crate_spec::utils::pkcs::{KeyStrengthPolicy, PKCS};
+use crate_spec::utils::timings;
+use crate_spec::tr_format;
+use std::borrow::Cow;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// 本地解码参数
@@ -11,6 +23,33 @@ pub struct LocalDecodeParams {
     pub root_ca_paths: Vec<String>,
     pub output: String,
     pub input: String,
+    pub check_crate_integrity: bool,
+    pub cross_validate: bool,
+    /// 检查签名时间是否在签名证书有效期内
+    pub verify_timestamps: bool,
+    /// 检查字符串表中是否有未被引用的项
+    pub check_unreferenced_strings: bool,
+    pub max_chain_depth: u32,
+    /// 签名者密钥强度要求
+    pub key_policy: KeyStrengthPolicy,
+    /// 包中每种类型至少要有一个签名，否则不输出文件（--require-sig-type）
+    pub require_sig_types: Vec<SIGTYPE>,
+    /// 包中没有签名时报错（--require-signature）；默认只提示没有签名
+    pub require_signature: bool,
+    /// 分离签名文件（.scrate.sig）路径，设置时包体中不含签名段
+    pub detached_sig: Option<String>,
+    /// 元数据输出格式
+    pub metadata_format: MetadataFormat,
+    /// 发布策略（--compat-check），设置时不满足策略则不输出文件
+    pub policy: Option<Policy>,
+    /// 只校验指纹和签名（及其他已选的检查），不输出文件；此时 `output` 可为空
+    pub verify_only: bool,
+    /// 逐步验证并输出说明树（--explain），不输出文件，其他检查不执行
+    pub explain: bool,
+    /// 某个输出写入失败时继续写其余输出，最后一并报告（--keep-going）
+    pub keep_going: bool,
+    /// 按文件哈希清单核对该目录下已解压的 crate 文件（--check-file-hashes）
+    pub check_file_hashes: Option<String>,
 }
 
 /// 网络解码参数
@@ -18,6 +57,188 @@ pub struct LocalDecodeParams {
 pub struct NetworkDecodeParams {
     pub input: String,
     pub output: String,
+    pub check_crate_integrity: bool,
+    pub cross_validate: bool,
+    /// 检查签名时间是否在签名证书有效期内
+    pub verify_timestamps: bool,
+    /// 检查字符串表中是否有未被引用的项
+    pub check_unreferenced_strings: bool,
+    pub max_chain_depth: u32,
+    /// 签名者密钥强度要求
+    pub key_policy: KeyStrengthPolicy,
+    /// 包中每种类型至少要有一个签名，否则不输出文件（--require-sig-type）
+    pub require_sig_types: Vec<SIGTYPE>,
+    /// 包中没有签名时报错（--require-signature）；默认只提示没有签名
+    pub require_signature: bool,
+    /// 分离签名文件（.scrate.sig）路径，设置时包体中不含签名段
+    pub detached_sig: Option<String>,
+    /// 元数据输出格式
+    pub metadata_format: MetadataFormat,
+    /// 发布策略（--compat-check），设置时不满足策略则不输出文件
+    pub policy: Option<Policy>,
+    /// 只校验指纹和签名（及其他已选的检查），不输出文件；此时 `output` 可为空
+    pub verify_only: bool,
+    /// 逐步验证并输出说明树（--explain），不输出文件，其他检查不执行
+    pub explain: bool,
+    /// 某个输出写入失败时继续写其余输出，最后一并报告（--keep-going）
+    pub keep_going: bool,
+    /// 按文件哈希清单核对该目录下已解压的 crate 文件（--check-file-hashes）
+    pub check_file_hashes: Option<String>,
+}
+
+/// 读取 .scrate 输入，"-" 表示标准输入
+fn read_input(input: &str) -> Result<Vec<u8>> {
+    if is_stdin_path(input) {
+        read_stdin()
+    } else {
+        read_file(&validate_input_file(input)?)
+    }
+}
+
+/// 包中没有签名时给出提示；未指定 --require-signature 时无签名的包照常解码
+fn report_unsigned(pack_context: &PackageContext) {
+    if pack_context.sigs.is_empty() {
+        println!(
+            "{}",
+            tr("包中没有签名，如为分离签名的包体请使用 --detached-sig 指定 .scrate.sig 文件", "no signatures present; for a detached package body pass the .scrate.sig file with --detached-sig")
+        );
+    }
+}
+
+/// 输出每个本地签名的信任锚（验证通过的根 CA）
+fn report_trust_anchors(pack_context: &PackageContext) {
+    for (i, siginfo) in pack_context.sigs.iter().enumerate() {
+        if let Some(anchor) = &siginfo.trust_anchor {
+            match lang() {
+                Lang::Zh => println!("签名 #{} 信任锚: {}", i, anchor),
+                Lang::En => println!("signature #{} trust anchor: {}", i, anchor),
+            }
+        }
+    }
+}
+
+/// 输出构建元数据中记录的生成者
+fn report_producer(pack_context: &PackageContext) {
+    if let Some(producer) = pack_context.producer() {
+        match (&producer.operator, lang()) {
+            (Some(operator), Lang::Zh) => println!("生成者: {}（操作者: {}）", producer.tool, operator),
+            (Some(operator), Lang::En) => println!("produced by: {} (operator: {})", producer.tool, operator),
+            (None, Lang::Zh) => println!("生成者: {}", producer.tool),
+            (None, Lang::En) => println!("produced by: {}", producer.tool),
+        }
+    }
+}
+
+/// 校验并输出每个本地签名的签名时间
+fn report_signing_times(pack_context: &PackageContext) -> Result<()> {
+    for (i, time) in pack_context.verify_timestamps()? {
+        match lang() {
+            Lang::Zh => println!("签名 #{} 签名时间: {}（在证书有效期内）", i, time),
+            Lang::En => println!("signature #{} signing time: {} (within certificate validity)", i, time),
+        }
+    }
+    Ok(())
+}
+
+/// 按文件哈希清单核对 `dir` 下已解压的文件并输出结果
+fn report_file_hashes(pack_context: &PackageContext, dir: &str) -> Result<()> {
+    let count = pack_context.check_file_hashes(Path::new(dir))?;
+    match lang() {
+        Lang::Zh => println!("文件哈希核对通过: {} 个文件与 {} 一致", count, dir),
+        Lang::En => println!("file hashes match: {} file(s) under {}", count, dir),
+    }
+    Ok(())
+}
+
+/// 按发布策略检查已解码的包并输出报告，有违规时返回错误
+fn check_policy(pack_context: &PackageContext, policy: Option<&Policy>) -> Result<()> {
+    let Some(policy) = policy else {
+        return Ok(());
+    };
+    let report = policy.check(pack_context);
+    println!("{}", report);
+    if !report.passed() {
+        return Err(CrateSpecError::ValidationError(tr_format!(
+            "包 {} 不满足发布策略，共 {} 项违规",
+            "package {} violates the release policy ({} violations)",
+            report.package,
+            report.violations.len()
+        )));
+    }
+    Ok(())
+}
+
+/// --explain：逐步验证并输出说明树；有失败步骤时返回错误
+fn explain(input: &str, bin: &[u8], mut pack_context: PackageContext) -> Result<()> {
+    let explanation = pack_context.explain(bin);
+    print!("{}\n{}", input, explanation);
+    match explanation.first_failure() {
+        Some(step) => Err(CrateSpecError::ValidationError(tr_format!("验证失败于: {}", "verification failed at: {}", step))),
+        None => Ok(()),
+    }
+}
+
+/// 仅验证模式（--verify）的结果输出
+fn report_verified(pack_context: &PackageContext) {
+    let (name, version, sigs) = (&pack_context.pack_info.name, &pack_context.pack_info.version, pack_context.sigs.len());
+    match lang() {
+        Lang::Zh => println!("验证通过: {}-{}（{} 个签名），未输出文件", name, version, sigs),
+        Lang::En => println!("verified: {}-{} ({} signature(s)), no files written", name, version, sigs),
+    }
+    for (typ, count) in pack_context.sig_counts_by_type() {
+        match lang() {
+            Lang::Zh => println!("  {:?} 签名通过: {} 个", typ, count),
+            Lang::En => println!("  {:?} signatures passed: {}", typ, count),
+        }
+    }
+}
+
+/// 将 crate 二进制和元数据写入输出目录；裁剪包只有摘要，写出 `.crate.sha256`（十六进制摘要）
+///
+/// 每个输出经临时文件写入，失败时不留下不完整的输出，也不改动原有文件。默认在第一个失败处停止；
+/// `keep_going` 时继续写其余输出，最后一并报告所有失败项。
+fn write_decoded(pack_context: &PackageContext, output: &str, metadata_format: MetadataFormat, keep_going: bool) -> Result<()> {
+    let output_path = ensure_output_dir(output)?;
+
+    // crate bin 文件
+    let crate_name = format!("{}-{}.crate", pack_context.pack_info.name, pack_context.pack_info.version);
+    let crate_output: (PathBuf, Result<Cow<[u8]>>) = if pack_context.binary_pruned {
+        let digest_hex = digest_to_hex_string(&pack_context.crate_binary.bytes);
+        (output_path.join(format!("{}.sha256", crate_name)), Ok(Cow::Owned(format!("{}\n", digest_hex).into_bytes())))
+    } else {
+        (output_path.join(crate_name), Ok(Cow::Borrowed(&pack_context.crate_binary.bytes)))
+    };
+
+    // 元数据
+    let metadata_path = output_path.join(format!(
+        "{}-{}-metadata.{}",
+        pack_context.pack_info.name,
+        pack_context.pack_info.version,
+        metadata_format.extension()
+    ));
+    let metadata = pack_context.metadata_as(metadata_format).map(|text| Cow::Owned(text.into_bytes()));
+
+    let mut failures = vec![];
+    for (path, content) in [crate_output, (metadata_path, metadata)] {
+        // 经临时文件写入，失败时不留下写了一半的文件，也不删除运行前已存在的文件
+        let written = content.and_then(|content| replace_file(&path, &content));
+        if let Err(e) = written {
+            if !keep_going {
+                return Err(e);
+            }
+            eprintln!("{}", tr_format!("输出失败: {}: {}", "failed to write output: {}: {}", path.display(), e));
+            failures.push(format!("{}: {}", path.display(), e));
+        }
+    }
+    if !failures.is_empty() {
+        return Err(CrateSpecError::Other(tr_format!(
+            "{} 个输出写入失败（原有文件未改动）: {}",
+            "{} output(s) failed to write (existing files left untouched): {}",
+            failures.len(),
+            failures.join("; ")
+        )));
+    }
+    Ok(())
 }
 
 /// 本地解码命令
@@ -26,38 +247,71 @@ pub struct LocalDecodeCommand;
 impl LocalDecodeCommand {
     /// 执行本地解码操作
     pub fn execute(params: LocalDecodeParams) -> Result<()> {
-        // 验证输入文件
-        validate_input_file(&params.input)?;
+        if params.explain {
+            let bin = read_input(&params.input)?;
+            let mut pack_context = PackageContext::new();
+            pack_context.set_root_cas_bin(PKCS::root_ca_bins(params.root_ca_paths)?);
+            pack_context.max_chain_depth = params.max_chain_depth;
+            pack_context.key_policy = params.key_policy;
+            pack_context.require_sig_types = params.require_sig_types;
+            pack_context.require_signature = params.require_signature;
+            return explain(&params.input, &bin, pack_context);
+        }
 
-        // 解码
-        let pack_context = unpack_context(&params.input, params.root_ca_paths)?;
+        // 解码（输入为 "-" 时从标准输入读取）
+        let pack_context = if let Some(sig_path) = &params.detached_sig {
+            let bin = timings::measure(timings::label_read_binary(), || read_input(&params.input))?;
+            let sig_bin = read_file(&validate_input_file(sig_path)?)?;
+            unpack_context_detached(&bin, &sig_bin, params.root_ca_paths, params.max_chain_depth, &params.key_policy)?
+        } else if is_stdin_path(&params.input) {
+            let bin = timings::measure(timings::label_read_binary(), read_stdin)?;
+            unpack_context_from_bytes(&bin, params.root_ca_paths, params.max_chain_depth, &params.key_policy, params.require_signature)?
+        } else {
+            // 验证输入文件
+            validate_input_file(&params.input)?;
+            unpack_context(&params.input, params.root_ca_paths, params.max_chain_depth, &params.key_policy, params.require_signature)?
+        };
+        report_unsigned(&pack_context);
+        pack_context.check_sig_types(&params.require_sig_types)?;
 
-        // 输出文件
-        let output_path = ensure_output_dir(&params.output)?;
+        report_trust_anchors(&pack_context);
+        report_producer(&pack_context);
 
-        // 提取 crate bin 文件
-        let mut bin_path = output_path.clone();
-        bin_path.push(format!(
-            "{}-{}.crate",
-            pack_context.pack_info.name, pack_context.pack_info.version
-        ));
-        write_file(&bin_path, &pack_context.crate_binary.bytes)?;
-
-        // 输出元数据
-        let mut metadata_path = output_path;
-        metadata_path.push(format!(
-            "{}-{}-metadata.txt",
-            pack_context.pack_info.name, pack_context.pack_info.version
-        ));
-        write_text_file(
-            &metadata_path,
-            &format!(
-                "{:#?}\n{:#?}",
-                pack_context.pack_info, pack_context.dep_infos
-            ),
-        )?;
+        // 可选：校验 crate 二进制的 gzip/tar 结构
+        if params.check_crate_integrity {
+            pack_context.require_crate_binary()?;
+            pack_context.crate_binary.check_integrity()?;
+        }
 
-        Ok(())
+        // 可选：交叉校验 crate 内的 Cargo.toml 与元数据
+        if params.cross_validate {
+            pack_context.cross_validate()?;
+        }
+
+        // 可选：签名时间与证书有效期交叉校验
+        if params.verify_timestamps {
+            report_signing_times(&pack_context)?;
+        }
+
+        // 可选：检查字符串表中未被引用的项
+        if params.check_unreferenced_strings {
+            pack_context.check_unreferenced_strings()?;
+        }
+
+        // 可选：按文件哈希清单核对已解压的文件
+        if let Some(dir) = &params.check_file_hashes {
+            report_file_hashes(&pack_context, dir)?;
+        }
+
+        // 可选：发布策略检查
+        check_policy(&pack_context, params.policy.as_ref())?;
+
+        // 仅验证时不输出文件
+        if params.verify_only {
+            report_verified(&pack_context);
+            return Ok(());
+        }
+        write_decoded(&pack_context, &params.output, params.metadata_format, params.keep_going)
     }
 }
 
@@ -67,48 +321,324 @@ pub struct NetworkDecodeCommand;
 impl NetworkDecodeCommand {
     /// 执行网络解码操作
     pub fn execute(params: NetworkDecodeParams, config: &Config) -> Result<()> {
-        // 验证输入文件
-        let input_path = validate_input_file(&params.input)?;
+        // 验证输入文件（输入为 "-" 时从标准输入读取）
+        let input_path = if is_stdin_path(&params.input) {
+            None
+        } else {
+            Some(validate_input_file(&params.input)?)
+        };
 
         // 从配置创建 PKI 客户端
         let pki_client = config.create_pki_client()?;
 
-        // 读取文件并解码
-        let bin = read_file(&input_path)?;
+        // 读取输入并解码
+        let bin = timings::measure(timings::label_read_binary(), || match input_path {
+            Some(path) => read_file(&path),
+            None => read_stdin(),
+        })?;
         
         let mut pack_context = PackageContext::new();
         // 设置网络客户端
         pack_context.network_client = Some(Arc::new(pki_client));
+        pack_context.max_chain_depth = params.max_chain_depth;
+        pack_context.key_policy = params.key_policy.clone();
+        pack_context.require_sig_types = params.require_sig_types.clone();
+        pack_context.require_signature = params.require_signature;
+        if params.explain {
+            return explain(&params.input, &bin, pack_context);
+        }
         
-        // 解码并验证签名
-        let (_crate_package, _str_table) = pack_context.decode_from_crate_package(&bin)?;
+        // 解码并验证签名（分离签名时使用旁路文件验证）
+        match &params.detached_sig {
+            Some(sig_path) => {
+                let sig_bin = read_file(&validate_input_file(sig_path)?)?;
+                timings::measure(timings::label_decode(), || pack_context.decode_detached(&bin, &sig_bin))?;
+            }
+            None => {
+                timings::measure(timings::label_decode(), || pack_context.decode_from_crate_package(&bin))?;
+            }
+        }
+        report_unsigned(&pack_context);
 
-        // 输出文件
-        let output_path = ensure_output_dir(&params.output)?;
+        report_trust_anchors(&pack_context);
+        report_producer(&pack_context);
+
+        // 可选：校验 crate 二进制的 gzip/tar 结构
+        if params.check_crate_integrity {
+            pack_context.require_crate_binary()?;
+            pack_context.crate_binary.check_integrity()?;
+        }
 
-        // 提取 crate bin 文件
-        let mut bin_path = output_path.clone();
-        bin_path.push(format!(
-            "{}-{}.crate",
-            pack_context.pack_info.name, pack_context.pack_info.version
-        ));
-        write_file(&bin_path, &pack_context.crate_binary.bytes)?;
-
-        // 输出元数据
-        let mut metadata_path = output_path;
-        metadata_path.push(format!(
-            "{}-{}-metadata.txt",
-            pack_context.pack_info.name, pack_context.pack_info.version
-        ));
-        write_text_file(
-            &metadata_path,
-            &format!(
-                "{:#?}\n{:#?}",
-                pack_context.pack_info, pack_context.dep_infos
-            ),
-        )?;
+        // 可选：交叉校验 crate 内的 Cargo.toml 与元数据
+        if params.cross_validate {
+            pack_context.cross_validate()?;
+        }
 
+        // 可选：签名时间与证书有效期交叉校验
+        if params.verify_timestamps {
+            report_signing_times(&pack_context)?;
+        }
+
+        // 可选：检查字符串表中未被引用的项
+        if params.check_unreferenced_strings {
+            pack_context.check_unreferenced_strings()?;
+        }
+
+        // 可选：按文件哈希清单核对已解压的文件
+        if let Some(dir) = &params.check_file_hashes {
+            report_file_hashes(&pack_context, dir)?;
+        }
+
+        // 可选：发布策略检查
+        check_policy(&pack_context, params.policy.as_ref())?;
+
+        // 仅验证时不输出文件
+        if params.verify_only {
+            report_verified(&pack_context);
+            return Ok(());
+        }
+        write_decoded(&pack_context, &params.output, params.metadata_format, params.keep_going)
+    }
+}
+
+/// 批量验证参数
+#[derive(Debug, Clone)]
+pub struct BatchVerifyParams {
+    /// .scrate 文件或目录（目录展开为其中的 .scrate 文件）
+    pub inputs: Vec<String>,
+    pub root_ca_paths: Vec<String>,
+    pub max_chain_depth: u32,
+    /// 签名者密钥强度要求
+    pub key_policy: KeyStrengthPolicy,
+    /// 包中没有签名时该项失败（--require-signature）
+    pub require_signature: bool,
+    /// 逐项结果的输出格式
+    pub format: BatchFormat,
+    /// 标准输出是终端且格式不是 jsonl 时在标准错误上显示进度条
+    pub progress: bool,
+}
+
+/// 批量验证命令：逐个校验指纹和签名，不提取文件，每完成一项输出一行结果
+pub struct BatchVerifyCommand;
+
+impl BatchVerifyCommand {
+    /// `network_client` 在网络模式下用于验证网络签名；有失败项时返回错误
+    pub fn execute(params: BatchVerifyParams, network_client: Option<Arc<PkiClient>>) -> Result<()> {
+        let root_cas = PKCS::root_ca_bins(params.root_ca_paths.clone())?;
+        let inputs = expand_inputs(&params.inputs)?;
+        let stdout = std::io::stdout();
+        let progress = BatchProgress::enabled(params.progress, params.format, stdout.is_terminal())
+            .then(|| BatchProgress::new(inputs.len()));
+        let mut out: Box<dyn Write> = match &progress {
+            Some(progress) => Box::new(progress.writer(stdout.lock())),
+            None => Box::new(stdout.lock()),
+        };
+        let summary = run_batch(&inputs, params.format, &mut out, |input| {
+            let bin = read_file(&validate_input_file(input)?)?;
+            let mut pack_context = PackageContext::new();
+            pack_context.set_root_cas_bin(root_cas.clone());
+            pack_context.max_chain_depth = params.max_chain_depth;
+            pack_context.key_policy = params.key_policy.clone();
+            pack_context.network_client = network_client.clone();
+            pack_context.require_signature = params.require_signature;
+            pack_context.decode_from_crate_package(&bin)?;
+            let info = &pack_context.pack_info;
+            Ok(match params.format {
+                BatchFormat::Summary => format!("{}@{}  sigs={}", info.name, info.version, pack_context.sigs.len()),
+                _ => format!("{}-{}", info.name, info.version),
+            })
+        }, |summary| {
+            if let Some(progress) = &progress {
+                progress.update(summary);
+            }
+        })?;
+        drop(out);
+        if let Some(progress) = &progress {
+            progress.finish();
+        }
+        // JSON Lines 模式下标准输出只含逐项结果，汇总写到标准错误；摘要模式不输出汇总
+        match params.format {
+            BatchFormat::Text => println!("{}", summary),
+            BatchFormat::Jsonl => eprintln!("{}", summary),
+            BatchFormat::Summary => {}
+        }
+        if summary.failed > 0 {
+            return Err(CrateSpecError::ValidationError(summary.to_string()));
+        }
         Ok(())
     }
 }
 
+/// 导出数据段参数
+#[derive(Debug, Clone)]
+pub struct DumpSectionsParams {
+    pub input: String,
+    pub output: String,
+}
+
+/// 导出数据段命令（仅用于分析文件格式，不做指纹和签名校验）
+pub struct DumpSectionsCommand;
+
+impl DumpSectionsCommand {
+    /// 将 .scrate 的各段原始字节写入输出目录
+    pub fn execute(params: DumpSectionsParams) -> Result<()> {
+        let bin = read_input(&params.input)?;
+        let output_path = ensure_output_dir(&params.output)?;
+        dump_sections(&bin, &output_path)?;
+        Ok(())
+    }
+}
+
+/// 包信息输出参数
+#[derive(Debug, Clone)]
+pub struct InfoParams {
+    pub input: String,
+    /// 输出文件，None 时输出到标准输出
+    pub output: Option<String>,
+}
+
+/// 包信息输出命令：只读取包信息和依赖表，以 JSON 输出，不做指纹和签名校验
+pub struct InfoCommand;
+
+impl InfoCommand {
+    pub fn execute(params: InfoParams) -> Result<()> {
+        let pack_context = if is_stdin_path(&params.input) {
+            PackageContext::read_info(&mut std::io::Cursor::new(read_stdin()?))?
+        } else {
+            PackageContext::read_info_from_path(validate_input_file(&params.input)?)?
+        };
+        let json = pack_context.info_json()?;
+        match params.output {
+            Some(output) => write_file(Path::new(&output), json.as_bytes()),
+            None => std::io::stdout()
+                .write_all(json.as_bytes())
+                .map_err(|e| CrateSpecError::Other(tr_format!("无法写入标准输出: {}", "cannot write to stdout: {}", e))),
+        }
+    }
+}
+
+#[test]
+fn test_network_verify_only() {
+    use crate_spec::network::{BaseConfig, KeyPair};
+    use crate_spec::utils::context::SIGTYPE;
+    use crate_spec::utils::testing::TestDir;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    // 依次应答签名和验签请求的最小 PKI 服务
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        for reply in [
+            r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"signature":"sig"}"#,
+            r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"result":"OK"}"#,
+        ] {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            reader.read_exact(&mut vec![0u8; content_length]).unwrap();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                reply.len(),
+                reply
+            )
+            .unwrap();
+        }
+    });
+
+    let base_config = BaseConfig { algo: "sm2".to_string(), kms: String::new(), flow: "sign".to_string() };
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("netverify".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![0u8; 8]);
+    ctx.network_client = Some(Arc::new(PkiClient::new(base_url.clone(), 0, 0).unwrap()));
+    ctx.network_keypair = Some(Arc::new(KeyPair {
+        priv_key: "priv".to_string(),
+        pub_key: "pub".to_string(),
+        key_id: "key-1".to_string(),
+        base_config,
+        fetched_at: None,
+        ttl_secs: None,
+    }));
+    ctx.add_sig(PKCS::new(), SIGTYPE::NETWORK);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+
+    let dir = TestDir::new("net-verify");
+    let input = dir.write("netverify-0.1.0.scrate", &bin);
+    let output = dir.join("out");
+    let params = NetworkDecodeParams {
+        input: input.display().to_string(),
+        output: String::new(),
+        check_crate_integrity: false,
+        cross_validate: false,
+        verify_timestamps: false,
+        check_unreferenced_strings: false,
+        max_chain_depth: crate_spec::utils::pkcs::DEFAULT_MAX_CHAIN_DEPTH,
+        key_policy: KeyStrengthPolicy::default(),
+        require_sig_types: vec![SIGTYPE::NETWORK],
+        require_signature: false,
+        detached_sig: None,
+        metadata_format: MetadataFormat::default(),
+        policy: None,
+        verify_only: true,
+        explain: false,
+        keep_going: false,
+        check_file_hashes: None,
+    };
+
+    // 没有 [net] 配置时无法创建 PKI 客户端
+    let config: Config = toml::from_str("[network.decode]\n").unwrap();
+    assert!(matches!(
+        NetworkDecodeCommand::execute(params.clone(), &config),
+        Err(CrateSpecError::ConfigError(_))
+    ));
+
+    let config: Config = toml::from_str(&format!("[net]\npki_base_url = \"{}\"\n", base_url)).unwrap();
+    NetworkDecodeCommand::execute(params, &config).unwrap();
+    server.join().unwrap();
+    assert!(!output.exists());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_write_decoded_keep_going() {
+    use crate_spec::utils::testing::TestDir;
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("partial".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![7u8; 16]);
+
+    // 与 crate 文件同名的目录使其写入失败
+    let dir = TestDir::new("keep-going");
+    let output = dir.join_str("out");
+    std::fs::create_dir_all(dir.join("out/partial-0.1.0.crate")).unwrap();
+    let metadata = dir.join("out/partial-0.1.0-metadata.txt");
+
+    // 默认在第一个失败处停止，元数据不会写出
+    assert!(matches!(
+        write_decoded(&ctx, &output, MetadataFormat::Txt, false),
+        Err(CrateSpecError::Io(_))
+    ));
+    assert!(!metadata.exists());
+
+    // --keep-going：仍写出元数据，最后报告失败的输出
+    let err = write_decoded(&ctx, &output, MetadataFormat::Txt, true).unwrap_err();
+    assert!(err.to_string().contains("1 个输出写入失败"), "{}", err);
+    assert!(err.to_string().contains("partial-0.1.0.crate"), "{}", err);
+    assert_eq!(std::fs::read_to_string(&metadata).unwrap(), ctx.metadata_text());
+    assert!(dir.join("out/partial-0.1.0.crate").is_dir());
+    // 失败的输出没有留下临时文件
+    assert_eq!(std::fs::read_dir(dir.join("out")).unwrap().count(), 2);
+}
@@ -1,19 +1,79 @@
-use crate::pack::{pack_context, pack_name};
+use crate_spec::utils::pack::{is_git_url, pack_context, pack_name, pack_workspace, PackOptions};
 use crate::config::Config;
-use crate_spec::error::Result;
-use crate_spec::utils::context::SIGTYPE;
-use crate_spec::utils::file_ops::{validate_input_file, ensure_output_dir, write_file};
-use crate_spec::utils::pkcs::PKCS;
+use crate_spec::error::{CrateSpecError, Result};
+use crate_spec::locale::{lang, tr, Lang};
+use crate_spec::utils::context::{DepInfo, PackageContext, Producer, SIGTYPE};
+use crate_spec::utils::detached::DETACHED_SIG_EXT;
+use crate_spec::utils::file_ops::{resolve_output_path, validate_input_file, ensure_output_dir, write_file, CollisionStrategy};
+use crate_spec::utils::pkcs::{DigestAlgo, EngineKey, PKCS};
+use crate_spec::utils::policy::DepSourcePolicy;
+use crate_spec::utils::progress::{self, EncodeStage, ProgressSink};
+use crate_spec::utils::compression::CompressionKind;
+use crate_spec::utils::package::Endianness;
+use crate_spec::tr_format;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// 本地编码参数
 #[derive(Debug, Clone)]
 pub struct LocalEncodeParams {
     pub cert_path: String,
-    pub pkey_path: String,
+    pub pkey_path: Option<String>,
+    pub engine_key: Option<EngineKey>,
+    /// 签名时嵌入的中间 CA 证书文件（--cert-chain）
+    pub cert_chain_paths: Vec<String>,
     pub root_ca_paths: Vec<String>,
     pub output: String,
     pub input: String,
+    /// 签名写入单独的 .scrate.sig 文件，包体中不含签名段
+    pub detached: bool,
+    /// cargo 构建输出目录（--target-dir）
+    pub target_dir: Option<String>,
+    /// Cargo.toml 缺少 name/version 时使用占位值而不是报错
+    pub lenient_toml: bool,
+    /// cargo package 超时时间（秒）
+    pub package_timeout: Option<u64>,
+    /// 记录打包所用的 cargo/rustc 版本
+    pub record_toolchain: bool,
+    /// 输入为 git 仓库地址时检出的 tag/分支
+    pub git_ref: Option<String>,
+    /// 输出文件已存在时的处理方式
+    pub on_collision: CollisionStrategy,
+    /// 打印因含不支持的键而未写入依赖表的依赖
+    pub report_unverified_deps: bool,
+    /// 打包出现警告时失败
+    pub fail_on_warning: bool,
+    /// 依赖源策略（配置文件 [policy] 段）
+    pub dep_source_policy: DepSourcePolicy,
+    /// target/package 中 `.crate` 文件名的模式
+    pub crate_file_pattern: Option<String>,
+    /// 写入构建元数据的生成者标识
+    pub producer: Option<Producer>,
+    /// crate 二进制段只保存 `.crate` 的 SHA256 摘要
+    pub prune_binary: bool,
+    /// 数据段对齐字节数，1 表示不填充
+    pub section_align: usize,
+    /// 字符串表长度前缀和段索引字段的字节序
+    pub endianness: Endianness,
+    /// crate 二进制段的压缩方式
+    pub compression: CompressionKind,
+    /// 记录 `.crate` 中每个文件的 SHA256
+    pub file_hashes: bool,
+    /// 签名内容和指纹使用的摘要算法
+    pub digest: DigestAlgo,
+    /// 打包后从依赖表删除的依赖名（--remove-dep）
+    pub remove_deps: Vec<String>,
+    /// 打包后追加到依赖表的依赖（--add-dep）
+    pub add_deps: Vec<DepInfo>,
+    /// 只输出将要生成的包信息和文件名，不执行 cargo package、不签名、不写文件
+    pub dry_run: bool,
+    /// 输入为 Cargo 工作区，为每个成员各生成一个 .scrate
+    pub workspace: bool,
+    /// 输出文件名（--output-name），原样使用；未设置时为 `{name}-{version}.scrate`
+    pub output_name: Option<String>,
+    /// 进入各编码阶段时的回调
+    pub progress: Option<Arc<dyn ProgressSink>>,
 }
 
 /// 网络编码参数
@@ -21,6 +81,170 @@ pub struct LocalEncodeParams {
 pub struct NetworkEncodeParams {
     pub input: String,
     pub output: String,
+    /// 签名写入单独的 .scrate.sig 文件，包体中不含签名段
+    pub detached: bool,
+    /// cargo 构建输出目录（--target-dir）
+    pub target_dir: Option<String>,
+    /// Cargo.toml 缺少 name/version 时使用占位值而不是报错
+    pub lenient_toml: bool,
+    /// cargo package 超时时间（秒）
+    pub package_timeout: Option<u64>,
+    /// 记录打包所用的 cargo/rustc 版本
+    pub record_toolchain: bool,
+    /// 输入为 git 仓库地址时检出的 tag/分支
+    pub git_ref: Option<String>,
+    /// 输出文件已存在时的处理方式
+    pub on_collision: CollisionStrategy,
+    /// 打印因含不支持的键而未写入依赖表的依赖
+    pub report_unverified_deps: bool,
+    /// 打包出现警告时失败
+    pub fail_on_warning: bool,
+    /// 依赖源策略（配置文件 [policy] 段）
+    pub dep_source_policy: DepSourcePolicy,
+    /// target/package 中 `.crate` 文件名的模式
+    pub crate_file_pattern: Option<String>,
+    /// 写入构建元数据的生成者标识
+    pub producer: Option<Producer>,
+    /// crate 二进制段只保存 `.crate` 的 SHA256 摘要
+    pub prune_binary: bool,
+    /// 数据段对齐字节数，1 表示不填充
+    pub section_align: usize,
+    /// 字符串表长度前缀和段索引字段的字节序
+    pub endianness: Endianness,
+    /// crate 二进制段的压缩方式
+    pub compression: CompressionKind,
+    /// 记录 `.crate` 中每个文件的 SHA256
+    pub file_hashes: bool,
+    /// 打包后从依赖表删除的依赖名（--remove-dep）
+    pub remove_deps: Vec<String>,
+    /// 打包后追加到依赖表的依赖（--add-dep）
+    pub add_deps: Vec<DepInfo>,
+    /// 只输出将要生成的包信息和文件名，不执行 cargo package、不签名、不写文件
+    pub dry_run: bool,
+    /// 输入为 Cargo 工作区，为每个成员各生成一个 .scrate
+    pub workspace: bool,
+    /// 输出文件名（--output-name），原样使用；未设置时为 `{name}-{version}.scrate`
+    pub output_name: Option<String>,
+    /// 进入各编码阶段时的回调
+    pub progress: Option<Arc<dyn ProgressSink>>,
+}
+
+/// 打包输入：`workspace` 时打包工作区的每个成员，否则只打包输入的 crate
+fn pack_inputs(input: &str, workspace: bool, options: PackOptions) -> Result<Vec<PackageContext>> {
+    if workspace {
+        pack_workspace(input, options)
+    } else {
+        Ok(vec![pack_context(input, options)?])
+    }
+}
+
+/// 按 --remove-dep/--add-dep 修改依赖表：先删除再追加，追加的依赖同样受依赖源策略约束
+///
+/// 在签名之前调用，签名覆盖修改后的依赖表。
+fn edit_deps(
+    pack_context: &mut PackageContext,
+    remove_deps: &[String],
+    add_deps: &[DepInfo],
+    policy: &DepSourcePolicy,
+) -> Result<()> {
+    for name in remove_deps {
+        if pack_context.remove_dep(name) == 0 {
+            return Err(CrateSpecError::ValidationError(tr_format!("依赖表中没有 {}，无法删除", "{} is not in the dependency table and cannot be removed", name)));
+        }
+    }
+    for dep in add_deps {
+        policy
+            .check(&dep.src)
+            .map_err(|e| CrateSpecError::ValidationError(tr_format!("追加的依赖 {}: {}", "added dependency {}: {}", dep.name, e)))?;
+        // 同名同平台同类别的依赖解码时会被拒绝
+        if pack_context
+            .deps()
+            .iter()
+            .any(|d| d.name == dep.name && d.src_platform == dep.src_platform && d.kind == dep.kind)
+        {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "依赖表中已有 {}（平台 {}），请先用 --remove-dep 删除",
+                "{} (platform {}) is already in the dependency table; remove it first with --remove-dep",
+                dep.name, dep.src_platform
+            )));
+        }
+        pack_context.dep_infos.push(dep.clone());
+    }
+    Ok(())
+}
+
+/// 输出文件名：指定了 --output-name 时原样使用，否则由包名和版本生成
+fn output_file_name(pack_context: &PackageContext, output_name: Option<&str>) -> String {
+    output_name.map_or_else(|| pack_name(pack_context), str::to_string)
+}
+
+/// --dry-run：输出包信息、依赖、签名类型和将要写出的文件名
+fn report_dry_run(
+    pack_context: &PackageContext,
+    output: &str,
+    output_name: Option<&str>,
+    sig_type: SIGTYPE,
+    detached: bool,
+) {
+    let info = &pack_context.pack_info;
+    let bin_path = Path::new(output).join(output_file_name(pack_context, output_name));
+    match lang() {
+        Lang::Zh => {
+            println!("预演: {}-{}（许可证: {}），未执行 cargo package，未写出文件", info.name, info.version, info.license);
+            println!("签名类型: {:?}", sig_type);
+        }
+        Lang::En => {
+            println!("dry run: {}-{} (license: {}), cargo package not run, no files written", info.name, info.version, info.license);
+            println!("signature type: {:?}", sig_type);
+        }
+    }
+    for (dep, (name, ver_req, src)) in pack_context.deps().iter().zip(pack_context.dep_entries()) {
+        match lang() {
+            Lang::Zh => println!("  依赖 {} {}，来源 {}，平台 {}，类别 {}", name, ver_req, src, dep.src_platform, dep.kind),
+            Lang::En => println!("  dependency {} {}, source {}, platform {}, kind {}", name, ver_req, src, dep.src_platform, dep.kind),
+        }
+    }
+    match lang() {
+        Lang::Zh => println!("输出文件: {}", bin_path.display()),
+        Lang::En => println!("output file: {}", bin_path.display()),
+    }
+    if detached {
+        match lang() {
+            Lang::Zh => println!("分离签名: {}.{}", bin_path.display(), DETACHED_SIG_EXT),
+            Lang::En => println!("detached signature: {}.{}", bin_path.display(), DETACHED_SIG_EXT),
+        }
+    }
+}
+
+/// 编码为二进制并写入输出目录；分离签名时额外写出 .scrate.sig
+fn write_output(
+    pack_context: &mut PackageContext,
+    output: &str,
+    output_name: Option<&str>,
+    detached: bool,
+    on_collision: CollisionStrategy,
+    progress: Option<&dyn ProgressSink>,
+) -> Result<()> {
+    let output_dir = ensure_output_dir(output)?;
+    let bin_path = resolve_output_path(
+        &output_dir.join(output_file_name(pack_context, output_name)),
+        on_collision,
+        detached.then_some(DETACHED_SIG_EXT),
+    )?;
+    progress::report(progress, EncodeStage::Signing);
+    if detached {
+        let (body, sig_bin) = pack_context.encode_detached()?;
+        progress::report(progress, EncodeStage::Writing);
+        write_file(&bin_path, &body)?;
+        let mut sig_path = bin_path.into_os_string();
+        sig_path.push(format!(".{}", DETACHED_SIG_EXT));
+        write_file(sig_path.as_ref(), &sig_bin)?;
+    } else {
+        let (_, _, bin) = pack_context.encode_to_crate_package()?;
+        progress::report(progress, EncodeStage::Writing);
+        write_file(&bin_path, &bin)?;
+    }
+    Ok(())
 }
 
 /// 本地编码命令
@@ -29,31 +253,79 @@ pub struct LocalEncodeCommand;
 impl LocalEncodeCommand {
     /// 执行本地编码操作
     pub fn execute(params: LocalEncodeParams) -> Result<()> {
-        // 验证输入文件
-        validate_input_file(&params.input)?;
-
-        // 打包
-        let mut pack_context = pack_context(&params.input)?;
+        // 验证输入文件（git 仓库地址在打包时克隆）
+        if !is_git_url(&params.input) {
+            validate_input_file(&params.input)?;
+        }
 
-        // 设置签名工具
-        let mut pkcs = PKCS::new();
-        pkcs.load_from_file_writer(
-            params.cert_path,
-            params.pkey_path,
-            params.root_ca_paths,
+        // 打包（工作区时每个成员各一个上下文）
+        let mut pack_contexts = pack_inputs(
+            &params.input,
+            params.workspace,
+            PackOptions {
+                target_dir: params.target_dir.clone(),
+                lenient_toml: params.lenient_toml,
+                timeout: params.package_timeout.map(Duration::from_secs),
+                record_toolchain: params.record_toolchain,
+                git_ref: params.git_ref.clone(),
+                report_dropped_deps: params.report_unverified_deps,
+                fail_on_warning: params.fail_on_warning,
+                dep_source_policy: params.dep_source_policy.clone(),
+                crate_file_pattern: params.crate_file_pattern.clone(),
+                file_hashes: params.file_hashes,
+                metadata_only: params.dry_run,
+                progress: params.progress.clone(),
+            },
         )?;
 
-        pack_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+        for pack_context in pack_contexts.iter_mut() {
+            if let Some(producer) = params.producer.clone() {
+                pack_context.set_producer(producer.tool, producer.operator);
+            }
+            edit_deps(pack_context, &params.remove_deps, &params.add_deps, &params.dep_source_policy)?;
+            if params.dry_run {
+                report_dry_run(pack_context, &params.output, params.output_name.as_deref(), SIGTYPE::CRATEBIN, params.detached);
+            }
+        }
+        if params.dry_run {
+            return Ok(());
+        }
 
-        // 编码为二进制
-        let (_, _, bin) = pack_context.encode_to_crate_package()?;
+        // 设置签名工具
+        let mut pkcs = PKCS::new();
+        match (params.engine_key, params.pkey_path) {
+            (Some(engine_key), _) => {
+                pkcs.load_from_engine_writer(params.cert_path, engine_key, params.root_ca_paths)?;
+            }
+            (None, Some(pkey_path)) => {
+                pkcs.load_from_file_writer(params.cert_path, pkey_path, params.root_ca_paths)?;
+            }
+            (None, None) => {
+                return Err(CrateSpecError::ValidationError(tr("必须提供私钥路径或 engine 私钥", "a private key path or an engine private key is required").to_string()));
+            }
+        }
+        pkcs.load_chain_from_files(params.cert_chain_paths)?;
 
-        // 输出文件
-        let output_dir = ensure_output_dir(&params.output)?;
-        let mut bin_path = output_dir;
-        bin_path.push(pack_name(&pack_context));
-        write_file(&bin_path, &bin)?;
+        for mut pack_context in pack_contexts {
+            if params.prune_binary {
+                pack_context.prune_binary();
+            }
+            pack_context.section_align = params.section_align;
+            pack_context.endianness = params.endianness;
+            pack_context.compression = params.compression;
+            pack_context.fingerprint_digest = params.digest;
+            pack_context.add_sig_with_digest(pkcs.clone(), SIGTYPE::CRATEBIN, params.digest);
 
+            // 编码并输出文件
+            write_output(
+                &mut pack_context,
+                &params.output,
+                params.output_name.as_deref(),
+                params.detached,
+                params.on_collision,
+                params.progress.as_deref(),
+            )?;
+        }
         Ok(())
     }
 }
@@ -64,33 +336,121 @@ pub struct NetworkEncodeCommand;
 impl NetworkEncodeCommand {
     /// 执行网络编码操作
     pub fn execute(params: NetworkEncodeParams, config: &Config) -> Result<()> {
-        // 验证输入文件
-        validate_input_file(&params.input)?;
+        // 验证输入文件（git 仓库地址在打包时克隆）
+        if !is_git_url(&params.input) {
+            validate_input_file(&params.input)?;
+        }
 
-        // 从配置获取网络资源
-        let pki_client = config.create_pki_client()?;
-        let keypair = config.get_or_fetch_keypair()?;
+        // 从配置获取网络资源，预演时不访问 PKI；cargo package 可能耗时很久，先确认 PKI 平台可达
+        let network = if params.dry_run {
+            None
+        } else {
+            let pki_client = config.create_pki_client()?;
+            if config.pki_health_check_enabled() {
+                pki_client.health_check().map_err(CrateSpecError::PkiError)?;
+            }
+            Some((pki_client, config.get_or_fetch_keypair()?))
+        };
 
-        // 打包
-        let mut pack_context = pack_context(&params.input)?;
+        // 打包（工作区时每个成员各一个上下文）
+        let mut pack_contexts = pack_inputs(
+            &params.input,
+            params.workspace,
+            PackOptions {
+                target_dir: params.target_dir.clone(),
+                lenient_toml: params.lenient_toml,
+                timeout: params.package_timeout.map(Duration::from_secs),
+                record_toolchain: params.record_toolchain,
+                git_ref: params.git_ref.clone(),
+                report_dropped_deps: params.report_unverified_deps,
+                fail_on_warning: params.fail_on_warning,
+                dep_source_policy: params.dep_source_policy.clone(),
+                crate_file_pattern: params.crate_file_pattern.clone(),
+                file_hashes: params.file_hashes,
+                metadata_only: params.dry_run,
+                progress: params.progress.clone(),
+            },
+        )?;
 
-        // 设置网络客户端和密钥对
-        pack_context.network_client = Some(Arc::new(pki_client));
-        pack_context.network_keypair = Some(keypair);
+        for pack_context in pack_contexts.iter_mut() {
+            if let Some(producer) = params.producer.clone() {
+                pack_context.set_producer(producer.tool, producer.operator);
+            }
+            edit_deps(pack_context, &params.remove_deps, &params.add_deps, &params.dep_source_policy)?;
+        }
+        let Some((pki_client, keypair)) = network else {
+            for pack_context in pack_contexts.iter() {
+                report_dry_run(pack_context, &params.output, params.output_name.as_deref(), SIGTYPE::NETWORK, params.detached);
+            }
+            return Ok(());
+        };
+        let pki_client = Arc::new(pki_client);
 
-        // 添加网络签名（使用空的 PKCS，因为网络签名不需要本地证书）
-        pack_context.add_sig(PKCS::new(), SIGTYPE::NETWORK);
+        for mut pack_context in pack_contexts {
+            if params.prune_binary {
+                pack_context.prune_binary();
+            }
+            pack_context.section_align = params.section_align;
+            pack_context.endianness = params.endianness;
+            pack_context.compression = params.compression;
 
-        // 编码为二进制
-        let (_, _, bin) = pack_context.encode_to_crate_package()?;
+            // 设置网络客户端和密钥对
+            pack_context.network_client = Some(pki_client.clone());
+            pack_context.network_keypair = Some(keypair.clone());
 
-        // 输出文件
-        let output_dir = ensure_output_dir(&params.output)?;
-        let mut bin_path = output_dir;
-        bin_path.push(pack_name(&pack_context));
-        write_file(&bin_path, &bin)?;
+            // 添加网络签名（使用空的 PKCS，因为网络签名不需要本地证书）
+            pack_context.add_sig(PKCS::new(), SIGTYPE::NETWORK);
 
+            // 编码并输出文件
+            write_output(
+                &mut pack_context,
+                &params.output,
+                params.output_name.as_deref(),
+                params.detached,
+                params.on_collision,
+                params.progress.as_deref(),
+            )?;
+        }
         Ok(())
     }
 }
 
+
+#[test]
+fn test_edit_deps_from_cli_specs() {
+    use crate_spec::utils::context::SrcTypePath;
+
+    let mut pack_context = PackageContext::new();
+    pack_context.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "default".to_string());
+    pack_context.add_dep_info("libc".to_string(), "0.2".to_string(), SrcTypePath::CratesIo, "cfg(unix)".to_string());
+    pack_context.add_dep_info("libc".to_string(), "0.2".to_string(), SrcTypePath::CratesIo, "cfg(windows)".to_string());
+
+    let add_deps: Vec<DepInfo> = ["toml@0.7", "local@0.1;source=git+https://example.com/local.git;platform=cfg(unix)"]
+        .iter()
+        .map(|spec| spec.parse().unwrap())
+        .collect();
+    edit_deps(&mut pack_context, &["libc".to_string()], &add_deps, &DepSourcePolicy::default()).unwrap();
+    let deps: Vec<_> = pack_context.dep_entries().collect();
+    assert_eq!(
+        deps,
+        vec![
+            ("serde", "1.0", "crates.io".to_string()),
+            ("toml", "0.7", "crates.io".to_string()),
+            ("local", "0.1", "git+https://example.com/local.git".to_string()),
+        ]
+    );
+    assert_eq!(pack_context.deps()[2].src_platform, "cfg(unix)");
+
+    // 删除不存在的依赖、格式错误的描述、不符合依赖源策略的来源都报错
+    assert!(edit_deps(&mut pack_context, &["libc".to_string()], &[], &DepSourcePolicy::default()).is_err());
+    for spec in ["toml", "@0.7", "toml@0.7;features=derive", "toml@0.7;source=svn+x"] {
+        assert!(spec.parse::<DepInfo>().is_err(), "{}", spec);
+    }
+    let policy = DepSourcePolicy { allowed_dep_sources: vec!["crates-io".parse().unwrap()], ..Default::default() };
+    let err = edit_deps(&mut pack_context, &[], &add_deps[1..], &policy).unwrap_err();
+    assert!(err.to_string().contains("local"), "{}", err);
+
+    // 追加已有的同名同平台依赖会报错
+    let err = edit_deps(&mut pack_context, &[], &add_deps[..1], &DepSourcePolicy::default()).unwrap_err();
+    assert!(err.to_string().contains("--remove-dep"), "{}", err);
+}
@@ -1,6 +1,8 @@
 pub mod encode;
 pub mod decode;
+pub mod sign_digest;
 
 pub use encode::{LocalEncodeCommand, NetworkEncodeCommand};
-pub use decode::{LocalDecodeCommand, NetworkDecodeCommand};
+pub use decode::{BatchVerifyCommand, DumpSectionsCommand, InfoCommand, LocalDecodeCommand, NetworkDecodeCommand};
+pub use sign_digest::SignDigestCommand;
 
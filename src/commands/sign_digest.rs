@@ -0,0 +1,39 @@
+use crate::config::Config;
+use crate_spec::error::{CrateSpecError, Result};
+use crate_spec::network::{parse_digest_hex, NetworkSignature, NETWORK_SIG_EXT};
+use crate_spec::utils::file_ops::{ensure_output_dir, read_file, validate_input_file, write_file};
+use crate_spec::locale::tr;
+use crate_spec::tr_format;
+
+/// 摘要签名参数
+#[derive(Debug, Clone)]
+pub struct SignDigestParams {
+    /// 十六进制 SHA256 摘要文件
+    pub digest_file: String,
+    pub output: String,
+}
+
+/// 摘要签名命令：不打包 crate，直接用 PKI 平台对已算好的摘要签名
+pub struct SignDigestCommand;
+
+impl SignDigestCommand {
+    /// 签名摘要文件，输出序列化的 NetworkSignature 到 `<摘要文件名>.netsig`
+    pub fn execute(params: SignDigestParams, config: &Config) -> Result<()> {
+        let digest_path = validate_input_file(&params.digest_file)?;
+        let text = String::from_utf8(read_file(&digest_path)?)
+            .map_err(|_| CrateSpecError::ValidationError(tr("摘要文件不是有效的 UTF-8 文本", "the digest file is not valid UTF-8 text").to_string()))?;
+        let digest_hex = parse_digest_hex(&text).map_err(CrateSpecError::ValidationError)?;
+
+        let pki_client = config.create_pki_client()?;
+        let keypair = config.get_or_fetch_keypair()?;
+        let network_sig = NetworkSignature::sign(&pki_client, &keypair, &digest_hex).map_err(CrateSpecError::PkiError)?;
+        let bin = network_sig.to_bytes().map_err(CrateSpecError::EncodeError)?;
+
+        let file_name = digest_path
+            .file_name()
+            .ok_or_else(|| CrateSpecError::ValidationError(tr_format!("无效的摘要文件路径: {}", "invalid digest file path: {}", params.digest_file)))?;
+        let mut sig_name = file_name.to_os_string();
+        sig_name.push(format!(".{}", NETWORK_SIG_EXT));
+        write_file(&ensure_output_dir(&params.output)?.join(sig_name), &bin)
+    }
+}
@@ -1,26 +1,143 @@
+use crate::locale::tr;
+use crate::tr_format;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 
 pub const DEFAULT_CONFIG_PATH: &str = "config/config.toml";
 
+/// [net] http_timeout_secs 和 --timeout 允许的最大值（秒）
+pub const MAX_HTTP_TIMEOUT_SECS: u64 = 600;
+
+// 环境变量配置（用于 Config::from_env）
+pub const ENV_PKI_BASE_URL: &str = "CRATE_SPEC_PKI_BASE_URL";
+pub const ENV_ALGO: &str = "CRATE_SPEC_ALGO";
+pub const ENV_FLOW: &str = "CRATE_SPEC_FLOW";
+pub const ENV_KMS: &str = "CRATE_SPEC_KMS";
+pub const ENV_KEY_PAIR_PATH: &str = "CRATE_SPEC_KEY_PAIR_PATH";
+pub const ENV_RETRY_TIMES: &str = "CRATE_SPEC_RETRY_TIMES";
+pub const ENV_RETRY_DELAY: &str = "CRATE_SPEC_RETRY_DELAY";
+pub const ENV_BATCH_VERIFY_PATH: &str = "CRATE_SPEC_BATCH_VERIFY_PATH";
+pub const ENV_PKI_CLIENT_CERT_PATH: &str = "CRATE_SPEC_PKI_CLIENT_CERT_PATH";
+pub const ENV_PKI_CLIENT_KEY_PATH: &str = "CRATE_SPEC_PKI_CLIENT_KEY_PATH";
+pub const ENV_AUTH_TOKEN: &str = "CRATE_SPEC_AUTH_TOKEN";
+pub const ENV_CERT_PATH: &str = "CRATE_SPEC_CERT_PATH";
+pub const ENV_CERT_CHAIN_PATH: &str = "CRATE_SPEC_CERT_CHAIN_PATH";
+pub const ENV_PRIVATE_KEY_PATH: &str = "CRATE_SPEC_PRIVATE_KEY_PATH";
+pub const ENV_ROOT_CA_PATH: &str = "CRATE_SPEC_ROOT_CA_PATH";
+pub const ENV_ENGINE_ID: &str = "CRATE_SPEC_ENGINE_ID";
+pub const ENV_ENGINE_KEY_ID: &str = "CRATE_SPEC_ENGINE_KEY_ID";
+pub const ENV_ENCODE_INPUT_PATH: &str = "CRATE_SPEC_ENCODE_INPUT_PATH";
+pub const ENV_ENCODE_OUTPUT_PATH: &str = "CRATE_SPEC_ENCODE_OUTPUT_PATH";
+pub const ENV_DECODE_INPUT_PATH: &str = "CRATE_SPEC_DECODE_INPUT_PATH";
+pub const ENV_DECODE_OUTPUT_PATH: &str = "CRATE_SPEC_DECODE_OUTPUT_PATH";
+
+/// [net] 段必填的环境变量
+const ENV_NET_REQUIRED: [&str; 4] = [ENV_PKI_BASE_URL, ENV_ALGO, ENV_FLOW, ENV_KEY_PAIR_PATH];
+
+/// 检查 PKI 请求超时时间在 1 到 [`MAX_HTTP_TIMEOUT_SECS`] 秒之间
+pub fn check_http_timeout_secs(secs: u64) -> Result<(), String> {
+    if !(1..=MAX_HTTP_TIMEOUT_SECS).contains(&secs) {
+        return Err(tr_format!("http_timeout_secs 必须在 1 到 {} 秒之间，实际为 {}", "http_timeout_secs must be between 1 and {} seconds, got {}", MAX_HTTP_TIMEOUT_SECS, secs));
+    }
+    Ok(())
+}
+
+/// 展开字符串中的 `$VAR` 和 `${VAR}`；`$` 后不是变量名时原样保留
+fn expand_env_vars<F: Fn(&str) -> Option<String>>(value: &str, lookup: &F) -> Result<String, String> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, remaining) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| tr_format!("路径 {} 中的 ${{ 缺少对应的 }}", "unterminated ${{ in path {}", value))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) || !name.chars().all(is_name_char) {
+            if after.starts_with('{') {
+                return Err(tr_format!("路径 {} 中的变量名无效: {}", "invalid variable name in path {}: {}", value, name));
+            }
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        let var = lookup(name).ok_or_else(|| tr_format!("路径 {} 引用的环境变量 {} 未设置", "path {} references unset environment variable {}", value, name))?;
+        expanded.push_str(&var);
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
 // 本地签名模式的配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalEncodeConfig {
     pub cert_path: Option<String>,
+    // 已弃用，保留为单个根 CA 的写法，与 root_ca_paths 合并使用
     pub root_ca_path: Option<String>,
+    // 同时信任的多个根 CA 文件（如 CA 迁移期间新旧根 CA 并存）
+    pub root_ca_paths: Option<Vec<String>>,
     pub private_key_path: Option<String>,
     pub output_path: Option<String>,
     pub input_path: Option<String>,
+    // 私钥存放在 OpenSSL engine（如 PKCS#11 HSM）中时使用，替代 private_key_path
+    pub engine_id: Option<String>,
+    pub engine_key_id: Option<String>,
+    // cargo 构建输出目录，未设置时依次使用 CARGO_TARGET_DIR 和 <input_path>/target
+    pub target_dir: Option<String>,
+    // cargo package 超时时间（秒），超时后终止 cargo 进程；未设置时不限时
+    pub package_timeout: Option<u64>,
+    // target/package 中 .crate 文件名的模式，{name}/{version} 为占位符；默认 {name}-{version}.crate
+    pub crate_file_pattern: Option<String>,
+    // 签名内容和指纹使用的摘要算法（sha256/sha384/sha512），默认 sha256
+    pub digest: Option<String>,
+    // 签名者证书的中间 CA 证书（PEM，可包含多张），签名时嵌入
+    pub cert_chain_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalDecodeConfig {
+    // 已弃用，保留为单个根 CA 的写法，与 root_ca_paths 合并使用
     pub root_ca_path: Option<String>,
+    // 同时信任的多个根 CA 文件
+    pub root_ca_paths: Option<Vec<String>>,
     pub output_path: Option<String>,
     pub input_path: Option<String>,
 }
 
+/// 合并 root_ca_paths 和已弃用的 root_ca_path，按出现顺序去重
+fn merge_root_ca_paths(paths: &Option<Vec<String>>, path: &Option<String>) -> Vec<String> {
+    let mut merged: Vec<String> = vec![];
+    for p in paths.iter().flatten().chain(path.iter()) {
+        if !merged.contains(p) {
+            merged.push(p.clone());
+        }
+    }
+    merged
+}
+
+impl LocalEncodeConfig {
+    /// 配置的全部根 CA 文件：root_ca_paths 在前，root_ca_path 在后
+    pub fn merged_root_ca_paths(&self) -> Vec<String> {
+        merge_root_ca_paths(&self.root_ca_paths, &self.root_ca_path)
+    }
+}
+
+impl LocalDecodeConfig {
+    /// 配置的全部根 CA 文件：root_ca_paths 在前，root_ca_path 在后
+    pub fn merged_root_ca_paths(&self) -> Vec<String> {
+        merge_root_ca_paths(&self.root_ca_paths, &self.root_ca_path)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalConfig {
     pub encode: Option<LocalEncodeConfig>,
@@ -36,6 +153,12 @@ pub struct NetworkEncodeConfig {
     pub algo: Option<String>,
     pub flow: Option<String>,
     pub kms: Option<String>,
+    // cargo 构建输出目录，未设置时依次使用 CARGO_TARGET_DIR 和 <input_path>/target
+    pub target_dir: Option<String>,
+    // cargo package 超时时间（秒），超时后终止 cargo 进程；未设置时不限时
+    pub package_timeout: Option<u64>,
+    // target/package 中 .crate 文件名的模式，{name}/{version} 为占位符；默认 {name}-{version}.crate
+    pub crate_file_pattern: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,7 +174,7 @@ pub struct NetworkConfig {
 }
 
 // 网络配置段 [net]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NetConfig {
     pub algo: Option<String>,
     pub flow: Option<String>,
@@ -60,6 +183,68 @@ pub struct NetConfig {
     pub key_pair_path: Option<String>,
     pub retry_times: Option<u32>,
     pub retry_delay: Option<u64>, // 单位：毫秒
+    pub batch_verify_path: Option<String>, // 批量验签接口路径，未配置时逐个验签
+    pub pki_client_cert_path: Option<String>, // mTLS 客户端证书（PEM），需与 pki_client_key_path 同时配置
+    pub pki_client_key_path: Option<String>, // mTLS 客户端私钥（PEM）
+    pub max_response_bytes: Option<u64>, // PKI 响应体大小上限（字节），默认 1 MiB
+    pub total_retry_budget: Option<u32>, // 一次操作中所有 PKI 请求合计的重试次数上限，默认不限制
+    pub http_timeout_secs: Option<u64>, // 单个 PKI 请求的超时时间（秒），1-600，默认 30；命令行 --timeout 优先
+    pub key_pair_ttl_secs: Option<u64>, // 缓存密钥对的有效期（秒），过期后重新获取；默认永不过期
+    pub skip_health_check: Option<bool>, // 网络编码打包前不检查 PKI 平台是否可达（离线测试用），默认 false
+    pub auth_token: Option<String>, // 每个 PKI 请求携带的认证令牌，默认以 `Authorization: Bearer <token>` 发送
+    pub auth_header_name: Option<String>, // 携带令牌的请求头名称，默认 Authorization；其他头名直接发送令牌
+}
+
+// 手动实现 Debug，避免打印配置时泄露 auth_token
+impl std::fmt::Debug for NetConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetConfig")
+            .field("algo", &self.algo)
+            .field("flow", &self.flow)
+            .field("kms", &self.kms)
+            .field("pki_base_url", &self.pki_base_url)
+            .field("key_pair_path", &self.key_pair_path)
+            .field("retry_times", &self.retry_times)
+            .field("retry_delay", &self.retry_delay)
+            .field("batch_verify_path", &self.batch_verify_path)
+            .field("pki_client_cert_path", &self.pki_client_cert_path)
+            .field("pki_client_key_path", &self.pki_client_key_path)
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("total_retry_budget", &self.total_retry_budget)
+            .field("http_timeout_secs", &self.http_timeout_secs)
+            .field("key_pair_ttl_secs", &self.key_pair_ttl_secs)
+            .field("skip_health_check", &self.skip_health_check)
+            .field("auth_token", &self.auth_token.as_ref().map(|_| "<redacted>"))
+            .field("auth_header_name", &self.auth_header_name)
+            .finish()
+    }
+}
+
+// 策略 [policy]：打包时的依赖源策略和验签时的签名者密钥强度要求，未配置时不做限制
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    // 允许的依赖源：crates-io、git、url、registry、p2p，或 git+<host>、url+<host>；为空时不限制
+    #[serde(default)]
+    pub allowed_dep_sources: Vec<String>,
+    // 禁止的 git/url 依赖主机（同时匹配子域名）
+    #[serde(default)]
+    pub denied_dep_hosts: Vec<String>,
+    // 不符合策略时的处理方式：error（默认，打包失败）或 drop（不写入依赖表并警告）
+    pub on_violation: Option<String>,
+    // 验签时签名者 RSA 公钥的最小位数
+    pub min_rsa_bits: Option<u32>,
+    // 验签时允许的签名者 EC 曲线（如 prime256v1、secp384r1、SM2）；为空时不限制
+    #[serde(default)]
+    pub allowed_ec_curves: Vec<String>,
+}
+
+// 编码时写入构建元数据的生成者标识 [producer]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProducerConfig {
+    // 工具标识，例如 "release-pipeline/2.1"
+    pub tool: Option<String>,
+    // 操作者或 CI 任务标识，需同时配置 tool
+    pub operator: Option<String>,
 }
 
 // 主配置结构
@@ -69,6 +254,8 @@ pub struct Config {
     pub network: Option<NetworkConfig>,
     #[serde(rename = "net")]
     pub net: Option<NetConfig>,
+    pub policy: Option<PolicyConfig>,
+    pub producer: Option<ProducerConfig>,
 }
 
 // 为了向后兼容，保留旧的配置结构（用于从 [encode] 和 [decode] 读取）
@@ -92,16 +279,41 @@ pub struct LegacyDecodeConfig {
 pub struct LegacyConfig {
     pub encode: Option<LegacyEncodeConfig>,
     pub decode: Option<LegacyDecodeConfig>,
+    pub policy: Option<PolicyConfig>,
+    pub producer: Option<ProducerConfig>,
+}
+
+/// 从内存中的配置文本加载（如从密钥管理服务取得），与 [`Config::from_file`] 使用相同的新旧格式解析和环境变量展开
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        // Windows 编辑器保存的文件可能带 UTF-8 BOM，toml 解析器不接受
+        let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+        let mut config = Self::parse_content(content)?;
+        config.expand_env_paths_with(|key| std::env::var(key).ok())?;
+        Ok(config)
+    }
 }
 
 impl Config {
     /// 从文件加载配置
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
         let content =
-            fs::read_to_string(path.as_ref()).map_err(|e| format!("无法读取配置文件: {}", e))?;
+            fs::read_to_string(path.as_ref()).map_err(|e| tr_format!("无法读取配置文件: {}", "cannot read config file: {}", e))?;
+        Self::from_str(&content)
+    }
+
+    /// 序列化为 TOML 文本（新格式），旧格式加载的配置会写成 [local.encode] / [local.decode]
+    pub fn to_toml_string(&self) -> Result<String, String> {
+        toml::to_string(self).map_err(|e| tr_format!("无法序列化配置: {}", "cannot serialize config: {}", e))
+    }
 
+    /// 解析配置文件内容，支持新格式和旧格式
+    fn parse_content(content: &str) -> Result<Self, String> {
         // 首先尝试解析新格式 [local.encode] 和 [local.decode]
-        match toml::from_str::<Config>(&content) {
+        match toml::from_str::<Config>(content) {
             Ok(config) => {
                 // 如果成功解析，检查是否有 local 配置
                 if config.local.is_some() {
@@ -115,19 +327,28 @@ impl Config {
         }
 
         // 尝试解析旧格式 [encode] 和 [decode]（向后兼容）
-        match toml::from_str::<LegacyConfig>(&content) {
+        match toml::from_str::<LegacyConfig>(content) {
             Ok(legacy) => {
                 // 将旧格式转换为新格式
                 let local = LocalConfig {
                     encode: legacy.encode.map(|e| LocalEncodeConfig {
                         cert_path: e.cert_path,
                         root_ca_path: e.root_ca_path,
+                        root_ca_paths: None,
                         private_key_path: e.private_key_path,
                         output_path: e.output_path,
                         input_path: e.input_path,
+                        engine_id: None,
+                        engine_key_id: None,
+                        target_dir: None,
+                        package_timeout: None,
+                        crate_file_pattern: None,
+                        digest: None,
+                        cert_chain_path: None,
                     }),
                     decode: legacy.decode.map(|d| LocalDecodeConfig {
                         root_ca_path: d.root_ca_path,
+                        root_ca_paths: None,
                         output_path: d.output_path,
                         input_path: d.input_path,
                     }),
@@ -136,17 +357,201 @@ impl Config {
                     local: Some(local),
                     network: None,
                     net: None,
+                    policy: legacy.policy,
+                    producer: legacy.producer,
                 })
             }
-            Err(e) => Err(format!("解析配置文件失败: {}", e)),
+            Err(e) => Err(tr_format!("解析配置文件失败: {}", "failed to parse config file: {}", e)),
         }
     }
 
+    /// 展开所有路径字段和 `auth_token` 中的 `$VAR` 和 `${VAR}`，引用的变量未设置时返回错误
+    pub fn expand_env_paths_with<F: Fn(&str) -> Option<String>>(&mut self, lookup: F) -> Result<(), String> {
+        let lookup = &lookup;
+        let expand = |field: &mut Option<String>| -> Result<(), String> {
+            if let Some(value) = field {
+                *value = expand_env_vars(value, lookup)?;
+            }
+            Ok(())
+        };
+        if let Some(local) = &mut self.local {
+            if let Some(encode) = &mut local.encode {
+                expand(&mut encode.cert_path)?;
+                expand(&mut encode.root_ca_path)?;
+                for path in encode.root_ca_paths.iter_mut().flatten() {
+                    *path = expand_env_vars(path, lookup)?;
+                }
+                expand(&mut encode.private_key_path)?;
+                expand(&mut encode.output_path)?;
+                expand(&mut encode.input_path)?;
+                expand(&mut encode.target_dir)?;
+                expand(&mut encode.cert_chain_path)?;
+            }
+            if let Some(decode) = &mut local.decode {
+                expand(&mut decode.root_ca_path)?;
+                for path in decode.root_ca_paths.iter_mut().flatten() {
+                    *path = expand_env_vars(path, lookup)?;
+                }
+                expand(&mut decode.output_path)?;
+                expand(&mut decode.input_path)?;
+            }
+        }
+        if let Some(network) = &mut self.network {
+            if let Some(encode) = &mut network.encode {
+                expand(&mut encode.input_path)?;
+                expand(&mut encode.output_path)?;
+                expand(&mut encode.key_pair_path)?;
+                expand(&mut encode.target_dir)?;
+            }
+            if let Some(decode) = &mut network.decode {
+                expand(&mut decode.input_path)?;
+                expand(&mut decode.output_path)?;
+            }
+        }
+        if let Some(net) = &mut self.net {
+            expand(&mut net.key_pair_path)?;
+            expand(&mut net.pki_client_cert_path)?;
+            expand(&mut net.pki_client_key_path)?;
+            // 令牌可以写成 `$PKI_TOKEN`，不必明文保存在配置文件中
+            expand(&mut net.auth_token)?;
+        }
+        Ok(())
+    }
+
     /// 从默认配置文件加载
     pub fn from_default() -> Result<Self, String> {
         Self::from_file(Path::new(DEFAULT_CONFIG_PATH))
     }
 
+    /// 完全从环境变量构建配置（不读取任何配置文件）
+    ///
+    /// 只要设置了任一 `[net]` 相关变量，就会构建 `[net]` 段，此时
+    /// `CRATE_SPEC_PKI_BASE_URL`、`CRATE_SPEC_ALGO`、`CRATE_SPEC_FLOW`、
+    /// `CRATE_SPEC_KEY_PAIR_PATH` 均为必填，缺失时返回的错误会列出所有缺失项。
+    /// 编解码路径变量同时用于本地模式和网络模式的 encode/decode 段。
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// 使用自定义的变量查询函数构建配置，便于测试
+    pub fn from_env_with<F: Fn(&str) -> Option<String>>(lookup: F) -> Result<Self, String> {
+        let var = |key: &str| lookup(key).filter(|v| !v.is_empty());
+
+        let mut errors = vec![];
+        let retry_times = match var(ENV_RETRY_TIMES) {
+            Some(v) => match v.parse::<u32>() {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    errors.push(tr_format!("{} 不是有效的整数: {}", "{} is not a valid integer: {}", ENV_RETRY_TIMES, v));
+                    None
+                }
+            },
+            None => None,
+        };
+        let retry_delay = match var(ENV_RETRY_DELAY) {
+            Some(v) => match v.parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    errors.push(tr_format!("{} 不是有效的整数: {}", "{} is not a valid integer: {}", ENV_RETRY_DELAY, v));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let net_keys = [
+            ENV_KMS,
+            ENV_RETRY_TIMES,
+            ENV_RETRY_DELAY,
+            ENV_BATCH_VERIFY_PATH,
+            ENV_PKI_CLIENT_CERT_PATH,
+            ENV_PKI_CLIENT_KEY_PATH,
+            ENV_AUTH_TOKEN,
+        ];
+        let has_net = ENV_NET_REQUIRED.iter().chain(net_keys.iter()).any(|k| var(k).is_some());
+        let net = if has_net {
+            let missing: Vec<&str> = ENV_NET_REQUIRED
+                .iter()
+                .filter(|k| var(k).is_none())
+                .copied()
+                .collect();
+            if !missing.is_empty() {
+                errors.push(tr_format!("缺少必需的环境变量: {}", "missing required environment variables: {}", missing.join(", ")));
+            }
+            Some(NetConfig {
+                algo: var(ENV_ALGO),
+                flow: var(ENV_FLOW),
+                kms: var(ENV_KMS),
+                pki_base_url: var(ENV_PKI_BASE_URL),
+                key_pair_path: var(ENV_KEY_PAIR_PATH),
+                retry_times,
+                retry_delay,
+                batch_verify_path: var(ENV_BATCH_VERIFY_PATH),
+                pki_client_cert_path: var(ENV_PKI_CLIENT_CERT_PATH),
+                pki_client_key_path: var(ENV_PKI_CLIENT_KEY_PATH),
+                max_response_bytes: None,
+                total_retry_budget: None,
+                http_timeout_secs: None,
+                key_pair_ttl_secs: None,
+                skip_health_check: None,
+                auth_token: var(ENV_AUTH_TOKEN),
+                auth_header_name: None,
+            })
+        } else {
+            None
+        };
+
+        if !errors.is_empty() {
+            return Err(tr_format!("从环境变量构建配置失败: {}", "failed to build config from environment: {}", errors.join("; ")));
+        }
+
+        Ok(Config {
+            local: Some(LocalConfig {
+                encode: Some(LocalEncodeConfig {
+                    cert_path: var(ENV_CERT_PATH),
+                    root_ca_path: var(ENV_ROOT_CA_PATH),
+                    root_ca_paths: None,
+                    private_key_path: var(ENV_PRIVATE_KEY_PATH),
+                    output_path: var(ENV_ENCODE_OUTPUT_PATH),
+                    input_path: var(ENV_ENCODE_INPUT_PATH),
+                    engine_id: var(ENV_ENGINE_ID),
+                    engine_key_id: var(ENV_ENGINE_KEY_ID),
+                    target_dir: None,
+                    package_timeout: None,
+                    crate_file_pattern: None,
+                    digest: None,
+                    cert_chain_path: var(ENV_CERT_CHAIN_PATH),
+                }),
+                decode: Some(LocalDecodeConfig {
+                    root_ca_path: var(ENV_ROOT_CA_PATH),
+                    root_ca_paths: None,
+                    output_path: var(ENV_DECODE_OUTPUT_PATH),
+                    input_path: var(ENV_DECODE_INPUT_PATH),
+                }),
+            }),
+            network: Some(NetworkConfig {
+                encode: Some(NetworkEncodeConfig {
+                    input_path: var(ENV_ENCODE_INPUT_PATH),
+                    output_path: var(ENV_ENCODE_OUTPUT_PATH),
+                    key_pair_path: None,
+                    algo: None,
+                    flow: None,
+                    kms: None,
+                    target_dir: None,
+                    package_timeout: None,
+                    crate_file_pattern: None,
+                }),
+                decode: Some(NetworkDecodeConfig {
+                    input_path: var(ENV_DECODE_INPUT_PATH),
+                    output_path: var(ENV_DECODE_OUTPUT_PATH),
+                }),
+            }),
+            net,
+            policy: None,
+            producer: None,
+        })
+    }
+
     /// 获取本地编码配置
     pub fn get_local_encode_config(&self) -> Option<&LocalEncodeConfig> {
         self.local.as_ref()?.encode.as_ref()
@@ -192,24 +597,24 @@ impl Config {
             if let Some(encode) = &local.encode {
                 if let Some(cert_path) = &encode.cert_path {
                     if !Path::new(cert_path).exists() {
-                        return Err(format!("证书文件不存在: {}", cert_path));
+                        return Err(tr_format!("证书文件不存在: {}", "certificate file does not exist: {}", cert_path));
                     }
                 }
                 if let Some(pkey_path) = &encode.private_key_path {
                     if !Path::new(pkey_path).exists() {
-                        return Err(format!("私钥文件不存在: {}", pkey_path));
+                        return Err(tr_format!("私钥文件不存在: {}", "private key file does not exist: {}", pkey_path));
                     }
                 }
-                if let Some(root_ca_path) = &encode.root_ca_path {
-                    if !Path::new(root_ca_path).exists() {
-                        return Err(format!("根CA文件不存在: {}", root_ca_path));
+                for root_ca_path in encode.merged_root_ca_paths() {
+                    if !Path::new(&root_ca_path).exists() {
+                        return Err(tr_format!("根CA文件不存在: {}", "root CA file does not exist: {}", root_ca_path));
                     }
                 }
             }
             if let Some(decode) = &local.decode {
-                if let Some(root_ca_path) = &decode.root_ca_path {
-                    if !Path::new(root_ca_path).exists() {
-                        return Err(format!("根CA文件不存在: {}", root_ca_path));
+                for root_ca_path in decode.merged_root_ca_paths() {
+                    if !Path::new(&root_ca_path).exists() {
+                        return Err(tr_format!("根CA文件不存在: {}", "root CA file does not exist: {}", root_ca_path));
                     }
                 }
             }
@@ -220,35 +625,50 @@ impl Config {
             // 验证 URL 格式
             if let Some(url) = &net.pki_base_url {
                 if !url.starts_with("http://") && !url.starts_with("https://") {
-                    return Err(format!("无效的 PKI URL: {}", url));
+                    return Err(tr_format!("无效的 PKI URL: {}", "invalid PKI URL: {}", url));
                 }
             }
 
             // 验证重试次数范围
             if let Some(retry_times) = net.retry_times {
                 if retry_times == 0 {
-                    return Err("重试次数不能为 0".to_string());
+                    return Err(tr("重试次数不能为 0", "retry_times must not be 0").to_string());
                 }
                 if retry_times > 100 {
-                    return Err("重试次数不能超过 100".to_string());
+                    return Err(tr("重试次数不能超过 100", "retry_times must not exceed 100").to_string());
                 }
             }
 
             // 验证重试延迟范围
             if let Some(retry_delay) = net.retry_delay {
                 if retry_delay == 0 {
-                    return Err("重试延迟不能为 0".to_string());
+                    return Err(tr("重试延迟不能为 0", "retry_delay must not be 0").to_string());
                 }
                 if retry_delay > 60000 {
-                    return Err("重试延迟不能超过 60000 毫秒".to_string());
+                    return Err(tr("重试延迟不能超过 60000 毫秒", "retry_delay must not exceed 60000 ms").to_string());
                 }
             }
 
-            // 验证密钥对路径
+            if let Some(secs) = net.http_timeout_secs {
+                check_http_timeout_secs(secs)?;
+            }
+
+            if net.key_pair_ttl_secs == Some(0) {
+                return Err(tr("key_pair_ttl_secs 不能为 0", "key_pair_ttl_secs must not be 0").to_string());
+            }
+
+            match (&net.auth_token, &net.auth_header_name) {
+                (Some(token), _) if token.is_empty() => return Err(tr("auth_token 不能为空", "auth_token must not be empty").to_string()),
+                (None, Some(_)) => return Err(tr("配置了 auth_header_name 但缺少 auth_token", "auth_header_name is set but auth_token is missing").to_string()),
+                _ => {}
+            }
+
+            // 验证密钥对路径：目录不存在时由 KeyPair::save_to_file 创建，首次运行不应被拦截；
+            // 只有同名路径已被普通文件占用时才无法创建
             if let Some(key_pair_path) = &net.key_pair_path {
                 if let Some(parent) = Path::new(key_pair_path).parent() {
-                    if !parent.exists() {
-                        return Err(format!("密钥对文件目录不存在: {}", parent.display()));
+                    if parent.exists() && !parent.is_dir() {
+                        return Err(tr_format!("密钥对文件目录不是目录: {}", "key pair file directory is not a directory: {}", parent.display()));
                     }
                 }
             }
@@ -269,17 +689,29 @@ mod tests {
                 encode: Some(LocalEncodeConfig {
                     cert_path: Some("test/cert.pem".to_string()),
                     root_ca_path: Some("test/root-ca.pem".to_string()),
+                    root_ca_paths: None,
                     private_key_path: Some("test/key.pem".to_string()),
                     output_path: Some("test/output/".to_string()),
                     input_path: Some("../crate-spec".to_string()),
+                    engine_id: None,
+                    engine_key_id: None,
+                    target_dir: None,
+                    package_timeout: None,
+                    crate_file_pattern: None,
+                    digest: None,
+                    cert_chain_path: None,
                 }),
                 decode: Some(LocalDecodeConfig {
                     root_ca_path: Some("test/root-ca.pem".to_string()),
+                    root_ca_paths: None,
                     output_path: Some("test/output/".to_string()),
                     input_path: Some("test/output/crate-spec-0.1.0.scrate".to_string()),
                 }),
             }),
             network: None,
+            net: None,
+            policy: None,
+            producer: None,
         };
 
         let toml_str = toml::to_string(&config).unwrap();
@@ -334,12 +766,21 @@ input_path = "test/output/crate-spec-0.1.0.scrate"
             encode: legacy.encode.map(|e| LocalEncodeConfig {
                 cert_path: e.cert_path,
                 root_ca_path: e.root_ca_path,
+                root_ca_paths: None,
                 private_key_path: e.private_key_path,
                 output_path: e.output_path,
                 input_path: e.input_path,
+                engine_id: None,
+                engine_key_id: None,
+                target_dir: None,
+                package_timeout: None,
+                crate_file_pattern: None,
+                digest: None,
+                cert_chain_path: None,
             }),
             decode: legacy.decode.map(|d| LocalDecodeConfig {
                 root_ca_path: d.root_ca_path,
+                root_ca_paths: None,
                 output_path: d.output_path,
                 input_path: d.input_path,
             }),
@@ -347,6 +788,9 @@ input_path = "test/output/crate-spec-0.1.0.scrate"
         let config = Config {
             local: Some(local),
             network: None,
+            net: None,
+            policy: None,
+            producer: None,
         };
         
         assert!(config.local.is_some());
@@ -355,4 +799,211 @@ input_path = "test/output/crate-spec-0.1.0.scrate"
         let encode = config.get_local_encode_config().unwrap();
         assert_eq!(encode.cert_path.as_ref().unwrap(), "test/cert.pem");
     }
+
+    fn env_lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: std::collections::HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| map.get(key).cloned()
+    }
+
+    #[test]
+    fn test_config_from_env_full_net() {
+        let lookup = env_lookup(&[
+            (ENV_PKI_BASE_URL, "https://pki.example.com"),
+            (ENV_ALGO, "sm2"),
+            (ENV_FLOW, "classic"),
+            (ENV_KEY_PAIR_PATH, "config/keypair.bin"),
+            (ENV_RETRY_TIMES, "5"),
+            (ENV_RETRY_DELAY, "200"),
+            (ENV_AUTH_TOKEN, "s3cret-token"),
+            (ENV_ENCODE_INPUT_PATH, "../crate-spec"),
+            (ENV_ENCODE_OUTPUT_PATH, "test/output/"),
+            (ENV_DECODE_INPUT_PATH, "test/output/crate-spec-0.2.0.scrate"),
+            (ENV_DECODE_OUTPUT_PATH, "test/output/"),
+        ]);
+        let config = Config::from_env_with(lookup).unwrap();
+
+        let net = config.get_net_config().unwrap();
+        assert_eq!(net.pki_base_url.as_deref(), Some("https://pki.example.com"));
+        assert_eq!(net.algo.as_deref(), Some("sm2"));
+        assert_eq!(net.flow.as_deref(), Some("classic"));
+        assert_eq!(net.kms, None);
+        assert_eq!(net.key_pair_path.as_deref(), Some("config/keypair.bin"));
+        assert_eq!(net.retry_times, Some(5));
+        assert_eq!(net.retry_delay, Some(200));
+        assert_eq!(net.batch_verify_path, None);
+        assert_eq!(net.auth_token.as_deref(), Some("s3cret-token"));
+        // 令牌不出现在 Debug 输出中
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("s3cret-token") && debug.contains("<redacted>"), "{}", debug);
+
+        let encode = config.get_network_encode_config().unwrap();
+        assert_eq!(encode.input_path.as_deref(), Some("../crate-spec"));
+        let decode = config.get_network_decode_config().unwrap();
+        assert_eq!(decode.input_path.as_deref(), Some("test/output/crate-spec-0.2.0.scrate"));
+        assert_eq!(decode.output_path.as_deref(), Some("test/output/"));
+    }
+
+    #[test]
+    fn test_validate_key_pair_dir_not_created_yet() {
+        // config.rs 同时编译进库和二进制，这里直接使用 tempfile 而不是 utils::testing
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().join("keypair");
+        let net = |path: &Path| NetConfig {
+            pki_base_url: Some("https://pki.example.com".to_string()),
+            algo: Some("sm2".to_string()),
+            flow: Some("classic".to_string()),
+            kms: None,
+            key_pair_path: Some(path.to_string_lossy().into_owned()),
+            retry_times: None,
+            retry_delay: None,
+            batch_verify_path: None,
+            pki_client_cert_path: None,
+            pki_client_key_path: None,
+            max_response_bytes: None,
+            total_retry_budget: None,
+            http_timeout_secs: None,
+            key_pair_ttl_secs: None,
+            skip_health_check: None,
+            auth_token: None,
+            auth_header_name: None,
+        };
+
+        // 首次运行：目录尚不存在，验证通过且不产生副作用
+        let key_pair_path = base.join("nested").join("keypair.bin");
+        let config = Config { local: None, network: None, net: Some(net(&key_pair_path)), policy: None, producer: None };
+        assert!(config.validate().is_ok());
+        assert!(!base.exists());
+
+        // 同名路径被普通文件占用，目录无法创建
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::write(base.join("nested"), b"").unwrap();
+        let config = Config { local: None, network: None, net: Some(net(&key_pair_path)), policy: None, producer: None };
+        assert!(config.validate().unwrap_err().contains("不是目录"));
+    }
+
+    #[test]
+    fn test_config_from_env_missing_required() {
+        let lookup = env_lookup(&[(ENV_PKI_BASE_URL, "https://pki.example.com")]);
+        let err = Config::from_env_with(lookup).unwrap_err();
+        assert!(err.contains(ENV_ALGO));
+        assert!(err.contains(ENV_FLOW));
+        assert!(err.contains(ENV_KEY_PAIR_PATH));
+        assert!(!err.contains(ENV_PKI_BASE_URL));
+
+        let lookup = env_lookup(&[(ENV_RETRY_TIMES, "many")]);
+        let err = Config::from_env_with(lookup).unwrap_err();
+        assert!(err.contains(ENV_RETRY_TIMES));
+    }
+
+    #[test]
+    fn test_config_from_env_without_net() {
+        let lookup = env_lookup(&[
+            (ENV_CERT_PATH, "test/cert.pem"),
+            (ENV_ROOT_CA_PATH, "test/root-ca.pem"),
+        ]);
+        let config = Config::from_env_with(lookup).unwrap();
+        assert!(config.get_net_config().is_none());
+        let encode = config.get_local_encode_config().unwrap();
+        assert_eq!(encode.cert_path.as_deref(), Some("test/cert.pem"));
+        let decode = config.get_local_decode_config().unwrap();
+        assert_eq!(decode.root_ca_path.as_deref(), Some("test/root-ca.pem"));
+    }
+
+    #[test]
+    fn test_config_from_file_with_bom() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("bom.toml");
+        fs::write(
+            &path,
+            "\u{feff}[local.encode]\ncert_path = \"test/cert.pem\"\n\n[local.decode]\nroot_ca_path = \"test/root-ca.pem\"\n",
+        )
+        .unwrap();
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.get_local_encode_config().unwrap().cert_path.as_deref(), Some("test/cert.pem"));
+        assert_eq!(config.get_local_decode_config().unwrap().root_ca_path.as_deref(), Some("test/root-ca.pem"));
+    }
+
+    #[test]
+    fn test_config_merges_root_ca_paths() {
+        let config: Config = toml::from_str(
+            r#"
+[local.encode]
+root_ca_paths = ["test/chain/root-ca.pem"]
+
+[local.decode]
+root_ca_paths = ["test/chain/root-ca.pem", "test/root-ca.pem"]
+root_ca_path = "test/root-ca.pem"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.get_local_encode_config().unwrap().merged_root_ca_paths(), vec!["test/chain/root-ca.pem"]);
+        // 已弃用的 root_ca_path 排在后面，与 root_ca_paths 重复时只保留一次
+        assert_eq!(
+            config.get_local_decode_config().unwrap().merged_root_ca_paths(),
+            vec!["test/chain/root-ca.pem", "test/root-ca.pem"]
+        );
+        assert!(config.validate().is_ok());
+
+        let config: Config =
+            toml::from_str("[local.decode]\nroot_ca_paths = [\"test/root-ca.pem\", \"test/missing-ca.pem\"]\n").unwrap();
+        assert!(config.validate().unwrap_err().contains("test/missing-ca.pem"));
+    }
+
+    #[test]
+    fn test_config_expands_env_vars_in_paths() {
+        let mut config = Config::parse_content(
+            "[local.encode]\ncert_path = \"$CERT_DIR/leaf.pem\"\nroot_ca_paths = [\"${CERT_DIR}/root.pem\"]\noutput_path = \"out/$5/\"\n",
+        )
+        .unwrap();
+        config.expand_env_paths_with(env_lookup(&[("CERT_DIR", "/secrets")])).unwrap();
+        let encode = config.get_local_encode_config().unwrap();
+        assert_eq!(encode.cert_path.as_deref(), Some("/secrets/leaf.pem"));
+        assert_eq!(encode.merged_root_ca_paths(), vec!["/secrets/root.pem".to_string()]);
+        // $ 后不是变量名时原样保留
+        assert_eq!(encode.output_path.as_deref(), Some("out/$5/"));
+
+        let mut config: Config = toml::from_str("[net]\nauth_token = \"${PKI_TOKEN}\"\n").unwrap();
+        config.expand_env_paths_with(env_lookup(&[("PKI_TOKEN", "tok")])).unwrap();
+        assert_eq!(config.get_net_config().unwrap().auth_token.as_deref(), Some("tok"));
+
+        let mut config = Config::parse_content("[local.decode]\nroot_ca_path = \"${MISSING}/root.pem\"\n").unwrap();
+        let err = config.expand_env_paths_with(env_lookup(&[])).unwrap_err();
+        assert!(err.contains("MISSING"));
+
+        let mut config = Config::parse_content("[local.decode]\nroot_ca_path = \"${CERT_DIR/root.pem\"\n").unwrap();
+        assert!(config.expand_env_paths_with(env_lookup(&[("CERT_DIR", "/secrets")])).is_err());
+    }
+
+    #[test]
+    fn test_config_from_str_matches_from_file() {
+        let legacy = "[encode]\ncert_path = \"test/cert.pem\"\n\n[decode]\nroot_ca_path = \"test/root-ca.pem\"\n\n[policy]\nallowed_dep_sources = [\"crates-io\"]\n";
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("legacy.toml");
+        fs::write(&path, legacy).unwrap();
+
+        // 旧格式经 from_str 和 from_file 转换后的结果一致
+        let from_str = Config::from_str(legacy).unwrap();
+        let from_file = Config::from_file(&path).unwrap();
+        assert_eq!(from_str.to_toml_string().unwrap(), from_file.to_toml_string().unwrap());
+        assert_eq!(from_str.get_local_encode_config().unwrap().cert_path.as_deref(), Some("test/cert.pem"));
+
+        // 序列化为新格式后可以原样读回
+        let toml_str = from_str.to_toml_string().unwrap();
+        assert!(toml_str.contains("[local.encode]") && !toml_str.contains("[encode]"), "{}", toml_str);
+        let reparsed: Config = toml_str.parse().unwrap();
+        assert_eq!(reparsed.to_toml_string().unwrap(), toml_str);
+
+        assert!(Config::from_str("[local.encode\n").is_err());
+    }
+
+    #[test]
+    fn test_config_validates_auth_token() {
+        let parse = |net: &str| Config::parse_content(&format!("[local.decode]\n\n[net]\n{}", net)).unwrap();
+        assert!(parse("auth_token = \"tok\"\nauth_header_name = \"X-Api-Key\"\n").validate().is_ok());
+        assert!(parse("auth_token = \"\"\n").validate().unwrap_err().contains("auth_token"));
+        assert!(parse("auth_header_name = \"X-Api-Key\"\n").validate().unwrap_err().contains("auth_header_name"));
+    }
 }
@@ -1,36 +1,133 @@
-use crate::config::{Config, NetConfig};
+use crate::config::{check_http_timeout_secs, Config, NetConfig};
 use crate_spec::error::{Result, CrateSpecError};
-use crate_spec::network::{BaseConfig, PkiClient, KeyPair};
+use crate_spec::network::{load_client_identity, AuthHeader, BaseConfig, PkiClient, KeyPair, RetryBudget};
+use crate_spec::utils::pkcs::KeyStrengthPolicy;
+use crate_spec::utils::policy::DepSourcePolicy;
+use crate_spec::utils::storage::FsStorage;
+use crate_spec::locale::tr;
+use crate_spec::tr_format;
+use reqwest::Identity;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// 网络配置扩展方法
 impl Config {
+    /// 由 [policy] 段构建打包时的依赖源策略，未配置时允许所有来源
+    pub fn dep_source_policy(&self) -> Result<DepSourcePolicy> {
+        let Some(policy) = self.policy.as_ref() else {
+            return Ok(DepSourcePolicy::default());
+        };
+        let invalid = |e: CrateSpecError| CrateSpecError::ConfigError(tr_format!("[policy] 配置错误: {}", "invalid [policy] setting: {}", e));
+        Ok(DepSourcePolicy {
+            allowed_dep_sources: policy.allowed_dep_sources.iter()
+                .map(|source| source.parse())
+                .collect::<Result<_>>()
+                .map_err(invalid)?,
+            denied_dep_hosts: policy.denied_dep_hosts.clone(),
+            on_violation: policy.on_violation.as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(invalid)?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// 由 [policy] 段构建验签时的签名者密钥强度要求，未配置时不限制
+    pub fn key_strength_policy(&self) -> KeyStrengthPolicy {
+        self.policy
+            .as_ref()
+            .map(|policy| KeyStrengthPolicy {
+                min_rsa_bits: policy.min_rsa_bits,
+                allowed_ec_curves: policy.allowed_ec_curves.clone(),
+            })
+            .unwrap_or_default()
+    }
+
     /// 获取网络配置，如果不存在则返回错误
     pub fn require_net_config(&self) -> Result<&NetConfig> {
         self.get_net_config()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 [net] 配置段".to_string()))
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 [net] 配置段", "the config file is missing the [net] table").to_string()))
     }
 
     /// 创建 PKI 客户端
     pub fn create_pki_client(&self) -> Result<PkiClient> {
         let net_config = self.require_net_config()?;
         let pki_base_url = net_config.pki_base_url.as_ref()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 pki_base_url".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 pki_base_url", "the config file is missing pki_base_url").to_string()))?;
         let retry_times = net_config.retry_times.unwrap_or(crate_spec::network::DEFAULT_RETRY_TIMES);
         let retry_delay = net_config.retry_delay.unwrap_or(crate_spec::network::DEFAULT_RETRY_DELAY_MS);
+        let timeout = self.http_timeout()?;
         
-        PkiClient::new(pki_base_url.clone(), retry_times, retry_delay)
-            .map_err(|e| CrateSpecError::NetworkError(e))
+        let mut client = PkiClient::new(pki_base_url.clone(), retry_times, retry_delay)
+            .and_then(|client| {
+                client
+                    .with_batch_verify_path(net_config.batch_verify_path.clone())
+                    .with_max_response_bytes(self.max_response_bytes())
+                    .with_retry_budget(net_config.total_retry_budget.map(RetryBudget::new))
+                    .with_timeout(timeout)
+            })
+            .map_err(|e| CrateSpecError::NetworkError(e.into()))?;
+        if let Some(identity) = self.pki_client_identity()? {
+            client = client.with_identity(&identity).map_err(|e| CrateSpecError::NetworkError(e.into()))?;
+        }
+        Ok(client.with_auth(self.pki_auth_header()?))
+    }
+
+    /// PKI 请求的认证头，未配置 auth_token 时返回 None
+    pub fn pki_auth_header(&self) -> Result<Option<AuthHeader>> {
+        let net_config = self.require_net_config()?;
+        net_config
+            .auth_token
+            .as_deref()
+            .map(|token| AuthHeader::new(token, net_config.auth_header_name.as_deref()))
+            .transpose()
+            .map_err(CrateSpecError::ConfigError)
+    }
+
+    /// 网络编码打包前是否检查 PKI 平台可达，`[net] skip_health_check = true` 时不检查
+    pub fn pki_health_check_enabled(&self) -> bool {
+        self.get_net_config().and_then(|net| net.skip_health_check) != Some(true)
+    }
+
+    /// PKI 响应体大小上限，未配置时使用默认值
+    pub fn max_response_bytes(&self) -> u64 {
+        self.get_net_config()
+            .and_then(|net| net.max_response_bytes)
+            .unwrap_or(crate_spec::network::DEFAULT_MAX_RESPONSE_BYTES)
+    }
+
+    /// 单个 PKI 请求的超时时间，未配置时使用默认值
+    pub fn http_timeout(&self) -> Result<Duration> {
+        let secs = self
+            .get_net_config()
+            .and_then(|net| net.http_timeout_secs)
+            .unwrap_or(crate_spec::network::DEFAULT_HTTP_TIMEOUT_SECS);
+        check_http_timeout_secs(secs).map_err(CrateSpecError::ConfigError)?;
+        Ok(Duration::from_secs(secs))
+    }
+
+    /// 加载 mTLS 客户端身份，未配置时返回 None
+    pub fn pki_client_identity(&self) -> Result<Option<Identity>> {
+        let net_config = self.require_net_config()?;
+        match (&net_config.pki_client_cert_path, &net_config.pki_client_key_path) {
+            (Some(cert_path), Some(key_path)) => load_client_identity(cert_path, key_path)
+                .map(Some)
+                .map_err(CrateSpecError::ConfigError),
+            (None, None) => Ok(None),
+            _ => Err(CrateSpecError::ConfigError(
+                tr("pki_client_cert_path 与 pki_client_key_path 必须同时配置", "pki_client_cert_path and pki_client_key_path must be configured together").to_string(),
+            )),
+        }
     }
 
     /// 创建 BaseConfig
     pub fn create_base_config(&self) -> Result<BaseConfig> {
         let net_config = self.require_net_config()?;
         let algo = net_config.algo.as_ref()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 algo".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 algo", "the config file is missing algo").to_string()))?;
         let flow = net_config.flow.as_ref()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 flow".to_string()))?;
-        let kms = net_config.kms.as_ref().map(|s| s.as_str()).unwrap_or("");
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 flow", "the config file is missing flow").to_string()))?;
+        let kms = net_config.kms.as_deref().unwrap_or("");
         
         Ok(BaseConfig {
             algo: algo.clone(),
@@ -43,14 +140,77 @@ impl Config {
     pub fn get_or_fetch_keypair(&self) -> Result<Arc<KeyPair>> {
         let net_config = self.require_net_config()?;
         let pki_base_url = net_config.pki_base_url.as_ref()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 pki_base_url".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 pki_base_url", "the config file is missing pki_base_url").to_string()))?;
         let key_pair_path = net_config.key_pair_path.as_ref()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 key_pair_path".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 key_pair_path", "the config file is missing key_pair_path").to_string()))?;
         let base_config = self.create_base_config()?;
         
-        KeyPair::get_or_fetch(key_pair_path, pki_base_url, &base_config)
-            .map(|kp| Arc::new(kp))
-            .map_err(|e| CrateSpecError::PkiError(e))
+        let identity = self.pki_client_identity()?;
+        let auth = self.pki_auth_header()?;
+        let max_response_bytes = self.max_response_bytes();
+        let timeout = self.http_timeout()?;
+
+        KeyPair::get_or_fetch(&FsStorage, key_pair_path, net_config.key_pair_ttl_secs, || {
+            KeyPair::fetch_from_pki(pki_base_url, &base_config, identity.as_ref(), auth.as_ref(), max_response_bytes, timeout)
+        })
+            .map(Arc::new)
+            .map_err(CrateSpecError::PkiError)
     }
 }
 
+
+#[test]
+fn test_pki_client_identity_config_errors() {
+    let mut config: Config = toml::from_str(
+        "[net]\npki_base_url = \"https://pki.example.com\"\npki_client_cert_path = \"test/cert.pem\"\n",
+    )
+    .unwrap();
+    assert!(matches!(config.pki_client_identity(), Err(CrateSpecError::ConfigError(_))));
+
+    let net = config.net.as_mut().unwrap();
+    net.pki_client_key_path = Some("test/no-such-key.pem".to_string());
+    assert!(matches!(config.create_pki_client(), Err(CrateSpecError::ConfigError(_))));
+
+    config.net.as_mut().unwrap().pki_client_key_path = Some("test/key.pem".to_string());
+    assert!(config.pki_client_identity().unwrap().is_some());
+    assert!(config.create_pki_client().is_ok());
+}
+
+#[test]
+fn test_http_timeout_from_config() {
+    let mut config: Config = toml::from_str("[net]\npki_base_url = \"https://pki.example.com\"\n").unwrap();
+    assert_eq!(config.http_timeout().unwrap(), Duration::from_secs(crate_spec::network::DEFAULT_HTTP_TIMEOUT_SECS));
+    assert!(config.validate().is_ok());
+
+    config.net.as_mut().unwrap().http_timeout_secs = Some(90);
+    assert_eq!(config.http_timeout().unwrap(), Duration::from_secs(90));
+    assert!(config.create_pki_client().is_ok());
+
+    for secs in [0, 601] {
+        config.net.as_mut().unwrap().http_timeout_secs = Some(secs);
+        assert!(matches!(config.http_timeout(), Err(CrateSpecError::ConfigError(_))));
+        assert!(config.validate().unwrap_err().contains("http_timeout_secs"));
+    }
+}
+
+#[test]
+fn test_dep_source_policy_from_config() {
+    use crate_spec::utils::policy::{DepSourceAction, DepSourceRule};
+
+    let config: Config = toml::from_str("[net]\npki_base_url = \"https://pki.example.com\"\n").unwrap();
+    assert_eq!(config.dep_source_policy().unwrap(), DepSourcePolicy::default());
+
+    let config: Config = toml::from_str(
+        "[policy]\nallowed_dep_sources = [\"crates-io\", \"git+github.com\"]\n\
+         denied_dep_hosts = [\"blocked.example\"]\non_violation = \"drop\"\n",
+    )
+    .unwrap();
+    let policy = config.dep_source_policy().unwrap();
+    assert_eq!(policy.allowed_dep_sources.len(), 2);
+    assert!(matches!(&policy.allowed_dep_sources[1], DepSourceRule::Host(_, host) if host == "github.com"));
+    assert_eq!(policy.denied_dep_hosts, ["blocked.example"]);
+    assert_eq!(policy.on_violation, DepSourceAction::Drop);
+
+    let config: Config = toml::from_str("[policy]\nallowed_dep_sources = [\"svn\"]\n").unwrap();
+    assert!(matches!(config.dep_source_policy(), Err(CrateSpecError::ConfigError(_))));
+}
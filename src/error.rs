@@ -1,7 +1,110 @@
+use crate::locale::{lang, Lang};
 use std::fmt;
 use std::io;
 use std::path::PathBuf;
 
+/// 网络 / PKI 请求失败的细分类别，由 PKI 客户端在请求失败处记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetworkErrorKind {
+    /// 请求超时
+    Timeout,
+    /// 无法建立连接（拒绝连接、DNS 解析失败、TLS 握手失败等）
+    Connection,
+    /// 平台返回了非成功的 HTTP 状态码
+    HttpStatus(u16),
+    /// 其他失败，如响应过大、响应格式错误或本地处理失败
+    Other,
+}
+
+/// 网络 / PKI 错误的细分类别和说明，`source` 保留底层错误（如 `reqwest::Error`）
+#[derive(Debug)]
+pub struct NetworkFailure {
+    pub kind: NetworkErrorKind,
+    pub message: String,
+    pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl NetworkFailure {
+    pub fn new(kind: NetworkErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into(), source: None }
+    }
+
+    /// 附加底层错误，通过 [`std::error::Error::source`] 返回
+    pub fn with_source(mut self, source: impl std::error::Error + Send + Sync + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+}
+
+impl fmt::Display for NetworkFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for NetworkFailure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<String> for NetworkFailure {
+    fn from(message: String) -> Self {
+        Self::new(NetworkErrorKind::Other, message)
+    }
+}
+
+impl From<&str> for NetworkFailure {
+    fn from(message: &str) -> Self {
+        Self::new(NetworkErrorKind::Other, message)
+    }
+}
+
+/// [`CrateSpecError`] 的类别，供调用方按类别匹配，不必解析错误信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CrateSpecErrorKind {
+    Io,
+    FileNotFound,
+    Config,
+    Validation,
+    Network(NetworkErrorKind),
+    Pki(NetworkErrorKind),
+    Signature,
+    Decode,
+    Encode,
+    Parse,
+    CommandFailed,
+    Other,
+}
+
+impl CrateSpecErrorKind {
+    /// 稳定的数字错误码，CLI 以此作为退出码；已分配的值不会改变
+    ///
+    /// 网络和 PKI 错误按细分类别区分：x0 其他、x1 超时、x2 连接失败、x3 HTTP 状态码。
+    pub fn code(self) -> i32 {
+        let network = |base: i32, kind: NetworkErrorKind| match kind {
+            NetworkErrorKind::Other => base,
+            NetworkErrorKind::Timeout => base + 1,
+            NetworkErrorKind::Connection => base + 2,
+            NetworkErrorKind::HttpStatus(_) => base + 3,
+        };
+        match self {
+            CrateSpecErrorKind::Other => 1,
+            CrateSpecErrorKind::Io => 10,
+            CrateSpecErrorKind::FileNotFound => 11,
+            CrateSpecErrorKind::Config => 20,
+            CrateSpecErrorKind::Validation => 21,
+            CrateSpecErrorKind::Parse => 22,
+            CrateSpecErrorKind::Network(kind) => network(30, kind),
+            CrateSpecErrorKind::Pki(kind) => network(40, kind),
+            CrateSpecErrorKind::Signature => 50,
+            CrateSpecErrorKind::Decode => 60,
+            CrateSpecErrorKind::Encode => 61,
+            CrateSpecErrorKind::CommandFailed => 70,
+        }
+    }
+}
+
 /// 项目统一的错误类型
 #[derive(Debug)]
 pub enum CrateSpecError {
@@ -14,9 +117,9 @@ pub enum CrateSpecError {
     /// 参数验证错误
     ValidationError(String),
     /// 网络请求错误
-    NetworkError(String),
+    NetworkError(NetworkFailure),
     /// PKI 平台错误
-    PkiError(String),
+    PkiError(NetworkFailure),
     /// 签名错误
     SignatureError(String),
     /// 解码错误
@@ -25,32 +128,85 @@ pub enum CrateSpecError {
     EncodeError(String),
     /// 解析错误
     ParseError(String),
+    /// 外部命令执行失败或超时
+    CommandFailed(String),
     /// 其他错误
     Other(String),
 }
 
-impl fmt::Display for CrateSpecError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl CrateSpecError {
+    /// 错误类别，网络和 PKI 错误带有超时、连接失败或 HTTP 状态码等细分类别
+    pub fn kind(&self) -> CrateSpecErrorKind {
+        match self {
+            CrateSpecError::Io(_) => CrateSpecErrorKind::Io,
+            CrateSpecError::FileNotFound(_) => CrateSpecErrorKind::FileNotFound,
+            CrateSpecError::ConfigError(_) => CrateSpecErrorKind::Config,
+            CrateSpecError::ValidationError(_) => CrateSpecErrorKind::Validation,
+            CrateSpecError::NetworkError(failure) => CrateSpecErrorKind::Network(failure.kind),
+            CrateSpecError::PkiError(failure) => CrateSpecErrorKind::Pki(failure.kind),
+            CrateSpecError::SignatureError(_) => CrateSpecErrorKind::Signature,
+            CrateSpecError::DecodeError(_) => CrateSpecErrorKind::Decode,
+            CrateSpecError::EncodeError(_) => CrateSpecErrorKind::Encode,
+            CrateSpecError::ParseError(_) => CrateSpecErrorKind::Parse,
+            CrateSpecError::CommandFailed(_) => CrateSpecErrorKind::CommandFailed,
+            CrateSpecError::Other(_) => CrateSpecErrorKind::Other,
+        }
+    }
+
+    /// 稳定的数字错误码，见 [`CrateSpecErrorKind::code`]
+    pub fn code(&self) -> i32 {
+        self.kind().code()
+    }
+
+    /// 错误类别的名称
+    fn label(&self, lang: Lang) -> &'static str {
+        match self {
+            CrateSpecError::Io(_) => lang.pick("IO 错误", "I/O error"),
+            CrateSpecError::FileNotFound(_) => lang.pick("文件不存在", "file not found"),
+            CrateSpecError::ConfigError(_) => lang.pick("配置错误", "configuration error"),
+            CrateSpecError::ValidationError(_) => lang.pick("参数验证错误", "validation error"),
+            CrateSpecError::NetworkError(_) => lang.pick("网络错误", "network error"),
+            CrateSpecError::PkiError(_) => lang.pick("PKI 平台错误", "PKI error"),
+            CrateSpecError::SignatureError(_) => lang.pick("签名错误", "signature error"),
+            CrateSpecError::DecodeError(_) => lang.pick("解码错误", "decode error"),
+            CrateSpecError::EncodeError(_) => lang.pick("编码错误", "encode error"),
+            CrateSpecError::ParseError(_) => lang.pick("解析错误", "parse error"),
+            CrateSpecError::CommandFailed(_) => lang.pick("命令执行失败", "command failed"),
+            CrateSpecError::Other(_) => lang.pick("错误", "error"),
+        }
+    }
+
+    /// 按指定语言格式化，[`Display`](fmt::Display) 使用 [`lang`] 决定的当前语言
+    pub fn localized(&self, lang: Lang) -> String {
         match self {
-            CrateSpecError::Io(e) => write!(f, "IO 错误: {}", e),
-            CrateSpecError::FileNotFound(path) => write!(f, "文件不存在: {}", path.display()),
-            CrateSpecError::ConfigError(msg) => write!(f, "配置错误: {}", msg),
-            CrateSpecError::ValidationError(msg) => write!(f, "参数验证错误: {}", msg),
-            CrateSpecError::NetworkError(msg) => write!(f, "网络错误: {}", msg),
-            CrateSpecError::PkiError(msg) => write!(f, "PKI 平台错误: {}", msg),
-            CrateSpecError::SignatureError(msg) => write!(f, "签名错误: {}", msg),
-            CrateSpecError::DecodeError(msg) => write!(f, "解码错误: {}", msg),
-            CrateSpecError::EncodeError(msg) => write!(f, "编码错误: {}", msg),
-            CrateSpecError::ParseError(msg) => write!(f, "解析错误: {}", msg),
-            CrateSpecError::Other(msg) => write!(f, "错误: {}", msg),
+            CrateSpecError::Io(e) => format!("{}: {}", self.label(lang), e),
+            CrateSpecError::FileNotFound(path) => format!("{}: {}", self.label(lang), path.display()),
+            CrateSpecError::NetworkError(failure) | CrateSpecError::PkiError(failure) => {
+                format!("{}: {}", self.label(lang), failure)
+            }
+            CrateSpecError::ConfigError(msg)
+            | CrateSpecError::ValidationError(msg)
+            | CrateSpecError::SignatureError(msg)
+            | CrateSpecError::DecodeError(msg)
+            | CrateSpecError::EncodeError(msg)
+            | CrateSpecError::ParseError(msg)
+            | CrateSpecError::CommandFailed(msg)
+            | CrateSpecError::Other(msg) => format!("{}: {}", self.label(lang), msg),
         }
     }
 }
 
+impl fmt::Display for CrateSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localized(lang()))
+    }
+}
+
 impl std::error::Error for CrateSpecError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             CrateSpecError::Io(e) => Some(e),
+            CrateSpecError::NetworkError(f) | CrateSpecError::PkiError(f) => std::error::Error::source(f),
             _ => None,
         }
     }
@@ -77,3 +233,71 @@ impl From<&str> for CrateSpecError {
 /// Result 类型别名，使用项目统一的错误类型
 pub type Result<T> = std::result::Result<T, CrateSpecError>;
 
+
+#[test]
+fn test_localized_messages() {
+    let err = CrateSpecError::FileNotFound(PathBuf::from("demo.scrate"));
+    assert_eq!(err.localized(Lang::En), "file not found: demo.scrate");
+    assert_eq!(err.localized(Lang::Zh), "文件不存在: demo.scrate");
+
+    let err = CrateSpecError::ConfigError("missing [net]".to_string());
+    assert_eq!(err.localized(Lang::En), "configuration error: missing [net]");
+    assert_eq!(err.localized(Lang::Zh), "配置错误: missing [net]");
+    assert_eq!(CrateSpecError::SignatureError("x".to_string()).localized(Lang::En), "signature error: x");
+    assert_eq!(CrateSpecError::PkiError("x".into()).localized(Lang::En), "PKI error: x");
+}
+
+#[test]
+fn test_error_kind_and_code() {
+    let timeout = CrateSpecError::PkiError(NetworkFailure::new(NetworkErrorKind::Timeout, "timed out"));
+    assert_eq!(timeout.kind(), CrateSpecErrorKind::Pki(NetworkErrorKind::Timeout));
+    assert_eq!(timeout.code(), 41);
+    let status = CrateSpecError::NetworkError(NetworkFailure::new(NetworkErrorKind::HttpStatus(503), "unavailable"));
+    assert_eq!(status.kind(), CrateSpecErrorKind::Network(NetworkErrorKind::HttpStatus(503)));
+    assert_eq!(status.code(), 33);
+    assert_eq!(CrateSpecError::SignatureError("x".to_string()).kind(), CrateSpecErrorKind::Signature);
+    assert_eq!(CrateSpecError::from("x").code(), 1);
+
+    // 错误码互不相同，且都能作为进程退出码
+    let kinds = [
+        CrateSpecErrorKind::Io,
+        CrateSpecErrorKind::FileNotFound,
+        CrateSpecErrorKind::Config,
+        CrateSpecErrorKind::Validation,
+        CrateSpecErrorKind::Parse,
+        CrateSpecErrorKind::Signature,
+        CrateSpecErrorKind::Decode,
+        CrateSpecErrorKind::Encode,
+        CrateSpecErrorKind::CommandFailed,
+        CrateSpecErrorKind::Other,
+    ];
+    let network = [
+        NetworkErrorKind::Other,
+        NetworkErrorKind::Timeout,
+        NetworkErrorKind::Connection,
+        NetworkErrorKind::HttpStatus(500),
+    ];
+    let mut codes: Vec<i32> = kinds
+        .into_iter()
+        .chain(network.into_iter().map(CrateSpecErrorKind::Network))
+        .chain(network.into_iter().map(CrateSpecErrorKind::Pki))
+        .map(CrateSpecErrorKind::code)
+        .collect();
+    assert!(codes.iter().all(|code| (1..126).contains(code)));
+    let len = codes.len();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), len);
+}
+
+#[test]
+fn test_network_error_source() {
+    use std::error::Error;
+    let failure = NetworkFailure::new(NetworkErrorKind::Connection, "PKI platform unreachable")
+        .with_source(io::Error::new(io::ErrorKind::ConnectionRefused, "refused"));
+    let err = CrateSpecError::PkiError(failure);
+    assert_eq!(err.localized(Lang::En), "PKI error: PKI platform unreachable");
+    let source = err.source().expect("PkiError 应保留底层错误");
+    assert_eq!(source.downcast_ref::<io::Error>().unwrap().kind(), io::ErrorKind::ConnectionRefused);
+    assert!(CrateSpecError::NetworkError("x".into()).source().is_none());
+}
@@ -2,5 +2,8 @@ pub mod utils;
 pub mod config;
 pub mod network;
 pub mod error;
+pub mod locale;
 
-pub use error::{CrateSpecError, Result};
\ No newline at end of file
+pub use error::{CrateSpecError, Result};
+pub use utils::pack::{encode_local, encode_local_with_options};
+pub use utils::pkcs::{default_root_cas, set_default_root_cas};
\ No newline at end of file
@@ -0,0 +1,67 @@
+//! 用户可见文本的语言选择
+//!
+//! 由环境变量 `CRATE_SPEC_LANG` 决定：以 `en` 开头的值（如 `en`、`en_US.UTF-8`）为英文，
+//! 其余或未设置时为中文。覆盖 [`CrateSpecError`](crate::error::CrateSpecError) 的错误类别、
+//! 解码、校验、签名、PKI 与网络错误的具体说明以及命令行的主要输出。
+use std::sync::OnceLock;
+
+/// 选择语言的环境变量
+pub const LANG_ENV: &str = "CRATE_SPEC_LANG";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Lang {
+    /// 解析 `CRATE_SPEC_LANG` 的值，无法识别时使用默认的中文
+    pub fn from_env_value(value: &str) -> Self {
+        if value.trim().to_ascii_lowercase().starts_with("en") {
+            Lang::En
+        } else {
+            Lang::Zh
+        }
+    }
+
+    /// 按语言选择文本
+    pub fn pick<'a>(self, zh: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Lang::Zh => zh,
+            Lang::En => en,
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// 当前语言，首次调用时读取 `CRATE_SPEC_LANG`，之后不再变化
+pub fn lang() -> Lang {
+    *LANG.get_or_init(|| std::env::var(LANG_ENV).map(|v| Lang::from_env_value(&v)).unwrap_or_default())
+}
+
+/// 按当前语言选择文本
+pub fn tr<'a>(zh: &'a str, en: &'a str) -> &'a str {
+    lang().pick(zh, en)
+}
+
+/// 按当前语言选择格式串并格式化，参数与 `format!` 相同
+#[macro_export]
+macro_rules! tr_format {
+    ($zh:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $crate::locale::lang() {
+            $crate::locale::Lang::Zh => format!($zh $(, $arg)*),
+            $crate::locale::Lang::En => format!($en $(, $arg)*),
+        }
+    };
+}
+
+#[test]
+fn test_lang_from_env_value() {
+    assert_eq!(Lang::from_env_value("en"), Lang::En);
+    assert_eq!(Lang::from_env_value("EN_us.UTF-8"), Lang::En);
+    assert_eq!(Lang::from_env_value("zh"), Lang::Zh);
+    assert_eq!(Lang::from_env_value("fr"), Lang::Zh);
+    assert_eq!(Lang::En.pick("错误", "error"), "error");
+}
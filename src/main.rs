@@ -1,17 +1,23 @@
 use crate::config::Config;
 use crate_spec::error::{CrateSpecError, Result};
+use crate_spec::locale::tr;
 use clap::Parser;
-use crate::commands::{LocalEncodeCommand, NetworkEncodeCommand, LocalDecodeCommand, NetworkDecodeCommand};
+use crate::commands::{BatchVerifyCommand, LocalEncodeCommand, NetworkEncodeCommand, LocalDecodeCommand, NetworkDecodeCommand, DumpSectionsCommand, InfoCommand, SignDigestCommand};
 use crate::params::ParamsBuilder;
+use crate_spec::utils::pkcs::DEFAULT_MAX_CHAIN_DEPTH;
+use crate_spec::utils::timings;
+// config.rs 同时编译进库和二进制，二进制中的 crate::locale 和 crate::tr_format 由这里导入
+use crate_spec::locale;
+use crate_spec::tr_format;
+use std::sync::Arc;
+use std::time::Instant;
 
-pub mod pack;
 pub mod unpack;
 pub mod config;
 pub mod config_ext;
-pub mod network;
 pub mod commands;
 pub mod params;
-use config::DEFAULT_CONFIG_PATH;
+use config::{DEFAULT_CONFIG_PATH, MAX_HTTP_TIMEOUT_SECS};
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -22,6 +28,12 @@ pub struct Args {
     ///decode crate
     #[clap(short, long, required = false)]
     decode: bool,
+    ///sign a precomputed SHA256 digest with the PKI instead of packing a crate (net mode only)
+    #[clap(long, required = false, conflicts_with_all = ["encode", "decode"], requires = "digest_file")]
+    sign_digest: bool,
+    ///hex SHA256 digest file signed by --sign-digest; the NetworkSignature is written to <output>/<file>.netsig
+    #[clap(long, value_name = "PATH", required = false, requires = "sign_digest")]
+    digest_file: Option<String>,
     ///mode: net or local (default: local)
     #[clap(long, value_name = "MODE", default_value = "local")]
     mode: String,
@@ -31,31 +43,198 @@ pub struct Args {
     ///use command line arguments for local mode (mutually exclusive with --config)
     #[clap(long, required = false)]
     cli: bool,
+    ///build config entirely from CRATE_SPEC_* environment variables instead of a config file
+    #[clap(long, required = false, conflicts_with_all = ["config", "cli"])]
+    config_from_env: bool,
+    ///timeout in seconds for each PKI request (1-600), overrides [net] http_timeout_secs; default 30 (net mode only)
+    #[clap(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..=MAX_HTTP_TIMEOUT_SECS))]
+    timeout: Option<u64>,
     ///root-ca file paths
     #[clap(short, long, required = false)]
     root_ca_paths: Vec<String>,
     ///certification file path
     #[clap(short, long, required = false)]
     cert_path: Option<String>,
+    ///PEM file with the intermediate CA certificates of -c, embedded in local signatures so verifiers only need the root CA; repeatable (also `cert_chain_path` in [local.encode], encode only)
+    #[clap(long, value_name = "PEM", required = false, requires = "encode")]
+    cert_chain: Vec<String>,
     ///private key path
     #[clap(short, long, required = false)]
     pkey_path: Option<String>,
+    ///OpenSSL engine id holding the private key, e.g. pkcs11 (replaces -p)
+    #[clap(long, value_name = "ENGINE", required = false, requires = "engine_key")]
+    engine: Option<String>,
+    ///private key id/URI inside the OpenSSL engine, e.g. pkcs11:object=signing-key
+    #[clap(long, value_name = "KEY_ID", required = false, requires = "engine")]
+    engine_key: Option<String>,
     ///output file path
     #[clap(short, long, required = false)]
     output: Option<String>,
-    ///input file path
+    ///input file path ("-" reads from stdin)
     #[clap(required = false)]
     input: Option<String>,
+    ///read the .scrate input from stdin (decode only, same as input "-")
+    #[clap(long, required = false, conflicts_with = "input")]
+    stdin: bool,
+    ///check gzip/tar integrity of the decoded crate binary (decode only)
+    #[clap(long, required = false)]
+    check_crate_integrity: bool,
+    ///check that Cargo.toml inside the crate binary matches the package/dependency metadata (decode only)
+    #[clap(long, required = false)]
+    cross_validate: bool,
+    ///check that each signature's signing time lies within its signer certificate's validity period (decode only)
+    #[clap(long, required = false, requires = "decode")]
+    verify_timestamps: bool,
+    ///fail if the string table holds entries no section references, which could hide smuggled data (decode only)
+    #[clap(long, required = false, requires = "decode")]
+    check_unreferenced_strings: bool,
+    ///verify fingerprint and signatures (plus any selected checks) without writing the .crate or metadata and print how many signatures of each type passed; -o / output_path is not needed (decode only)
+    #[clap(long, alias = "verify-only", required = false, requires = "decode")]
+    verify: bool,
+    ///walk the verification steps (magic, header offsets, fingerprint, sections, each signature) and print what was checked as a tree; writes no files and fails if any step failed (decode only)
+    #[clap(long, required = false, requires = "decode", conflicts_with_all = ["detached_sig", "batch", "dump_sections"])]
+    explain: bool,
+    ///when writing one decode output (the .crate or the metadata) fails, still write the others and report all failures at the end; files left half-written are deleted (decode only)
+    #[clap(long, required = false, requires = "decode", conflicts_with_all = ["verify", "explain", "batch", "dump_sections"])]
+    keep_going: bool,
+    ///max number of intermediate CA certificates accepted when verifying signatures (decode only)
+    #[clap(long, value_name = "DEPTH", default_value_t = DEFAULT_MAX_CHAIN_DEPTH)]
+    verify_chain_depth: u32,
+    ///write signatures to a separate <name>.scrate.sig file instead of embedding them (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    detached: bool,
+    ///cargo target directory used when packaging (default: CARGO_TARGET_DIR or <input>/target, encode only)
+    #[clap(long, value_name = "DIR", required = false, requires = "encode")]
+    target_dir: Option<String>,
+    ///substitute placeholders (unknown/0.0.0) for a missing package name/version in Cargo.toml (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    lenient_toml: bool,
+    ///kill `cargo package` if it runs longer than SECS seconds (encode only)
+    #[clap(long, value_name = "SECS", required = false, requires = "encode")]
+    package_timeout: Option<u64>,
+    ///file name of the .crate in <target>/package, with {name}/{version} placeholders (default: {name}-{version}.crate, encode only)
+    #[clap(long, value_name = "PATTERN", required = false, requires = "encode")]
+    crate_file_pattern: Option<String>,
+    ///tool identity recorded in the build metadata section, e.g. "release-pipeline/2.1" (encode only)
+    #[clap(long, value_name = "TOOL", required = false, requires = "encode")]
+    producer_tool: Option<String>,
+    ///operator or CI job identity recorded with --producer-tool (encode only)
+    #[clap(long, value_name = "ID", required = false, requires = "encode")]
+    producer_operator: Option<String>,
+    ///print the package info, dependencies, signature type and output file name without running cargo package, signing, contacting the PKI or writing files (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    dry_run: bool,
+    ///treat the input as a Cargo workspace and write one .scrate per member into the output directory (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    workspace: bool,
+    ///write the package as NAME (used verbatim, e.g. mycrate-latest.scrate) instead of <name>-<version>.scrate; must not contain path separators (encode only)
+    #[clap(long, value_name = "NAME", required = false, requires = "encode", conflicts_with = "workspace")]
+    output_name: Option<String>,
+    ///store only the SHA256 of the .crate instead of its bytes; signatures cover the digest (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    prune_binary: bool,
+    ///pad data sections so each starts at a multiple of BYTES (power of two, at most 32768; e.g. 4096 to mmap the crate binary), default 1 (encode only)
+    #[clap(long, value_name = "BYTES", required = false, requires = "encode")]
+    section_align: Option<usize>,
+    ///byte order of the string table length prefixes and section index fields: little (default) or big, for readers that expect big endian; decoding follows the header (encode only)
+    #[clap(long, value_name = "ORDER", required = false, requires = "encode")]
+    endianness: Option<String>,
+    ///compress the crate binary section with KIND: none (default), gzip or zstd; signatures still cover the uncompressed .crate and decoding detects the compression itself (encode only)
+    #[clap(long, value_name = "KIND", required = false, requires = "encode", conflicts_with = "prune_binary")]
+    compress: Option<String>,
+    ///record the SHA256 of every file inside the .crate in a file hash section covered by FILE signatures (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    file_hashes: bool,
+    ///digest for signed content and the package fingerprint: sha256 (default), sha384 or sha512; network signatures only support sha256 (also `digest` in [local.encode], encode only)
+    #[clap(long, value_name = "ALGO", required = false, requires = "encode")]
+    digest: Option<String>,
+    ///after verification, compare the files under DIR (where the .crate was extracted) with the package's file hash list and name every modified, missing or extra file (decode only)
+    #[clap(long, value_name = "DIR", required = false, requires = "decode", conflicts_with_all = ["explain", "batch", "dump_sections"])]
+    check_file_hashes: Option<String>,
+    ///append a dependency to the packed dep table, as 'name@req[;source=git+<url>|registry+<name>|...][;platform=<cfg>]'; repeatable (encode only)
+    #[clap(long, alias = "append-dep", value_name = "SPEC", required = false, requires = "encode")]
+    add_dep: Vec<String>,
+    ///remove every dependency with this name from the packed dep table before --add-dep is applied; repeatable (encode only)
+    #[clap(long, value_name = "NAME", required = false, requires = "encode")]
+    remove_dep: Vec<String>,
+    ///record the cargo/rustc versions used for packing in a build metadata section (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    record_toolchain: bool,
+    ///tag or branch to check out when the input is a git repository URL (encode only)
+    #[clap(long, value_name = "REF", required = false, requires = "encode")]
+    git_ref: Option<String>,
+    ///what to do when the output .scrate already exists: overwrite (default), refuse or suffix (encode only)
+    #[clap(long, value_name = "STRATEGY", required = false, requires = "encode")]
+    on_collision: Option<String>,
+    ///print dependencies left out of the dependency table because of unsupported keys such as path (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    report_unverified_deps: bool,
+    ///fail packing when it produces warnings: placeholder name/version or dropped dependencies (encode only)
+    #[clap(long, required = false, requires = "encode")]
+    fail_on_warning: bool,
+    ///verify the .scrate body with a detached .scrate.sig file (decode only)
+    #[clap(long, value_name = "PATH", required = false, requires = "decode")]
+    detached_sig: Option<String>,
+    ///metadata file format written on decode: txt, json or toml
+    #[clap(long, value_name = "FORMAT", default_value = "txt")]
+    output_metadata_format: String,
+    ///reject local signatures whose signer RSA key is shorter than BITS; overrides [policy] min_rsa_bits (decode only)
+    #[clap(long, value_name = "BITS", required = false, requires = "decode")]
+    min_rsa_bits: Option<u32>,
+    ///comma-separated EC curves (e.g. prime256v1,secp384r1) allowed for signer keys; overrides [policy] allowed_ec_curves (decode only)
+    #[clap(long, value_name = "CURVES", value_delimiter = ',', required = false, requires = "decode")]
+    allowed_ec_curves: Vec<String>,
+    ///comma-separated signature types (file, cratebin, network) the package must each contain at least one of; nothing is written otherwise (decode only)
+    #[clap(long, value_name = "TYPES", value_delimiter = ',', required = false, requires = "decode")]
+    require_sig_type: Vec<String>,
+    ///fail when the package has no signatures; by default an unsigned package is decoded and reported as "no signatures present" (decode only)
+    #[clap(long, required = false, requires = "decode")]
+    require_signature: bool,
+    ///check the decoded package against a policy TOML file and fail on any violation (decode only)
+    #[clap(long, value_name = "POLICY", required = false, requires = "decode")]
+    compat_check: Option<String>,
+    ///verify several .scrate files or directories of them (fingerprint and signatures only, nothing is extracted) and print one result per file (decode only)
+    #[clap(long, value_name = "SCRATE", num_args = 1.., required = false, requires = "decode")]
+    batch: Vec<String>,
+    ///per-file result format for --batch: text or jsonl (one JSON object per line on stdout)
+    #[clap(long, value_name = "FORMAT", required = false, requires = "batch")]
+    batch_format: Option<String>,
+    ///with --batch, print only one "OK|FAIL <file>  name@version  sigs=N" line per file (same as --batch-format summary)
+    #[clap(long, required = false, requires = "batch", conflicts_with = "batch_format")]
+    summary_only: bool,
+    ///with --batch, show a progress bar (done/total, failures) on stderr; only when stdout is a terminal and the format is not jsonl
+    #[clap(long, required = false, requires = "batch")]
+    progress: bool,
+    ///write each section's raw bytes to DIR without verifying fingerprint or signatures (decode only)
+    #[clap(long, value_name = "DIR", required = false, requires = "decode")]
+    dump_sections: Option<String>,
+    ///print the package info and dependency table as JSON to stdout, or to the file given by -o; reads neither the crate binary nor any config, and checks no fingerprint or signatures (decode only)
+    #[clap(long, required = false, requires = "decode", conflicts_with_all = ["verify", "explain", "keep_going", "detached_sig", "batch", "dump_sections", "check_file_hashes"])]
+    info: bool,
+    ///print a per-phase timing breakdown after encode/decode
+    #[clap(long, required = false)]
+    timings: bool,
 }
 
 /// 从指定路径加载配置文件
 fn load_config(config_path: &str) -> Result<Config> {
     Config::from_file(config_path)
-        .map_err(|e| CrateSpecError::ConfigError(format!("无法加载配置文件 {}: {}", config_path, e)))
+        .map_err(|e| CrateSpecError::ConfigError(tr_format!("无法加载配置文件 {}: {}", "cannot load config file {}: {}", config_path, e)))
+}
+
+/// 从环境变量加载配置
+fn load_config_from_env() -> Result<Config> {
+    Config::from_env().map_err(CrateSpecError::ConfigError)
 }
 
 /// 确定配置加载方式
-fn determine_config(mode: &str, cli: bool, config_path: Option<&str>) -> Result<Option<Config>> {
+fn determine_config(mode: &str, cli: bool, config_path: Option<&str>, from_env: bool) -> Result<Option<Config>> {
+    if from_env {
+        return match mode {
+            "local" | "net" => load_config_from_env().map(Some),
+            _ => Err(CrateSpecError::ValidationError(tr_format!("无效的模式: {}，必须是 'local' 或 'net'", "invalid mode: {}, must be 'local' or 'net'", mode))),
+        };
+    }
     match mode {
         "local" => {
             if cli {
@@ -69,7 +248,7 @@ fn determine_config(mode: &str, cli: bool, config_path: Option<&str>) -> Result<
             let path = config_path.unwrap_or(DEFAULT_CONFIG_PATH);
             load_config(path).map(Some)
         }
-        _ => Err(CrateSpecError::ValidationError(format!("无效的模式: {}，必须是 'local' 或 'net'", mode))),
+        _ => Err(CrateSpecError::ValidationError(tr_format!("无效的模式: {}，必须是 'local' 或 'net'", "invalid mode: {}, must be 'local' or 'net'", mode))),
     }
 }
 
@@ -82,7 +261,7 @@ fn execute_encode(mode: &str, params_builder: &ParamsBuilder) -> Result<()> {
         }
         "net" => {
             let config = params_builder.config.as_ref()
-                .ok_or_else(|| CrateSpecError::ConfigError("网络模式需要配置文件".to_string()))?;
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("网络模式需要配置文件", "network mode requires a config file").to_string()))?;
             let params = params_builder.build_network_encode_params()?;
             NetworkEncodeCommand::execute(params, config)
         }
@@ -92,6 +271,22 @@ fn execute_encode(mode: &str, params_builder: &ParamsBuilder) -> Result<()> {
 
 /// 执行解码操作
 fn execute_decode(mode: &str, params_builder: &ParamsBuilder) -> Result<()> {
+    if params_builder.info {
+        return InfoCommand::execute(params_builder.build_info_params()?);
+    }
+    if params_builder.dump_sections.is_some() {
+        let params = params_builder.build_dump_sections_params(mode)?;
+        return DumpSectionsCommand::execute(params);
+    }
+    if !params_builder.batch.is_empty() {
+        let params = params_builder.build_batch_verify_params(mode)?;
+        let network_client = match (mode, params_builder.config.as_ref()) {
+            ("net", Some(config)) => Some(Arc::new(config.create_pki_client()?)),
+            ("net", None) => return Err(CrateSpecError::ConfigError(tr("网络模式需要配置文件", "network mode requires a config file").to_string())),
+            _ => None,
+        };
+        return BatchVerifyCommand::execute(params, network_client);
+    }
     match mode {
         "local" => {
             let params = params_builder.build_local_decode_params()?;
@@ -99,7 +294,7 @@ fn execute_decode(mode: &str, params_builder: &ParamsBuilder) -> Result<()> {
         }
         "net" => {
             let config = params_builder.config.as_ref()
-                .ok_or_else(|| CrateSpecError::ConfigError("网络模式需要配置文件".to_string()))?;
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("网络模式需要配置文件", "network mode requires a config file").to_string()))?;
             let params = params_builder.build_network_decode_params()?;
             NetworkDecodeCommand::execute(params, config)
         }
@@ -107,37 +302,75 @@ fn execute_decode(mode: &str, params_builder: &ParamsBuilder) -> Result<()> {
     }
 }
 
+/// 执行摘要签名操作
+fn execute_sign_digest(mode: &str, params_builder: &ParamsBuilder) -> Result<()> {
+    if mode != "net" {
+        return Err(CrateSpecError::ValidationError(tr("--sign-digest 只能在网络模式 (--mode net) 下使用", "--sign-digest can only be used in network mode (--mode net)").to_string()));
+    }
+    let config = params_builder.config.as_ref()
+        .ok_or_else(|| CrateSpecError::ConfigError(tr("网络模式需要配置文件", "network mode requires a config file").to_string()))?;
+    let params = params_builder.build_sign_digest_params()?;
+    SignDigestCommand::execute(params, config)
+}
+
 fn main() {
     let args = Args::parse();
     let mode = args.mode.as_str();
 
-    // 加载配置
-    let config = match determine_config(mode, args.cli, args.config.as_deref()) {
+    // 加载配置；--info 不读取配置，标准输出只有 JSON
+    let loaded = if args.info {
+        Ok(None)
+    } else {
+        determine_config(mode, args.cli, args.config.as_deref(), args.config_from_env)
+    };
+    let mut config = match loaded {
         Ok(cfg) => {
             if cfg.is_some() {
-                println!("从配置文件加载: {}", args.config.as_deref().unwrap_or(DEFAULT_CONFIG_PATH));
+                if args.config_from_env {
+                    println!("{}", tr("从环境变量加载配置", "configuration loaded from environment variables"));
+                } else {
+                    println!(
+                        "{}: {}",
+                        tr("从配置文件加载", "configuration loaded from"),
+                        args.config.as_deref().unwrap_or(DEFAULT_CONFIG_PATH)
+                    );
+                }
             }
             cfg
         }
         Err(e) => {
-            eprintln!("错误: {}", e);
-            std::process::exit(1);
+            eprintln!("{}: {}", tr("错误", "error"), e);
+            std::process::exit(e.code());
         }
     };
 
+    // --timeout 覆盖配置文件中的 http_timeout_secs
+    if let (Some(secs), Some(net)) = (args.timeout, config.as_mut().and_then(|cfg| cfg.net.as_mut())) {
+        net.http_timeout_secs = Some(secs);
+    }
+
     // 创建参数构建器
     let params_builder = ParamsBuilder::from_args(&args, config);
 
     // 执行操作
+    if args.timings {
+        timings::enable();
+    }
+    let start = Instant::now();
     let result = match (args.encode, args.decode) {
+        _ if args.sign_digest => execute_sign_digest(mode, &params_builder),
         (true, false) => execute_encode(mode, &params_builder),
         (false, true) => execute_decode(mode, &params_builder),
-        _ => Err(CrateSpecError::ValidationError("必须指定 -e (编码) 或 -d (解码)".to_string())),
+        _ => Err(CrateSpecError::ValidationError(tr("必须指定 -e (编码)、-d (解码) 或 --sign-digest", "one of -e (encode), -d (decode) or --sign-digest is required").to_string())),
     };
+    timings::record(timings::label_total(), start.elapsed());
+    if let Some(report) = timings::take_report() {
+        println!("{}", report);
+    }
 
     // 处理结果
     if let Err(e) = result {
-        eprintln!("错误: {}", e);
-        std::process::exit(1);
+        eprintln!("{}: {}", tr("错误", "error"), e);
+        std::process::exit(e.code());
     }
 }
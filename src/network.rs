@@ -1,15 +1,33 @@
 use bincode::{Decode, Encode};
-use reqwest::blocking::Client;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Identity;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::io::Read;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::{NetworkErrorKind, NetworkFailure};
+use crate::utils::decode::parallel_map;
+use crate::utils::storage::{FsStorage, Storage};
+use crate::utils::timings;
+use crate::locale::tr;
+use crate::tr_format;
+
+mod async_client;
+pub use async_client::AsyncPkiClient;
 
 // 网络相关常量
 /// 默认 HTTP 请求超时时间（秒）
 pub const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 30;
 
+/// 健康检查的超时时间（秒），不超过客户端的请求超时时间
+pub const HEALTH_CHECK_TIMEOUT_SECS: u64 = 5;
+
 /// 密钥对文件权限（仅所有者可读写）
 #[cfg(unix)]
 pub const KEYPAIR_FILE_MODE: u32 = 0o600;
@@ -20,6 +38,138 @@ pub const DEFAULT_RETRY_TIMES: u32 = 3;
 /// 默认重试延迟（毫秒）
 pub const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
 
+/// 默认 PKI 响应体大小上限（字节）
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
+
+/// 默认的 PKI 认证头名称
+pub const DEFAULT_AUTH_HEADER_NAME: &str = "Authorization";
+
+fn response_too_large(max_bytes: u64) -> String {
+    tr_format!("PKI 平台响应过大（response too large）: 超过上限 {} 字节", "PKI response too large: over the limit of {} bytes", max_bytes)
+}
+
+/// 网络连接错误（超时、连接失败等）可以重试，收到响应后的错误不重试
+fn is_retryable(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect() || e.is_request()
+}
+
+/// 读取响应体，超过 `max_bytes` 时报错而不是继续缓冲，防止异常的 PKI 平台耗尽内存
+fn read_body(response: Response, max_bytes: u64) -> Result<Vec<u8>, NetworkFailure> {
+    let too_large = || NetworkFailure::from(response_too_large(max_bytes));
+    if response.content_length().is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+    let mut body = vec![];
+    response
+        .take(max_bytes + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| NetworkFailure::from(tr_format!("无法读取响应: {}", "cannot read the response: {}", e)).with_source(e))?;
+    if body.len() as u64 > max_bytes {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+/// 在大小上限内读取响应体并解析为 JSON
+fn read_json<T: DeserializeOwned>(response: Response, max_bytes: u64) -> Result<T, NetworkFailure> {
+    serde_json::from_slice(&read_body(response, max_bytes)?)
+        .map_err(|e| NetworkFailure::from(tr_format!("无法解析响应 JSON: {}", "cannot parse the response JSON: {}", e)).with_source(e))
+}
+
+/// 在大小上限内读取错误响应的文本，读取失败时返回说明
+fn read_error_text(response: Response, max_bytes: u64) -> String {
+    read_body(response, max_bytes)
+        .map(|body| String::from_utf8_lossy(&body).into_owned())
+        .unwrap_or_else(|e| tr_format!("无法读取错误信息: {}", "cannot read the error message: {}", e))
+}
+
+/// 从 PEM 证书和私钥文件加载 mTLS 客户端身份，私钥可为 PKCS#8 或传统 PKCS#1/SEC1 格式
+pub fn load_client_identity(cert_path: &str, key_path: &str) -> Result<Identity, String> {
+    let cert = fs::read(cert_path).map_err(|e| tr_format!("无法读取客户端证书 {}: {}", "cannot read client certificate {}: {}", cert_path, e))?;
+    let key = fs::read(key_path).map_err(|e| tr_format!("无法读取客户端私钥 {}: {}", "cannot read client private key {}: {}", key_path, e))?;
+    // reqwest 只接受 PKCS#8，先用 openssl 解析再统一转换
+    let key = openssl::pkey::PKey::private_key_from_pem(&key)
+        .and_then(|pkey| pkey.private_key_to_pem_pkcs8())
+        .map_err(|e| tr_format!("无法解析客户端私钥 {}: {}", "cannot parse client private key {}: {}", key_path, e))?;
+    Identity::from_pkcs8_pem(&cert, &key).map_err(|e| tr_format!("无法加载客户端证书 {}: {}", "cannot load client certificate {}: {}", cert_path, e))
+}
+
+/// 创建 HTTP 客户端，提供 identity 时在 TLS 握手中出示客户端证书
+fn http_client(identity: Option<&Identity>, timeout: Duration) -> Result<Client, String> {
+    let mut builder = Client::builder().timeout(timeout);
+    if let Some(identity) = identity {
+        builder = builder.identity(identity.clone());
+    }
+    builder.build().map_err(|e| tr_format!("无法创建 HTTP 客户端: {}", "cannot create the HTTP client: {}", e))
+}
+
+/// 附加到每个 PKI 请求上的认证头，`Debug` 输出中令牌被隐去
+#[derive(Clone)]
+pub struct AuthHeader {
+    name: HeaderName,
+    value: HeaderValue,
+}
+
+impl AuthHeader {
+    /// 使用 `header_name`（默认 [`DEFAULT_AUTH_HEADER_NAME`]）携带 `token`：
+    /// `Authorization` 头发送 `Bearer <token>`，其他头名直接发送令牌
+    pub fn new(token: &str, header_name: Option<&str>) -> Result<Self, String> {
+        if token.is_empty() {
+            return Err(tr("认证令牌不能为空", "the auth token must not be empty").to_string());
+        }
+        let header_name = header_name.unwrap_or(DEFAULT_AUTH_HEADER_NAME);
+        let name = HeaderName::from_bytes(header_name.as_bytes())
+            .map_err(|_| tr_format!("无效的认证头名称: {}", "invalid auth header name: {}", header_name))?;
+        let value = if name == AUTHORIZATION { format!("Bearer {}", token) } else { token.to_string() };
+        let mut value = HeaderValue::from_str(&value).map_err(|_| tr("认证令牌包含不能放入 HTTP 头的字符", "the auth token contains characters that are not allowed in an HTTP header").to_string())?;
+        value.set_sensitive(true);
+        Ok(AuthHeader { name, value })
+    }
+
+    /// 认证头名称（小写）
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl std::fmt::Debug for AuthHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthHeader").field("name", &self.name).field("value", &"<redacted>").finish()
+    }
+}
+
+/// 配置了认证头时附加到请求上
+fn authorize(request: RequestBuilder, auth: Option<&AuthHeader>) -> RequestBuilder {
+    match auth {
+        Some(auth) => request.header(auth.name.clone(), auth.value.clone()),
+        None => request,
+    }
+}
+
+/// 按 reqwest 错误的性质（超时、连接失败、HTTP 状态码）归类请求失败
+fn request_failure(message: String, e: reqwest::Error) -> NetworkFailure {
+    let kind = if e.is_timeout() {
+        NetworkErrorKind::Timeout
+    } else if e.is_connect() {
+        NetworkErrorKind::Connection
+    } else if let Some(status) = e.status() {
+        NetworkErrorKind::HttpStatus(status.as_u16())
+    } else {
+        NetworkErrorKind::Other
+    };
+    NetworkFailure::new(kind, message).with_source(e)
+}
+
+/// 平台返回非成功状态码时的错误
+fn status_failure(status: reqwest::StatusCode, message: String) -> NetworkFailure {
+    NetworkFailure::new(NetworkErrorKind::HttpStatus(status.as_u16()), message)
+}
+
+/// 当前 Unix 时间（秒）
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
 // BaseConfig 用于 API 请求和 KeyPair 序列化
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct BaseConfig {
@@ -35,6 +185,51 @@ pub struct KeyPair {
     pub pub_key: String,
     pub key_id: String,
     pub base_config: BaseConfig,
+    /// 从平台获取的时间（Unix 秒），None 表示旧格式的缓存，视为永不过期
+    pub fetched_at: Option<u64>,
+    /// 缓存有效期（秒），None 表示永不过期
+    pub ttl_secs: Option<u64>,
+}
+
+// 增加 fetched_at / ttl_secs 字段之前的密钥对文件格式
+#[derive(Decode)]
+struct LegacyKeyPair {
+    priv_key: String,
+    pub_key: String,
+    key_id: String,
+    base_config: BaseConfig,
+}
+
+/// Ed25519 算法名，该算法直接对原始内容签名
+pub const ALGO_ED25519: &str = "ed25519";
+
+/// 发送给 PKI 平台签名/验签接口的 `digest` 字段的构造方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Encode, Decode)]
+pub enum SignScheme {
+    /// 内容的 SHA256 摘要（小写十六进制），用于 sm2 等先哈希再签名的算法
+    #[default]
+    Sha256Hex,
+    /// 原始内容（base64），用于 Ed25519 等直接对消息签名的算法
+    RawBase64,
+}
+
+impl SignScheme {
+    /// 按算法选择签名输入格式
+    pub fn for_algo(algo: &str) -> Self {
+        if algo.eq_ignore_ascii_case(ALGO_ED25519) {
+            SignScheme::RawBase64
+        } else {
+            SignScheme::Sha256Hex
+        }
+    }
+
+    /// 由待签名内容构造请求中的 `digest` 字段
+    pub fn prepare(self, content: &[u8]) -> String {
+        match self {
+            SignScheme::Sha256Hex => digest_to_hex_string(&openssl::sha::sha256(content)),
+            SignScheme::RawBase64 => openssl::base64::encode_block(content),
+        }
+    }
 }
 
 // NetworkSignature 结构体（使用 bincode 序列化，存储到签名段）
@@ -46,6 +241,19 @@ pub struct NetworkSignature {
     pub flow: String,
     pub kms: Option<String>,
     pub key_id: Option<String>,
+    /// 签名输入格式，验签时据此重建相同的输入
+    pub scheme: SignScheme,
+}
+
+// 增加 scheme 字段之前的签名段格式，均为 SHA256 摘要签名
+#[derive(Decode)]
+struct LegacyNetworkSignature {
+    pub_key: String,
+    signature: String,
+    algo: String,
+    flow: String,
+    kms: Option<String>,
+    key_id: Option<String>,
 }
 
 // API 请求/响应结构体
@@ -100,50 +308,205 @@ struct VerifyDigestResponse {
     error: Option<String>,
 }
 
+impl VerifyDigestResponse {
+    /// 平台返回 `OK` 时验签通过，否则带上平台给出的原因
+    fn into_result(self) -> Result<bool, String> {
+        if self.result == "OK" {
+            Ok(true)
+        } else {
+            Err(tr_format!("验签失败: {}", "verification failed: {}", self.error.unwrap_or_else(|| tr("未知错误", "unknown error").to_string())))
+        }
+    }
+}
+
+/// [`NetworkSignature`] 的构造器，未设置的字段为空；未调用 `with_scheme` 时签名输入格式按算法选择
+#[derive(Debug, Clone, Default)]
+pub struct NetworkSignatureBuilder {
+    pub_key: String,
+    signature: String,
+    algo: String,
+    flow: String,
+    kms: Option<String>,
+    key_id: Option<String>,
+    scheme: Option<SignScheme>,
+}
+
+impl NetworkSignatureBuilder {
+    pub fn with_pub_key(mut self, pub_key: impl Into<String>) -> Self {
+        self.pub_key = pub_key.into();
+        self
+    }
+
+    pub fn with_signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = signature.into();
+        self
+    }
+
+    pub fn with_algo(mut self, algo: impl Into<String>) -> Self {
+        self.algo = algo.into();
+        self
+    }
+
+    pub fn with_flow(mut self, flow: impl Into<String>) -> Self {
+        self.flow = flow.into();
+        self
+    }
+
+    pub fn with_kms(mut self, kms: impl Into<String>) -> Self {
+        self.kms = Some(kms.into());
+        self
+    }
+
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    pub fn with_scheme(mut self, scheme: SignScheme) -> Self {
+        self.scheme = Some(scheme);
+        self
+    }
+
+    pub fn build(self) -> NetworkSignature {
+        NetworkSignature {
+            scheme: self.scheme.unwrap_or_else(|| SignScheme::for_algo(&self.algo)),
+            pub_key: self.pub_key,
+            signature: self.signature,
+            algo: self.algo,
+            flow: self.flow,
+            kms: self.kms,
+            key_id: self.key_id,
+        }
+    }
+}
+
+/// 单独签名摘要时输出的 NetworkSignature 文件扩展名，追加在摘要文件名之后
+pub const NETWORK_SIG_EXT: &str = "netsig";
+
+impl NetworkSignature {
+    /// 不经 PKI 平台直接构造网络签名，用于测试或检查签名段内容
+    pub fn builder() -> NetworkSignatureBuilder {
+        NetworkSignatureBuilder::default()
+    }
+
+    /// 调用 PKI 平台对预先计算的十六进制 SHA256 摘要签名，并附上密钥对的公钥和算法信息
+    ///
+    /// Ed25519 必须对原始内容签名，此时返回错误，应改用 [`NetworkSignature::sign_content`]。
+    pub fn sign(pki_client: &PkiClient, keypair: &KeyPair, digest_hex: &str) -> Result<Self, NetworkFailure> {
+        if SignScheme::for_algo(&keypair.base_config.algo) != SignScheme::Sha256Hex {
+            return Err(tr_format!("算法 {} 直接对原始内容签名，不支持签名预先计算的摘要", "algorithm {} signs the raw content and cannot sign a precomputed digest", keypair.base_config.algo).into());
+        }
+        Self::sign_input(pki_client, keypair, SignScheme::Sha256Hex, digest_hex)
+    }
+
+    /// 按密钥对的算法构造签名输入（摘要或原始内容）后调用 PKI 平台签名
+    pub fn sign_content(pki_client: &PkiClient, keypair: &KeyPair, content: &[u8]) -> Result<Self, NetworkFailure> {
+        let scheme = SignScheme::for_algo(&keypair.base_config.algo);
+        Self::sign_input(pki_client, keypair, scheme, &scheme.prepare(content))
+    }
+
+    fn sign_input(
+        pki_client: &PkiClient,
+        keypair: &KeyPair,
+        scheme: SignScheme,
+        input: &str,
+    ) -> Result<Self, NetworkFailure> {
+        let (signature, _cert) = pki_client.sign_digest(&keypair.priv_key, input, &keypair.base_config)?;
+        Ok(Self {
+            pub_key: keypair.pub_key.clone(),
+            signature,
+            algo: keypair.base_config.algo.clone(),
+            flow: keypair.base_config.flow.clone(),
+            kms: (!keypair.base_config.kms.is_empty()).then(|| keypair.base_config.kms.clone()),
+            key_id: (!keypair.key_id.is_empty()).then(|| keypair.key_id.clone()),
+            scheme,
+        })
+    }
+
+    /// 序列化为签名段中保存的格式
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        bincode::encode_to_vec(self, bincode::config::standard()).map_err(|e| tr_format!("无法序列化网络签名: {}", "cannot serialize the network signature: {}", e))
+    }
+
+    /// 从签名段中保存的格式反序列化，兼容没有 scheme 字段的旧格式
+    pub fn from_bytes(bin: &[u8]) -> Result<Self, String> {
+        match bincode::decode_from_slice(bin, bincode::config::standard()) {
+            Ok((sig, _)) => Ok(sig),
+            Err(e) => bincode::decode_from_slice::<LegacyNetworkSignature, _>(bin, bincode::config::standard())
+                .map(|(legacy, _)| Self {
+                    pub_key: legacy.pub_key,
+                    signature: legacy.signature,
+                    algo: legacy.algo,
+                    flow: legacy.flow,
+                    kms: legacy.kms,
+                    key_id: legacy.key_id,
+                    scheme: SignScheme::Sha256Hex,
+                })
+                .map_err(|_| tr_format!("无法反序列化网络签名: {}", "cannot deserialize the network signature: {}", e)),
+        }
+    }
+}
+
 impl KeyPair {
     /// 从文件加载密钥对
     pub fn load_from_file(path: &str) -> Result<Self, String> {
-        let bin = fs::read(path).map_err(|e| format!("无法读取密钥对文件 {}: {}", path, e))?;
-        bincode::decode_from_slice(&bin, bincode::config::standard())
-            .map(|(keypair, _)| keypair)
-            .map_err(|e| format!("无法解析密钥对文件 {}: {}", path, e))
+        Self::load_from_storage(&FsStorage, path)
     }
 
-    /// 保存密钥对到文件
+    /// 保存密钥对到文件（仅所有者可读写）
     pub fn save_to_file(&self, path: &str) -> Result<(), String> {
-        let encoded = bincode::encode_to_vec(self, bincode::config::standard())
-            .map_err(|e| format!("无法序列化密钥对: {}", e))?;
-        
-        // 确保目录存在
-        if let Some(parent) = Path::new(path).parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("无法创建目录: {}", e))?;
+        self.save_to_storage(&FsStorage, path)
+    }
+
+    /// 从存储中读取 `key` 对应的密钥对
+    pub fn load_from_storage(storage: &dyn Storage, key: &str) -> Result<Self, String> {
+        let bin = storage
+            .get(key)
+            .map_err(|e| tr_format!("无法读取密钥对 {}: {}", "cannot read key pair {}: {}", key, e))?
+            .ok_or_else(|| tr_format!("密钥对不存在: {}", "key pair not found: {}", key))?;
+        match bincode::decode_from_slice(&bin, bincode::config::standard()) {
+            Ok((keypair, _)) => Ok(keypair),
+            Err(e) => bincode::decode_from_slice::<LegacyKeyPair, _>(&bin, bincode::config::standard())
+                .map(|(legacy, _)| KeyPair {
+                    priv_key: legacy.priv_key,
+                    pub_key: legacy.pub_key,
+                    key_id: legacy.key_id,
+                    base_config: legacy.base_config,
+                    fetched_at: None,
+                    ttl_secs: None,
+                })
+                .map_err(|_| tr_format!("无法解析密钥对 {}: {}", "cannot parse key pair {}: {}", key, e)),
         }
-        
-        fs::write(path, encoded)
-            .map_err(|e| format!("无法写入密钥对文件 {}: {}", path, e))?;
-        
-        // 设置文件权限（仅所有者可读写）
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(path)
-                .map_err(|e| format!("无法获取文件元数据: {}", e))?
-                .permissions();
-            perms.set_mode(KEYPAIR_FILE_MODE);
-            fs::set_permissions(path, perms)
-                .map_err(|e| format!("无法设置文件权限: {}", e))?;
+    }
+
+    /// 缓存是否已过期：`ttl_secs` 优先于密钥对自身记录的有效期，缺少获取时间或有效期时永不过期
+    pub fn is_expired(&self, ttl_secs: Option<u64>, now: u64) -> bool {
+        match (self.fetched_at, ttl_secs.or(self.ttl_secs)) {
+            (Some(fetched_at), Some(ttl)) => now.saturating_sub(fetched_at) >= ttl,
+            _ => false,
         }
-        
-        Ok(())
     }
 
-    /// 从 PKI 平台获取新密钥对
-    pub fn fetch_from_pki(base_url: &str, base_config: &BaseConfig) -> Result<Self, String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS))
-            .build()
-            .map_err(|e| format!("无法创建 HTTP 客户端: {}", e))?;
+    /// 将密钥对写入存储
+    pub fn save_to_storage(&self, storage: &dyn Storage, key: &str) -> Result<(), String> {
+        let encoded = bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| tr_format!("无法序列化密钥对: {}", "cannot serialize the key pair: {}", e))?;
+        storage
+            .put(key, &encoded)
+            .map_err(|e| tr_format!("无法写入密钥对 {}: {}", "cannot write key pair {}: {}", key, e))
+    }
+
+    /// 从 PKI 平台获取新密钥对，`identity` 为 mTLS 客户端身份，`auth` 为认证头，
+    /// 响应体不超过 `max_response_bytes`，请求超过 `timeout` 未完成时失败
+    pub fn fetch_from_pki(
+        base_url: &str,
+        base_config: &BaseConfig,
+        identity: Option<&Identity>,
+        auth: Option<&AuthHeader>,
+        max_response_bytes: u64,
+        timeout: Duration,
+    ) -> Result<Self, NetworkFailure> {
+        let client = http_client(identity, timeout)?;
         
         let url = format!("{}/v1/keypair", base_url);
         let request = KeyPairRequest {
@@ -152,52 +515,129 @@ impl KeyPair {
             flow: base_config.flow.clone(),
         };
         
-        let response = client
-            .post(&url)
+        let response = authorize(client.post(&url), auth)
             .json(&request)
             .send()
-            .map_err(|e| format!("网络请求失败: {}", e))?;
+            .map_err(|e| request_failure(tr_format!("网络请求失败: {} (URL: {})", "network request failed: {} (URL: {})", e, url), e))?;
         
-        if !response.status().is_success() {
-            return Err(format!(
-                "PKI 平台返回错误: {} {}",
-                response.status(),
-                response.text().unwrap_or_default()
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_failure(
+                status,
+                tr_format!("PKI 平台返回错误: {} {}", "PKI platform returned an error: {} {}", status, read_error_text(response, max_response_bytes)),
             ));
         }
         
-        let keypair_resp: KeyPairResponse = response
-            .json()
-            .map_err(|e| format!("无法解析响应: {}", e))?;
+        let keypair_resp: KeyPairResponse = read_json(response, max_response_bytes)?;
         
         Ok(KeyPair {
             priv_key: keypair_resp.priv_key,
             pub_key: keypair_resp.pub_key,
             key_id: keypair_resp.key_id.unwrap_or_default(),
             base_config: keypair_resp.base_config,
+            fetched_at: Some(unix_now()),
+            ttl_secs: None,
         })
     }
 
-    /// 优先从本地加载，不存在或损坏则从平台获取并保存
+    /// 优先从 `storage` 加载，不存在、损坏或超过有效期则调用 `fetch`（通常为 [`KeyPair::fetch_from_pki`]）
+    /// 获取并保存；`ttl_secs` 记录到新密钥对中，并优先于缓存中记录的有效期
     pub fn get_or_fetch(
-        path: &str,
-        base_url: &str,
-        base_config: &BaseConfig,
-    ) -> Result<Self, String> {
-        // 尝试从本地加载
-        match Self::load_from_file(path) {
-            Ok(keypair) => Ok(keypair),
+        storage: &dyn Storage,
+        key: &str,
+        ttl_secs: Option<u64>,
+        fetch: impl FnOnce() -> Result<Self, NetworkFailure>,
+    ) -> Result<Self, NetworkFailure> {
+        // 尝试从存储加载
+        match Self::load_from_storage(storage, key) {
+            Ok(keypair) if !keypair.is_expired(ttl_secs, unix_now()) => Ok(keypair),
+            Ok(stale) => {
+                // 新密钥对沿用过期密钥对的有效期
+                println!("{}", tr("密钥对已超过有效期，从 PKI 平台重新获取...", "key pair expired, fetching a new one from the PKI platform..."));
+                Self::fetch_and_save(storage, key, ttl_secs.or(stale.ttl_secs), fetch)
+            }
             Err(_) => {
                 // 本地不存在或损坏，从平台获取
-                println!("从 PKI 平台获取新密钥对...");
-                let keypair = Self::fetch_from_pki(base_url, base_config)?;
-                // 保存到本地
-                keypair.save_to_file(path)?;
-                println!("密钥对已保存到: {}", path);
-                Ok(keypair)
+                println!("{}", tr("从 PKI 平台获取新密钥对...", "fetching a new key pair from the PKI platform..."));
+                Self::fetch_and_save(storage, key, ttl_secs, fetch)
             }
         }
     }
+
+    fn fetch_and_save(
+        storage: &dyn Storage,
+        key: &str,
+        ttl_secs: Option<u64>,
+        fetch: impl FnOnce() -> Result<Self, NetworkFailure>,
+    ) -> Result<Self, NetworkFailure> {
+        let mut keypair = fetch()?;
+        keypair.fetched_at.get_or_insert_with(unix_now);
+        keypair.ttl_secs = ttl_secs;
+        keypair.save_to_storage(storage, key)?;
+        println!("{}", tr_format!("密钥对已保存到: {}", "key pair saved to: {}", key));
+        Ok(keypair)
+    }
+}
+
+/// 批量验签中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyItem {
+    pub base_config: BaseConfig,
+    #[serde(rename = "pub")]
+    pub pub_key: String,
+    pub digest: String,
+    pub signature: String,
+}
+
+/// 批量验签中单项的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyOutcome {
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchVerifyRequest<'a> {
+    items: &'a [VerifyItem],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BatchVerifyResult {
+    result: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BatchVerifyResponse {
+    results: Vec<BatchVerifyResult>,
+}
+
+/// 整个操作（如一次批量验签）共享的重试次数预算，克隆后共用同一计数
+///
+/// 每个请求自己的 `retry_times` 仍然生效；预算耗尽后所有后续请求都不再重试，
+/// PKI 平台大面积故障时尽快失败，而不是每个文件都重试一遍。
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    remaining: Arc<AtomicU32>,
+}
+
+impl RetryBudget {
+    pub fn new(total: u32) -> Self {
+        RetryBudget { remaining: Arc::new(AtomicU32::new(total)) }
+    }
+
+    /// 剩余可用的重试次数
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::SeqCst)
+    }
+
+    /// 占用一次重试，预算已用尽时返回 false
+    pub fn try_take(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
 }
 
 /// PKI API 客户端
@@ -205,6 +645,13 @@ pub struct PkiClient {
     base_url: String,
     retry_times: u32,
     retry_delay: u64, // 毫秒
+    batch_verify_path: Option<String>,
+    max_response_bytes: u64,
+    retry_budget: Option<RetryBudget>,
+    /// 重建 HTTP 客户端时沿用的 mTLS 身份和超时时间
+    identity: Option<Identity>,
+    timeout: Duration,
+    auth: Option<AuthHeader>,
     client: Client,
 }
 
@@ -214,6 +661,11 @@ impl std::fmt::Debug for PkiClient {
             .field("base_url", &self.base_url)
             .field("retry_times", &self.retry_times)
             .field("retry_delay", &self.retry_delay)
+            .field("batch_verify_path", &self.batch_verify_path)
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("retry_budget", &self.retry_budget)
+            .field("timeout", &self.timeout)
+            .field("auth", &self.auth)
             .finish()
     }
 }
@@ -221,78 +673,175 @@ impl std::fmt::Debug for PkiClient {
 impl PkiClient {
     /// 创建新的 PKI 客户端
     pub fn new(base_url: String, retry_times: u32, retry_delay: u64) -> Result<Self, String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS))
-            .build()
-            .map_err(|e| format!("无法创建 HTTP 客户端: {}", e))?;
+        let timeout = Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS);
+        let client = http_client(None, timeout)?;
         
         Ok(PkiClient {
             base_url,
             retry_times,
             retry_delay,
+            batch_verify_path: None,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            retry_budget: None,
+            identity: None,
+            timeout,
+            auth: None,
             client,
         })
     }
 
-    /// 调用签名接口
-    pub fn sign_digest(
+    /// 使用 mTLS 客户端身份重建 HTTP 客户端
+    pub fn with_identity(mut self, identity: &Identity) -> Result<Self, String> {
+        self.client = http_client(Some(identity), self.timeout)?;
+        self.identity = Some(identity.clone());
+        Ok(self)
+    }
+
+    /// 设置单个 HTTP 请求的超时时间并重建 HTTP 客户端，默认 [`DEFAULT_HTTP_TIMEOUT_SECS`] 秒
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<Self, String> {
+        self.client = http_client(self.identity.as_ref(), timeout)?;
+        self.timeout = timeout;
+        Ok(self)
+    }
+
+    /// 设置批量验签接口路径（相对 base_url，例如 `/v1/verify/digests`），None 表示逐个验签
+    pub fn with_batch_verify_path(mut self, path: Option<String>) -> Self {
+        self.batch_verify_path = path;
+        self
+    }
+
+    /// 设置响应体大小上限（字节），超过时请求失败
+    pub fn with_max_response_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    /// 设置共享的重试预算，None 表示只受每个请求的 `retry_times` 限制
+    pub fn with_retry_budget(mut self, budget: Option<RetryBudget>) -> Self {
+        self.retry_budget = budget;
+        self
+    }
+
+    /// 设置附加到每个请求上的认证头，None 表示不认证
+    pub fn with_auth(mut self, auth: Option<AuthHeader>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// 发送 JSON POST 请求，网络连接错误时按配置重试，收到响应后不再重试
+    fn post_with_retry<T: Serialize + ?Sized>(
         &self,
-        priv_key: &str,
-        digest: &str,
-        base_config: &BaseConfig,
-    ) -> Result<(String, Option<String>), String> {
-        let url = format!("{}/v1/sign/digest", self.base_url);
-        let request = SignDigestRequest {
-            base_config: base_config.clone(),
-            priv_key: priv_key.to_string(),
-            digest: digest.to_string(),
-        };
-        
+        url: &str,
+        request: &T,
+        label: &str,
+        action: &str,
+    ) -> Result<Response, NetworkFailure> {
         let mut last_error: Option<String> = None;
         for attempt in 0..=self.retry_times {
-            match self.client.post(&url).json(&request).send() {
+            let attempt_start = Instant::now();
+            let sent = authorize(self.client.post(url), self.auth.as_ref()).json(request).send();
+            timings::record(
+                tr_format!("{} 第 {} 次尝试", "{} attempt {}", label, attempt + 1),
+                attempt_start.elapsed(),
+            );
+            match sent {
                 Ok(response) => {
                     // 收到响应，无论状态码如何都不重试
                     let status = response.status();
                     if !status.is_success() {
-                        let error_text = response.text().unwrap_or_else(|_| "无法读取错误信息".to_string());
-                        return Err(format!(
-                            "PKI 平台返回错误 (HTTP {}): {}",
+                        let error_text = read_error_text(response, self.max_response_bytes);
+                        return Err(status_failure(
                             status,
-                            error_text
+                            tr_format!("PKI 平台返回错误 (HTTP {}): {}", "PKI platform returned an error (HTTP {}): {}", status, error_text),
                         ));
                     }
-                    
-                    let sign_resp: SignDigestResponse = response
-                        .json()
-                        .map_err(|e| format!("无法解析响应 JSON: {}", e))?;
-                    
-                    return Ok((sign_resp.signature, sign_resp.cert));
+                    return Ok(response);
                 }
                 Err(e) => {
-                    // 检查是否是网络连接错误（超时、连接失败等）
-                    let is_retryable = e.is_timeout() || e.is_connect() || e.is_request();
-                    
-                    if is_retryable && attempt < self.retry_times {
-                        eprintln!("网络连接失败（{}），{} 毫秒后重试 (尝试 {}/{})...", 
-                            e, self.retry_delay, attempt + 1, self.retry_times + 1);
+                    if is_retryable(&e) && attempt < self.retry_times {
+                        if self.retry_budget.as_ref().is_some_and(|budget| !budget.try_take()) {
+                            return Err(request_failure(
+                                tr_format!("网络请求失败: {} (URL: {})，本次操作的重试预算已用尽", "network request failed: {} (URL: {}), the retry budget of this operation is used up", e, url),
+                                e,
+                            ));
+                        }
+                        eprintln!("{}", tr_format!(
+                            "网络连接失败（{}），{} 毫秒后重试 (尝试 {}/{})...",
+                            "network connection failed ({}), retrying in {} ms (attempt {}/{})...",
+                            e, self.retry_delay, attempt + 1, self.retry_times + 1
+                        ));
                         thread::sleep(Duration::from_millis(self.retry_delay));
-                        last_error = Some(format!("网络连接失败: {} (URL: {})", e, url));
+                        last_error = Some(tr_format!("网络连接失败: {} (URL: {})", "network connection failed: {} (URL: {})", e, url));
                         continue;
                     } else {
                         // 非可重试错误或已达到最大重试次数，直接返回错误
-                        return Err(format!("网络请求失败: {} (URL: {})", e, url));
+                        return Err(request_failure(tr_format!("网络请求失败: {} (URL: {})", "network request failed: {} (URL: {})", e, url), e));
                     }
                 }
             }
         }
         
         // 理论上不会到达这里（所有路径都已返回），但为了代码完整性保留
-        Err(format!(
-            "签名请求失败（已重试 {} 次）: {}",
+        Err(tr_format!(
+            "{}请求失败（已重试 {} 次）: {}",
+            "{} request failed after {} retries: {}",
+            action,
             self.retry_times,
-            last_error.unwrap_or_else(|| "未知错误".to_string())
-        ))
+            last_error.unwrap_or_else(|| tr("未知错误", "unknown error").to_string())
+        )
+        .into())
+    }
+
+    /// 检查 PKI 平台是否可达：向 `{base_url}/v1/health` 发送一次 GET，不重试，
+    /// 超时时间为 [`HEALTH_CHECK_TIMEOUT_SECS`] 秒与客户端超时时间中的较小值
+    ///
+    /// 只要收到 5xx 以外的响应即视为可达（平台未实现该接口时返回 404 也算），
+    /// 用于在耗时的打包之前发现 `pki_base_url` 写错或网络不通。
+    pub fn health_check(&self) -> Result<(), NetworkFailure> {
+        let url = format!("{}/v1/health", self.base_url);
+        let timeout = self.timeout.min(Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS));
+        let start = Instant::now();
+        let sent = authorize(self.client.get(&url), self.auth.as_ref()).timeout(timeout).send();
+        timings::record(timings::label_pki_health(), start.elapsed());
+        let response = sent.map_err(|e| request_failure(tr_format!("PKI 平台不可达: {} (URL: {})", "PKI platform unreachable: {} (URL: {})", e, url), e))?;
+        let status = response.status();
+        if status.is_server_error() {
+            return Err(status_failure(
+                status,
+                tr_format!("PKI 平台不可用 (HTTP {}): {}", "PKI platform unavailable (HTTP {}): {}", status, read_error_text(response, self.max_response_bytes)),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 调用签名接口
+    pub fn sign_digest(
+        &self,
+        priv_key: &str,
+        digest: &str,
+        base_config: &BaseConfig,
+    ) -> Result<(String, Option<String>), NetworkFailure> {
+        timings::measure(tr_format!("{}（含重试）", "{} (with retries)", timings::label_pki_sign()), || {
+            self.sign_digest_with_retry(priv_key, digest, base_config)
+        })
+    }
+
+    fn sign_digest_with_retry(
+        &self,
+        priv_key: &str,
+        digest: &str,
+        base_config: &BaseConfig,
+    ) -> Result<(String, Option<String>), NetworkFailure> {
+        let url = format!("{}/v1/sign/digest", self.base_url);
+        let request = SignDigestRequest {
+            base_config: base_config.clone(),
+            priv_key: priv_key.to_string(),
+            digest: digest.to_string(),
+        };
+        
+        let response = self.post_with_retry(&url, &request, timings::label_pki_sign(), tr("签名", "signing"))?;
+        let sign_resp: SignDigestResponse = read_json(response, self.max_response_bytes)?;
+        Ok((sign_resp.signature, sign_resp.cert))
     }
 
     /// 调用验签接口
@@ -302,7 +851,19 @@ impl PkiClient {
         digest: &str,
         signature: &str,
         base_config: &BaseConfig,
-    ) -> Result<bool, String> {
+    ) -> Result<bool, NetworkFailure> {
+        timings::measure(tr_format!("{}（含重试）", "{} (with retries)", timings::label_pki_verify()), || {
+            self.verify_digest_with_retry(pub_key, digest, signature, base_config)
+        })
+    }
+
+    fn verify_digest_with_retry(
+        &self,
+        pub_key: &str,
+        digest: &str,
+        signature: &str,
+        base_config: &BaseConfig,
+    ) -> Result<bool, NetworkFailure> {
         let url = format!("{}/v1/verify/digest", self.base_url);
         let request = VerifyDigestRequest {
             base_config: base_config.clone(),
@@ -311,58 +872,51 @@ impl PkiClient {
             signature: signature.to_string(),
         };
         
-        let mut last_error: Option<String> = None;
-        for attempt in 0..=self.retry_times {
-            match self.client.post(&url).json(&request).send() {
-                Ok(response) => {
-                    // 收到响应，无论状态码如何都不重试
-                    let status = response.status();
-                    if !status.is_success() {
-                        let error_text = response.text().unwrap_or_else(|_| "无法读取错误信息".to_string());
-                        return Err(format!(
-                            "PKI 平台返回错误 (HTTP {}): {}",
-                            status,
-                            error_text
-                        ));
-                    }
-                    
-                    let verify_resp: VerifyDigestResponse = response
-                        .json()
-                        .map_err(|e| format!("无法解析响应 JSON: {}", e))?;
-                    
-                    if verify_resp.result == "OK" {
-                        return Ok(true);
-                    } else {
-                        return Err(format!(
-                            "验签失败: {}",
-                            verify_resp.error.unwrap_or_else(|| "未知错误".to_string())
-                        ));
-                    }
-                }
-                Err(e) => {
-                    // 检查是否是网络连接错误（超时、连接失败等）
-                    let is_retryable = e.is_timeout() || e.is_connect() || e.is_request();
-                    
-                    if is_retryable && attempt < self.retry_times {
-                        eprintln!("网络连接失败（{}），{} 毫秒后重试 (尝试 {}/{})...", 
-                            e, self.retry_delay, attempt + 1, self.retry_times + 1);
-                        thread::sleep(Duration::from_millis(self.retry_delay));
-                        last_error = Some(format!("网络连接失败: {} (URL: {})", e, url));
-                        continue;
-                    } else {
-                        // 非可重试错误或已达到最大重试次数，直接返回错误
-                        return Err(format!("网络请求失败: {} (URL: {})", e, url));
-                    }
-                }
+        let response = self.post_with_retry(&url, &request, timings::label_pki_verify(), tr("验签", "verification"))?;
+        let verify_resp: VerifyDigestResponse = read_json(response, self.max_response_bytes)?;
+        Ok(verify_resp.into_result()?)
+    }
+
+    /// 批量验签，结果与 `items` 一一对应
+    ///
+    /// 未配置批量接口时退化为并行地逐个调用 [`PkiClient::verify_digest`]，全部完成后再返回。
+    pub fn verify_digests_batch(&self, items: &[VerifyItem]) -> Result<Vec<VerifyOutcome>, NetworkFailure> {
+        let path = match &self.batch_verify_path {
+            Some(path) => path,
+            None => {
+                return parallel_map(items.len(), |i| {
+                    let item = &items[i];
+                    self.verify_digest(&item.pub_key, &item.digest, &item.signature, &item.base_config)
+                        .map(|ok| VerifyOutcome { ok, error: None })
+                })
+                .into_iter()
+                .collect();
             }
+        };
+        if items.is_empty() {
+            return Ok(vec![]);
         }
-        
-        // 理论上不会到达这里，但为了安全起见保留
-        Err(format!(
-            "验签请求失败（已重试 {} 次）: {}",
-            self.retry_times,
-            last_error.unwrap_or_else(|| "未知错误".to_string())
-        ))
+
+        timings::measure(tr_format!("{}（批量 {} 项，含重试）", "{} (batch of {}, with retries)", timings::label_pki_verify(), items.len()), || {
+            let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+            let request = BatchVerifyRequest { items };
+            let response = self.post_with_retry(&url, &request, timings::label_pki_verify(), tr("批量验签", "batch verification"))?;
+            let batch_resp: BatchVerifyResponse = read_json(response, self.max_response_bytes)?;
+            if batch_resp.results.len() != items.len() {
+                return Err(tr_format!(
+                    "批量验签结果数量不符: 请求 {} 项，返回 {} 项",
+                    "batch verification returned {1} results for {0} requested items",
+                    items.len(),
+                    batch_resp.results.len()
+                )
+                .into());
+            }
+            Ok(batch_resp
+                .results
+                .into_iter()
+                .map(|r| VerifyOutcome { ok: r.result == "OK", error: r.error })
+                .collect())
+        })
     }
 }
 
@@ -371,3 +925,560 @@ pub fn digest_to_hex_string(digest: &[u8]) -> String {
     digest.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
+/// 解析摘要文件内容：去掉首尾空白后必须是 64 个十六进制字符（SHA256），返回小写形式
+pub fn parse_digest_hex(text: &str) -> Result<String, String> {
+    let digest = text.trim();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(tr_format!("无效的 SHA256 摘要，应为 64 个十六进制字符: {:?}", "invalid SHA256 digest, expected 64 hex characters: {:?}", digest));
+    }
+    Ok(digest.to_ascii_lowercase())
+}
+
+
+#[cfg(test)]
+/// 依次应答 `replies.len()` 个请求，返回 (请求路径, 请求体) 列表
+pub(crate) fn mock_server(replies: Vec<String>) -> (String, thread::JoinHandle<Vec<(String, String)>>) {
+    let (base_url, handle) = mock_server_with_headers(replies);
+    let handle = thread::spawn(move || {
+        handle.join().unwrap().into_iter().map(|(path, body, _)| (path, body)).collect()
+    });
+    (base_url, handle)
+}
+
+/// 模拟服务器收到的请求：(请求路径, 请求体, 头部行)
+#[cfg(test)]
+type MockRequest = (String, String, Vec<String>);
+
+#[cfg(test)]
+/// 同 [`mock_server`]，另外返回每个请求的头部行（去掉行尾换行）
+fn mock_server_with_headers(replies: Vec<String>) -> (String, thread::JoinHandle<Vec<MockRequest>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut requests = vec![];
+        for reply in replies {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            let mut headers = vec![];
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                headers.push(line.trim_end().to_string());
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let path = request_line.split_whitespace().nth(1).unwrap().to_string();
+            requests.push((path, String::from_utf8(body).unwrap(), headers));
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                reply.len(),
+                reply
+            )
+            .unwrap();
+        }
+        requests
+    });
+    (base_url, handle)
+}
+
+#[test]
+fn test_verify_digests_batch() {
+    let base_config = BaseConfig {
+        algo: "sm2".to_string(),
+        kms: String::new(),
+        flow: "sign".to_string(),
+    };
+    let items: Vec<VerifyItem> = (0..3)
+        .map(|i| VerifyItem {
+            base_config: base_config.clone(),
+            pub_key: format!("pub{}", i),
+            digest: format!("digest{}", i),
+            signature: format!("sig{}", i),
+        })
+        .collect();
+
+    // 配置了批量接口：一次请求返回全部结果
+    let (base_url, server) = mock_server(vec![
+        r#"{"results":[{"result":"OK"},{"result":"FAIL","error":"bad sig"},{"result":"OK","error":null}]}"#.to_string(),
+    ]);
+    let client = PkiClient::new(base_url, 0, 0)
+        .unwrap()
+        .with_batch_verify_path(Some("/v1/verify/digests".to_string()));
+    let outcomes = client.verify_digests_batch(&items).unwrap();
+    assert_eq!(
+        outcomes,
+        vec![
+            VerifyOutcome { ok: true, error: None },
+            VerifyOutcome { ok: false, error: Some("bad sig".to_string()) },
+            VerifyOutcome { ok: true, error: None },
+        ]
+    );
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].0, "/v1/verify/digests");
+    let body: serde_json::Value = serde_json::from_str(&requests[0].1).unwrap();
+    assert_eq!(body["items"].as_array().unwrap().len(), 3);
+    assert_eq!(body["items"][1]["pub"], "pub1");
+
+    // 返回数量与请求不符
+    let (base_url, server) = mock_server(vec![r#"{"results":[{"result":"OK"}]}"#.to_string()]);
+    let client = PkiClient::new(base_url, 0, 0)
+        .unwrap()
+        .with_batch_verify_path(Some("v1/verify/digests".to_string()));
+    assert!(client.verify_digests_batch(&items).is_err());
+    server.join().unwrap();
+
+    // 未配置批量接口：逐个调用单项验签接口
+    let single = r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"result":"OK"}"#.to_string();
+    let (base_url, server) = mock_server(vec![single; 3]);
+    let client = PkiClient::new(base_url, 0, 0).unwrap();
+    let outcomes = client.verify_digests_batch(&items).unwrap();
+    assert!(outcomes.iter().all(|o| o.ok));
+    let requests = server.join().unwrap();
+    assert!(requests.iter().all(|(path, _)| path == "/v1/verify/digest"));
+    // 逐项请求并行发出，到达顺序不定
+    for i in 0..3 {
+        assert!(requests.iter().any(|(_, body)| body.contains(&format!("digest{}", i))));
+    }
+}
+
+#[test]
+fn test_pki_client_with_identity() {
+    let identity = load_client_identity("test/cert.pem", "test/key.pem").unwrap();
+    let client = PkiClient::new("https://pki.example.com".to_string(), 0, 0)
+        .unwrap()
+        .with_identity(&identity)
+        .unwrap();
+    assert_eq!(client.base_url, "https://pki.example.com");
+
+    assert!(load_client_identity("test/cert.pem", "test/no-such-key.pem").unwrap_err().contains("私钥"));
+    // 证书文件内容不是证书
+    assert!(load_client_identity("test/key.pem", "test/key.pem").is_err());
+}
+
+#[test]
+fn test_network_signature_sign_digest() {
+    let digest = parse_digest_hex(&format!("  {}\n", "AB".repeat(32))).unwrap();
+    assert_eq!(digest, "ab".repeat(32));
+    assert!(parse_digest_hex("abcd").is_err());
+    assert!(parse_digest_hex(&"zz".repeat(32)).is_err());
+
+    let (base_url, server) = mock_server(vec![
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"signature":"sig-for-digest"}"#.to_string(),
+    ]);
+    let client = PkiClient::new(base_url, 0, 0).unwrap();
+    let keypair = KeyPair {
+        priv_key: "priv".to_string(),
+        pub_key: "pub".to_string(),
+        key_id: "key-1".to_string(),
+        base_config: BaseConfig {
+            algo: "sm2".to_string(),
+            kms: String::new(),
+            flow: "sign".to_string(),
+        },
+        fetched_at: None,
+        ttl_secs: None,
+    };
+    let network_sig = NetworkSignature::sign(&client, &keypair, &digest).unwrap();
+    assert_eq!(network_sig.signature, "sig-for-digest");
+    assert_eq!(network_sig.pub_key, "pub");
+    assert_eq!(network_sig.kms, None);
+    assert_eq!(network_sig.key_id.as_deref(), Some("key-1"));
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0].0, "/v1/sign/digest");
+    let body: serde_json::Value = serde_json::from_str(&requests[0].1).unwrap();
+    assert_eq!(body["digest"], digest);
+    assert_eq!(body["priv"], "priv");
+
+    let decoded = NetworkSignature::from_bytes(&network_sig.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.signature, network_sig.signature);
+    assert_eq!(decoded.key_id, network_sig.key_id);
+}
+
+#[test]
+fn test_network_signature_builder() {
+    let sig = NetworkSignature::builder()
+        .with_pub_key("pub")
+        .with_signature("sig")
+        .with_algo("Ed25519")
+        .with_flow("sign")
+        .with_key_id("key-1")
+        .build();
+    assert_eq!((sig.pub_key.as_str(), sig.signature.as_str()), ("pub", "sig"));
+    assert_eq!((sig.kms, sig.key_id.as_deref()), (None, Some("key-1")));
+    // 未指定时签名输入格式按算法选择
+    assert_eq!(sig.scheme, SignScheme::RawBase64);
+    let sig = NetworkSignature::builder().with_algo("ed25519").with_scheme(SignScheme::Sha256Hex).build();
+    assert_eq!(sig.scheme, SignScheme::Sha256Hex);
+    assert_eq!(NetworkSignature::builder().with_algo("sm2").with_kms("hsm").build().kms.as_deref(), Some("hsm"));
+}
+
+#[test]
+fn test_pki_client_health_check() {
+    use std::net::TcpListener;
+
+    let (base_url, server) = mock_server(vec![r#"{"status":"ok"}"#.to_string()]);
+    PkiClient::new(base_url, 3, 1).unwrap().health_check().unwrap();
+    assert_eq!(server.join().unwrap()[0].0, "/v1/health");
+
+    // 端口未监听时立即失败，不重试
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let budget = RetryBudget::new(3);
+    let client = PkiClient::new(format!("http://127.0.0.1:{}", port), 3, 1)
+        .unwrap()
+        .with_retry_budget(Some(budget.clone()));
+    let err = client.health_check().unwrap_err();
+    assert!(err.message.contains("不可达") && err.message.contains("/v1/health"), "{}", err);
+    assert_eq!(err.kind, NetworkErrorKind::Connection);
+    let err = crate::error::CrateSpecError::PkiError(err);
+    let source = std::error::Error::source(&err).expect("应保留 reqwest 错误");
+    assert!(source.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_connect()));
+    assert_eq!(budget.remaining(), 3);
+
+    // 接受连接但不响应时按较短的超时失败
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = PkiClient::new(format!("http://{}", listener.local_addr().unwrap()), 0, 0)
+        .unwrap()
+        .with_timeout(Duration::from_millis(200))
+        .unwrap();
+    let start = Instant::now();
+    assert_eq!(client.health_check().unwrap_err().kind, NetworkErrorKind::Timeout);
+    assert!(start.elapsed() < Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS));
+
+    // 5xx 视为不可用，记录状态码
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        use std::io::{BufRead, BufReader, Write};
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while line != "\r\n" {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\nConnection: close\r\n\r\ndown")
+            .unwrap();
+    });
+    let err = PkiClient::new(base_url, 0, 0).unwrap().health_check().unwrap_err();
+    assert_eq!(err.kind, NetworkErrorKind::HttpStatus(503));
+    assert!(err.message.contains("down"), "{}", err);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_pki_client_timeout() {
+    use std::net::TcpListener;
+
+    // 接受连接但从不响应的服务端
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let base_config = BaseConfig { algo: "sm2".to_string(), kms: String::new(), flow: "sign".to_string() };
+    let identity = load_client_identity("test/cert.pem", "test/key.pem").unwrap();
+    let client = PkiClient::new(base_url.clone(), 0, 0)
+        .unwrap()
+        .with_timeout(Duration::from_millis(200))
+        .unwrap()
+        .with_identity(&identity)
+        .unwrap();
+    // 设置身份后重建的客户端沿用超时时间
+    assert_eq!(client.timeout, Duration::from_millis(200));
+    let start = Instant::now();
+    assert!(client.verify_digest("pub", "digest", "sig", &base_config).is_err());
+    assert!(start.elapsed() < Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS));
+
+    let start = Instant::now();
+    assert!(KeyPair::fetch_from_pki(&base_url, &base_config, None, None, 1024, Duration::from_millis(200)).is_err());
+    assert!(start.elapsed() < Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS));
+    drop(listener);
+}
+
+#[test]
+fn test_auth_header_on_every_request() {
+    let base_config = BaseConfig {
+        algo: "sm2".to_string(),
+        kms: String::new(),
+        flow: "sign".to_string(),
+    };
+    let (base_url, server) = mock_server_with_headers(vec![
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"signature":"sig"}"#.to_string(),
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"result":"OK"}"#.to_string(),
+        r#"{"status":"ok"}"#.to_string(),
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"priv":"k","pub":"p"}"#.to_string(),
+    ]);
+    let auth = AuthHeader::new("s3cret", None).unwrap();
+    let client = PkiClient::new(base_url.clone(), 0, 0).unwrap().with_auth(Some(auth.clone()));
+    client.sign_digest("priv", "digest", &base_config).unwrap();
+    assert!(client.verify_digest("pub", "digest", "sig", &base_config).unwrap());
+    client.health_check().unwrap();
+    let timeout = Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS);
+    KeyPair::fetch_from_pki(&base_url, &base_config, None, Some(&auth), DEFAULT_MAX_RESPONSE_BYTES, timeout).unwrap();
+    for (path, _, headers) in server.join().unwrap() {
+        assert!(headers.iter().any(|h| h == "authorization: Bearer s3cret"), "{}: {:?}", path, headers);
+    }
+
+    // 令牌不出现在 Debug 输出中
+    assert!(!format!("{:?}", client).contains("s3cret"));
+    assert!(!format!("{:?}", auth).contains("s3cret"));
+
+    // 自定义头名直接发送令牌
+    let (base_url, server) = mock_server_with_headers(vec![r#"{"status":"ok"}"#.to_string()]);
+    let auth = AuthHeader::new("s3cret", Some("X-Api-Key")).unwrap();
+    assert_eq!(auth.name(), "x-api-key");
+    PkiClient::new(base_url, 0, 0).unwrap().with_auth(Some(auth)).health_check().unwrap();
+    let (_, _, headers) = server.join().unwrap().remove(0);
+    assert!(headers.iter().any(|h| h == "x-api-key: s3cret"), "{:?}", headers);
+    assert!(!headers.iter().any(|h| h.starts_with("authorization")));
+
+    assert!(AuthHeader::new("", None).is_err());
+    assert!(AuthHeader::new("tok", Some("bad header")).is_err());
+    assert!(AuthHeader::new("line\nbreak", None).is_err());
+}
+
+#[test]
+fn test_response_size_limit() {
+    let base_config = BaseConfig {
+        algo: "sm2".to_string(),
+        kms: String::new(),
+        flow: "sign".to_string(),
+    };
+    let oversized = format!(
+        r#"{{"base_config":{{"algo":"sm2","kms":"","flow":"sign"}},"signature":"{}"}}"#,
+        "s".repeat(4096)
+    );
+
+    let (base_url, server) = mock_server(vec![oversized.clone(), oversized.clone()]);
+    let client = PkiClient::new(base_url, 0, 0).unwrap().with_max_response_bytes(1024);
+    let err = client.sign_digest("priv", "digest", &base_config).unwrap_err();
+    assert!(err.message.contains("response too large"), "{}", err);
+    assert!(client.verify_digest("pub", "digest", "sig", &base_config).unwrap_err().message.contains("response too large"));
+    server.join().unwrap();
+
+    // 同样的响应在默认上限内正常解析
+    let (base_url, server) = mock_server(vec![oversized]);
+    let client = PkiClient::new(base_url, 0, 0).unwrap();
+    let (signature, _) = client.sign_digest("priv", "digest", &base_config).unwrap();
+    assert_eq!(signature.len(), 4096);
+    server.join().unwrap();
+
+    let keypair = format!(
+        r#"{{"base_config":{{"algo":"sm2","kms":"","flow":"sign"}},"priv":"{}","pub":"p"}}"#,
+        "k".repeat(4096)
+    );
+    let (base_url, server) = mock_server(vec![keypair]);
+    let err = KeyPair::fetch_from_pki(&base_url, &base_config, None, None, 1024, Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS)).unwrap_err();
+    assert!(err.message.contains("response too large"), "{}", err);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_ed25519_signs_raw_content() {
+    use crate::utils::context::{PackageContext, SIGTYPE};
+    use crate::utils::pkcs::PKCS;
+
+    assert_eq!(SignScheme::for_algo("Ed25519"), SignScheme::RawBase64);
+    assert_eq!(SignScheme::for_algo("sm2"), SignScheme::Sha256Hex);
+    assert_eq!(SignScheme::RawBase64.prepare(b"crate"), "Y3JhdGU=");
+
+    let keypair = KeyPair {
+        priv_key: "priv".to_string(),
+        pub_key: "pub".to_string(),
+        key_id: String::new(),
+        base_config: BaseConfig {
+            algo: ALGO_ED25519.to_string(),
+            kms: String::new(),
+            flow: "sign".to_string(),
+        },
+        fetched_at: None,
+        ttl_secs: None,
+    };
+    // 预先计算的摘要无法用 Ed25519 签名
+    let client = PkiClient::new("http://127.0.0.1:9".to_string(), 0, 0).unwrap();
+    assert!(NetworkSignature::sign(&client, &keypair, &"ab".repeat(32)).unwrap_err().message.contains(ALGO_ED25519));
+
+    let (base_url, server) = mock_server(vec![
+        r#"{"base_config":{"algo":"ed25519","kms":"","flow":"sign"},"signature":"ed-sig"}"#.to_string(),
+        r#"{"base_config":{"algo":"ed25519","kms":"","flow":"sign"},"result":"OK"}"#.to_string(),
+    ]);
+    let client = Arc::new(PkiClient::new(base_url, 0, 0).unwrap());
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("ed".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(b"crate".to_vec());
+    pack_context.network_client = Some(client.clone());
+    pack_context.network_keypair = Some(Arc::new(keypair));
+    pack_context.add_sig(PKCS::new(), SIGTYPE::NETWORK);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let mut decoded = PackageContext::new();
+    decoded.network_client = Some(client);
+    decoded.decode_from_crate_package(&bin).unwrap();
+    let network_sig = decoded.sigs[0].network_signature().unwrap().unwrap();
+    assert_eq!(network_sig.scheme, SignScheme::RawBase64);
+
+    // 签名和验签请求都发送原始内容的 base64，而不是 SHA256 摘要
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0].0, "/v1/sign/digest");
+    assert_eq!(requests[1].0, "/v1/verify/digest");
+    for (_, body) in &requests {
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["digest"], "Y3JhdGU=");
+    }
+
+    // 旧格式（无 scheme 字段）按 SHA256 摘要处理
+    #[derive(Encode)]
+    struct Legacy {
+        pub_key: String,
+        signature: String,
+        algo: String,
+        flow: String,
+        kms: Option<String>,
+        key_id: Option<String>,
+    }
+    let legacy = Legacy {
+        pub_key: "pub".to_string(),
+        signature: "sig".to_string(),
+        algo: "sm2".to_string(),
+        flow: "sign".to_string(),
+        kms: None,
+        key_id: None,
+    };
+    let bin = bincode::encode_to_vec(&legacy, bincode::config::standard()).unwrap();
+    let sig = NetworkSignature::from_bytes(&bin).unwrap();
+    assert_eq!(sig.scheme, SignScheme::Sha256Hex);
+    assert_eq!(sig.signature, "sig");
+}
+
+#[test]
+fn test_retry_budget_shared_across_calls() {
+    use std::net::TcpListener;
+
+    // 绑定后立即释放端口，连接被拒绝，属于可重试错误
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let base_config = BaseConfig {
+        algo: "sm2".to_string(),
+        kms: String::new(),
+        flow: "sign".to_string(),
+    };
+
+    let budget = RetryBudget::new(3);
+    let client = PkiClient::new(base_url, 2, 1)
+        .unwrap()
+        .with_retry_budget(Some(budget.clone()));
+    // 第一次调用用掉 2 次重试后失败
+    let err = client.sign_digest("priv", "digest", &base_config).unwrap_err();
+    assert!(!err.message.contains("重试预算"), "{}", err);
+    assert_eq!(budget.remaining(), 1);
+    // 第二次调用只能再重试 1 次
+    let err = client.sign_digest("priv", "digest", &base_config).unwrap_err();
+    assert!(err.message.contains("重试预算已用尽"), "{}", err);
+    assert_eq!(budget.remaining(), 0);
+    // 预算耗尽后不再重试
+    let err = client.sign_digest("priv", "digest", &base_config).unwrap_err();
+    assert!(err.message.contains("重试预算已用尽"), "{}", err);
+    assert!(!budget.try_take());
+}
+
+#[test]
+fn test_get_or_fetch_with_storage() {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// 内存存储，模拟接入外部缓存/密钥服务
+    #[derive(Default)]
+    struct MemoryStorage(Mutex<HashMap<String, Vec<u8>>>);
+
+    impl Storage for MemoryStorage {
+        fn get(&self, key: &str) -> crate::error::Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+        fn put(&self, key: &str, value: &[u8]) -> crate::error::Result<()> {
+            self.0.lock().unwrap().insert(key.to_string(), value.to_vec());
+            Ok(())
+        }
+        fn delete(&self, key: &str) -> crate::error::Result<()> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    let base_config = BaseConfig {
+        algo: "sm2".to_string(),
+        kms: String::new(),
+        flow: "sign".to_string(),
+    };
+    // 只应答一次：第二次获取必须命中存储
+    let (base_url, server) = mock_server(vec![
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"priv":"priv1","pub":"pub1","keyId":"k1"}"#.to_string(),
+    ]);
+    let fetch = || {
+        KeyPair::fetch_from_pki(&base_url, &base_config, None, None, DEFAULT_MAX_RESPONSE_BYTES, Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS))
+    };
+    let storage = MemoryStorage::default();
+    let fetched = KeyPair::get_or_fetch(&storage, "keys/sm2", Some(3600), fetch).unwrap();
+    assert_eq!(fetched.key_id, "k1");
+    assert_eq!(fetched.ttl_secs, Some(3600));
+    assert!(fetched.fetched_at.is_some());
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0].0, "/v1/keypair");
+
+    let cached = KeyPair::get_or_fetch(&storage, "keys/sm2", None, fetch).unwrap();
+    assert_eq!(cached.priv_key, "priv1");
+    let stored = KeyPair::load_from_storage(&storage, "keys/sm2").unwrap();
+    assert_eq!(stored.pub_key, "pub1");
+    assert_eq!(stored.ttl_secs, Some(3600));
+
+    // 超过有效期后重新获取，记录新的获取时间
+    let mut stale = stored.clone();
+    stale.fetched_at = Some(unix_now() - 7200);
+    stale.save_to_storage(&storage, "keys/sm2").unwrap();
+    let rotated = KeyPair {
+        priv_key: "priv2".to_string(),
+        fetched_at: None,
+        ..stored.clone()
+    };
+    let refreshed = KeyPair::get_or_fetch(&storage, "keys/sm2", None, || Ok(rotated.clone())).unwrap();
+    assert_eq!((refreshed.priv_key.as_str(), refreshed.ttl_secs), ("priv2", Some(3600)));
+    assert!(refreshed.fetched_at.unwrap() >= unix_now() - 60);
+    // 调用方给出的有效期优先于缓存中记录的有效期
+    assert!(!refreshed.is_expired(None, refreshed.fetched_at.unwrap() + 60));
+    assert!(refreshed.is_expired(Some(30), refreshed.fetched_at.unwrap() + 60));
+    // 过期后获取失败时报错，不再使用旧密钥
+    stale.save_to_storage(&storage, "keys/sm2").unwrap();
+    let err = KeyPair::get_or_fetch(&storage, "keys/sm2", None, || Err("offline".into())).unwrap_err();
+    assert_eq!(err.message, "offline");
+
+    // 旧格式的缓存没有获取时间，视为永不过期
+    let legacy = (
+        "priv0".to_string(),
+        "pub0".to_string(),
+        String::new(),
+        base_config.clone(),
+    );
+    storage
+        .put("keys/legacy", &bincode::encode_to_vec(&legacy, bincode::config::standard()).unwrap())
+        .unwrap();
+    let loaded = KeyPair::get_or_fetch(&storage, "keys/legacy", Some(1), || Err("unused".into())).unwrap();
+    assert_eq!((loaded.priv_key.as_str(), loaded.fetched_at, loaded.ttl_secs), ("priv0", None, None));
+
+    storage.delete("keys/sm2").unwrap();
+    assert!(KeyPair::load_from_storage(&storage, "keys/sm2").unwrap_err().contains("不存在"));
+}
@@ -0,0 +1,204 @@
+//! 基于 `reqwest::Client` 的异步 PKI 客户端
+//!
+//! 供在 Tokio 服务中并发签名/验签的调用方使用：请求和重试等待都不阻塞线程。
+//! 请求、响应格式和重试规则与阻塞的 [`PkiClient`](super::PkiClient) 相同，CLI 仍使用阻塞客户端。
+use super::{
+    is_retryable, request_failure, response_too_large, status_failure, AuthHeader, BaseConfig, RetryBudget, SignDigestRequest, SignDigestResponse,
+    VerifyDigestRequest, VerifyDigestResponse, DEFAULT_HTTP_TIMEOUT_SECS, DEFAULT_MAX_RESPONSE_BYTES,
+};
+use crate::error::NetworkFailure;
+use crate::tr_format;
+use reqwest::{Client, Identity, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+
+/// 读取响应体，超过 `max_bytes` 时报错而不是继续缓冲
+async fn read_body(mut response: Response, max_bytes: u64) -> Result<Vec<u8>, NetworkFailure> {
+    if response.content_length().is_some_and(|len| len > max_bytes) {
+        return Err(response_too_large(max_bytes).into());
+    }
+    let mut body = vec![];
+    while let Some(chunk) = response.chunk().await.map_err(|e| request_failure(tr_format!("无法读取响应: {}", "cannot read the response: {}", e), e))? {
+        body.extend_from_slice(&chunk);
+        if body.len() as u64 > max_bytes {
+            return Err(response_too_large(max_bytes).into());
+        }
+    }
+    Ok(body)
+}
+
+async fn read_json<T: DeserializeOwned>(response: Response, max_bytes: u64) -> Result<T, NetworkFailure> {
+    serde_json::from_slice(&read_body(response, max_bytes).await?)
+        .map_err(|e| NetworkFailure::from(tr_format!("无法解析响应 JSON: {}", "cannot parse the response JSON: {}", e)).with_source(e))
+}
+
+/// 异步 PKI API 客户端
+#[derive(Debug)]
+pub struct AsyncPkiClient {
+    base_url: String,
+    retry_times: u32,
+    retry_delay: u64, // 毫秒
+    max_response_bytes: u64,
+    retry_budget: Option<RetryBudget>,
+    auth: Option<AuthHeader>,
+    client: Client,
+}
+
+/// 创建异步 HTTP 客户端，提供 identity 时在 TLS 握手中出示客户端证书
+fn http_client(identity: Option<&Identity>) -> Result<Client, String> {
+    let mut builder = Client::builder().timeout(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS));
+    if let Some(identity) = identity {
+        builder = builder.identity(identity.clone());
+    }
+    builder.build().map_err(|e| tr_format!("无法创建 HTTP 客户端: {}", "cannot create the HTTP client: {}", e))
+}
+
+impl AsyncPkiClient {
+    /// 创建新的异步 PKI 客户端
+    pub fn new(base_url: String, retry_times: u32, retry_delay: u64) -> Result<Self, String> {
+        Ok(AsyncPkiClient {
+            base_url,
+            retry_times,
+            retry_delay,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            retry_budget: None,
+            auth: None,
+            client: http_client(None)?,
+        })
+    }
+
+    /// 使用 mTLS 客户端身份重建 HTTP 客户端
+    pub fn with_identity(mut self, identity: &Identity) -> Result<Self, String> {
+        self.client = http_client(Some(identity))?;
+        Ok(self)
+    }
+
+    /// 设置响应体大小上限（字节），超过时请求失败
+    pub fn with_max_response_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    /// 设置共享的重试预算，None 表示只受每个请求的 `retry_times` 限制
+    pub fn with_retry_budget(mut self, budget: Option<RetryBudget>) -> Self {
+        self.retry_budget = budget;
+        self
+    }
+
+    /// 设置附加到每个请求上的认证头，None 表示不认证
+    pub fn with_auth(mut self, auth: Option<AuthHeader>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// 发送 JSON POST 请求，网络连接错误时按配置重试，收到响应后不再重试
+    async fn post_with_retry<T: Serialize + ?Sized>(&self, url: &str, request: &T) -> Result<Response, NetworkFailure> {
+        let mut attempt = 0;
+        loop {
+            let mut builder = self.client.post(url);
+            if let Some(auth) = &self.auth {
+                builder = builder.header(auth.name.clone(), auth.value.clone());
+            }
+            match builder.json(request).send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    if !status.is_success() {
+                        let error_text = read_body(response, self.max_response_bytes)
+                            .await
+                            .map(|body| String::from_utf8_lossy(&body).into_owned())
+                            .unwrap_or_else(|e| tr_format!("无法读取错误信息: {}", "cannot read the error message: {}", e));
+                        return Err(status_failure(status, tr_format!("PKI 平台返回错误 (HTTP {}): {}", "PKI platform returned an error (HTTP {}): {}", status, error_text)));
+                    }
+                    return Ok(response);
+                }
+                Err(e) if is_retryable(&e) && attempt < self.retry_times => {
+                    if self.retry_budget.as_ref().is_some_and(|budget| !budget.try_take()) {
+                        return Err(request_failure(
+                            tr_format!("网络请求失败: {} (URL: {})，本次操作的重试预算已用尽", "network request failed: {} (URL: {}), the retry budget of this operation is used up", e, url),
+                            e,
+                        ));
+                    }
+                    attempt += 1;
+                    tokio::time::sleep(Duration::from_millis(self.retry_delay)).await;
+                }
+                Err(e) => return Err(request_failure(tr_format!("网络请求失败: {} (URL: {})", "network request failed: {} (URL: {})", e, url), e)),
+            }
+        }
+    }
+
+    /// 调用签名接口，返回签名和平台可能附带的证书
+    pub async fn sign_digest(
+        &self,
+        priv_key: &str,
+        digest: &str,
+        base_config: &BaseConfig,
+    ) -> Result<(String, Option<String>), NetworkFailure> {
+        let url = format!("{}/v1/sign/digest", self.base_url);
+        let request = SignDigestRequest {
+            base_config: base_config.clone(),
+            priv_key: priv_key.to_string(),
+            digest: digest.to_string(),
+        };
+        let response = self.post_with_retry(&url, &request).await?;
+        let sign_resp: SignDigestResponse = read_json(response, self.max_response_bytes).await?;
+        Ok((sign_resp.signature, sign_resp.cert))
+    }
+
+    /// 调用验签接口
+    pub async fn verify_digest(
+        &self,
+        pub_key: &str,
+        digest: &str,
+        signature: &str,
+        base_config: &BaseConfig,
+    ) -> Result<bool, NetworkFailure> {
+        let url = format!("{}/v1/verify/digest", self.base_url);
+        let request = VerifyDigestRequest {
+            base_config: base_config.clone(),
+            pub_key: pub_key.to_string(),
+            digest: digest.to_string(),
+            signature: signature.to_string(),
+        };
+        let response = self.post_with_retry(&url, &request).await?;
+        let verify_resp: VerifyDigestResponse = read_json(response, self.max_response_bytes).await?;
+        Ok(verify_resp.into_result()?)
+    }
+}
+
+#[test]
+fn test_async_sign_and_verify() {
+    use super::mock_server;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let base_config = BaseConfig { algo: "sm2".to_string(), kms: String::new(), flow: "sign".to_string() };
+    let (base_url, server) = mock_server(vec![
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"signature":"sig-1","cert":"cert-1"}"#.to_string(),
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"result":"OK"}"#.to_string(),
+        r#"{"base_config":{"algo":"sm2","kms":"","flow":"sign"},"result":"FAIL","error":"bad sig"}"#.to_string(),
+    ]);
+    let client = AsyncPkiClient::new(base_url, 0, 0).unwrap();
+    runtime.block_on(async {
+        let signed = client.sign_digest("priv", "ab12", &base_config).await.unwrap();
+        assert_eq!(signed, ("sig-1".to_string(), Some("cert-1".to_string())));
+        assert!(client.verify_digest("pub", "ab12", "sig-1", &base_config).await.unwrap());
+        let err = client.verify_digest("pub", "ab12", "forged", &base_config).await.unwrap_err();
+        assert!(err.message.contains("bad sig"), "{}", err);
+    });
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0].0, "/v1/sign/digest");
+    assert!(requests[0].1.contains(r#""priv":"priv""#), "{}", requests[0].1);
+    assert_eq!(requests[2].0, "/v1/verify/digest");
+    assert!(requests[2].1.contains(r#""signature":"forged""#), "{}", requests[2].1);
+
+    // 连接被拒绝时按重试次数重试，并占用共享的重试预算
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let budget = RetryBudget::new(1);
+    let client = AsyncPkiClient::new(format!("http://127.0.0.1:{}", port), 2, 1)
+        .unwrap()
+        .with_retry_budget(Some(budget.clone()));
+    let err = runtime.block_on(client.sign_digest("priv", "ab12", &base_config)).unwrap_err();
+    assert!(err.message.contains("重试预算已用尽"), "{}", err);
+    assert_eq!(err.kind, crate::error::NetworkErrorKind::Connection);
+    assert_eq!(budget.remaining(), 0);
+}
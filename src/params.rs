@@ -1,7 +1,20 @@
 use crate::config::Config;
 use crate_spec::error::{Result, CrateSpecError};
 use crate::commands::encode::{LocalEncodeParams, NetworkEncodeParams};
-use crate::commands::decode::{LocalDecodeParams, NetworkDecodeParams};
+use crate::commands::decode::{BatchVerifyParams, DumpSectionsParams, InfoParams, LocalDecodeParams, NetworkDecodeParams};
+use crate::commands::sign_digest::SignDigestParams;
+use crate_spec::utils::batch::BatchFormat;
+use crate_spec::utils::context::{DepInfo, Producer, SIGTYPE};
+use crate_spec::utils::file_ops::{CollisionStrategy, STDIN_PATH};
+use crate_spec::utils::metadata::MetadataFormat;
+use crate_spec::utils::compression::CompressionKind;
+use crate_spec::utils::package::{is_valid_section_align, Endianness, MAX_SECTION_ALIGN};
+use crate_spec::utils::policy::{DepSourcePolicy, Policy};
+use crate_spec::utils::progress::ConsoleProgress;
+use crate_spec::utils::pkcs::{DigestAlgo, EngineKey, KeyStrengthPolicy, DEFAULT_MAX_CHAIN_DEPTH};
+use crate_spec::locale::tr;
+use crate_spec::tr_format;
+use std::sync::Arc;
 
 /// 参数构建器
 pub struct ParamsBuilder {
@@ -9,9 +22,59 @@ pub struct ParamsBuilder {
     pub decode: bool,
     pub root_ca_paths: Vec<String>,
     pub cert_path: Option<String>,
+    pub cert_chain_paths: Vec<String>,
     pub pkey_path: Option<String>,
+    pub engine_id: Option<String>,
+    pub engine_key_id: Option<String>,
     pub output: Option<String>,
     pub input: Option<String>,
+    pub stdin: bool,
+    pub check_crate_integrity: bool,
+    pub cross_validate: bool,
+    pub verify_timestamps: bool,
+    pub check_unreferenced_strings: bool,
+    pub verify: bool,
+    pub explain: bool,
+    pub keep_going: bool,
+    pub verify_chain_depth: u32,
+    pub min_rsa_bits: Option<u32>,
+    pub allowed_ec_curves: Vec<String>,
+    pub require_sig_types: Vec<String>,
+    pub require_signature: bool,
+    pub dump_sections: Option<String>,
+    pub info: bool,
+    pub batch: Vec<String>,
+    pub batch_format: Option<String>,
+    pub summary_only: bool,
+    pub progress: bool,
+    pub detached: bool,
+    pub target_dir: Option<String>,
+    pub lenient_toml: bool,
+    pub package_timeout: Option<u64>,
+    pub crate_file_pattern: Option<String>,
+    pub producer_tool: Option<String>,
+    pub producer_operator: Option<String>,
+    pub prune_binary: bool,
+    pub section_align: usize,
+    pub endianness: Option<String>,
+    pub compress: Option<String>,
+    pub file_hashes: bool,
+    pub digest: Option<String>,
+    pub check_file_hashes: Option<String>,
+    pub add_deps: Vec<String>,
+    pub dry_run: bool,
+    pub workspace: bool,
+    pub output_name: Option<String>,
+    pub remove_deps: Vec<String>,
+    pub record_toolchain: bool,
+    pub git_ref: Option<String>,
+    pub on_collision: Option<String>,
+    pub report_unverified_deps: bool,
+    pub fail_on_warning: bool,
+    pub detached_sig: Option<String>,
+    pub output_metadata_format: String,
+    pub compat_check: Option<String>,
+    pub digest_file: Option<String>,
     pub config: Option<Config>,
 }
 
@@ -22,129 +85,535 @@ impl ParamsBuilder {
             decode: args.decode,
             root_ca_paths: args.root_ca_paths.clone(),
             cert_path: args.cert_path.clone(),
+            cert_chain_paths: args.cert_chain.clone(),
             pkey_path: args.pkey_path.clone(),
+            engine_id: args.engine.clone(),
+            engine_key_id: args.engine_key.clone(),
             output: args.output.clone(),
-            input: args.input.clone(),
+            input: if args.stdin { Some(STDIN_PATH.to_string()) } else { args.input.clone() },
+            stdin: args.stdin,
+            check_crate_integrity: args.check_crate_integrity,
+            cross_validate: args.cross_validate,
+            verify_timestamps: args.verify_timestamps,
+            check_unreferenced_strings: args.check_unreferenced_strings,
+            verify: args.verify,
+            explain: args.explain,
+            keep_going: args.keep_going,
+            verify_chain_depth: args.verify_chain_depth,
+            min_rsa_bits: args.min_rsa_bits,
+            allowed_ec_curves: args.allowed_ec_curves.clone(),
+            require_sig_types: args.require_sig_type.clone(),
+            require_signature: args.require_signature,
+            dump_sections: args.dump_sections.clone(),
+            info: args.info,
+            batch: args.batch.clone(),
+            batch_format: args.batch_format.clone(),
+            summary_only: args.summary_only,
+            progress: args.progress,
+            detached: args.detached,
+            target_dir: args.target_dir.clone(),
+            lenient_toml: args.lenient_toml,
+            package_timeout: args.package_timeout,
+            crate_file_pattern: args.crate_file_pattern.clone(),
+            producer_tool: args.producer_tool.clone(),
+            producer_operator: args.producer_operator.clone(),
+            prune_binary: args.prune_binary,
+            section_align: args.section_align.unwrap_or(1),
+            endianness: args.endianness.clone(),
+            compress: args.compress.clone(),
+            file_hashes: args.file_hashes,
+            digest: args.digest.clone(),
+            check_file_hashes: args.check_file_hashes.clone(),
+            add_deps: args.add_dep.clone(),
+            dry_run: args.dry_run,
+            workspace: args.workspace,
+            output_name: args.output_name.clone(),
+            remove_deps: args.remove_dep.clone(),
+            record_toolchain: args.record_toolchain,
+            git_ref: args.git_ref.clone(),
+            on_collision: args.on_collision.clone(),
+            report_unverified_deps: args.report_unverified_deps,
+            fail_on_warning: args.fail_on_warning,
+            detached_sig: args.detached_sig.clone(),
+            output_metadata_format: args.output_metadata_format.clone(),
+            compat_check: args.compat_check.clone(),
+            digest_file: args.digest_file.clone(),
             config,
         }
     }
 
     /// 获取本地编码参数
     pub fn build_local_encode_params(&self) -> Result<LocalEncodeParams> {
-        if let Some(cfg) = &self.config {
-            Self::extract_local_encode_from_config(cfg)
+        let mut params = if let Some(cfg) = &self.config {
+            Self::extract_local_encode_from_config(cfg)?
         } else {
-            Self::extract_local_encode_from_cli(self)
+            Self::extract_local_encode_from_cli(self)?
+        };
+        params.detached = self.detached;
+        if !self.cert_chain_paths.is_empty() {
+            params.cert_chain_paths = self.cert_chain_paths.clone();
+        }
+        params.target_dir = self.target_dir.clone().or(params.target_dir);
+        params.lenient_toml = self.lenient_toml;
+        params.package_timeout = self.package_timeout.or(params.package_timeout);
+        params.crate_file_pattern = self.crate_file_pattern.clone().or(params.crate_file_pattern);
+        params.record_toolchain = self.record_toolchain;
+        params.git_ref = self.git_ref.clone();
+        params.on_collision = self.on_collision()?;
+        params.report_unverified_deps = self.report_unverified_deps;
+        params.fail_on_warning = self.fail_on_warning;
+        if let Some(cfg) = &self.config {
+            params.dep_source_policy = cfg.dep_source_policy()?;
+        }
+        params.producer = self.producer()?;
+        params.prune_binary = self.prune_binary;
+        params.section_align = self.section_align()?;
+        params.endianness = self.endianness()?;
+        params.compression = self.compression()?;
+        params.file_hashes = self.file_hashes;
+        params.digest = self.digest(params.digest)?;
+        params.remove_deps = self.remove_deps.clone();
+        params.add_deps = self.add_deps()?;
+        params.dry_run = self.dry_run;
+        params.workspace = self.workspace;
+        params.output_name = self.output_name()?;
+        params.progress = Some(Arc::new(ConsoleProgress));
+        Ok(params)
+    }
+
+    /// 生成者标识，命令行优先于配置文件 [producer] 段；操作者必须与工具标识一起提供
+    fn producer(&self) -> Result<Option<Producer>> {
+        let config = self.config.as_ref().and_then(|cfg| cfg.producer.as_ref());
+        let tool = self.producer_tool.clone().or_else(|| config.and_then(|p| p.tool.clone()));
+        let operator = self.producer_operator.clone().or_else(|| config.and_then(|p| p.operator.clone()));
+        match (tool, operator) {
+            (Some(tool), operator) => Ok(Some(Producer { tool, operator })),
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(CrateSpecError::ValidationError(
+                tr("设置操作者标识时必须同时提供生成工具标识（--producer-tool 或 [producer] tool）", "an operator identity requires a producer tool identity as well (--producer-tool or [producer] tool)").to_string(),
+            )),
+        }
+    }
+
+    /// 签名者密钥强度要求，命令行逐项优先于配置文件 [policy] 段
+    fn key_policy(&self) -> KeyStrengthPolicy {
+        let config = self.config.as_ref().map(Config::key_strength_policy).unwrap_or_default();
+        KeyStrengthPolicy {
+            min_rsa_bits: self.min_rsa_bits.or(config.min_rsa_bits),
+            allowed_ec_curves: if self.allowed_ec_curves.is_empty() {
+                config.allowed_ec_curves
+            } else {
+                self.allowed_ec_curves.clone()
+            },
+        }
+    }
+
+    /// 解析 --require-sig-type 的签名类型
+    fn require_sig_types(&self) -> Result<Vec<SIGTYPE>> {
+        self.require_sig_types.iter().map(|typ| typ.parse()).collect()
+    }
+
+    /// 解析 --add-dep 的依赖描述
+    /// 数据段对齐在打包前检查，避免 cargo package 之后才报错
+    fn section_align(&self) -> Result<usize> {
+        if !is_valid_section_align(self.section_align) {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "--section-align 必须是不超过 {} 的 2 的幂: {}",
+                "--section-align must be a power of two no greater than {}: {}",
+                MAX_SECTION_ALIGN, self.section_align
+            )));
+        }
+        Ok(self.section_align)
+    }
+
+    /// 字符串表和段索引的字节序，未指定时为小端
+    fn endianness(&self) -> Result<Endianness> {
+        self.endianness.as_deref().map_or(Ok(Endianness::default()), str::parse)
+    }
+
+    /// crate 二进制段的压缩方式，未指定时不压缩
+    fn compression(&self) -> Result<CompressionKind> {
+        self.compress.as_deref().map_or(Ok(CompressionKind::default()), str::parse)
+    }
+
+    /// 输出文件已存在时的处理方式，未指定时覆盖
+    fn on_collision(&self) -> Result<CollisionStrategy> {
+        self.on_collision.as_deref().map_or(Ok(CollisionStrategy::default()), str::parse)
+    }
+
+    /// --output-name 指定的输出文件名，只能是文件名，不能包含路径分隔符
+    fn output_name(&self) -> Result<Option<String>> {
+        let Some(name) = &self.output_name else {
+            return Ok(None);
+        };
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的输出文件名: {:?}，只能是文件名，不能包含路径分隔符",
+                "invalid output file name: {:?}, it must be a bare file name without path separators",
+                name
+            )));
+        }
+        Ok(Some(name.clone()))
+    }
+
+    /// 签名摘要算法，命令行优先于配置文件 [local.encode] digest
+    fn digest(&self, config: DigestAlgo) -> Result<DigestAlgo> {
+        self.digest.as_deref().map_or(Ok(config), str::parse)
+    }
+
+    fn add_deps(&self) -> Result<Vec<DepInfo>> {
+        self.add_deps.iter().map(|spec| spec.parse()).collect()
+    }
+
+    /// 读取 --compat-check 指定的策略文件
+    fn policy(&self) -> Result<Option<Policy>> {
+        self.compat_check.as_deref().map(Policy::from_file).transpose()
+    }
+
+    /// engine id 和密钥标识必须成对出现
+    fn engine_key(engine_id: Option<String>, key_id: Option<String>) -> Result<Option<EngineKey>> {
+        match (engine_id, key_id) {
+            (Some(engine_id), Some(key_id)) => Ok(Some(EngineKey { engine_id, key_id })),
+            (None, None) => Ok(None),
+            _ => Err(CrateSpecError::ValidationError(tr("engine id 与 engine 密钥标识必须同时提供", "the engine id and the engine key id must be given together").to_string())),
         }
     }
 
     fn extract_local_encode_from_config(config: &Config) -> Result<LocalEncodeParams> {
         let encode_config = config
             .get_encode_config()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中没有 [local.encode] 部分".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中没有 [local.encode] 部分", "the config file has no [local.encode] table").to_string()))?;
+        let engine_key = Self::engine_key(encode_config.engine_id.clone(), encode_config.engine_key_id.clone())?;
 
         Ok(LocalEncodeParams {
             cert_path: encode_config.cert_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 cert_path".to_string()))?,
-            pkey_path: encode_config.private_key_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 private_key_path".to_string()))?,
-            root_ca_paths: encode_config.root_ca_path.as_ref()
-                .map(|p| vec![p.clone()])
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 cert_path", "the config file is missing cert_path").to_string()))?,
+            pkey_path: match engine_key {
+                Some(_) => encode_config.private_key_path.clone(),
+                None => Some(encode_config.private_key_path.clone()
+                    .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 private_key_path", "the config file is missing private_key_path").to_string()))?),
+            },
+            engine_key,
+            cert_chain_paths: encode_config.cert_chain_path.iter().cloned().collect(),
+            root_ca_paths: Some(encode_config.merged_root_ca_paths())
                 .filter(|v| !v.is_empty())
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 root_ca_path".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 root_ca_paths 或 root_ca_path", "the config file is missing root_ca_paths or root_ca_path").to_string()))?,
             output: encode_config.output_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 output_path".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 output_path", "the config file is missing output_path").to_string()))?,
             input: encode_config.input_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 input_path".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 input_path", "the config file is missing input_path").to_string()))?,
+            detached: false,
+            target_dir: encode_config.target_dir.clone(),
+            lenient_toml: false,
+            package_timeout: encode_config.package_timeout,
+            crate_file_pattern: encode_config.crate_file_pattern.clone(),
+            record_toolchain: false,
+            git_ref: None,
+            on_collision: CollisionStrategy::default(),
+            report_unverified_deps: false,
+            fail_on_warning: false,
+            dep_source_policy: DepSourcePolicy::default(),
+            producer: None,
+            prune_binary: false,
+            section_align: 1,
+            endianness: Endianness::default(),
+            compression: CompressionKind::default(),
+            file_hashes: false,
+            digest: encode_config.digest.as_deref().map_or(Ok(DigestAlgo::default()), str::parse)
+                .map_err(|e| CrateSpecError::ConfigError(tr_format!("[local.encode] digest 配置错误: {}", "invalid [local.encode] digest setting: {}", e)))?,
+            remove_deps: vec![],
+            add_deps: vec![],
+            dry_run: false,
+            workspace: false,
+            output_name: None,
+            progress: None,
         })
     }
 
     fn extract_local_encode_from_cli(builder: &ParamsBuilder) -> Result<LocalEncodeParams> {
+        let engine_key = Self::engine_key(builder.engine_id.clone(), builder.engine_key_id.clone())?;
+
         Ok(LocalEncodeParams {
             cert_path: builder.cert_path.clone()
-                .ok_or_else(|| CrateSpecError::ValidationError("必须提供证书路径 (-c)".to_string()))?,
-            pkey_path: builder.pkey_path.clone()
-                .ok_or_else(|| CrateSpecError::ValidationError("必须提供私钥路径 (-p)".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供证书路径 (-c)", "a certificate path (-c) is required").to_string()))?,
+            pkey_path: match engine_key {
+                Some(_) => builder.pkey_path.clone(),
+                None => Some(builder.pkey_path.clone()
+                    .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供私钥路径 (-p) 或 --engine/--engine-key", "a private key path (-p) or --engine/--engine-key is required").to_string()))?),
+            },
+            engine_key,
+            cert_chain_paths: vec![],
             root_ca_paths: if builder.root_ca_paths.is_empty() {
-                return Err(CrateSpecError::ValidationError("必须提供根CA路径 (-r)".to_string()));
+                return Err(CrateSpecError::ValidationError(tr("必须提供根CA路径 (-r)", "a root CA path (-r) is required").to_string()));
             } else {
                 builder.root_ca_paths.clone()
             },
             output: builder.output.clone()
-                .ok_or_else(|| CrateSpecError::ValidationError("必须提供输出路径 (-o)".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供输出路径 (-o)", "an output path (-o) is required").to_string()))?,
             input: builder.input.clone()
-                .ok_or_else(|| CrateSpecError::ValidationError("必须提供输入路径".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供输入路径", "an input path is required").to_string()))?,
+            detached: false,
+            target_dir: None,
+            lenient_toml: false,
+            package_timeout: None,
+            crate_file_pattern: None,
+            record_toolchain: false,
+            git_ref: None,
+            on_collision: CollisionStrategy::default(),
+            report_unverified_deps: false,
+            fail_on_warning: false,
+            dep_source_policy: DepSourcePolicy::default(),
+            producer: None,
+            prune_binary: false,
+            section_align: 1,
+            endianness: Endianness::default(),
+            compression: CompressionKind::default(),
+            file_hashes: false,
+            digest: DigestAlgo::default(),
+            remove_deps: vec![],
+            add_deps: vec![],
+            dry_run: false,
+            workspace: false,
+            output_name: None,
+            progress: None,
         })
     }
 
     /// 获取本地解码参数
     pub fn build_local_decode_params(&self) -> Result<LocalDecodeParams> {
-        if let Some(cfg) = &self.config {
-            Self::extract_local_decode_from_config(cfg)
+        let mut params = if let Some(cfg) = &self.config {
+            Self::extract_local_decode_from_config(cfg)?
         } else {
-            Self::extract_local_decode_from_cli(self)
+            Self::extract_local_decode_from_cli(self)?
+        };
+        if self.stdin {
+            params.input = STDIN_PATH.to_string();
+        }
+        params.check_crate_integrity = self.check_crate_integrity;
+        params.cross_validate = self.cross_validate;
+        params.verify_timestamps = self.verify_timestamps;
+        params.check_unreferenced_strings = self.check_unreferenced_strings;
+        params.max_chain_depth = self.verify_chain_depth;
+        params.key_policy = self.key_policy();
+        params.require_sig_types = self.require_sig_types()?;
+        params.require_signature = self.require_signature;
+        params.detached_sig = self.detached_sig.clone();
+        params.metadata_format = self.output_metadata_format.parse()?;
+        params.policy = self.policy()?;
+        params.verify_only = self.verify;
+        params.explain = self.explain;
+        params.keep_going = self.keep_going;
+        params.check_file_hashes = self.check_file_hashes.clone();
+        self.require_decode_output(&params.output)?;
+        Ok(params)
+    }
+
+    /// 解码需要输出目录，仅验证（--verify、--explain）时可以不提供
+    fn require_decode_output(&self, output: &str) -> Result<()> {
+        if !output.is_empty() || self.verify || self.explain {
+            return Ok(());
         }
+        Err(match self.config {
+            Some(_) => CrateSpecError::ConfigError(tr("配置文件中缺少 output_path", "the config file is missing output_path").to_string()),
+            None => CrateSpecError::ValidationError(tr("必须提供输出路径 (-o)", "an output path (-o) is required").to_string()),
+        })
     }
 
     fn extract_local_decode_from_config(config: &Config) -> Result<LocalDecodeParams> {
         let decode_config = config
             .get_decode_config()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中没有 [local.decode] 部分".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中没有 [local.decode] 部分", "the config file has no [local.decode] table").to_string()))?;
 
         Ok(LocalDecodeParams {
-            root_ca_paths: decode_config.root_ca_path.as_ref()
-                .map(|p| vec![p.clone()])
+            root_ca_paths: Some(decode_config.merged_root_ca_paths())
                 .filter(|v| !v.is_empty())
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 root_ca_path".to_string()))?,
-            output: decode_config.output_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 output_path".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 root_ca_paths 或 root_ca_path", "the config file is missing root_ca_paths or root_ca_path").to_string()))?,
+            output: decode_config.output_path.clone().unwrap_or_default(),
             input: decode_config.input_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 input_path".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 input_path", "the config file is missing input_path").to_string()))?,
+            check_crate_integrity: false,
+            cross_validate: false,
+            verify_timestamps: false,
+            check_unreferenced_strings: false,
+            max_chain_depth: DEFAULT_MAX_CHAIN_DEPTH,
+            key_policy: KeyStrengthPolicy::default(),
+            require_sig_types: vec![],
+            require_signature: false,
+            detached_sig: None,
+            metadata_format: MetadataFormat::default(),
+            policy: None,
+            verify_only: false,
+            explain: false,
+            keep_going: false,
+            check_file_hashes: None,
         })
     }
 
     fn extract_local_decode_from_cli(builder: &ParamsBuilder) -> Result<LocalDecodeParams> {
         Ok(LocalDecodeParams {
             root_ca_paths: if builder.root_ca_paths.is_empty() {
-                return Err(CrateSpecError::ValidationError("必须提供根CA路径 (-r)".to_string()));
+                return Err(CrateSpecError::ValidationError(tr("必须提供根CA路径 (-r)", "a root CA path (-r) is required").to_string()));
             } else {
                 builder.root_ca_paths.clone()
             },
-            output: builder.output.clone()
-                .ok_or_else(|| CrateSpecError::ValidationError("必须提供输出路径 (-o)".to_string()))?,
+            output: builder.output.clone().unwrap_or_default(),
             input: builder.input.clone()
-                .ok_or_else(|| CrateSpecError::ValidationError("必须提供输入路径".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供输入路径", "an input path is required").to_string()))?,
+            check_crate_integrity: false,
+            cross_validate: false,
+            verify_timestamps: false,
+            check_unreferenced_strings: false,
+            max_chain_depth: DEFAULT_MAX_CHAIN_DEPTH,
+            key_policy: KeyStrengthPolicy::default(),
+            require_sig_types: vec![],
+            require_signature: false,
+            detached_sig: None,
+            metadata_format: MetadataFormat::default(),
+            policy: None,
+            verify_only: false,
+            explain: false,
+            keep_going: false,
+            check_file_hashes: None,
         })
     }
 
     /// 获取网络编码参数
     pub fn build_network_encode_params(&self) -> Result<NetworkEncodeParams> {
         let config = self.config.as_ref()
-            .ok_or_else(|| CrateSpecError::ConfigError("网络模式需要配置文件".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("网络模式需要配置文件", "network mode requires a config file").to_string()))?;
         let encode_config = config.get_network_encode_config()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 [network.encode] 配置段".to_string()))?;
-        
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 [network.encode] 配置段", "the config file is missing the [network.encode] table").to_string()))?;
+        if self.digest(DigestAlgo::Sha256)? != DigestAlgo::Sha256 {
+            return Err(CrateSpecError::ValidationError(tr("网络签名只支持 sha256 摘要，不能使用 --digest", "network signatures only support sha256 digests, --digest cannot be used").to_string()));
+        }
+
         Ok(NetworkEncodeParams {
             input: encode_config.input_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 input_path".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 input_path", "the config file is missing input_path").to_string()))?,
             output: encode_config.output_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 output_path".to_string()))?,
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 output_path", "the config file is missing output_path").to_string()))?,
+            detached: self.detached,
+            target_dir: self.target_dir.clone().or_else(|| encode_config.target_dir.clone()),
+            lenient_toml: self.lenient_toml,
+            package_timeout: self.package_timeout.or(encode_config.package_timeout),
+            crate_file_pattern: self.crate_file_pattern.clone().or_else(|| encode_config.crate_file_pattern.clone()),
+            record_toolchain: self.record_toolchain,
+            git_ref: self.git_ref.clone(),
+            on_collision: self.on_collision()?,
+            report_unverified_deps: self.report_unverified_deps,
+            fail_on_warning: self.fail_on_warning,
+            dep_source_policy: config.dep_source_policy()?,
+            producer: self.producer()?,
+            prune_binary: self.prune_binary,
+            section_align: self.section_align()?,
+            endianness: self.endianness()?,
+            compression: self.compression()?,
+            file_hashes: self.file_hashes,
+            remove_deps: self.remove_deps.clone(),
+            add_deps: self.add_deps()?,
+            dry_run: self.dry_run,
+            workspace: self.workspace,
+            output_name: self.output_name()?,
+            progress: Some(Arc::new(ConsoleProgress)),
         })
     }
 
     /// 获取网络解码参数
     pub fn build_network_decode_params(&self) -> Result<NetworkDecodeParams> {
         let config = self.config.as_ref()
-            .ok_or_else(|| CrateSpecError::ConfigError("网络模式需要配置文件".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("网络模式需要配置文件", "network mode requires a config file").to_string()))?;
         let decode_config = config.get_network_decode_config()
-            .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 [network.decode] 配置段".to_string()))?;
+            .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 [network.decode] 配置段", "the config file is missing the [network.decode] table").to_string()))?;
         
+        let input = if self.stdin {
+            STDIN_PATH.to_string()
+        } else {
+            decode_config.input_path.clone()
+                .ok_or_else(|| CrateSpecError::ConfigError(tr("配置文件中缺少 input_path", "the config file is missing input_path").to_string()))?
+        };
+
+        let output = decode_config.output_path.clone().unwrap_or_default();
+        self.require_decode_output(&output)?;
+
         Ok(NetworkDecodeParams {
-            input: decode_config.input_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 input_path".to_string()))?,
-            output: decode_config.output_path.clone()
-                .ok_or_else(|| CrateSpecError::ConfigError("配置文件中缺少 output_path".to_string()))?,
+            input,
+            output,
+            check_crate_integrity: self.check_crate_integrity,
+            cross_validate: self.cross_validate,
+            verify_timestamps: self.verify_timestamps,
+            check_unreferenced_strings: self.check_unreferenced_strings,
+            max_chain_depth: self.verify_chain_depth,
+            key_policy: self.key_policy(),
+            require_sig_types: self.require_sig_types()?,
+            require_signature: self.require_signature,
+            detached_sig: self.detached_sig.clone(),
+            metadata_format: self.output_metadata_format.parse()?,
+            policy: self.policy()?,
+            verify_only: self.verify,
+            explain: self.explain,
+            keep_going: self.keep_going,
+            check_file_hashes: self.check_file_hashes.clone(),
         })
     }
-}
 
+    /// 获取导出数据段参数：输入优先取命令行，其次取对应模式的解码配置
+    pub fn build_dump_sections_params(&self, mode: &str) -> Result<DumpSectionsParams> {
+        let output = self.dump_sections.clone()
+            .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供导出目录 (--dump-sections)", "an export directory (--dump-sections) is required").to_string()))?;
+        let config_input = self.config.as_ref().and_then(|cfg| match mode {
+            "net" => cfg.get_network_decode_config()?.input_path.clone(),
+            _ => cfg.get_decode_config()?.input_path.clone(),
+        });
+        let input = self.input.clone()
+            .or(config_input)
+            .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供输入路径", "an input path is required").to_string()))?;
+        Ok(DumpSectionsParams { input, output })
+    }
+
+    /// 获取包信息输出参数：只取命令行的输入路径，-o 为输出文件，未提供时输出到标准输出
+    pub fn build_info_params(&self) -> Result<InfoParams> {
+        let input = self.input.clone()
+            .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供输入路径", "an input path is required").to_string()))?;
+        Ok(InfoParams { input, output: self.output.clone() })
+    }
+
+    /// 获取摘要签名参数：输出目录优先取命令行，其次取 [network.encode] 的 output_path
+    pub fn build_sign_digest_params(&self) -> Result<SignDigestParams> {
+        let digest_file = self.digest_file.clone()
+            .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供摘要文件 (--digest-file)", "a digest file (--digest-file) is required").to_string()))?;
+        let config_output = self.config.as_ref()
+            .and_then(|cfg| cfg.get_network_encode_config()?.output_path.clone());
+        let output = self.output.clone()
+            .or(config_output)
+            .ok_or_else(|| CrateSpecError::ValidationError(tr("必须提供输出路径 (-o)", "an output path (-o) is required").to_string()))?;
+        Ok(SignDigestParams { digest_file, output })
+    }
+
+    /// 获取批量验证参数：根 CA 优先取命令行，其次取本地解码配置；网络模式可以不提供根 CA
+    pub fn build_batch_verify_params(&self, mode: &str) -> Result<BatchVerifyParams> {
+        let root_ca_paths = if self.root_ca_paths.is_empty() {
+            self.config.as_ref()
+                .and_then(|cfg| cfg.get_decode_config())
+                .map(|decode| decode.merged_root_ca_paths())
+                .unwrap_or_default()
+        } else {
+            self.root_ca_paths.clone()
+        };
+        if mode == "local" && root_ca_paths.is_empty() {
+            return Err(CrateSpecError::ValidationError(tr("必须提供根CA路径 (-r)", "a root CA path (-r) is required").to_string()));
+        }
+        Ok(BatchVerifyParams {
+            inputs: self.batch.clone(),
+            root_ca_paths,
+            max_chain_depth: self.verify_chain_depth,
+            key_policy: self.key_policy(),
+            require_signature: self.require_signature,
+            format: match (self.summary_only, self.batch_format.as_deref()) {
+                (true, _) => BatchFormat::Summary,
+                (false, Some(format)) => format.parse()?,
+                (false, None) => BatchFormat::default(),
+            },
+            progress: self.progress,
+        })
+    }
+}
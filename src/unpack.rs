@@ -1,6 +1,10 @@
 use crate_spec::utils::context::PackageContext;
-use crate_spec::utils::pkcs::PKCS;
+use crate_spec::utils::file_ops::STDIN_PATH;
+use crate_spec::utils::pkcs::{KeyStrengthPolicy, DEFAULT_MAX_CHAIN_DEPTH, PKCS};
+use crate_spec::utils::timings;
 use crate_spec::{Result, CrateSpecError};
+use crate_spec::locale::tr;
+use crate_spec::tr_format;
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -8,71 +12,157 @@ use std::str::FromStr;
 struct Unpacking {
     file_path: PathBuf,
     cas_path: Vec<String>,
+    max_chain_depth: u32,
+    key_policy: KeyStrengthPolicy,
+    require_signature: bool,
 }
 
 impl Unpacking {
     pub fn new(path: &str) -> Result<Self> {
         Ok(Unpacking {
             file_path: PathBuf::from_str(path)
-                .map_err(|e| CrateSpecError::ValidationError(format!("无效的路径: {}", e)))?,
+                .map_err(|e| CrateSpecError::ValidationError(tr_format!("无效的路径: {}", "invalid path: {}", e)))?,
             cas_path: Vec::new(),
+            max_chain_depth: DEFAULT_MAX_CHAIN_DEPTH,
+            key_policy: KeyStrengthPolicy::default(),
+            require_signature: false,
         })
     }
 
     pub fn add_ca_from_file(&mut self, path: &str) -> Result<()> {
         let path_buf = PathBuf::from_str(path)
-            .map_err(|e| CrateSpecError::ValidationError(format!("无效的 CA 路径: {}", e)))?;
+            .map_err(|e| CrateSpecError::ValidationError(tr_format!("无效的 CA 路径: {}", "invalid CA path: {}", e)))?;
         let file_path = fs::canonicalize(&path_buf)
             .map_err(|_e| CrateSpecError::FileNotFound(path_buf.clone()))?;
         let file_path_str = file_path.to_str()
-            .ok_or_else(|| CrateSpecError::Other("无法将路径转换为字符串".to_string()))?;
+            .ok_or_else(|| CrateSpecError::Other(tr("无法将路径转换为字符串", "path is not valid UTF-8").to_string()))?;
         self.cas_path.push(file_path_str.to_string());
         Ok(())
     }
 
     pub fn unpack_context(self) -> Result<PackageContext> {
+        let bin = timings::measure(timings::label_read_binary(), || fs::read(&self.file_path))
+            .map_err(|_e| CrateSpecError::FileNotFound(self.file_path.clone()))?;
+        self.unpack_context_from_bytes(bin.as_slice())
+    }
+
+    pub fn unpack_context_from_bytes(self, bin: &[u8]) -> Result<PackageContext> {
         let mut package_context_new = PackageContext::new();
         package_context_new.set_root_cas_bin(PKCS::root_ca_bins(self.cas_path)?);
-        let bin = fs::read(&self.file_path)
-            .map_err(|_e| CrateSpecError::FileNotFound(self.file_path.clone()))?;
+        package_context_new.max_chain_depth = self.max_chain_depth;
+        package_context_new.key_policy = self.key_policy.clone();
+        package_context_new.require_signature = self.require_signature;
         let (_crate_package_new, _str_table) =
-            package_context_new.decode_from_crate_package(bin.as_slice())
+            timings::measure(timings::label_decode(), || package_context_new.decode_from_crate_package(bin))
                 .map_err(|e| CrateSpecError::DecodeError(e.to_string()))?;
         Ok(package_context_new)
     }
+
+    pub fn unpack_detached_from_bytes(self, bin: &[u8], sig_bin: &[u8]) -> Result<PackageContext> {
+        let mut package_context_new = PackageContext::new();
+        package_context_new.set_root_cas_bin(PKCS::root_ca_bins(self.cas_path)?);
+        package_context_new.max_chain_depth = self.max_chain_depth;
+        package_context_new.key_policy = self.key_policy.clone();
+        timings::measure(timings::label_decode(), || package_context_new.decode_detached(bin, sig_bin))?;
+        Ok(package_context_new)
+    }
 }
 
-pub fn unpack_context(file_path: &str, cas_path: Vec<String>) -> Result<PackageContext> {
+pub fn unpack_context(
+    file_path: &str,
+    cas_path: Vec<String>,
+    max_chain_depth: u32,
+    key_policy: &KeyStrengthPolicy,
+    require_signature: bool,
+) -> Result<PackageContext> {
     let mut unpack = Unpacking::new(file_path)?;
+    unpack.max_chain_depth = max_chain_depth;
+    unpack.key_policy = key_policy.clone();
+    unpack.require_signature = require_signature;
     for ca_path in cas_path {
         unpack.add_ca_from_file(&ca_path)?;
     }
     unpack.unpack_context()
 }
 
+/// 从内存中的 .scrate 数据解码（例如从标准输入读取的数据）
+pub fn unpack_context_from_bytes(
+    bin: &[u8],
+    cas_path: Vec<String>,
+    max_chain_depth: u32,
+    key_policy: &KeyStrengthPolicy,
+    require_signature: bool,
+) -> Result<PackageContext> {
+    let mut unpack = Unpacking::new(STDIN_PATH)?;
+    unpack.max_chain_depth = max_chain_depth;
+    unpack.key_policy = key_policy.clone();
+    unpack.require_signature = require_signature;
+    for ca_path in cas_path {
+        unpack.add_ca_from_file(&ca_path)?;
+    }
+    unpack.unpack_context_from_bytes(bin)
+}
+
+/// 解码不含签名段的包体，并用分离签名文件（.scrate.sig）的内容验证
+pub fn unpack_context_detached(
+    bin: &[u8],
+    sig_bin: &[u8],
+    cas_path: Vec<String>,
+    max_chain_depth: u32,
+    key_policy: &KeyStrengthPolicy,
+) -> Result<PackageContext> {
+    let mut unpack = Unpacking::new(STDIN_PATH)?;
+    unpack.max_chain_depth = max_chain_depth;
+    unpack.key_policy = key_policy.clone();
+    for ca_path in cas_path {
+        unpack.add_ca_from_file(&ca_path)?;
+    }
+    unpack.unpack_detached_from_bytes(bin, sig_bin)
+}
+
 #[test]
 fn test_unpack() {
-    use crate::pack::pack_context;
+    use crate_spec::utils::pack::{pack_context, PackOptions};
     use crate_spec::utils::context::SIGTYPE;
-    let mut pack_context = pack_context("../crate-spec");
-    fn sign() -> PKCS {
-        let mut pkcs1 = PKCS::new();
-        pkcs1.load_from_file_writer(
-            "test/cert.pem".to_string(),
-            "test/key.pem".to_string(),
-            ["test/root-ca.pem".to_string()].to_vec(),
-        );
-        pkcs1
-    }
-    pack_context.add_sig(sign(), SIGTYPE::CRATEBIN);
+    use crate_spec::utils::testing::{TestDir, signer};
+    let dir = TestDir::new("unpack");
+    let crate_path = dir.fixture_crate("unpack-fixture", "0.1.0");
+    let mut pack_context = pack_context(&crate_path, PackOptions::default()).unwrap();
+    pack_context.add_sig(signer(), SIGTYPE::CRATEBIN);
 
-    let (_, _, bin) = pack_context.encode_to_crate_package();
-    fs::write(PathBuf::from_str("test/crate-spec.cra").unwrap(), bin).unwrap();
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    let path = dir.write("unpack-fixture.scrate", bin);
 
     let pack_context_decode =
-        unpack_context("test/crate-spec.cra", vec!["test/root-ca.pem".to_string()]);
+        unpack_context(path.to_str().unwrap(), vec!["test/root-ca.pem".to_string()], DEFAULT_MAX_CHAIN_DEPTH, &KeyStrengthPolicy::default(), true).unwrap();
 
     assert_eq!(pack_context_decode.pack_info, pack_context.pack_info);
     assert_eq!(pack_context_decode.dep_infos, pack_context.dep_infos);
     assert_eq!(pack_context_decode.crate_binary, pack_context.crate_binary);
 }
+
+#[test]
+fn test_unpack_from_stdin_stream() {
+    use crate_spec::utils::context::{PackageInfo, SIGTYPE};
+    use crate_spec::utils::file_ops::{read_stream, MAX_STREAM_INPUT_BYTES};
+    use crate_spec::utils::testing::signer;
+
+    let mut pack_context = PackageContext::new();
+    pack_context.pack_info = PackageInfo::new(
+        "stdin-crate".to_string(),
+        "0.1.0".to_string(),
+        "MIT".to_string(),
+        vec![],
+    );
+    pack_context.add_crate_bin(vec![1u8; 64]);
+    let pkcs = signer();
+    pack_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    // 模拟通过管道传入的字节流
+    let piped = read_stream(std::io::Cursor::new(bin), MAX_STREAM_INPUT_BYTES).unwrap();
+    let pack_context_decode =
+        unpack_context_from_bytes(&piped, vec!["test/root-ca.pem".to_string()], DEFAULT_MAX_CHAIN_DEPTH, &KeyStrengthPolicy::default(), true).unwrap();
+    assert_eq!(pack_context_decode.pack_info, pack_context.pack_info);
+    assert_eq!(pack_context_decode.crate_binary, pack_context.crate_binary);
+}
@@ -0,0 +1,314 @@
+//! 批量处理的逐项结果输出
+//!
+//! 每处理完一项就输出一行，`jsonl` 格式下每行是一个 JSON 对象：
+//! `{"input":"a.scrate","status":"ok","output":"demo-0.1.0"}` 或
+//! `{"input":"b.scrate","status":"error","error":"..."}`，便于调用方边处理边解析。
+//! `summary` 格式每项只输出一行 `OK`/`FAIL` 摘要，不输出汇总，便于人工快速浏览。
+//! 交互使用时可用 [`BatchProgress`] 在标准错误上显示进度条。
+use crate::error::{CrateSpecError, Result};
+use crate::locale::tr;
+use crate::tr_format;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+/// 逐项结果的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchFormat {
+    /// 人类可读的文本（默认），结束时输出汇总
+    #[default]
+    Text,
+    /// 每项一行 JSON（JSON Lines），汇总不写入该输出
+    Jsonl,
+    /// 每项一行 `OK`/`FAIL` 摘要，不输出汇总（`--summary-only`）
+    Summary,
+}
+
+impl FromStr for BatchFormat {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(BatchFormat::Text),
+            "jsonl" => Ok(BatchFormat::Jsonl),
+            "summary" => Ok(BatchFormat::Summary),
+            _ => Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的批量输出格式: {}，必须是 'text'、'jsonl' 或 'summary'",
+                "invalid batch output format: {}, must be 'text', 'jsonl' or 'summary'",
+                s
+            ))),
+        }
+    }
+}
+
+/// 单项处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Ok,
+    Error,
+}
+
+/// 单项处理结果，成功时带 `output`，失败时带 `error`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchItem {
+    pub input: String,
+    pub status: BatchStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchItem {
+    pub fn new(input: &str, result: Result<String>) -> Self {
+        let (status, output, error) = match result {
+            Ok(output) => (BatchStatus::Ok, Some(output), None),
+            Err(e) => (BatchStatus::Error, None, Some(e.to_string())),
+        };
+        BatchItem { input: input.to_string(), status, output, error }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == BatchStatus::Ok
+    }
+
+    /// 单行 JSON（不含换行符）
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("BatchItem 只含字符串字段，序列化不会失败")
+    }
+
+    /// 单行摘要：`OK   <input>  <output>` 或 `FAIL <input>  <error>`，错误信息中的换行替换为空格
+    pub fn to_summary_line(&self) -> String {
+        match (&self.output, &self.error) {
+            (Some(output), _) => format!("OK   {}  {}", self.input, output),
+            (None, error) => format!(
+                "FAIL {}  {}",
+                self.input,
+                error.as_deref().unwrap_or(tr("未知错误", "unknown error")).replace('\n', " ")
+            ),
+        }
+    }
+}
+
+impl Display for BatchItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.output, &self.error) {
+            (Some(output), _) => write!(f, "[{}] {} -> {}", tr("通过", "pass"), self.input, output),
+            (None, error) => write!(f, "[{}] {}: {}", tr("失败", "FAIL"), self.input, error.as_deref().unwrap_or(tr("未知错误", "unknown error"))),
+        }
+    }
+}
+
+/// 批量处理汇总
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchSummary {
+    pub ok: usize,
+    pub failed: usize,
+}
+
+impl Display for BatchSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&tr_format!(
+            "共 {} 项: {} 项通过，{} 项失败",
+            "{} item(s): {} passed, {} failed",
+            self.ok + self.failed,
+            self.ok,
+            self.failed
+        ))
+    }
+}
+
+/// 依次处理每个输入，每完成一项立即按 `format` 向 `out` 写出一行并刷新，再以当前汇总调用 `on_progress`
+///
+/// 单项失败不会中断后续处理；`process` 成功时返回的字符串作为该项的 `output`。
+pub fn run_batch<W, F, P>(
+    inputs: &[String],
+    format: BatchFormat,
+    out: &mut W,
+    mut process: F,
+    mut on_progress: P,
+) -> Result<BatchSummary>
+where
+    W: Write + ?Sized,
+    F: FnMut(&str) -> Result<String>,
+    P: FnMut(&BatchSummary),
+{
+    let mut summary = BatchSummary::default();
+    for input in inputs {
+        let item = BatchItem::new(input, process(input));
+        if item.is_ok() {
+            summary.ok += 1;
+        } else {
+            summary.failed += 1;
+        }
+        // 整行一次写出，进度条只需为每项暂停一次
+        let line = match format {
+            BatchFormat::Text => format!("{}\n", item),
+            BatchFormat::Jsonl => format!("{}\n", item.to_json_line()),
+            BatchFormat::Summary => format!("{}\n", item.to_summary_line()),
+        };
+        out.write_all(line.as_bytes())?;
+        out.flush()?;
+        on_progress(&summary);
+    }
+    Ok(summary)
+}
+
+/// 批量处理进度条，显示在标准错误上：已完成数/总数与失败数
+pub struct BatchProgress {
+    bar: ProgressBar,
+}
+
+impl BatchProgress {
+    /// 是否显示进度条：须显式请求，且标准输出是终端、格式不是 jsonl（避免与逐项 JSON 输出交错）
+    pub fn enabled(requested: bool, format: BatchFormat, stdout_is_terminal: bool) -> bool {
+        requested && stdout_is_terminal && format != BatchFormat::Jsonl
+    }
+
+    pub fn new(total: usize) -> Self {
+        let bar = ProgressBar::new(total as u64);
+        bar.set_style(
+            ProgressStyle::with_template("[{bar:30}] {pos}/{len} {msg}")
+                .expect("进度条模板是常量")
+                .progress_chars("=> "),
+        );
+        BatchProgress { bar }
+    }
+
+    /// 按当前汇总更新进度，作为 [`run_batch`] 的 `on_progress` 回调
+    pub fn update(&self, summary: &BatchSummary) {
+        self.bar.set_position((summary.ok + summary.failed) as u64);
+        self.bar.set_message(tr_format!("失败 {}", "failed {}", summary.failed));
+    }
+
+    /// 清除进度条，之后的输出不会与之重叠
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+
+    /// 包装逐项结果的输出：每次写入前暂时擦除进度条，写完后重绘
+    pub fn writer<W: Write>(&self, inner: W) -> ProgressWriter<'_, W> {
+        ProgressWriter { bar: &self.bar, inner }
+    }
+}
+
+/// 见 [`BatchProgress::writer`]
+pub struct ProgressWriter<'a, W> {
+    bar: &'a ProgressBar,
+    inner: W,
+}
+
+impl<W: Write> Write for ProgressWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let inner = &mut self.inner;
+        self.bar.suspend(|| inner.write(buf))
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        let inner = &mut self.inner;
+        self.bar.suspend(|| inner.write_all(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 展开批量输入：目录替换为其中的 `.scrate` 文件（按文件名排序，不递归），其他输入原样保留
+pub fn expand_inputs(inputs: &[String]) -> Result<Vec<String>> {
+    let mut expanded = vec![];
+    for input in inputs {
+        let path = Path::new(input);
+        if !path.is_dir() {
+            expanded.push(input.clone());
+            continue;
+        }
+        let mut files = vec![];
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.is_file() && file.extension().is_some_and(|ext| ext == "scrate") {
+                files.push(file.display().to_string());
+            }
+        }
+        files.sort();
+        expanded.extend(files);
+    }
+    Ok(expanded)
+}
+
+#[test]
+fn test_run_batch_jsonl() {
+    use crate::utils::context::{PackageContext, SIGTYPE};
+    use crate::utils::testing::{TestDir, signer, root_ca_bins};
+
+    let pkcs = signer();
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("batch".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![1u8; 16]);
+    pack_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let dir = TestDir::new("batch");
+    let good = dir.write("good.scrate", &bin);
+    let missing = dir.join("missing.scrate");
+    let inputs = vec![good.display().to_string(), missing.display().to_string()];
+
+    let root_cas = root_ca_bins();
+    let mut out = vec![];
+    let mut progress = vec![];
+    let summary = run_batch(
+        &inputs,
+        BatchFormat::Jsonl,
+        &mut out,
+        |input| {
+            let ctx = PackageContext::try_from_path(input, &root_cas)?;
+            Ok(format!("{}-{}", ctx.pack_info.name, ctx.pack_info.version))
+        },
+        |summary| progress.push(*summary),
+    )
+    .unwrap();
+
+    assert_eq!(summary, BatchSummary { ok: 1, failed: 1 });
+    assert_eq!(progress, vec![BatchSummary { ok: 1, failed: 0 }, summary]);
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["input"], inputs[0]);
+    assert_eq!(lines[0]["status"], "ok");
+    assert_eq!(lines[0]["output"], "batch-0.1.0");
+    assert_eq!(lines[1]["status"], "error");
+    assert!(lines[1]["error"].as_str().unwrap().contains("missing.scrate"));
+
+    assert_eq!("JSONL".parse::<BatchFormat>().unwrap(), BatchFormat::Jsonl);
+    assert!("csv".parse::<BatchFormat>().is_err());
+}
+
+#[test]
+fn test_batch_progress_suppressed() {
+    // 只有显式请求、标准输出是终端且不是 JSON Lines 时才显示
+    assert!(BatchProgress::enabled(true, BatchFormat::Text, true));
+    assert!(BatchProgress::enabled(true, BatchFormat::Summary, true));
+    assert!(!BatchProgress::enabled(false, BatchFormat::Text, true));
+    assert!(!BatchProgress::enabled(true, BatchFormat::Text, false));
+    assert!(!BatchProgress::enabled(true, BatchFormat::Jsonl, true));
+
+    // 包装后的输出原样写入
+    let progress = BatchProgress::new(2);
+    let mut out = vec![];
+    let inputs = vec!["a".to_string(), "b".to_string()];
+    let summary = run_batch(&inputs, BatchFormat::Summary, &mut progress.writer(&mut out), |input| {
+        Ok(input.to_uppercase())
+    }, |summary| progress.update(summary))
+    .unwrap();
+    progress.finish();
+    assert_eq!(summary, BatchSummary { ok: 2, failed: 0 });
+    assert_eq!(String::from_utf8(out).unwrap(), "OK   a  A\nOK   b  B\n");
+}
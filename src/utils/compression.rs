@@ -0,0 +1,206 @@
+//! crate 二进制段的可选压缩
+//!
+//! 压缩方式记录在文件头标志的第 8~9 位，原始长度记录在可选的压缩段中；不压缩时标志位为 0 且不写入该段，
+//! 文件与旧版本完全相同。旧版本解码器不认识这两个标志位，会拒绝这类文件，而不是把压缩后的数据当作 `.crate` 写出。
+//!
+//! CRATEBIN / NETWORK 签名始终针对解压后的 `.crate`，指纹和 FILE 签名覆盖文件中实际存储的压缩数据。
+use crate::error::{CrateSpecError, Result};
+use crate::utils::package::Uchar;
+use crate::tr_format;
+use flate2::write::{GzDecoder, GzEncoder};
+use std::fmt::{self, Display};
+use std::io::{self, Read, Write};
+
+/// 压缩段记录的原始长度上限（1 GiB），超过时拒绝解压，避免按伪造的长度无限制地分配内存
+pub const MAX_DECOMPRESSED_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// crate 二进制段的压缩方式，默认不压缩
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionKind {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl CompressionKind {
+    pub fn as_u8(self) -> Uchar {
+        match self {
+            CompressionKind::None => 0,
+            CompressionKind::Gzip => 1,
+            CompressionKind::Zstd => 2,
+        }
+    }
+
+    pub fn from_u8(value: Uchar) -> Result<Self> {
+        match value {
+            0 => Ok(CompressionKind::None),
+            1 => Ok(CompressionKind::Gzip),
+            2 => Ok(CompressionKind::Zstd),
+            _ => Err(CrateSpecError::DecodeError(tr_format!("未知的压缩方式: {}", "unknown compression kind: {}", value))),
+        }
+    }
+
+    /// 压缩 `bytes`，不压缩时原样返回
+    pub fn compress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        if self != CompressionKind::None && bytes.len() as u64 > MAX_DECOMPRESSED_BYTES {
+            return Err(CrateSpecError::EncodeError(tr_format!(
+                "crate 二进制 {} 字节，超过可压缩的上限 {} 字节",
+                "the crate binary is {} bytes, over the compressible limit of {} bytes",
+                bytes.len(),
+                MAX_DECOMPRESSED_BYTES
+            )));
+        }
+        let compressed = match self {
+            CompressionKind::None => Ok(bytes.to_vec()),
+            CompressionKind::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], flate2::Compression::best());
+                encoder.write_all(bytes).and_then(|_| encoder.finish())
+            }
+            CompressionKind::Zstd => zstd::encode_all(bytes, zstd::DEFAULT_COMPRESSION_LEVEL),
+        };
+        compressed.map_err(|e| CrateSpecError::EncodeError(tr_format!("{} 压缩失败: {}", "{} compression failed: {}", self, e)))
+    }
+
+    /// 解压 `bytes`，结果必须恰好为 `raw_size` 字节；`raw_size` 不得超过 [`MAX_DECOMPRESSED_BYTES`]，
+    /// 解压时最多读取 `raw_size + 1` 字节，避免压缩炸弹
+    pub fn decompress(self, bytes: &[u8], raw_size: u64) -> Result<Vec<u8>> {
+        if self != CompressionKind::None && raw_size > MAX_DECOMPRESSED_BYTES {
+            return Err(CrateSpecError::DecodeError(tr_format!(
+                "压缩段记录的原始长度 {} 字节超过上限 {} 字节",
+                "the uncompressed length {} bytes recorded in the compression section exceeds the limit of {} bytes",
+                raw_size, MAX_DECOMPRESSED_BYTES
+            )));
+        }
+        let limit = raw_size.saturating_add(1);
+        let mut out = vec![];
+        let read = match self {
+            CompressionKind::None => return Ok(bytes.to_vec()),
+            CompressionKind::Gzip => flate2::read::GzDecoder::new(bytes).take(limit).read_to_end(&mut out),
+            CompressionKind::Zstd => {
+                zstd::Decoder::new(bytes).and_then(|decoder| decoder.take(limit).read_to_end(&mut out))
+            }
+        };
+        read.map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 {} 解压失败: {}", "failed to decompress the {} crate binary: {}", self, e)))?;
+        if out.len() as u64 != raw_size {
+            return Err(CrateSpecError::DecodeError(tr_format!(
+                "crate 二进制解压后长度与压缩段记录不符: {} 字节，应为 {} 字节",
+                "the decompressed crate binary does not match the compression section: {} bytes, expected {} bytes",
+                out.len(),
+                raw_size
+            )));
+        }
+        Ok(out)
+    }
+
+    /// 把写入的压缩数据解压后写到 `out`，用于流式处理 crate 二进制段
+    pub fn decoder<W: Write>(self, out: W) -> Result<DecodeWriter<W>> {
+        Ok(match self {
+            CompressionKind::None => DecodeWriter::None(out),
+            CompressionKind::Gzip => DecodeWriter::Gzip(GzDecoder::new(out)),
+            CompressionKind::Zstd => DecodeWriter::Zstd(
+                zstd::stream::write::Decoder::new(out)
+                    .map_err(|e| CrateSpecError::DecodeError(tr_format!("创建 zstd 解压器失败: {}", "failed to create the zstd decoder: {}", e)))?,
+            ),
+        })
+    }
+}
+
+impl Display for CompressionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionKind::None => write!(f, "none"),
+            CompressionKind::Gzip => write!(f, "gzip"),
+            CompressionKind::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl std::str::FromStr for CompressionKind {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(CompressionKind::None),
+            "gzip" | "gz" => Ok(CompressionKind::Gzip),
+            "zstd" | "zst" => Ok(CompressionKind::Zstd),
+            _ => Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的压缩方式: {}，必须是 'none'、'gzip' 或 'zstd'",
+                "invalid compression kind: {}, must be 'none', 'gzip' or 'zstd'",
+                s
+            ))),
+        }
+    }
+}
+
+/// 边写入边解压的写入器，见 [`CompressionKind::decoder`]
+pub enum DecodeWriter<W: Write> {
+    None(W),
+    Gzip(GzDecoder<W>),
+    Zstd(zstd::stream::write::Decoder<'static, W>),
+}
+
+impl<W: Write> DecodeWriter<W> {
+    /// 写完剩余的解压数据并返回内部写入器；gzip 数据不完整或校验失败时报错
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            DecodeWriter::None(out) => Ok(out),
+            DecodeWriter::Gzip(decoder) => decoder.finish(),
+            DecodeWriter::Zstd(mut decoder) => {
+                decoder.flush()?;
+                Ok(decoder.into_inner())
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for DecodeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            DecodeWriter::None(out) => out.write(buf),
+            DecodeWriter::Gzip(decoder) => decoder.write(buf),
+            DecodeWriter::Zstd(decoder) => decoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            DecodeWriter::None(out) => out.flush(),
+            DecodeWriter::Gzip(decoder) => decoder.flush(),
+            DecodeWriter::Zstd(decoder) => decoder.flush(),
+        }
+    }
+}
+
+#[test]
+fn test_compression_round_trip() {
+    use std::str::FromStr;
+
+    let bytes: Vec<u8> = (0..20_000u32).map(|i| (i % 7) as u8).collect();
+    for kind in [CompressionKind::None, CompressionKind::Gzip, CompressionKind::Zstd] {
+        assert_eq!(CompressionKind::from_str(&kind.to_string()).unwrap(), kind);
+        assert_eq!(CompressionKind::from_u8(kind.as_u8()).unwrap(), kind);
+        let compressed = kind.compress(&bytes).unwrap();
+        if kind != CompressionKind::None {
+            assert!(compressed.len() < bytes.len());
+            // 记录的原始长度不符时报错
+            assert!(kind.decompress(&compressed, bytes.len() as u64 - 1).is_err());
+            assert!(kind.decompress(&compressed, bytes.len() as u64 + 1).is_err());
+        }
+        assert_eq!(kind.decompress(&compressed, bytes.len() as u64).unwrap(), bytes);
+
+        let mut writer = kind.decoder(vec![]).unwrap();
+        for chunk in compressed.chunks(1000) {
+            writer.write_all(chunk).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), bytes);
+    }
+    // 伪造的原始长度在解压前被拒绝
+    let compressed = CompressionKind::Zstd.compress(&bytes).unwrap();
+    for raw_size in [MAX_DECOMPRESSED_BYTES + 1, u64::MAX] {
+        let err = CompressionKind::Zstd.decompress(&compressed, raw_size).unwrap_err();
+        assert!(matches!(err, CrateSpecError::DecodeError(ref msg) if msg.contains("超过上限")), "{}", err);
+    }
+    assert!(CompressionKind::from_str("lz4").is_err());
+    assert!(CompressionKind::from_u8(3).is_err());
+}
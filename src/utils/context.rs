@@ -1,19 +1,60 @@
 use crate::utils::package::{
-    CrateBinarySection, CratePackage, DepTableEntry, LenArrayType, PackageSection, RawArrayType,
+    CrateBinarySection, CratePackage, DepTableEntry, Endianness, LenArrayType, PackageSection, RawArrayType,
     SigStructureSection, Size, Type,
 };
-use crate::utils::pkcs::PKCS;
+use crate::utils::pkcs::{DigestAlgo, KeyStrengthPolicy, TrustAnchor, DEFAULT_MAX_CHAIN_DEPTH, PKCS};
 use crate::network::{NetworkSignature, PkiClient, KeyPair};
 use crate::error::{Result, CrateSpecError};
-use std::collections::HashMap;
+use flate2::read::GzDecoder;
+use crate::utils::from_toml::CrateToml;
+use crate::utils::file_hashes::FileHash;
+use crate::utils::compression::CompressionKind;
+use crate::locale::tr;
+use crate::tr_format;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
 use std::sync::Arc;
+use tar::Archive;
 
 
 pub const NOT_SIG_NUM: usize = 3;
 
+/// 构建元数据中记录打包所用 cargo 版本的键
+pub const BUILD_META_CARGO_VERSION: &str = "cargo_version";
+/// 构建元数据中记录打包所用 rustc 版本的键
+pub const BUILD_META_RUSTC_VERSION: &str = "rustc_version";
+/// 构建元数据中记录生成该包的工具标识的键
+pub const BUILD_META_PRODUCER_TOOL: &str = "producer_tool";
+/// 构建元数据中记录操作者（人员或 CI 任务）标识的键
+pub const BUILD_META_PRODUCER_OPERATOR: &str = "producer_operator";
+/// 构建元数据中记录清单 `license-file` 字段（相对清单目录的路径）的键
+pub const BUILD_META_LICENSE_FILE: &str = "license_file";
+/// 构建元数据中记录 `license-file` 所指文件内容的键
+pub const BUILD_META_LICENSE_TEXT: &str = "license_text";
+
+/// 生成包的工具及操作者标识，记录在构建元数据段中
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Producer {
+    pub tool: String,
+    pub operator: Option<String>,
+}
+
 /// 字符串长度前缀字节数
 pub const STRING_LENGTH_PREFIX_BYTES: usize = 4;
 
+/// 解码时字符串表中单个字符串的默认最大字节数
+pub const DEFAULT_MAX_STRING_LEN: usize = 64 * 1024;
+
+/// 签名段类型字节的第 4~7 位：本地签名内容所用摘要算法的编号（见 [`DigestAlgo::as_u8`]）
+const SIG_DIGEST_MASK: Type = 0xf0;
+const SIG_DIGEST_SHIFT: u32 = 4;
+
+/// 签名类型，序列化为 `"file"` / `"cratebin"` / `"network"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SIGTYPE {
     FILE,
     CRATEBIN,
@@ -29,6 +70,40 @@ impl SIGTYPE {
             SIGTYPE::NETWORK => 2,
         }
     }
+
+    /// 由签名段中的数值还原签名类型，未知的类型返回 None
+    pub fn from_u32(typ: u32) -> Option<Self> {
+        [SIGTYPE::FILE, SIGTYPE::CRATEBIN, SIGTYPE::NETWORK]
+            .into_iter()
+            .find(|t| t.as_u32() == typ)
+    }
+}
+
+impl fmt::Display for SIGTYPE {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SIGTYPE::FILE => write!(f, "file"),
+            SIGTYPE::CRATEBIN => write!(f, "cratebin"),
+            SIGTYPE::NETWORK => write!(f, "network"),
+        }
+    }
+}
+
+impl FromStr for SIGTYPE {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [SIGTYPE::FILE, SIGTYPE::CRATEBIN, SIGTYPE::NETWORK]
+            .into_iter()
+            .find(|t| s.eq_ignore_ascii_case(&t.to_string()))
+            .ok_or_else(|| {
+                CrateSpecError::ValidationError(tr_format!(
+                    "无效的签名类型: {}，必须是 'file'、'cratebin' 或 'network'",
+                    "invalid signature type: {}, must be 'file', 'cratebin' or 'network'",
+                    s
+                ))
+            })
+    }
 }
 
 pub enum DATASECTIONTYPE {
@@ -36,6 +111,9 @@ pub enum DATASECTIONTYPE {
     DEPTABLE = 1,
     CRATEBIN = 3,
     SIGSTRUCTURE = 4,
+    BUILDMETA = 5,
+    FILEHASHES = 6,
+    COMPRESSION = 7,
 }
 
 impl DATASECTIONTYPE {
@@ -46,20 +124,56 @@ impl DATASECTIONTYPE {
             DATASECTIONTYPE::DEPTABLE => 1,
             DATASECTIONTYPE::CRATEBIN => 3,
             DATASECTIONTYPE::SIGSTRUCTURE => 4,
+            DATASECTIONTYPE::BUILDMETA => 5,
+            DATASECTIONTYPE::FILEHASHES => 6,
+            DATASECTIONTYPE::COMPRESSION => 7,
         }
     }
 }
 
 ///package context contains package's self and dependency package info
-#[derive(Debug)]
+///
+/// Cloning is cheap for the network handles (shared `Arc`s) and copies everything else,
+/// so a decoded context can be cloned and re-encoded, e.g. to strip or replace signatures.
+#[derive(Debug, Clone)]
 pub struct PackageContext {
     pub pack_info: PackageInfo,
     pub dep_infos: Vec<DepInfo>,
     pub crate_binary: CrateBinary,
     pub sigs: Vec<SigInfo>,
     pub root_cas: Vec<Vec<u8>>,
+    /// 验证本地签名时允许的证书链深度
+    pub max_chain_depth: u32,
+    /// 验证本地签名时对签名者公钥的强度要求
+    pub key_policy: KeyStrengthPolicy,
+    /// 字符串表中单个字符串允许的最大字节数；解码时据此拒绝过长的字符串，编码时据此拒绝过长的构建元数据
+    pub max_string_len: usize,
+    /// 指纹所用的摘要算法，编码时记录在头部，解码时从头部读出
+    pub fingerprint_digest: DigestAlgo,
+    /// 验签时要求至少有一个签名；默认不要求，没有签名的包验签时不会报错，解码后 `sigs` 为空
+    pub require_signature: bool,
+    /// 验签时要求每种类型至少有一个签名；默认为空，不限制签名类型
+    pub require_sig_types: Vec<SIGTYPE>,
+    /// 解码验签时验证全部签名并在错误中列出每个失败的签名，而不是在第一个失败处停止（见 [`PackageContext::verify_all`]）
+    pub verify_all_sigs: bool,
     pub network_client: Option<Arc<PkiClient>>,
     pub network_keypair: Option<Arc<KeyPair>>,
+    /// 构建元数据（如打包所用的 cargo/rustc 版本），为空时不写入构建元数据段
+    pub build_metadata: BTreeMap<String, String>,
+    /// `.crate` 中每个文件的 SHA256（见 [`PackageContext::record_file_hashes`]），为空时不写入文件哈希段
+    pub file_hashes: Vec<FileHash>,
+    /// 打包时因含不支持的键（如 path、features）而未写入依赖表的依赖名，不参与编码
+    pub dropped_deps: Vec<String>,
+    /// 解码时发现的、未被任何数据段引用的字符串表项 (偏移量, 字符串)，不参与编码
+    pub unreferenced_strings: Vec<(u32, String)>,
+    /// crate 二进制已裁剪：`crate_binary` 中只有 `.crate` 的 SHA256 摘要（见 [`PackageContext::prune_binary`]）
+    pub binary_pruned: bool,
+    /// 数据段对齐字节数（2 的幂，默认 1 即不填充），编码时记录在头部，解码时从头部读出
+    pub section_align: usize,
+    /// 字符串表长度前缀和段索引字段的字节序，编码时记录在头部，解码时从头部读出
+    pub endianness: Endianness,
+    /// crate 二进制段的压缩方式，编码时记录在压缩段中（不压缩时不写入），解码时从压缩段读出
+    pub compression: CompressionKind,
 }
 
 impl PackageContext {
@@ -70,11 +184,88 @@ impl PackageContext {
             dep_infos: vec![],
             sigs: vec![],
             root_cas: vec![],
+            max_chain_depth: DEFAULT_MAX_CHAIN_DEPTH,
+            key_policy: KeyStrengthPolicy::default(),
+            max_string_len: DEFAULT_MAX_STRING_LEN,
+            fingerprint_digest: DigestAlgo::Sha256,
+            require_signature: false,
+            require_sig_types: vec![],
+            verify_all_sigs: false,
             network_client: None,
             network_keypair: None,
+            build_metadata: BTreeMap::new(),
+            file_hashes: vec![],
+            dropped_deps: vec![],
+            unreferenced_strings: vec![],
+            binary_pruned: false,
+            section_align: 1,
+            endianness: Endianness::Little,
+            compression: CompressionKind::None,
         }
     }
 
+    /// 用已有的 `.crate` 内容构造上下文，不需要 Cargo.toml，也不执行 cargo package
+    ///
+    /// 依赖表为空，许可证和作者留空；需要从清单读取这些信息时使用
+    /// [`crate::utils::pack::pack_context_from_crate_file`]。
+    pub fn from_crate_bytes(name: String, version: String, bin: Vec<u8>) -> Result<Self> {
+        let mut ctx = Self::new();
+        ctx.set_package_info(name, version, String::new(), vec![]);
+        ctx.pack_info.check_name().map_err(CrateSpecError::ValidationError)?;
+        ctx.pack_info.check_version().map_err(CrateSpecError::ValidationError)?;
+        ctx.add_crate_bin(bin);
+        Ok(ctx)
+    }
+
+    /// 各类签名的个数，按 FILE、CRATEBIN、NETWORK 排列，省略没有签名的类型
+    pub fn sig_counts_by_type(&self) -> Vec<(SIGTYPE, usize)> {
+        [SIGTYPE::FILE, SIGTYPE::CRATEBIN, SIGTYPE::NETWORK]
+            .into_iter()
+            .map(|typ| (typ, self.sigs.iter().filter(|sig| sig.typ == typ.as_u32()).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    /// 签名段之前的数据段个数：固定的 3 个段，加上可选的构建元数据段和文件哈希段
+    pub fn non_sig_section_num(&self) -> usize {
+        NOT_SIG_NUM
+            + usize::from(!self.build_metadata.is_empty())
+            + usize::from(!self.file_hashes.is_empty())
+            + usize::from(self.compression != CompressionKind::None)
+    }
+
+    /// 在构建元数据中记录生成者；构建元数据段位于 FILE 签名覆盖的区域内，签名后无法篡改
+    pub fn set_producer(&mut self, tool: String, operator: Option<String>) {
+        self.build_metadata.insert(BUILD_META_PRODUCER_TOOL.to_string(), tool);
+        match operator {
+            Some(operator) => {
+                self.build_metadata.insert(BUILD_META_PRODUCER_OPERATOR.to_string(), operator);
+            }
+            None => {
+                self.build_metadata.remove(BUILD_META_PRODUCER_OPERATOR);
+            }
+        }
+    }
+
+    /// 构建元数据中记录的生成者，未记录工具标识时返回 None
+    pub fn producer(&self) -> Option<Producer> {
+        Some(Producer {
+            tool: self.build_metadata.get(BUILD_META_PRODUCER_TOOL)?.clone(),
+            operator: self.build_metadata.get(BUILD_META_PRODUCER_OPERATOR).cloned(),
+        })
+    }
+
+    /// 在构建元数据中记录许可证文件名及其内容
+    pub fn set_license_file(&mut self, file: String, text: String) {
+        self.build_metadata.insert(BUILD_META_LICENSE_FILE.to_string(), file);
+        self.build_metadata.insert(BUILD_META_LICENSE_TEXT.to_string(), text);
+    }
+
+    /// 构建元数据中记录的许可证文本，未使用 `license-file` 时返回 None
+    pub fn license_text(&self) -> Option<&str> {
+        self.build_metadata.get(BUILD_META_LICENSE_TEXT).map(|s| s.as_str())
+    }
+
     pub fn set_package_info(
         &mut self,
         name: String,
@@ -96,24 +287,108 @@ impl PackageContext {
         ver_req: String,
         src: SrcTypePath,
         src_platform: String,
+    ) {
+        self.add_dep_info_with_kind(name, ver_req, src, src_platform, DepKind::Normal);
+    }
+
+    /// 添加依赖，并指定依赖类别（普通、开发或构建依赖）
+    pub fn add_dep_info_with_kind(
+        &mut self,
+        name: String,
+        ver_req: String,
+        src: SrcTypePath,
+        src_platform: String,
+        kind: DepKind,
     ) {
         self.dep_infos.push(DepInfo {
             name,
             ver_req,
             src,
             src_platform,
+            kind,
             dump: true,
         });
     }
 
+    /// 删除名为 `name` 的全部依赖（包括不同平台下的同名依赖），返回删除的个数
+    ///
+    /// 修改依赖表会使已有签名失效，需要重新签名后再编码。
+    pub fn remove_dep(&mut self, name: &str) -> usize {
+        let before = self.dep_infos.len();
+        self.dep_infos.retain(|dep| dep.name != name);
+        before - self.dep_infos.len()
+    }
+
     pub fn dep_num(&self) -> usize {
         self.dep_infos.len()
     }
 
+    /// 全部依赖信息
+    ///
+    /// ```
+    /// use crate_spec::utils::context::{PackageContext, SrcTypePath};
+    ///
+    /// let mut ctx = PackageContext::new();
+    /// ctx.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "ALL".to_string());
+    /// assert_eq!(ctx.deps().len(), 1);
+    /// assert_eq!(ctx.deps()[0].name, "serde");
+    /// ```
+    pub fn deps(&self) -> &[DepInfo] {
+        &self.dep_infos
+    }
+
+    /// 按依赖源类型筛选依赖
+    ///
+    /// ```
+    /// use crate_spec::utils::context::{PackageContext, SrcKind, SrcTypePath};
+    ///
+    /// let mut ctx = PackageContext::new();
+    /// ctx.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "ALL".to_string());
+    /// ctx.add_dep_info(
+    ///     "local".to_string(),
+    ///     "0.1".to_string(),
+    ///     SrcTypePath::Git("https://example.com/local.git".to_string()),
+    ///     "ALL".to_string(),
+    /// );
+    /// let git_deps = ctx.deps_by_source(SrcKind::Git);
+    /// assert_eq!(git_deps.len(), 1);
+    /// assert_eq!(git_deps[0].name, "local");
+    /// ```
+    pub fn deps_by_source(&self, kind: SrcKind) -> Vec<&DepInfo> {
+        self.dep_infos.iter().filter(|dep| dep.src.kind() == kind).collect()
+    }
+
+    /// 依次返回每个依赖的 (名称, 版本要求, 规范化来源)
+    ///
+    /// ```
+    /// use crate_spec::utils::context::{PackageContext, SrcTypePath};
+    ///
+    /// let mut ctx = PackageContext::new();
+    /// ctx.add_dep_info(
+    ///     "toml".to_string(),
+    ///     "0.7".to_string(),
+    ///     SrcTypePath::Registry("my-registry".to_string()),
+    ///     "ALL".to_string(),
+    /// );
+    /// let deps: Vec<_> = ctx.dep_entries().collect();
+    /// assert_eq!(deps, vec![("toml", "0.7", "registry+my-registry".to_string())]);
+    /// ```
+    pub fn dep_entries(&self) -> impl Iterator<Item = (&str, &str, String)> + '_ {
+        self.dep_infos
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.ver_req.as_str(), dep.src.to_canonical_string()))
+    }
+
     pub fn add_sig(&mut self, pkcs: PKCS, sign_type: SIGTYPE) -> usize {
+        self.add_sig_with_digest(pkcs, sign_type, DigestAlgo::Sha256)
+    }
+
+    /// 添加签名，并指定签名内容所用的摘要算法（只影响 FILE / CRATEBIN 本地签名）
+    pub fn add_sig_with_digest(&mut self, pkcs: PKCS, sign_type: SIGTYPE, digest: DigestAlgo) -> usize {
         let mut siginfo = SigInfo::new();
         siginfo.pkcs = pkcs;
         siginfo.typ = sign_type.as_u32();
+        siginfo.digest = digest;
         self.sigs.push(siginfo);
         self.sigs.len() - 1
     }
@@ -130,24 +405,133 @@ impl PackageContext {
         self.root_cas.push(root_ca);
     }
 
+    /// 解码输出的元数据文本：包信息及每个依赖的规范化来源
+    pub fn metadata_text(&self) -> String {
+        let mut text = format!("{:#?}\ndependencies:\n", self.pack_info);
+        for (dep, (name, ver_req, src)) in self.dep_infos.iter().zip(self.dep_entries()) {
+            let kind = match dep.kind {
+                DepKind::Normal => String::new(),
+                kind => format!(", kind: {}", kind),
+            };
+            text.push_str(&format!(
+                "  {} {} ({}, platform: {}{})\n",
+                name, ver_req, src, dep.src_platform, kind
+            ));
+        }
+        if !self.build_metadata.is_empty() {
+            text.push_str("build metadata:\n");
+            for (key, value) in self.build_metadata.iter() {
+                if value.contains('\n') {
+                    // 多行内容（如许可证文本）逐行缩进输出
+                    text.push_str(&format!("  {}:\n", key));
+                    for line in value.lines() {
+                        text.push_str(&format!("    {}\n", line));
+                    }
+                } else {
+                    text.push_str(&format!("  {}: {}\n", key, value));
+                }
+            }
+        }
+        if !self.file_hashes.is_empty() {
+            // 与 sha256sum 的输出格式相同
+            text.push_str("files:\n");
+            for file in self.file_hashes.iter() {
+                text.push_str(&format!("  {}  {}\n", crate::network::digest_to_hex_string(&file.sha256), file.path));
+            }
+        }
+        text
+    }
+
     pub fn add_crate_bin(&mut self, bin: Vec<u8>) {
         let mut c = CrateBinary::new();
         c.set_bin(bin);
         self.crate_binary = c;
     }
 
+    /// 用 `.crate` 的 SHA256 摘要替换 crate 二进制，编码出只含摘要引用的包
+    ///
+    /// 用于 `.crate` 另存于内容仓库、只分发元数据和签名的场景。CRATEBIN 签名直接对该摘要签名，
+    /// 与对完整 `.crate` 的签名相同。已裁剪时不做任何操作。
+    pub fn prune_binary(&mut self) {
+        if !self.binary_pruned {
+            self.crate_binary.bytes = openssl::sha::sha256(&self.crate_binary.bytes).to_vec();
+            self.binary_pruned = true;
+        }
+    }
+
+    /// `.crate` 的 SHA256 摘要：已裁剪时即 crate 二进制段的内容，否则现场计算
+    pub fn crate_digest(&self) -> Vec<u8> {
+        if self.binary_pruned {
+            self.crate_binary.bytes.clone()
+        } else {
+            openssl::sha::sha256(&self.crate_binary.bytes).to_vec()
+        }
+    }
+
+    /// `.crate` 按 `algo` 计算的摘要；裁剪包只保存了 SHA256 摘要，其他算法返回错误
+    pub fn crate_digest_with(&self, algo: DigestAlgo) -> Result<Vec<u8>> {
+        match (self.binary_pruned, algo) {
+            (true, DigestAlgo::Sha256) => Ok(self.crate_binary.bytes.clone()),
+            (true, _) => Err(CrateSpecError::SignatureError(tr_format!(
+                "crate 二进制已裁剪，只有 SHA256 摘要，无法得到 {} 摘要",
+                "the crate binary is pruned to its SHA256 digest, so no {} digest is available",
+                algo
+            ))),
+            (false, _) => PKCS::new().gen_digest(algo, &self.crate_binary.bytes),
+        }
+    }
+
+    /// `required` 中的每种签名类型在 `sigs` 中至少出现一次，否则返回列出缺少类型的错误
+    pub fn check_sig_types(&self, required: &[SIGTYPE]) -> Result<()> {
+        let mut missing: Vec<String> = vec![];
+        for typ in required {
+            let name = typ.to_string();
+            if !self.sigs.iter().any(|sig| sig.typ == typ.as_u32()) && !missing.contains(&name) {
+                missing.push(name);
+            }
+        }
+        if !missing.is_empty() {
+            return Err(CrateSpecError::SignatureError(tr_format!("包中缺少 {} 类型的签名", "the package has no {} signature", missing.join("、"))));
+        }
+        Ok(())
+    }
+
+    /// 需要完整 crate 二进制的操作（完整性校验、交叉校验、提取 `.crate`）在裁剪包上返回错误
+    pub fn require_crate_binary(&self) -> Result<()> {
+        if self.binary_pruned {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "{}-{} 的 crate 二进制已裁剪，只包含摘要 {}",
+                "the crate binary of {}-{} is pruned and only holds the digest {}",
+                self.pack_info.name,
+                self.pack_info.version,
+                crate::network::digest_to_hex_string(&self.crate_binary.bytes)
+            )));
+        }
+        Ok(())
+    }
+
     /// Get binary data before signature section for signing/verification.
     /// This function removes the signature-related parts from section_index to break circular dependency:
     /// - section_index depends on sigStructure values
     /// - sigStructure calculation depends on section_index
+    ///
     /// Solution: zero out the signature-related parts in section_index when calculating signature digest.
-    pub fn binary_before_sig(&self, crate_package: &CratePackage, bin: &[u8]) -> Vec<u8> {
+    ///
+    /// The section index must hold either one signature section per entry in `sigs`, or none
+    /// (signing placeholders / detached bodies); any other count is an [`CrateSpecError::EncodeError`].
+    pub fn binary_before_sig(&self, crate_package: &CratePackage, bin: &[u8]) -> Result<Vec<u8>> {
         let ds_size = crate_package
             .section_index
             .datasection_size_without_sig();
         let total_size = crate_package.crate_header.ds_offset as usize + ds_size;
-        if crate_package.section_index.sig_num() != self.sigs.len() && !self.sigs.is_empty() {
-            assert_eq!(crate_package.section_index.sig_num(), 0);
+        let sig_num = crate_package.section_index.sig_num();
+        if sig_num != self.sigs.len() && sig_num != 0 {
+            return Err(CrateSpecError::EncodeError(tr_format!(
+                "段索引中的签名段数量与签名数量不一致: 期望 {} 个（或 0 个），实际 {} 个",
+                "the section index signature count does not match the signatures: expected {} (or 0), found {}",
+                self.sigs.len(),
+                sig_num
+            )));
         }
         let mut buf = bin[..total_size].to_vec();
         let zero_begin = crate_package.crate_header.si_offset as usize
@@ -159,7 +543,7 @@ impl PackageContext {
             *i = 0;
         }
 
-        buf
+        Ok(buf)
     }
 }
 
@@ -170,7 +554,7 @@ impl Default for PackageContext {
 }
 
 ///package's info
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PackageInfo {
     pub name: String,
     pub version: String,
@@ -199,6 +583,34 @@ impl PackageInfo {
         }
     }
 
+    /// 按 cargo 的包名规则检查包名：非空，只含 ASCII 字母、数字、`-` 和 `_`，不以数字开头
+    ///
+    /// 包名会写入输出文件名（`{name}-{version}.crate` 等），因此不能含路径分隔符或 `..`。
+    pub fn check_name(&self) -> std::result::Result<(), String> {
+        let valid_chars = self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        let starts_with_digit = self.name.starts_with(|c: char| c.is_ascii_digit());
+        if self.name.is_empty() || !valid_chars || starts_with_digit {
+            return Err(tr_format!(
+                "包名 {:?} 不符合 cargo 的包名规则（只能含 ASCII 字母、数字、- 和 _，且不以数字开头）",
+                "package name {:?} does not follow cargo's naming rules (ASCII letters, digits, - and _ only, not starting with a digit)",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// 检查版本是合法的 semver 版本
+    ///
+    /// 版本会写入输出文件名（`{name}-{version}.crate` 等）和索引条目，因此另外显式拒绝路径分隔符。
+    pub fn check_version(&self) -> std::result::Result<(), String> {
+        if self.version.contains(['/', '\\']) {
+            return Err(tr_format!("版本 {:?} 含路径分隔符", "version {:?} contains a path separator", self.version));
+        }
+        semver::Version::parse(&self.version)
+            .map(|_| ())
+            .map_err(|e| tr_format!("版本 {:?} 不是合法的 semver 版本: {}", "version {:?} is not a valid semver version: {}", self.version, e))
+    }
+
     pub fn write_to_package_section(&self, ps: &mut PackageSection, str_table: &mut StringTable) {
         ps.pkg_name = str_table.insert_str(self.name.clone());
         ps.pkg_version = str_table.insert_str(self.version.clone());
@@ -210,6 +622,18 @@ impl PackageInfo {
         ps.pkg_authors = LenArrayType::copy_from_vec(&authors_off);
     }
 
+    /// 规范化形式：去掉首尾空白，作者排序，用于忽略无意义差异的比较
+    pub fn normalized(&self) -> Self {
+        let mut authors: Vec<String> = self.authors.iter().map(|a| a.trim().to_string()).collect();
+        authors.sort();
+        Self {
+            name: self.name.trim().to_string(),
+            version: self.version.trim().to_string(),
+            license: self.license.trim().to_string(),
+            authors,
+        }
+    }
+
     pub fn read_from_package_section(&mut self, ps: &PackageSection, str_table: &StringTable) -> Result<()> {
         self.name = str_table.str_by_off(&ps.pkg_name)?;
         self.version = str_table.str_by_off(&ps.pkg_version)?;
@@ -222,24 +646,36 @@ impl PackageInfo {
     }
 }
 
+/// 未限定平台的依赖在 [`DepInfo::default`] 中使用的平台标签
+pub const DEFAULT_PLATFORM: &str = "default";
+
 ///dependencies' info
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DepInfo {
     pub name: String,
     pub ver_req: String,
     pub src: SrcTypePath,
     pub src_platform: String,
+    /// 依赖类别，来自 `[dependencies]`、`[dev-dependencies]` 或 `[build-dependencies]`
+    #[serde(default)]
+    pub kind: DepKind,
     ///only dump dependency that can be written to crate dependency table section
+    #[serde(skip_serializing, default = "default_dump")]
     pub dump: bool,
 }
 
+fn default_dump() -> bool {
+    true
+}
+
 impl Default for DepInfo {
     fn default() -> Self {
         Self {
             name: "".to_string(),
             ver_req: "default".to_string(),
             src: SrcTypePath::CratesIo,
-            src_platform: "default".to_string(),
+            src_platform: DEFAULT_PLATFORM.to_string(),
+            kind: DepKind::Normal,
             dump: true,
         }
     }
@@ -258,14 +694,35 @@ impl DepInfo {
             ver_req,
             src,
             src_platform,
+            kind: DepKind::Normal,
             dump,
         }
     }
 
+    /// 规范化形式：去掉首尾空白，无平台限定的 `"default"` 与 `""` 统一为 `""`
+    pub fn normalized(&self) -> Self {
+        let platform = self.src_platform.trim();
+        Self {
+            name: self.name.trim().to_string(),
+            ver_req: self.ver_req.trim().to_string(),
+            src: self.src.clone(),
+            src_platform: if platform == DEFAULT_PLATFORM { "" } else { platform }.to_string(),
+            kind: self.kind,
+            dump: self.dump,
+        }
+    }
+
+    /// (名称, 版本要求, 规范化来源字符串)，与 [`PackageContext::dep_entries`] 的元素相同
+    fn into_entry(self) -> (String, String, String) {
+        let src = self.src.to_canonical_string();
+        (self.name, self.ver_req, src)
+    }
+
     pub fn write_to_dep_table_entry(&self, dte: &mut DepTableEntry, str_table: &mut StringTable) {
         dte.dep_name = str_table.insert_str(self.name.clone());
         dte.dep_verreq = str_table.insert_str(self.ver_req.clone());
-        dte.dep_srctype = self.src.as_u8();
+        // 低 4 位为依赖源类型，其上两位为依赖类别；普通依赖与之前的格式相同
+        dte.dep_srctype = self.src.as_u8() | (self.kind.as_u8() << DEP_KIND_SHIFT);
         match &self.src {
             SrcTypePath::CratesIo => {
                 dte.dep_srcpath = str_table.insert_str("".to_string());
@@ -291,14 +748,55 @@ impl DepInfo {
         self.name = str_table.str_by_off(&dte.dep_name)?;
         self.ver_req = str_table.str_by_off(&dte.dep_verreq)?;
         let path = str_table.str_by_off(&dte.dep_srcpath)?;
-        self.src = SrcTypePath::from_u8_with_path(dte.dep_srctype, path)?;
+        self.src = SrcTypePath::from_u8_with_path(dte.dep_srctype & DEP_SRC_TYPE_MASK, path)?;
+        self.kind = DepKind::from_u8(dte.dep_srctype >> DEP_KIND_SHIFT)?;
         self.src_platform = str_table.str_by_off(&dte.dep_platform)?;
         Ok(())
     }
 }
 
-///dependencies' src type and path
-#[derive(Debug, PartialEq)]
+/// 解析命令行的依赖描述 `name@req[;source=<规范化来源>][;platform=<平台>]`
+///
+/// 来源为 [`SrcTypePath::to_canonical_string`] 的写法，默认 crates.io；平台默认不限定。
+///
+/// ```
+/// use crate_spec::utils::context::{DepInfo, SrcTypePath};
+///
+/// let dep: DepInfo = "local@0.1;source=git+https://example.com/local.git;platform=cfg(unix)".parse().unwrap();
+/// assert_eq!(dep.name, "local");
+/// assert_eq!(dep.ver_req, "0.1");
+/// assert_eq!(dep.src, SrcTypePath::Git("https://example.com/local.git".to_string()));
+/// assert_eq!(dep.src_platform, "cfg(unix)");
+/// ```
+impl FromStr for DepInfo {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| CrateSpecError::ValidationError(tr_format!("无效的依赖描述 {:?}: {}", "invalid dependency spec {:?}: {}", s, reason));
+        let mut parts = s.split(';');
+        let (name, ver_req) = parts
+            .next()
+            .and_then(|head| head.split_once('@'))
+            .ok_or_else(|| invalid(tr("应为 name@req[;source=...][;platform=...]", "expected name@req[;source=...][;platform=...]")))?;
+        let (name, ver_req) = (name.trim(), ver_req.trim());
+        if name.is_empty() || ver_req.is_empty() {
+            return Err(invalid(tr("名称和版本要求不能为空", "name and version requirement must not be empty")));
+        }
+        let mut dep = DepInfo { name: name.to_string(), ver_req: ver_req.to_string(), ..DepInfo::default() };
+        for part in parts.map(str::trim).filter(|part| !part.is_empty()) {
+            match part.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+                Some(("source", value)) => dep.src = SrcTypePath::from_canonical_string(value)?,
+                Some(("platform", value)) if !value.is_empty() => dep.src_platform = value.to_string(),
+                _ => return Err(invalid(&tr_format!("无法识别的字段 {}，只支持 source 和 platform", "unrecognized field {}, only source and platform are supported", part))),
+            }
+        }
+        Ok(dep)
+    }
+}
+
+///dependencies' src type and path, serialized as `{"kind": "git", "path": "..."}` (`{"kind": "crates-io"}` for crates.io)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "path", rename_all = "kebab-case")]
 pub enum SrcTypePath {
     CratesIo,
     Git(String),
@@ -307,7 +805,83 @@ pub enum SrcTypePath {
     P2p(String),
 }
 
+/// 依赖表项 `dep_srctype` 中依赖源类型所占的低 4 位
+const DEP_SRC_TYPE_MASK: u8 = 0x0f;
+/// 依赖类别在 `dep_srctype` 中的起始位
+const DEP_KIND_SHIFT: u32 = 4;
+
+///dependencies' kind, the manifest table it was read from, serialized as `"normal"` / `"dev"` / `"build"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DepKind {
+    #[default]
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DepKind {
+    /// 获取依赖类别的数值表示
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            DepKind::Normal => 0,
+            DepKind::Dev => 1,
+            DepKind::Build => 2,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(DepKind::Normal),
+            1 => Ok(DepKind::Dev),
+            2 => Ok(DepKind::Build),
+            _ => Err(CrateSpecError::ParseError(tr_format!("无效的依赖类别: {}", "invalid dependency kind: {}", value))),
+        }
+    }
+
+    /// Cargo.toml 中对应的依赖表名
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            DepKind::Normal => "dependencies",
+            DepKind::Dev => "dev-dependencies",
+            DepKind::Build => "build-dependencies",
+        }
+    }
+}
+
+impl fmt::Display for DepKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DepKind::Normal => write!(f, "normal"),
+            DepKind::Dev => write!(f, "dev"),
+            DepKind::Build => write!(f, "build"),
+        }
+    }
+}
+
+///dependencies' src type without path, used to filter dependencies by source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SrcKind {
+    CratesIo,
+    Git,
+    Url,
+    Registry,
+    P2p,
+}
+
 impl SrcTypePath {
+    /// 依赖源类型（不含路径）
+    pub fn kind(&self) -> SrcKind {
+        match self {
+            SrcTypePath::CratesIo => SrcKind::CratesIo,
+            SrcTypePath::Git(_) => SrcKind::Git,
+            SrcTypePath::Url(_) => SrcKind::Url,
+            SrcTypePath::Registry(_) => SrcKind::Registry,
+            SrcTypePath::P2p(_) => SrcKind::P2p,
+        }
+    }
+
     /// 获取依赖源类型的数值表示
     pub fn as_u8(&self) -> u8 {
         match self {
@@ -327,11 +901,46 @@ impl SrcTypePath {
             2 => Ok(SrcTypePath::Url(path)),
             3 => Ok(SrcTypePath::Registry(path)),
             4 => Ok(SrcTypePath::P2p(path)),
-            _ => Err(CrateSpecError::ParseError(format!("无效的依赖源类型: {}", value))),
+            _ => Err(CrateSpecError::ParseError(tr_format!("无效的依赖源类型: {}", "invalid dependency source type: {}", value))),
+        }
+    }
+
+    /// 规范字符串形式，参考 cargo 的 source id 写法：
+    /// `crates.io`、`git+<url>`、`path+<url>`、`registry+<name>`、`p2p+<addr>`
+    pub fn to_canonical_string(&self) -> String {
+        match self {
+            SrcTypePath::CratesIo => SRC_CRATES_IO.to_string(),
+            SrcTypePath::Git(path) => format!("{}{}", SRC_GIT_PREFIX, path),
+            SrcTypePath::Url(path) => format!("{}{}", SRC_PATH_PREFIX, path),
+            SrcTypePath::Registry(path) => format!("{}{}", SRC_REGISTRY_PREFIX, path),
+            SrcTypePath::P2p(path) => format!("{}{}", SRC_P2P_PREFIX, path),
+        }
+    }
+
+    /// 解析 [`SrcTypePath::to_canonical_string`] 生成的字符串
+    pub fn from_canonical_string(s: &str) -> Result<Self> {
+        if s == SRC_CRATES_IO {
+            Ok(SrcTypePath::CratesIo)
+        } else if let Some(path) = s.strip_prefix(SRC_GIT_PREFIX) {
+            Ok(SrcTypePath::Git(path.to_string()))
+        } else if let Some(path) = s.strip_prefix(SRC_PATH_PREFIX) {
+            Ok(SrcTypePath::Url(path.to_string()))
+        } else if let Some(path) = s.strip_prefix(SRC_REGISTRY_PREFIX) {
+            Ok(SrcTypePath::Registry(path.to_string()))
+        } else if let Some(path) = s.strip_prefix(SRC_P2P_PREFIX) {
+            Ok(SrcTypePath::P2p(path.to_string()))
+        } else {
+            Err(CrateSpecError::ParseError(tr_format!("无效的依赖源: {}", "invalid dependency source: {}", s)))
         }
     }
 }
 
+const SRC_CRATES_IO: &str = "crates.io";
+const SRC_GIT_PREFIX: &str = "git+";
+const SRC_PATH_PREFIX: &str = "path+";
+const SRC_REGISTRY_PREFIX: &str = "registry+";
+const SRC_P2P_PREFIX: &str = "p2p+";
+
 /// StringTable is a hash map to store the string and its offset.
 /// It can be used to store and get the string by its offset.
 /// When storing, every string(byte array) starts with its length(4 bytes).
@@ -341,6 +950,7 @@ pub struct StringTable {
     str2off: HashMap<String, u32>,
     off2str: HashMap<u32, String>,
     total_bytes: u32,
+    max_str_len: usize,
 }
 
 impl Default for StringTable {
@@ -355,17 +965,26 @@ impl StringTable {
             str2off: Default::default(),
             off2str: Default::default(),
             total_bytes: 0,
+            max_str_len: DEFAULT_MAX_STRING_LEN,
         };
         new_str_table.insert_str("".to_string());
         new_str_table
     }
 
+    /// 指定 `read_bytes` 时单个字符串允许的最大字节数
+    pub fn with_max_str_len(max_str_len: usize) -> Self {
+        Self {
+            max_str_len,
+            ..Self::new()
+        }
+    }
+
     // insert string to string table and return the offset of the new string.
     pub fn insert_str(&mut self, st: String) -> u32 {
         if let Some(&offset) = self.str2off.get(&st) {
             offset
         } else {
-            let st_len = st.as_bytes().len() as u32;
+            let st_len = st.len() as u32;
             let ret_val = self.total_bytes;
             self.str2off.insert(st.clone(), self.total_bytes);
             self.off2str.insert(self.total_bytes, st.clone());
@@ -381,25 +1000,45 @@ impl StringTable {
     pub fn off_by_str(&self, st: &String) -> Result<u32> {
         self.str2off.get(st)
             .copied()
-            .ok_or_else(|| CrateSpecError::Other(format!("字符串表中找不到字符串: {}", st)))
+            .ok_or_else(|| CrateSpecError::Other(tr_format!("字符串表中找不到字符串: {}", "string not found in string table: {}", st)))
     }
 
     pub fn str_by_off(&self, off: &u32) -> Result<String> {
         self.off2str.get(off)
             .cloned()
-            .ok_or_else(|| CrateSpecError::Other(format!("字符串表中找不到偏移量: {}", off)))
+            .ok_or_else(|| CrateSpecError::Other(tr_format!("字符串表中找不到偏移量: {}", "offset not found in string table: {}", off)))
+    }
+
+    /// 不在 `refs` 中的字符串偏移量（升序）
+    ///
+    /// 偏移量 0 的空字符串总是存在（见 [`StringTable::read_bytes`]），不计入结果。
+    pub fn unreferenced_offsets(&self, refs: &HashSet<u32>) -> Vec<u32> {
+        let mut offs: Vec<u32> = self.off2str.keys().filter(|off| **off != 0 && !refs.contains(off)).copied().collect();
+        offs.sort();
+        offs
     }
 
-    ///dump string table to bytes
+    /// `refs` 中不是任何字符串起始位置的偏移量（升序），如指向某个字符串中间的损坏偏移量
+    pub fn dangling_offsets(&self, refs: &HashSet<u32>) -> Vec<u32> {
+        let mut offs: Vec<u32> = refs.iter().filter(|off| !self.off2str.contains_key(off)).copied().collect();
+        offs.sort();
+        offs
+    }
+
+    ///dump string table to bytes, length prefixes in little endian
     pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(Endianness::Little)
+    }
+
+    ///dump string table to bytes, length prefixes in the given byte order
+    pub fn to_bytes_with(&self, endianness: Endianness) -> Vec<u8> {
         let mut offs: Vec<_> = self.off2str.keys().cloned().collect();
         offs.sort();
         let mut bytes = vec![];
         for off in offs {
-            //FIXME we use little endian
             if let Some(st) = self.off2str.get(&off) {
                 let st_bytes = st.bytes().collect::<Vec<u8>>();
-                bytes.extend((st_bytes.len() as u32).to_le_bytes());
+                bytes.extend(endianness.u32_to_bytes(st_bytes.len() as u32));
                 bytes.extend(st_bytes);
             }
         }
@@ -407,20 +1046,47 @@ impl StringTable {
     }
 
     ///parse string table from bytes
+    ///
+    /// 偏移量 0 总是对应空字符串（用作默认值，如 crates.io 依赖的 dep_srcpath）。
+    /// 空数据按只含空字符串的表处理；非空数据的首项不是空字符串时返回 `DecodeError`，
+    /// 不会改动文件中其余字符串的偏移量。长度前缀按小端解析。
     pub fn read_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.read_bytes_with(bytes, Endianness::Little)
+    }
+
+    /// 同 [`StringTable::read_bytes`]，长度前缀按指定字节序解析
+    pub fn read_bytes_with(&mut self, bytes: &[u8], endianness: Endianness) -> Result<()> {
+        self.str2off.clear();
+        self.off2str.clear();
+        self.total_bytes = 0;
+        if bytes.is_empty() {
+            self.insert_str("".to_string());
+            return Ok(());
+        }
+        if !bytes.starts_with(&[0; STRING_LENGTH_PREFIX_BYTES]) {
+            return Err(CrateSpecError::DecodeError(tr("字符串表的首项不是空字符串", "the first string table entry is not the empty string").to_string()));
+        }
         let mut i = 0;
         while i < bytes.len() {
             if i + STRING_LENGTH_PREFIX_BYTES > bytes.len() {
-                return Err(CrateSpecError::DecodeError("字符串表数据不完整".to_string()));
+                return Err(CrateSpecError::DecodeError(tr("字符串表数据不完整", "the string table is truncated").to_string()));
             }
             let mut len_bytes: [u8; STRING_LENGTH_PREFIX_BYTES] = [0; STRING_LENGTH_PREFIX_BYTES];
             len_bytes.copy_from_slice(bytes[i..i + STRING_LENGTH_PREFIX_BYTES].as_ref());
-            let len = u32::from_le_bytes(len_bytes) as usize;
+            let len = endianness.u32_from_bytes(len_bytes) as usize;
+            // 先检查声明的长度，避免按伪造的长度前缀分配大块内存
+            if len > self.max_str_len {
+                return Err(CrateSpecError::DecodeError(tr_format!(
+                    "字符串表中的字符串长度 {} 超过上限 {}",
+                    "a string table entry of length {} exceeds the limit of {}",
+                    len, self.max_str_len
+                )));
+            }
             if i + STRING_LENGTH_PREFIX_BYTES + len > bytes.len() {
-                return Err(CrateSpecError::DecodeError("字符串表数据不完整".to_string()));
+                return Err(CrateSpecError::DecodeError(tr("字符串表数据不完整", "the string table is truncated").to_string()));
             }
             let st = String::from_utf8(bytes[i + STRING_LENGTH_PREFIX_BYTES..i + STRING_LENGTH_PREFIX_BYTES + len].to_vec())
-                .map_err(|e| CrateSpecError::DecodeError(format!("UTF-8 解码失败: {}", e)))?;
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("UTF-8 解码失败: {}", "invalid UTF-8: {}", e)))?;
             self.str2off.insert(st.clone(), i as u32);
             self.off2str.insert(i as u32, st);
             i += STRING_LENGTH_PREFIX_BYTES + len;
@@ -430,7 +1096,7 @@ impl StringTable {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CrateBinary {
     //FIXME this maybe change to for fast read
     pub bytes: Vec<u8>,
@@ -451,22 +1117,161 @@ impl CrateBinary {
         self.bytes = bytes;
     }
 
-    pub fn write_to_crate_binary_section(&self, cbs: &mut CrateBinarySection) {
-        cbs.bin.arr = self.bytes.to_vec();
+    /// 按 `compression` 压缩后写入 crate 二进制段
+    pub fn write_to_crate_binary_section(&self, cbs: &mut CrateBinarySection, compression: CompressionKind) -> Result<()> {
+        cbs.bin.arr = compression.compress(&self.bytes)?;
+        Ok(())
     }
 
-    pub fn read_from_crate_biary_section(&mut self, cbs: &CrateBinarySection) {
-        self.bytes = cbs.bin.arr.to_vec();
+    /// 读取 crate 二进制段并按 `compression` 解压，解压后须为 `raw_size` 字节（不压缩时忽略）
+    pub fn read_from_crate_biary_section(
+        &mut self,
+        cbs: &CrateBinarySection,
+        compression: CompressionKind,
+        raw_size: u64,
+    ) -> Result<()> {
+        self.bytes = compression.decompress(&cbs.bin.arr, raw_size)?;
+        Ok(())
+    }
+
+    /// 校验 crate 二进制是否为结构完整的 gzip/tar 包
+    ///
+    /// 签名只能保证内容未被篡改，无法保证被签名的数据本身没有损坏。
+    /// 这里完整读取所有 tar 条目，并读到 gzip 流末尾以触发 CRC 校验。
+    pub fn check_integrity(&self) -> Result<()> {
+        let decoder = GzDecoder::new(self.bytes.as_slice());
+        let mut archive = Archive::new(decoder);
+        let entries = archive.entries()
+            .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制不是有效的 tar 包: {}", "the crate binary is not a valid tar archive: {}", e)))?;
+        for entry in entries {
+            let mut entry = entry
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 tar 条目损坏: {}", "corrupted tar entry in the crate binary: {}", e)))?;
+            io::copy(&mut entry, &mut io::sink())
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 tar 条目损坏: {}", "corrupted tar entry in the crate binary: {}", e)))?;
+        }
+        // tar 结束块之后可能仍有未读数据，读到流末尾才会校验 gzip CRC
+        io::copy(&mut archive.into_inner(), &mut io::sink())
+            .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 gzip 校验失败: {}", "gzip check of the crate binary failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// 取出 .crate 根目录（`<name>-<version>/`）下的 Cargo.toml 内容
+    pub fn cargo_toml(&self) -> Result<Vec<u8>> {
+        let mut archive = Archive::new(GzDecoder::new(self.bytes.as_slice()));
+        let entries = archive.entries()
+            .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制不是有效的 tar 包: {}", "the crate binary is not a valid tar archive: {}", e)))?;
+        for entry in entries {
+            let mut entry = entry
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 tar 条目损坏: {}", "corrupted tar entry in the crate binary: {}", e)))?;
+            let path = entry.path()
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 tar 条目路径无效: {}", "invalid tar entry path in the crate binary: {}", e)))?;
+            if path.components().count() == 2 && path.ends_with("Cargo.toml") {
+                let mut content = vec![];
+                io::Read::read_to_end(&mut entry, &mut content)
+                    .map_err(|e| CrateSpecError::DecodeError(tr_format!("读取 Cargo.toml 失败: {}", "failed to read Cargo.toml: {}", e)))?;
+                return Ok(content);
+            }
+        }
+        Err(CrateSpecError::ValidationError(tr("crate 二进制中没有 Cargo.toml", "the crate binary contains no Cargo.toml").to_string()))
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl PackageContext {
+    /// 规范化后的依赖表，按名称、平台、版本要求排序
+    pub fn normalized_deps(&self) -> Vec<DepInfo> {
+        let mut deps: Vec<DepInfo> = self.dep_infos.iter().map(DepInfo::normalized).collect();
+        deps.sort_by(|a, b| {
+            (&a.name, a.kind, &a.src_platform, &a.ver_req).cmp(&(&b.name, b.kind, &b.src_platform, &b.ver_req))
+        });
+        deps
+    }
+
+    /// 比较包信息和依赖表，忽略作者顺序、依赖顺序、首尾空白和平台标签写法的差异
+    pub fn metadata_eq(&self, other: &PackageContext) -> bool {
+        self.pack_info.normalized() == other.pack_info.normalized()
+            && self.normalized_deps() == other.normalized_deps()
+    }
+
+    /// 交叉校验：crate 二进制内 Cargo.toml 的包名、版本和依赖是否与解码出的包信息、依赖表一致
+    ///
+    /// 用于发现只改了元数据段而没有改 crate 二进制的篡改，所有不一致项会汇总在错误信息中。
+    pub fn cross_validate(&self) -> Result<()> {
+        self.require_crate_binary()?;
+        let mut toml = CrateToml::from_vec(self.crate_binary.cargo_toml()?)?;
+        let mut manifest = PackageContext::new();
+        toml.write_info_to_package_context(&mut manifest)?;
+
+        let mut discrepancies = vec![];
+        let (pack_info, manifest_info) = (self.pack_info.normalized(), manifest.pack_info.normalized());
+        if manifest_info.name != pack_info.name {
+            discrepancies.push(tr_format!(
+                "包名不一致: 元数据为 {}，Cargo.toml 为 {}",
+                "package name mismatch: metadata has {}, Cargo.toml has {}",
+                pack_info.name, manifest_info.name
+            ));
+        }
+        if manifest_info.version != pack_info.version {
+            discrepancies.push(tr_format!(
+                "版本不一致: 元数据为 {}，Cargo.toml 为 {}",
+                "version mismatch: metadata has {}, Cargo.toml has {}",
+                pack_info.version, manifest_info.version
+            ));
+        }
+        let deps: HashSet<_> = self.normalized_deps().into_iter().map(DepInfo::into_entry).collect();
+        let manifest_deps: HashSet<_> = manifest.normalized_deps().into_iter().map(DepInfo::into_entry).collect();
+        for (name, ver_req, src) in deps.difference(&manifest_deps) {
+            discrepancies.push(tr_format!("依赖 {} {} ({}) 不在 Cargo.toml 中", "dependency {} {} ({}) is not in Cargo.toml", name, ver_req, src));
+        }
+        for (name, ver_req, src) in manifest_deps.difference(&deps) {
+            discrepancies.push(tr_format!("Cargo.toml 中的依赖 {} {} ({}) 不在依赖表中", "dependency {} {} ({}) from Cargo.toml is not in the dependency table", name, ver_req, src));
+        }
+
+        if discrepancies.is_empty() {
+            Ok(())
+        } else {
+            discrepancies.sort();
+            Err(CrateSpecError::ValidationError(tr_format!(
+                "crate 二进制与元数据不一致: {}",
+                "the crate binary does not match the metadata: {}",
+                discrepancies.join("; ")
+            )))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct SigInfo {
     pub typ: u32,
     pub size: usize,
     pub bin: Vec<u8>,
     pub pkcs: PKCS,
     pub pub_key: Option<String>, // 用于网络签名（兼容性字段，实际数据从 NetworkSignature 中提取）
+    /// 本地签名所签内容的摘要算法；网络签名按 [`crate::network::SignScheme`] 签名，固定为 SHA256
+    pub digest: DigestAlgo,
+    /// 本地签名验证通过后记录的信任锚，网络签名和未验证时为 None
+    pub trust_anchor: Option<TrustAnchor>,
+}
+
+/// 签名的可序列化视图，供 JSON 输出使用
+///
+/// 只含公开信息：签名者取自本地签名内嵌的证书或网络签名的 `key_id`；
+/// 签名原文仅在 [`SigInfo::report`] 显式要求时以 base64 附带。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigInfoReport {
+    #[serde(rename = "type")]
+    pub typ: SIGTYPE,
+    pub size: usize,
+    #[serde(default)]
+    pub digest: DigestAlgo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pub_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// 验证通过后记录的信任锚，未验证或网络签名时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_anchor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bin: Option<String>,
 }
 
 impl Default for SigInfo {
@@ -483,27 +1288,30 @@ impl SigInfo {
             bin: vec![],
             pkcs: PKCS::new(),
             pub_key: None,
+            digest: DigestAlgo::Sha256,
+            trust_anchor: None,
         }
     }
 
     pub fn read_from_sig_structure_section(&mut self, sig: &SigStructureSection) -> Result<()> {
-        self.typ = sig.sigstruct_type as u32;
+        // 声明的长度与实际签名数据不一致说明签名段已损坏，继续解析会错位
+        if sig.sigstruct_size as usize != sig.sigstruct_sig.arr.len() {
+            return Err(CrateSpecError::DecodeError(tr_format!(
+                "签名段声明的长度 {} 与实际签名数据长度 {} 不一致",
+                "the signature section declares {} bytes but holds {} bytes",
+                sig.sigstruct_size,
+                sig.sigstruct_sig.arr.len()
+            )));
+        }
+        self.digest = DigestAlgo::from_u8((sig.sigstruct_type & SIG_DIGEST_MASK) >> SIG_DIGEST_SHIFT)?;
+        self.typ = (sig.sigstruct_type & !SIG_DIGEST_MASK) as u32;
         self.size = sig.sigstruct_size as usize;
         
         // 如果是网络签名，反序列化 NetworkSignature
         if self.typ == SIGTYPE::NETWORK.as_u32() {
-            match bincode::decode_from_slice::<NetworkSignature, _>(
-                &sig.sigstruct_sig.arr,
-                bincode::config::standard(),
-            ) {
-                Ok((network_sig, _)) => {
-                    self.bin = sig.sigstruct_sig.arr.clone();
-                    self.pub_key = Some(network_sig.pub_key.clone());
-                }
-                Err(e) => {
-                    return Err(CrateSpecError::DecodeError(format!("无法反序列化网络签名: {}", e)));
-                }
-            }
+            let network_sig = NetworkSignature::from_bytes(&sig.sigstruct_sig.arr).map_err(CrateSpecError::DecodeError)?;
+            self.bin = sig.sigstruct_sig.arr.clone();
+            self.pub_key = Some(network_sig.pub_key);
         } else {
             // 本地签名，直接复制
             self.bin = sig.sigstruct_sig.arr.clone();
@@ -511,8 +1319,40 @@ impl SigInfo {
         Ok(())
     }
 
+    /// 网络签名时反序列化签名段中的 [`NetworkSignature`]，可在编码后、写出文件前检查算法、flow、kms 和 key_id
+    ///
+    /// 本地签名（FILE / CRATEBIN）返回 `Ok(None)`，尚未签名（编码前 `bin` 为空）的网络签名也返回 `Ok(None)`。
+    pub fn network_signature(&self) -> Result<Option<NetworkSignature>> {
+        if self.typ != SIGTYPE::NETWORK.as_u32() || self.bin.is_empty() {
+            return Ok(None);
+        }
+        NetworkSignature::from_bytes(&self.bin).map(Some).map_err(CrateSpecError::DecodeError)
+    }
+
+    /// 生成可序列化的签名视图，`include_bin` 为 true 时附带 base64 编码的签名原文
+    pub fn report(&self, include_bin: bool) -> Result<SigInfoReport> {
+        let typ = SIGTYPE::from_u32(self.typ)
+            .ok_or_else(|| CrateSpecError::DecodeError(tr_format!("未知的签名类型: {}", "unknown signature type: {}", self.typ)))?;
+        let (pub_key, signer) = match typ {
+            SIGTYPE::NETWORK => match self.network_signature()? {
+                Some(network_sig) => (Some(network_sig.pub_key), network_sig.key_id),
+                None => (None, None),
+            },
+            SIGTYPE::FILE | SIGTYPE::CRATEBIN => (self.pub_key.clone(), Some(PKCS::signer_subject(&self.bin)?)),
+        };
+        Ok(SigInfoReport {
+            typ,
+            size: self.size,
+            digest: self.digest,
+            pub_key,
+            signer,
+            trust_anchor: self.trust_anchor.as_ref().map(|anchor| anchor.to_string()),
+            bin: include_bin.then(|| openssl::base64::encode_block(&self.bin)),
+        })
+    }
+
     pub fn write_to_sig_structure_section(&self, sig: &mut SigStructureSection) {
-        sig.sigstruct_type = self.typ as Type;
+        sig.sigstruct_type = self.typ as Type | (self.digest.as_u8() << SIG_DIGEST_SHIFT);
         sig.sigstruct_size = self.size as Size;
         
         // 如果是网络签名，bin 应该已经包含序列化的 NetworkSignature
@@ -520,3 +1360,309 @@ impl SigInfo {
         sig.sigstruct_sig = RawArrayType::from_vec(self.bin.clone());
     }
 }
+
+#[test]
+fn test_sig_structure_size_mismatch() {
+    let mut section = SigStructureSection::new();
+    section.sigstruct_type = SIGTYPE::CRATEBIN.as_u32() as Type;
+    section.sigstruct_sig = RawArrayType::from_vec(vec![1u8; 16]);
+    section.sigstruct_size = 17;
+    let err = SigInfo::new().read_from_sig_structure_section(&section).unwrap_err();
+    assert!(matches!(err, CrateSpecError::DecodeError(ref msg) if msg.contains("17")), "{}", err);
+
+    section.sigstruct_size = 16;
+    let mut sig_info = SigInfo::new();
+    sig_info.read_from_sig_structure_section(&section).unwrap();
+    assert_eq!(sig_info.size, 16);
+    assert_eq!(sig_info.bin, vec![1u8; 16]);
+}
+
+#[test]
+fn test_sig_info_network_signature() {
+    let mut sig_info = SigInfo::new();
+    sig_info.typ = SIGTYPE::CRATEBIN.as_u32();
+    sig_info.bin = vec![1u8; 16];
+    assert!(sig_info.network_signature().unwrap().is_none());
+
+    // 编码前网络签名尚未生成
+    sig_info.typ = SIGTYPE::NETWORK.as_u32();
+    sig_info.bin = vec![];
+    assert!(sig_info.network_signature().unwrap().is_none());
+
+    let network_sig = NetworkSignature::builder()
+        .with_pub_key("pub")
+        .with_signature("sig")
+        .with_algo("sm2")
+        .with_flow("sign")
+        .with_key_id("key-1")
+        .build();
+    sig_info.bin = network_sig.to_bytes().unwrap();
+    let parsed = sig_info.network_signature().unwrap().unwrap();
+    assert_eq!((parsed.algo.as_str(), parsed.key_id.as_deref()), ("sm2", Some("key-1")));
+
+    sig_info.bin = vec![0xff; 4];
+    assert!(matches!(sig_info.network_signature(), Err(CrateSpecError::DecodeError(_))));
+}
+
+#[test]
+fn test_sig_info_report() {
+    use crate::network::SignScheme;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("report".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![5u8; 32]);
+    pack_context.add_sig(signer(), SIGTYPE::FILE);
+    pack_context.add_sig(signer(), SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    let mut decoded = PackageContext::new();
+    decoded.set_root_cas_bin(root_ca_bins());
+    decoded.decode_from_crate_package(&bin).unwrap();
+
+    for (sig, name) in decoded.sigs.iter().zip(["file", "cratebin"]) {
+        let json = serde_json::to_value(sig.report(false).unwrap()).unwrap();
+        assert_eq!(json["type"], name);
+        assert_eq!(json["size"], sig.size);
+        assert!(json["signer"].as_str().unwrap().contains("CN="), "{}", json);
+        assert!(json["trust_anchor"].is_string(), "{}", json);
+        assert!(json.get("bin").is_none());
+    }
+
+    let network_sig = NetworkSignature {
+        pub_key: "pubkey".to_string(),
+        signature: "sig".to_string(),
+        algo: "sm2".to_string(),
+        flow: "sign".to_string(),
+        kms: None,
+        key_id: Some("key-1".to_string()),
+        scheme: SignScheme::Sha256Hex,
+    };
+    let mut sig = SigInfo::new();
+    sig.typ = SIGTYPE::NETWORK.as_u32();
+    sig.bin = network_sig.to_bytes().unwrap();
+    sig.size = sig.bin.len();
+    let report = sig.report(true).unwrap();
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["type"], "network");
+    assert_eq!(json["pub_key"], "pubkey");
+    assert_eq!(json["signer"], "key-1");
+    assert_eq!(openssl::base64::decode_block(json["bin"].as_str().unwrap()).unwrap(), sig.bin);
+    assert_eq!(serde_json::from_value::<SigInfoReport>(json).unwrap(), report);
+
+    sig.typ = 9;
+    assert!(sig.report(false).is_err());
+}
+
+#[test]
+fn test_crate_binary_integrity() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+    let content = b"[package]\nname = \"demo\"\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "demo-0.1.0/Cargo.toml", &content[..])
+        .unwrap();
+    let bytes = builder.into_inner().unwrap().finish().unwrap();
+
+    let mut crate_binary = CrateBinary::new();
+    crate_binary.set_bin(bytes.clone());
+    assert!(crate_binary.check_integrity().is_ok());
+
+    // gzip 尾部 8 字节为 CRC32 与原始长度，篡改 CRC 应被检测到
+    let mut corrupted = bytes.clone();
+    let crc_pos = corrupted.len() - 8;
+    corrupted[crc_pos] ^= 0xff;
+    crate_binary.set_bin(corrupted);
+    assert!(crate_binary.check_integrity().is_err());
+
+    let mut truncated = bytes;
+    truncated.truncate(truncated.len() / 2);
+    crate_binary.set_bin(truncated);
+    assert!(crate_binary.check_integrity().is_err());
+}
+
+#[test]
+fn test_from_crate_bytes() {
+    let mut ctx = PackageContext::from_crate_bytes("prebuilt".to_string(), "1.2.3".to_string(), vec![1, 2, 3]).unwrap();
+    assert_eq!(ctx.crate_binary.bytes, [1, 2, 3]);
+    assert!(ctx.deps().is_empty());
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+    let decoded = PackageContext::try_from_bytes_unverified(&bin).unwrap();
+    assert_eq!((decoded.pack_info.name.as_str(), decoded.pack_info.version.as_str()), ("prebuilt", "1.2.3"));
+
+    assert!(matches!(
+        PackageContext::from_crate_bytes("prebuilt".to_string(), "latest".to_string(), vec![]),
+        Err(CrateSpecError::ValidationError(_))
+    ));
+}
+
+#[test]
+fn test_sig_counts_by_type() {
+    let mut ctx = PackageContext::new();
+    assert!(ctx.sig_counts_by_type().is_empty());
+    ctx.add_sig(PKCS::new(), SIGTYPE::CRATEBIN);
+    ctx.add_sig(PKCS::new(), SIGTYPE::FILE);
+    ctx.add_sig(PKCS::new(), SIGTYPE::CRATEBIN);
+    assert_eq!(ctx.sig_counts_by_type(), [(SIGTYPE::FILE, 1), (SIGTYPE::CRATEBIN, 2)]);
+}
+
+#[test]
+fn test_string_table_empty_entry() {
+    // 空数据：偏移量 0 仍对应空字符串
+    let mut table = StringTable::new();
+    table.read_bytes(&[]).unwrap();
+    assert_eq!(table.str_by_off(&0).unwrap(), "");
+    assert_eq!(table.to_bytes(), StringTable::new().to_bytes());
+
+    // 只含一个空字符串
+    let mut table = StringTable::new();
+    table.read_bytes(&StringTable::new().to_bytes()).unwrap();
+    assert_eq!(table.str_by_off(&0).unwrap(), "");
+    assert_eq!(table.insert_str("a".to_string()), STRING_LENGTH_PREFIX_BYTES as u32);
+
+    // 首项不是空字符串：拒绝，而不是移动文件中的偏移量
+    let mut bytes = vec![];
+    for st in ["abc", "def"] {
+        bytes.extend((st.len() as u32).to_le_bytes());
+        bytes.extend(st.as_bytes());
+    }
+    let err = StringTable::new().read_bytes(&bytes).unwrap_err();
+    assert!(matches!(err, CrateSpecError::DecodeError(ref msg) if msg.contains("首项不是空字符串")), "{}", err);
+}
+
+#[test]
+fn test_string_table_max_str_len() {
+    let mut bytes = vec![0u8; STRING_LENGTH_PREFIX_BYTES];
+    bytes.extend(u32::MAX.to_le_bytes());
+    bytes.extend(b"abc");
+    let err = StringTable::new().read_bytes(&bytes).unwrap_err();
+    assert!(matches!(err, CrateSpecError::DecodeError(ref msg) if msg.contains("超过上限")), "{}", err);
+
+    let mut bytes = vec![0u8; STRING_LENGTH_PREFIX_BYTES];
+    bytes.extend(4u32.to_le_bytes());
+    bytes.extend(b"abcd");
+    assert!(StringTable::with_max_str_len(3).read_bytes(&bytes).is_err());
+    let mut table = StringTable::with_max_str_len(4);
+    table.read_bytes(&bytes).unwrap();
+    assert_eq!(table.str_by_off(&(STRING_LENGTH_PREFIX_BYTES as u32)).unwrap(), "abcd");
+}
+
+#[test]
+fn test_src_type_path_canonical_string() {
+    let cases = [
+        (SrcTypePath::CratesIo, "crates.io"),
+        (SrcTypePath::Git("https://github.com/a/b".to_string()), "git+https://github.com/a/b"),
+        (SrcTypePath::Url("file:///tmp/dep".to_string()), "path+file:///tmp/dep"),
+        (SrcTypePath::Registry("my-registry".to_string()), "registry+my-registry"),
+        (SrcTypePath::P2p("peer-1".to_string()), "p2p+peer-1"),
+    ];
+    for (src, expected) in cases {
+        let rendered = src.to_canonical_string();
+        assert_eq!(rendered, expected);
+        assert_eq!(SrcTypePath::from_canonical_string(&rendered).unwrap(), src);
+    }
+    assert!(SrcTypePath::from_canonical_string("svn+http://x").is_err());
+    assert!(SrcTypePath::from_canonical_string("").is_err());
+}
+
+#[test]
+fn test_dep_accessors() {
+    let mut ctx = PackageContext::new();
+    ctx.add_dep_info("a".to_string(), "1".to_string(), SrcTypePath::CratesIo, "ALL".to_string());
+    ctx.add_dep_info("b".to_string(), "2".to_string(), SrcTypePath::Git("g".to_string()), "ALL".to_string());
+    ctx.add_dep_info("c".to_string(), "3".to_string(), SrcTypePath::CratesIo, "ALL".to_string());
+
+    assert_eq!(ctx.deps().len(), ctx.dep_num());
+    let names: Vec<_> = ctx.deps_by_source(SrcKind::CratesIo).iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["a", "c"]);
+    assert!(ctx.deps_by_source(SrcKind::P2p).is_empty());
+    let entries: Vec<_> = ctx.dep_entries().collect();
+    assert_eq!(entries[1], ("b", "2", "git+g".to_string()));
+    assert_eq!(entries.len(), 3);
+}
+
+#[test]
+fn test_cross_validate() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let manifest = b"[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1.0\"\n";
+    let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "demo-0.1.0/Cargo.toml", &manifest[..])
+        .unwrap();
+    let bytes = builder.into_inner().unwrap().finish().unwrap();
+
+    let context = || {
+        let mut ctx = PackageContext::new();
+        ctx.set_package_info("demo".to_string(), "0.1.0".to_string(), "".to_string(), vec![]);
+        ctx.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "".to_string());
+        ctx.add_crate_bin(bytes.clone());
+        ctx
+    };
+    assert!(context().cross_validate().is_ok());
+
+    // 元数据被改动而 crate 二进制未变
+    let mut ctx = context();
+    ctx.pack_info.version = "0.2.0".to_string();
+    ctx.dep_infos[0].ver_req = "2.0".to_string();
+    let err = ctx.cross_validate().unwrap_err().to_string();
+    assert!(err.contains("版本不一致"), "{}", err);
+    assert!(err.contains("serde 2.0"), "{}", err);
+    assert!(err.contains("Cargo.toml 中的依赖 serde 1.0"), "{}", err);
+
+    let mut ctx = context();
+    ctx.add_crate_bin(vec![]);
+    assert!(ctx.cross_validate().is_err());
+
+    // 打包时读取的原始清单含 path + version 依赖，cargo 规范化后的内层清单只保留 version
+    let original = "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[dependencies]\nbdep = { path = \"../b\", version = \"0.1.0\" }\n";
+    let normalized = b"[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[dependencies.bdep]\nversion = \"0.1.0\"\n";
+    let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(normalized.len() as u64);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "demo-0.1.0/Cargo.toml", &normalized[..])
+        .unwrap();
+    let mut ctx = PackageContext::new();
+    let dropped = CrateToml::from_string(original).unwrap().write_info_to_package_context(&mut ctx).unwrap();
+    assert!(dropped.is_empty());
+    ctx.add_crate_bin(builder.into_inner().unwrap().finish().unwrap());
+    ctx.cross_validate().unwrap();
+}
+
+#[test]
+fn test_metadata_normalized_eq() {
+    fn context(authors: &[&str], deps: &[(&str, &str)]) -> PackageContext {
+        let mut ctx = PackageContext::new();
+        ctx.set_package_info(
+            "demo".to_string(),
+            "0.1.0".to_string(),
+            "MIT".to_string(),
+            authors.iter().map(|a| a.to_string()).collect(),
+        );
+        for (name, platform) in deps {
+            ctx.add_dep_info(name.to_string(), "1".to_string(), SrcTypePath::CratesIo, platform.to_string());
+        }
+        ctx
+    }
+
+    let a = context(&["alice", "bob"], &[("serde", ""), ("toml", "")]);
+    let b = context(&["bob", " alice "], &[("toml", DEFAULT_PLATFORM), ("serde", "")]);
+    assert_ne!(a.pack_info, b.pack_info);
+    assert_eq!(a.pack_info.normalized(), b.pack_info.normalized());
+    assert!(a.metadata_eq(&b));
+
+    let c = context(&["alice", "carol"], &[("serde", ""), ("toml", "")]);
+    assert!(!a.metadata_eq(&c));
+    let d = context(&["alice", "bob"], &[("serde", ""), ("toml", "cfg(unix)")]);
+    assert!(!a.metadata_eq(&d));
+}
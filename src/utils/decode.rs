@@ -1,12 +1,26 @@
 use crate::utils::context::{DepInfo, PackageContext, SigInfo, StringTable, DATASECTIONTYPE, SIGTYPE};
 use crate::utils::package::{
-    CrateBinarySection, CratePackage, DataSection, DepTableSection, PackageSection, SectionIndex,
-    SigStructureSection, FINGERPRINT_LEN,
+    BuildMetaSection, CompressionSection, CrateBinarySection, CratePackage, DataSection, DepTableSection, FileHashSection, PackageSection, SectionIndex,
+    SigStructureSection, CrateHeader, MAGIC_NUMBER, MAGIC_NUMBER_LEN, PRUNED_DIGEST_LEN,
 };
+use crate::utils::package::gen_bincode::{encode_size_by_bincode, BINCODE_CONFIG};
 use crate::error::Result;
+use crate::utils::compression::CompressionKind;
+use crate::utils::file_hashes::FileHash;
+use crate::utils::file_ops::write_file;
+use crate::locale::tr;
+use crate::tr_format;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::io::{Read, Seek};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::path::{Path, PathBuf};
 
-use crate::utils::pkcs::PKCS;
-use crate::network::{NetworkSignature, BaseConfig, digest_to_hex_string};
+use crate::utils::pkcs::{default_root_cas, DigestAlgo, TrustAnchor, PKCS};
+use crate::network::{NetworkSignature, BaseConfig, SignScheme, VerifyItem, digest_to_hex_string};
 
 impl SectionIndex {
     pub fn section_id_by_type(&self, typ: usize) -> Result<usize> {
@@ -15,11 +29,29 @@ impl SectionIndex {
                 return Ok(i);
             }
         }
-        Err(crate::error::CrateSpecError::DecodeError(format!("未找到类型为 {} 的数据段", typ)))
+        Err(crate::error::CrateSpecError::DecodeError(tr_format!("未找到类型为 {} 的数据段", "no data section of type {}", typ)))
     }
 }
 
 impl CratePackage {
+    /// 只解析魔数之后的文件头，用于在读取各部分之前得到头部标志（如指纹摘要算法）
+    pub fn peek_header(bin: &[u8]) -> Result<CrateHeader> {
+        let header_end = MAGIC_NUMBER_LEN + encode_size_by_bincode(&CrateHeader::new());
+        if bin.len() < header_end {
+            return Err(crate::error::CrateSpecError::DecodeError(tr_format!(
+                "文件只有 {} 字节，容纳不下文件头",
+                "the file is only {} bytes, too short for the header",
+                bin.len()
+            )));
+        }
+        if bin[..MAGIC_NUMBER_LEN] != MAGIC_NUMBER {
+            return Err(crate::error::CrateSpecError::DecodeError("magic not right!".to_string()));
+        }
+        bincode::decode_from_slice(&bin[MAGIC_NUMBER_LEN..header_end], BINCODE_CONFIG)
+            .map(|(header, _)| header)
+            .map_err(|e| crate::error::CrateSpecError::DecodeError(tr_format!("无法解析文件头: {}", "cannot parse the header: {}", e)))
+    }
+
     pub fn data_section_by_id(&self, id: usize) -> &DataSection {
         &self.data_sections.col.arr[id]
     }
@@ -55,6 +87,61 @@ impl CratePackage {
         }
     }
 
+    /// 可选的构建元数据段，不存在时返回 None
+    pub fn build_meta_section(&self) -> Result<Option<&BuildMetaSection>> {
+        let Ok(id) = self.section_index.section_id_by_type(DATASECTIONTYPE::BUILDMETA.as_u8() as usize) else {
+            return Ok(None);
+        };
+        match self.data_section_by_id(id) {
+            DataSection::BuildMetaSection(meta) => Ok(Some(meta)),
+            _ => {
+                Err(crate::error::CrateSpecError::DecodeError("build metadata section not found!".to_string()))
+            }
+        }
+    }
+
+    /// 可选的文件哈希段，不存在时返回 None
+    pub fn file_hash_section(&self) -> Result<Option<&FileHashSection>> {
+        let Ok(id) = self.section_index.section_id_by_type(DATASECTIONTYPE::FILEHASHES.as_u8() as usize) else {
+            return Ok(None);
+        };
+        match self.data_section_by_id(id) {
+            DataSection::FileHashSection(hashes) => Ok(Some(hashes)),
+            _ => {
+                Err(crate::error::CrateSpecError::DecodeError("file hash section not found!".to_string()))
+            }
+        }
+    }
+
+    /// 可选的压缩段，crate 二进制段未压缩时不存在，返回 None
+    pub fn compression_section(&self) -> Result<Option<&CompressionSection>> {
+        let Ok(id) = self.section_index.section_id_by_type(DATASECTIONTYPE::COMPRESSION.as_u8() as usize) else {
+            return Ok(None);
+        };
+        match self.data_section_by_id(id) {
+            DataSection::CompressionSection(compression) => Ok(Some(compression)),
+            _ => {
+                Err(crate::error::CrateSpecError::DecodeError("compression section not found!".to_string()))
+            }
+        }
+    }
+
+    /// crate 二进制段的压缩方式（取自文件头标志）及解压后的长度（取自压缩段），两者必须同时存在
+    pub fn compression(&self) -> Result<(CompressionKind, u64)> {
+        match (self.crate_header.compression()?, self.compression_section()?) {
+            (CompressionKind::None, None) => Ok((CompressionKind::None, 0)),
+            (CompressionKind::None, Some(_)) => Err(crate::error::CrateSpecError::DecodeError(
+                tr("文件头未标记压缩，却含有压缩段", "the header marks no compression, but a compression section is present").to_string(),
+            )),
+            (kind, Some(section)) => Ok((kind, section.raw_size)),
+            (kind, None) => Err(crate::error::CrateSpecError::DecodeError(tr_format!(
+                "文件头标记了 {} 压缩，却缺少压缩段",
+                "the header marks {} compression, but the compression section is missing",
+                kind
+            ))),
+        }
+    }
+
     pub fn sig_structure_section(&self, no: usize) -> Result<&SigStructureSection> {
         let base = self.section_index.section_id_by_type(DATASECTIONTYPE::SIGSTRUCTURE.as_u8() as usize)?;
         match self.data_section_by_id(no + base) {
@@ -66,33 +153,251 @@ impl CratePackage {
     }
 }
 
+/// 验签所需的摘要，按摘要算法分别保存可签名区域（见 `binary_before_sig`）和 `.crate` 的摘要
+#[derive(Debug, Default)]
+pub(crate) struct SigDigests {
+    pub file: HashMap<DigestAlgo, Vec<u8>>,
+    pub crate_bin: HashMap<DigestAlgo, Vec<u8>>,
+}
+
+impl SigDigests {
+    /// `typ` 类型的本地签名在 `algo` 下应签的摘要
+    fn get(&self, typ: u32, algo: DigestAlgo) -> Option<&[u8]> {
+        let digests = if typ == SIGTYPE::FILE.as_u32() { &self.file } else { &self.crate_bin };
+        digests.get(&algo).map(Vec::as_slice)
+    }
+}
+
+/// 在作用域线程中并行计算 `f(0..n)`，结果按序号排列；工作线程数不超过可用的 CPU 数
+pub(crate) fn parallel_map<T, F>(n: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get).min(n);
+    if workers <= 1 {
+        return (0..n).map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= n {
+                            return done;
+                        }
+                        done.push((i, f(i)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, t)| t).collect()
+}
+
+/// 网络签名按 `.crate` 的 SHA256 摘要验证
+fn network_digest(i: usize, digests: &SigDigests) -> Result<&[u8]> {
+    digests
+        .crate_bin
+        .get(&DigestAlgo::Sha256)
+        .map(Vec::as_slice)
+        .ok_or_else(|| crate::error::CrateSpecError::Other(tr_format!("缺少签名 #{} 所需的 sha256 摘要", "missing the sha256 digest required by signature #{}", i)))
+}
+
+/// 单个签名的验证结果（见 [`PackageContext::verify_all`]）
+#[derive(Debug, Clone)]
+pub struct SigVerifyResult {
+    /// 签名在签名段中的序号
+    pub index: usize,
+    /// 签名类型，未知类型为 None
+    pub typ: Option<SIGTYPE>,
+    pub ok: bool,
+    /// 验证失败的原因
+    pub reason: Option<String>,
+    /// 本地签名验证通过时的信任锚
+    pub trust_anchor: Option<TrustAnchor>,
+}
+
+impl Display for SigVerifyResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let typ = self.typ.map_or(tr("未知类型", "unknown type").to_string(), |typ| format!("{:?}", typ));
+        let reason = self.reason.as_deref().unwrap_or(tr("通过", "passed"));
+        write!(f, "{} #{} ({}): {}", tr("签名", "signature"), self.index, typ, reason)
+    }
+}
+
+impl CratePackage {
+    /// 各数据段（包信息、依赖表、构建元数据、文件哈希）引用的全部字符串偏移量
+    pub fn string_refs(&self) -> Result<HashSet<u32>> {
+        let ps = self.package_section()?;
+        let mut refs: HashSet<u32> = [ps.pkg_name, ps.pkg_version, ps.pkg_license].into();
+        refs.extend(ps.pkg_authors.arr.iter().copied());
+        for entry in self.dep_table_section()?.entries.arr.iter() {
+            refs.extend([entry.dep_name, entry.dep_verreq, entry.dep_srcpath, entry.dep_platform]);
+        }
+        if let Some(section) = self.build_meta_section()? {
+            for entry in section.entries.arr.iter() {
+                refs.extend([entry.key, entry.value]);
+            }
+        }
+        if let Some(section) = self.file_hash_section()? {
+            refs.extend(section.entries.arr.iter().map(|entry| entry.path));
+        }
+        Ok(refs)
+    }
+
+    /// 按头部和段索引切分出字符串表及各数据段的原始字节，返回 (文件名, 字节)
+    pub fn raw_sections<'a>(&self, bin: &'a [u8]) -> Result<Vec<(String, &'a [u8])>> {
+        let slice = |off: usize, size: usize, name: &str| -> Result<&'a [u8]> {
+            bin.get(off..off + size).ok_or_else(|| {
+                crate::error::CrateSpecError::DecodeError(tr_format!("{} 超出文件范围", "{} is out of the file bounds", name))
+            })
+        };
+        let header = &self.crate_header;
+        let mut sections = vec![(
+            "stringtable.bin".to_string(),
+            slice(header.strtable_offset as usize, header.strtable_size as usize, "stringtable.bin")?,
+        )];
+        let mut sig_no = 0;
+        for (i, entry) in self.section_index.entries.arr.iter().enumerate() {
+            let name = match entry.sh_type {
+                typ if typ == DATASECTIONTYPE::PACK.as_u8() => "package.bin".to_string(),
+                typ if typ == DATASECTIONTYPE::DEPTABLE.as_u8() => "deptable.bin".to_string(),
+                typ if typ == DATASECTIONTYPE::CRATEBIN.as_u8() => "cratebin.bin".to_string(),
+                typ if typ == DATASECTIONTYPE::BUILDMETA.as_u8() => "buildmeta.bin".to_string(),
+                typ if typ == DATASECTIONTYPE::FILEHASHES.as_u8() => "filehashes.bin".to_string(),
+                typ if typ == DATASECTIONTYPE::COMPRESSION.as_u8() => "compression.bin".to_string(),
+                typ if typ == DATASECTIONTYPE::SIGSTRUCTURE.as_u8() => {
+                    sig_no += 1;
+                    format!("sig{}.bin", sig_no - 1)
+                }
+                _ => format!("section{}.bin", i),
+            };
+            let off = header.ds_offset as usize + entry.sh_offset as usize;
+            let bytes = slice(off, entry.sh_size as usize, &name)?;
+            sections.push((name, bytes));
+        }
+        Ok(sections)
+    }
+}
+
+/// 将 .scrate 的各段原始字节分别写入 `dir`，用于分析文件格式
+///
+/// 只解析文件结构，不校验指纹和签名，因此对被篡改或无法验签的文件同样可用。
+pub fn dump_sections(bin: &[u8], dir: &Path) -> Result<Vec<PathBuf>> {
+    let crate_package = CratePackage::decode_from_slice(bin)
+        .map_err(|e| crate::error::CrateSpecError::DecodeError(tr_format!("解码失败: {}", "decoding failed: {}", e)))?;
+    let mut paths = vec![];
+    for (name, bytes) in crate_package.raw_sections(bin)? {
+        let path = dir.join(name);
+        write_file(&path, bytes)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
 impl PackageContext {
     pub fn binary_before_digest(&self, bin: &[u8]) -> Vec<u8> {
-        bin[..bin.len() - FINGERPRINT_LEN].to_vec()
+        bin[..bin.len().saturating_sub(self.fingerprint_digest.output_len())].to_vec()
     }
 
     fn pack_info(&mut self, crate_package: &CratePackage, str_table: &StringTable) -> Result<()> {
         self.pack_info
             .read_from_package_section(crate_package.package_section()?, str_table)?;
-        Ok(())
+        self.pack_info.check_name().map_err(crate::error::CrateSpecError::DecodeError)?;
+        self.pack_info.check_version().map_err(crate::error::CrateSpecError::DecodeError)
     }
 
-    fn deps(&mut self, crate_package: &CratePackage, str_table: &StringTable) -> Result<()> {
+    /// 同名同平台的依赖只能出现一次，重复的条目会让下游工具无法确定取哪一个
+    fn read_deps(&mut self, crate_package: &CratePackage, str_table: &StringTable) -> Result<()> {
+        let mut seen = HashSet::new();
         for entry in crate_package.dep_table_section()?.entries.arr.iter() {
             let mut dep_info = DepInfo::default();
             dep_info.read_from_dep_table_entry(entry, str_table)?;
+            if !seen.insert((dep_info.name.clone(), dep_info.src_platform.clone(), dep_info.kind)) {
+                return Err(crate::error::CrateSpecError::DecodeError(tr_format!(
+                    "依赖表中 {}（平台 {}，{} 依赖）重复出现",
+                    "{} (platform {}, {} dependency) appears more than once in the dependency table",
+                    dep_info.name, dep_info.src_platform, dep_info.kind
+                )));
+            }
             self.dep_infos.push(dep_info);
         }
         Ok(())
     }
 
+    /// 构建元数据段是可选的，不存在时保持为空
+    fn build_metadata(&mut self, crate_package: &CratePackage, str_table: &StringTable) -> Result<()> {
+        if let Some(section) = crate_package.build_meta_section()? {
+            for entry in section.entries.arr.iter() {
+                self.build_metadata
+                    .insert(str_table.str_by_off(&entry.key)?, str_table.str_by_off(&entry.value)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// 文件哈希段是可选的，不存在时保持为空
+    fn file_hashes(&mut self, crate_package: &CratePackage, str_table: &StringTable) -> Result<()> {
+        if let Some(section) = crate_package.file_hash_section()? {
+            for entry in section.entries.arr.iter() {
+                self.file_hashes.push(FileHash { path: str_table.str_by_off(&entry.path)?, sha256: entry.sha256 });
+            }
+        }
+        Ok(())
+    }
+
+    /// 头部标记为已裁剪时，crate 二进制段必须是 SHA256 摘要；有压缩段时解压出 `.crate`
     fn binary(&mut self, crate_package: &CratePackage) -> Result<()> {
-        self.crate_binary.bytes = crate_package.crate_binary_section()?.bin.arr.clone();
+        let (compression, raw_size) = crate_package.compression()?;
+        let section = crate_package.crate_binary_section()?;
+        // 流式解码时 crate 二进制段留在文件中，段内容为空，不需要解压
+        if section.bin.arr.is_empty() {
+            self.crate_binary.bytes.clear();
+        } else {
+            self.crate_binary.read_from_crate_biary_section(section, compression, raw_size)?;
+        }
+        self.compression = compression;
+        self.binary_pruned = crate_package.crate_header.binary_pruned();
+        if self.binary_pruned && compression != CompressionKind::None {
+            return Err(crate::error::CrateSpecError::DecodeError(
+                tr("已裁剪的 crate 二进制段不应被压缩", "a pruned crate binary section must not be compressed").to_string(),
+            ));
+        }
+        self.section_align = crate_package.crate_header.section_align();
+        self.fingerprint_digest = crate_package.crate_header.fingerprint_digest()?;
+        self.endianness = crate_package.crate_header.endianness();
+        if self.binary_pruned && self.crate_binary.bytes.len() != PRUNED_DIGEST_LEN {
+            return Err(crate::error::CrateSpecError::DecodeError(tr_format!(
+                "已裁剪的 crate 二进制段应为 {} 字节的 SHA256 摘要，实际 {} 字节",
+                "a pruned crate binary section must be a {}-byte SHA256 digest, found {} bytes",
+                PRUNED_DIGEST_LEN,
+                self.crate_binary.bytes.len()
+            )));
+        }
         Ok(())
     }
 
     fn sigs(&mut self, crate_package: &CratePackage) -> Result<()> {
-        let sig_num = crate_package.section_index.sig_num();
+        // 签名段必须排在所有其他数据段之后：FILE 签名只覆盖第一个签名段之前的内容
+        let index = &crate_package.section_index;
+        if index.entries.arr[index.none_sig_num()..]
+            .iter()
+            .any(|entry| entry.sh_type as usize != DATASECTIONTYPE::SIGSTRUCTURE.as_u8() as usize)
+        {
+            return Err(crate::error::CrateSpecError::DecodeError(
+                tr("签名段之后还有其他数据段，这些段不受 FILE 签名保护", "data sections follow a signature section and are not covered by FILE signatures").to_string(),
+            ));
+        }
+        let sig_num = index.sig_num();
         for no in 0..sig_num {
             let sig = crate_package.sig_structure_section(no)?;
             let mut sig_info = SigInfo::new();
@@ -102,105 +407,477 @@ impl PackageContext {
         Ok(())
     }
 
-    fn check_fingerprint(&self, bin_all: &[u8]) -> Result<bool> {
-        let calculated = PKCS::new().gen_digest_256(&bin_all[..bin_all.len() - FINGERPRINT_LEN])?;
-        Ok(calculated == bin_all[bin_all.len() - FINGERPRINT_LEN..])
+    /// 按头部记录的摘要算法校验末尾的指纹
+    pub(crate) fn check_fingerprint(&self, bin_all: &[u8]) -> Result<bool> {
+        let algo = CratePackage::peek_header(bin_all)?.fingerprint_digest()?;
+        let (body, stored) = bin_all.split_at(
+            bin_all.len().checked_sub(algo.output_len()).ok_or_else(|| {
+                crate::error::CrateSpecError::DecodeError(tr_format!("文件只有 {} 字节，容纳不下指纹", "the file is only {} bytes, too short for the fingerprint", bin_all.len()))
+            })?,
+        );
+        let calculated = PKCS::new().gen_digest(algo, body)?;
+        Ok(PKCS::digest_eq(&calculated, stored))
+    }
+
+    pub(crate) fn check_sigs(&mut self, crate_package: &CratePackage, bin_all: &[u8]) -> Result<()> {
+        let mut digests = SigDigests::default();
+        self.complete_sig_digests(&mut digests, || self.binary_before_sig(crate_package, bin_all))?;
+        self.check_sigs_with_digests(&digests)
+    }
+
+    /// 补齐 `digests` 中验签还缺的摘要，只计算签名实际用到的算法
+    ///
+    /// `signable` 生成可签名区域（见 `binary_before_sig`）的内容，没有 FILE 签名或摘要都已提供时不会调用。
+    /// 网络签名按 `.crate` 的 SHA256 摘要验证。
+    pub(crate) fn complete_sig_digests<F>(&self, digests: &mut SigDigests, signable: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<Vec<u8>>,
+    {
+        let mut file_algos = HashSet::new();
+        let mut crate_algos = HashSet::new();
+        for sig in self.sigs.iter() {
+            match sig.typ {
+                typ if typ == SIGTYPE::FILE.as_u32() => file_algos.insert(sig.digest),
+                typ if typ == SIGTYPE::NETWORK.as_u32() => crate_algos.insert(DigestAlgo::Sha256),
+                _ => crate_algos.insert(sig.digest),
+            };
+        }
+        file_algos.retain(|algo| !digests.file.contains_key(algo));
+        if !file_algos.is_empty() {
+            let signable = signable()?;
+            for algo in file_algos {
+                digests.file.insert(algo, PKCS::new().gen_digest(algo, &signable)?);
+            }
+        }
+        crate_algos.retain(|algo| !digests.crate_bin.contains_key(algo));
+        for algo in crate_algos {
+            digests.crate_bin.insert(algo, self.crate_digest_with(algo)?);
+        }
+        Ok(())
     }
 
-    fn check_sigs(&self, crate_package: &CratePackage, bin_all: &[u8]) -> Result<()> {
-        let bin_all = self.binary_before_sig(crate_package, bin_all);
-        let bin_crate = crate_package.crate_binary_section()?.bin.arr.as_slice();
-        
-        for siginfo in self.sigs.iter() {
+    /// 用已算好的摘要验证签名，`digests` 须包含各签名所用算法的摘要（见 [`PackageContext::complete_sig_digests`]）
+    pub(crate) fn check_sigs_with_digests(&mut self, digests: &SigDigests) -> Result<()> {
+        if self.require_signature && self.sigs.is_empty() {
+            return Err(crate::error::CrateSpecError::SignatureError(tr("包中没有签名", "the package has no signatures").to_string()));
+        }
+        self.check_sig_types(&self.require_sig_types)?;
+        // 网络签名先收集，本地签名全部通过后再统一请求 PKI 平台
+        let mut network_items = vec![];
+        let mut local = vec![];
+        for (i, siginfo) in self.sigs.iter().enumerate() {
             match siginfo.typ {
-                typ if typ == SIGTYPE::FILE.as_u32() || typ == SIGTYPE::CRATEBIN.as_u32() => {
-                    // 本地签名验证
-                    let actual_digest = match siginfo.typ {
-                        typ if typ == SIGTYPE::FILE.as_u32() => siginfo.pkcs.gen_digest_256(bin_all.as_slice())?,
-                        typ if typ == SIGTYPE::CRATEBIN.as_u32() => siginfo.pkcs.gen_digest_256(bin_crate)?,
-                        _ => unreachable!(),
-                    };
-                    let expect_digest = PKCS::decode_pkcs_bin(siginfo.bin.as_slice(), &self.root_cas)?;
-                    if actual_digest != expect_digest {
-                        return Err(crate::error::CrateSpecError::SignatureError("本地签名验证失败".to_string()));
-                    }
-                }
+                typ if typ == SIGTYPE::FILE.as_u32() || typ == SIGTYPE::CRATEBIN.as_u32() => local.push(i),
                 typ if typ == SIGTYPE::NETWORK.as_u32() => {
-                    // 网络签名验证
-                    // 从 PackageContext 获取 PkiClient
-                    let pki_client = self.network_client.as_ref()
-                        .ok_or_else(|| crate::error::CrateSpecError::Other("网络签名需要设置 network_client".to_string()))?;
-                    
-                    // 从 siginfo.bin 反序列化 NetworkSignature
-                    let network_sig: NetworkSignature = bincode::decode_from_slice(
-                        &siginfo.bin,
-                        bincode::config::standard(),
-                    )
-                    .map_err(|e| crate::error::CrateSpecError::DecodeError(format!("无法反序列化网络签名: {}", e)))?
-                    .0;
-                    
-                    // 计算内容摘要（网络签名统一使用 CRATEBIN 类型，只对 crate binary 签名）
-                    let actual_digest = siginfo.pkcs.gen_digest_256(bin_crate)?;
-                    
-                    // 转换为十六进制字符串
-                    let digest_hex = digest_to_hex_string(&actual_digest);
-                    
-                    // 使用从签名段提取的算法信息构建 BaseConfig
-                    let base_config = BaseConfig {
-                        algo: network_sig.algo.clone(),
-                        flow: network_sig.flow.clone(),
-                        kms: network_sig.kms.clone().unwrap_or_default(),
-                    };
-                    
-                    // 调用 PKI 平台验签接口
-                    match pki_client.verify_digest(
-                        &network_sig.pub_key,
-                        &digest_hex,
-                        &network_sig.signature,
-                        &base_config,
-                    ) {
-                        Ok(true) => {
-                            // 验签成功
-                        }
-                        Ok(false) => {
-                            return Err(crate::error::CrateSpecError::SignatureError("网络签名验证失败".to_string()));
-                        }
-                        Err(e) => {
-                            return Err(crate::error::CrateSpecError::PkiError(e));
-                        }
-                    }
+                    network_items.push(self.network_verify_item(siginfo, network_digest(i, digests)?)?);
                 }
                 _ => {
-                    return Err(crate::error::CrateSpecError::Other(format!("不支持的签名类型: {}", siginfo.typ)));
+                    return Err(crate::error::CrateSpecError::Other(tr_format!("不支持的签名类型: {}", "unsupported signature type: {}", siginfo.typ)));
+                }
+            }
+        }
+
+        // 本地签名相互独立，并行验证，全部完成后再汇总失败项
+        let root_cas = self.verify_root_cas();
+        let outcomes = parallel_map(local.len(), |k| {
+            let i = local[k];
+            self.verify_local_sig(i, &self.sigs[i], digests, &root_cas)
+        });
+        let mut anchors = vec![];
+        let mut failures = vec![];
+        for (i, outcome) in local.into_iter().zip(outcomes) {
+            match outcome {
+                Ok(anchor) => anchors.push((i, anchor)),
+                Err(e) => failures.push((i, e)),
+            }
+        }
+        if failures.len() == 1 {
+            return Err(failures.remove(0).1);
+        }
+        if !failures.is_empty() {
+            let reasons: Vec<String> = failures.iter().map(|(i, e)| tr_format!("签名 #{}: {}", "signature #{}: {}", i, e)).collect();
+            return Err(crate::error::CrateSpecError::SignatureError(tr_format!(
+                "{} 个本地签名验证失败: {}",
+                "{} local signatures failed verification: {}",
+                failures.len(),
+                reasons.join("; ")
+            )));
+        }
+        for (i, anchor) in anchors {
+            self.sigs[i].trust_anchor = Some(anchor);
+        }
+        self.check_network_sigs(&network_items)
+    }
+
+    /// 验证签名用的根 CA：未显式提供时使用进程级默认根 CA
+    fn verify_root_cas(&self) -> Cow<'_, [Vec<u8>]> {
+        if self.root_cas.is_empty() {
+            Cow::Owned(default_root_cas())
+        } else {
+            Cow::Borrowed(&self.root_cas)
+        }
+    }
+
+    /// 验证第 `i` 个本地签名：按签名记录的摘要算法比对摘要，并检查证书链和签名者公钥强度
+    fn verify_local_sig(&self, i: usize, siginfo: &SigInfo, digests: &SigDigests, root_cas: &[Vec<u8>]) -> Result<TrustAnchor> {
+        let actual_digest = digests.get(siginfo.typ, siginfo.digest).ok_or_else(|| {
+            crate::error::CrateSpecError::Other(tr_format!("缺少签名 #{} 所需的 {} 摘要", "missing the {1} digest required by signature #{0}", i, siginfo.digest))
+        })?;
+        let (expect_digest, anchor) = PKCS::verify_pkcs_bin(siginfo.bin.as_slice(), root_cas, self.max_chain_depth)?;
+        if !PKCS::digest_eq(actual_digest, &expect_digest) {
+            return Err(crate::error::CrateSpecError::SignatureError(tr("本地签名验证失败", "local signature verification failed").to_string()));
+        }
+        PKCS::check_signer_key(siginfo.bin.as_slice(), &self.key_policy).map_err(|e| match e {
+            crate::error::CrateSpecError::SignatureError(msg) => {
+                crate::error::CrateSpecError::SignatureError(tr_format!("签名 #{}: {}", "signature #{}: {}", i, msg))
+            }
+            e => e,
+        })?;
+        Ok(anchor)
+    }
+
+    /// 并行验证全部签名，返回每个签名的结果，不在第一个失败处停止
+    ///
+    /// 只有无法计算摘要等与单个签名无关的错误才返回 `Err`；网络签名各自请求验签接口。
+    /// 结果中的信任锚不会写回 `sigs`。
+    pub fn verify_all(&self, crate_package: &CratePackage, bin_all: &[u8]) -> Result<Vec<SigVerifyResult>> {
+        let mut digests = SigDigests::default();
+        self.complete_sig_digests(&mut digests, || self.binary_before_sig(crate_package, bin_all))?;
+        let root_cas = self.verify_root_cas();
+        let results = parallel_map(self.sigs.len(), |i| {
+            let siginfo = &self.sigs[i];
+            let outcome = match SIGTYPE::from_u32(siginfo.typ) {
+                Some(SIGTYPE::FILE | SIGTYPE::CRATEBIN) => {
+                    self.verify_local_sig(i, siginfo, &digests, &root_cas).map(Some)
                 }
+                Some(SIGTYPE::NETWORK) => network_digest(i, &digests)
+                    .and_then(|digest| self.network_verify_item(siginfo, digest))
+                    .and_then(|item| self.check_network_sigs(&[item]))
+                    .map(|_| None),
+                None => Err(crate::error::CrateSpecError::Other(tr_format!("不支持的签名类型: {}", "unsupported signature type: {}", siginfo.typ))),
+            };
+            SigVerifyResult {
+                index: i,
+                typ: SIGTYPE::from_u32(siginfo.typ),
+                ok: outcome.is_ok(),
+                reason: outcome.as_ref().err().map(|e| e.to_string()),
+                trust_anchor: outcome.ok().flatten(),
             }
+        });
+        Ok(results)
+    }
+
+    /// `verify_all_sigs` 时的验签：全部签名都验证一遍，有失败时在错误中列出每个失败的签名
+    fn check_all_sigs(&mut self, crate_package: &CratePackage, bin_all: &[u8]) -> Result<()> {
+        if self.require_signature && self.sigs.is_empty() {
+            return Err(crate::error::CrateSpecError::SignatureError(tr("包中没有签名", "the package has no signatures").to_string()));
+        }
+        self.check_sig_types(&self.require_sig_types)?;
+        let results = self.verify_all(crate_package, bin_all)?;
+        let failures: Vec<String> = results.iter().filter(|r| !r.ok).map(SigVerifyResult::to_string).collect();
+        if !failures.is_empty() {
+            return Err(crate::error::CrateSpecError::SignatureError(tr_format!(
+                "{} 个签名中 {} 个验证失败: {}",
+                "{} signatures, {} failed verification: {}",
+                results.len(),
+                failures.len(),
+                failures.join("; ")
+            )));
+        }
+        for result in results {
+            self.sigs[result.index].trust_anchor = result.trust_anchor;
         }
         Ok(())
     }
 
+    /// 由网络签名段构造验签请求
+    pub(crate) fn network_verify_item(&self, siginfo: &SigInfo, crate_digest: &[u8]) -> Result<VerifyItem> {
+        // 从 siginfo.bin 反序列化 NetworkSignature
+        let network_sig = NetworkSignature::from_bytes(&siginfo.bin)
+            .map_err(crate::error::CrateSpecError::DecodeError)?;
+
+        // 网络签名统一使用 CRATEBIN 类型，只对 crate binary 签名；按签名时记录的格式重建签名输入
+        let digest_hex = match network_sig.scheme {
+            SignScheme::Sha256Hex => digest_to_hex_string(crate_digest),
+            SignScheme::RawBase64 if self.binary_pruned => {
+                return Err(crate::error::CrateSpecError::SignatureError(tr_format!(
+                    "算法 {} 的签名需要完整的 crate 二进制，无法在裁剪包上验证",
+                    "{} signatures need the full crate binary and cannot be verified on a pruned package",
+                    network_sig.algo
+                )));
+            }
+            SignScheme::RawBase64 => SignScheme::RawBase64.prepare(&self.crate_binary.bytes),
+        };
+
+        // 使用从签名段提取的算法信息构建 BaseConfig
+        let base_config = BaseConfig {
+            algo: network_sig.algo.clone(),
+            flow: network_sig.flow.clone(),
+            kms: network_sig.kms.clone().unwrap_or_default(),
+        };
+
+        Ok(VerifyItem {
+            base_config,
+            pub_key: network_sig.pub_key,
+            digest: digest_hex,
+            signature: network_sig.signature,
+        })
+    }
+
+    /// 字符串表中存在未被任何数据段引用的项时返回错误（未引用的字符串可能夹带隐藏数据）
+    pub fn check_unreferenced_strings(&self) -> Result<()> {
+        if self.unreferenced_strings.is_empty() {
+            return Ok(());
+        }
+        let entries: Vec<String> = self
+            .unreferenced_strings
+            .iter()
+            .map(|(off, st)| tr_format!("偏移量 {} ({} 字节): {:?}", "offset {} ({} bytes): {:?}", off, st.len(), st))
+            .collect();
+        Err(crate::error::CrateSpecError::DecodeError(tr_format!(
+            "字符串表中有 {} 项未被任何数据段引用: {}",
+            "{} string table entries are not referenced by any section: {}",
+            entries.len(),
+            entries.join("; ")
+        )))
+    }
+
+    /// 检查每个本地签名记录的签名时间是否在签名者证书有效期内，返回 (签名序号, 签名时间)；
+    /// 没有记录签名时间的签名跳过
+    pub fn verify_timestamps(&self) -> Result<Vec<(usize, String)>> {
+        let mut times = vec![];
+        for (i, siginfo) in self.sigs.iter().enumerate() {
+            if siginfo.typ == SIGTYPE::NETWORK.as_u32() {
+                continue;
+            }
+            let time = PKCS::verify_signing_time(&siginfo.bin).map_err(|e| match e {
+                crate::error::CrateSpecError::SignatureError(msg) => {
+                    crate::error::CrateSpecError::SignatureError(tr_format!("签名 #{}: {}", "signature #{}: {}", i, msg))
+                }
+                e => e,
+            })?;
+            if let Some(time) = time {
+                times.push((i, time));
+            }
+        }
+        Ok(times)
+    }
+
+    /// 多个网络签名走批量验签接口，单个签名直接调用验签接口
+    pub(crate) fn check_network_sigs(&self, items: &[VerifyItem]) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        // 从 PackageContext 获取 PkiClient
+        let pki_client = self.network_client.as_ref()
+            .ok_or_else(|| crate::error::CrateSpecError::Other(tr("网络签名需要设置 network_client", "network signatures require network_client").to_string()))?;
+
+        if let [item] = items {
+            return match pki_client.verify_digest(&item.pub_key, &item.digest, &item.signature, &item.base_config) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(crate::error::CrateSpecError::SignatureError(tr("网络签名验证失败", "network signature verification failed").to_string())),
+                Err(e) => Err(crate::error::CrateSpecError::PkiError(e)),
+            };
+        }
+
+        let outcomes = pki_client
+            .verify_digests_batch(items)
+            .map_err(crate::error::CrateSpecError::PkiError)?;
+        // 与本地签名一样，列出全部失败的网络签名，而不是只报告第一个
+        let failures: Vec<(usize, &str)> = outcomes
+            .iter()
+            .enumerate()
+            .filter(|(_, outcome)| !outcome.ok)
+            .map(|(no, outcome)| (no, outcome.error.as_deref().unwrap_or(tr("未知错误", "unknown error"))))
+            .collect();
+        match failures.as_slice() {
+            [] => Ok(()),
+            [(no, reason)] => Err(crate::error::CrateSpecError::SignatureError(tr_format!(
+                "第 {} 个网络签名验证失败: {}",
+                "network signature {} failed verification: {}",
+                no,
+                reason
+            ))),
+            _ => {
+                let reasons: Vec<String> = failures
+                    .iter()
+                    .map(|(no, reason)| tr_format!("网络签名 #{}: {}", "network signature #{}: {}", no, reason))
+                    .collect();
+                Err(crate::error::CrateSpecError::SignatureError(tr_format!(
+                    "{} 个网络签名验证失败: {}",
+                    "{} network signatures failed verification: {}",
+                    failures.len(),
+                    reasons.join("; ")
+                )))
+            }
+        }
+    }
+
     pub fn decode_from_crate_package(
         &mut self,
         bin: &[u8],
     ) -> Result<(CratePackage, StringTable)> {
+        let (crate_package, str_table) = self.decode_sections(bin)?;
+        if self.verify_all_sigs {
+            self.check_all_sigs(&crate_package, bin)?;
+        } else {
+            self.check_sigs(&crate_package, bin)?;
+        }
+        Ok((crate_package, str_table))
+    }
+
+    /// 校验指纹并读取各数据段，不验证签名
+    pub(crate) fn decode_sections(&mut self, bin: &[u8]) -> Result<(CratePackage, StringTable)> {
         if !self.check_fingerprint(bin)? {
             return Err(crate::error::CrateSpecError::DecodeError("fingerprint not right".to_string()));
         }
+        self.read_sections(bin)
+    }
+
+    /// 读取各数据段，不校验指纹也不验证签名
+    pub(crate) fn read_sections(&mut self, bin: &[u8]) -> Result<(CratePackage, StringTable)> {
         let crate_package = CratePackage::decode_from_slice(bin)
-            .map_err(|e| crate::error::CrateSpecError::DecodeError(format!("解码失败: {}", e)))?;
-        let mut str_table = StringTable::new();
-        str_table.read_bytes(crate_package.string_table.arr.as_slice())?;
-        self.pack_info(&crate_package, &str_table)?;
-        self.deps(&crate_package, &str_table)?;
-        self.binary(&crate_package)?;
-        self.sigs(&crate_package)?;
-        self.check_sigs(&crate_package, bin)?;
+            .map_err(|e| crate::error::CrateSpecError::DecodeError(tr_format!("解码失败: {}", "decoding failed: {}", e)))?;
+        let str_table = self.read_package(&crate_package)?;
         Ok((crate_package, str_table))
     }
+
+    /// 从已解析的包结构读取包信息、依赖、构建元数据、文件哈希、crate 二进制和签名
+    pub(crate) fn read_package(&mut self, crate_package: &CratePackage) -> Result<StringTable> {
+        let mut str_table = StringTable::with_max_str_len(self.max_string_len);
+        str_table.read_bytes_with(crate_package.string_table.arr.as_slice(), crate_package.crate_header.endianness())?;
+        let refs = crate_package.string_refs()?;
+        check_string_refs(&str_table, &refs)?;
+        self.pack_info(crate_package, &str_table)?;
+        self.read_deps(crate_package, &str_table)?;
+        self.build_metadata(crate_package, &str_table)?;
+        self.file_hashes(crate_package, &str_table)?;
+        self.binary(crate_package)?;
+        self.sigs(crate_package)?;
+        self.unreferenced_strings = str_table
+            .unreferenced_offsets(&refs)
+            .into_iter()
+            .map(|off| Ok((off, str_table.str_by_off(&off)?)))
+            .collect::<Result<_>>()?;
+        Ok(str_table)
+    }
+
+    /// 解码 `.scrate` 数据并用给定的根 CA（PEM 内容）验证签名，返回可直接查看的上下文
+    ///
+    /// ```
+    /// use crate_spec::utils::context::{PackageContext, SIGTYPE};
+    /// use crate_spec::utils::pkcs::PKCS;
+    ///
+    /// let mut pkcs = PKCS::new();
+    /// pkcs.load_from_file_writer(
+    ///     "test/cert.pem".to_string(),
+    ///     "test/key.pem".to_string(),
+    ///     vec!["test/root-ca.pem".to_string()],
+    /// ).unwrap();
+    /// let mut ctx = PackageContext::new();
+    /// ctx.set_package_info("demo".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    /// ctx.add_crate_bin(vec![0u8; 8]);
+    /// ctx.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    /// let (_, _, bytes) = ctx.encode_to_crate_package().unwrap();
+    ///
+    /// let root_cas = PKCS::root_ca_bins(vec!["test/root-ca.pem".to_string()]).unwrap();
+    /// let decoded = PackageContext::try_from_bytes(&bytes, &root_cas).unwrap();
+    /// assert_eq!(decoded.pack_info.name, "demo");
+    /// assert!(PackageContext::try_from_bytes(&bytes, &[]).is_err());
+    ///
+    /// // root_cas 为空时使用进程级默认根 CA
+    /// crate_spec::set_default_root_cas(root_cas);
+    /// assert!(PackageContext::try_from_bytes(&bytes, &[]).is_ok());
+    /// ```
+    pub fn try_from_bytes(bytes: &[u8], root_cas: &[Vec<u8>]) -> Result<Self> {
+        let mut pack_context = PackageContext::new();
+        pack_context.set_root_cas_bin(root_cas.to_vec());
+        pack_context.decode_from_crate_package(bytes)?;
+        Ok(pack_context)
+    }
+
+    /// 同 [`PackageContext::try_from_bytes`]，从文件读取 `.scrate` 数据
+    ///
+    /// ```no_run
+    /// use crate_spec::utils::context::PackageContext;
+    /// use crate_spec::utils::pkcs::PKCS;
+    ///
+    /// let root_cas = PKCS::root_ca_bins(vec!["test/root-ca.pem".to_string()]).unwrap();
+    /// let ctx = PackageContext::try_from_path("demo-0.1.0.scrate", &root_cas).unwrap();
+    /// println!("{}", ctx.metadata_text());
+    /// ```
+    pub fn try_from_path<P: AsRef<Path>>(path: P, root_cas: &[Vec<u8>]) -> Result<Self> {
+        Self::try_from_bytes(&read_scrate(path.as_ref())?, root_cas)
+    }
+
+    /// 只校验指纹、不验证签名的解码，适合查看不受信任来源的包内容；
+    /// 签名信息仍会读入 `sigs`，但其 `trust_anchor` 为 None
+    ///
+    /// ```
+    /// use crate_spec::utils::context::PackageContext;
+    ///
+    /// let mut ctx = PackageContext::new();
+    /// ctx.set_package_info("demo".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    /// ctx.add_crate_bin(vec![0u8; 8]);
+    /// let (_, _, bytes) = ctx.encode_to_crate_package().unwrap();
+    ///
+    /// let decoded = PackageContext::try_from_bytes_unverified(&bytes).unwrap();
+    /// assert_eq!(decoded.crate_binary.bytes, vec![0u8; 8]);
+    /// ```
+    pub fn try_from_bytes_unverified(bytes: &[u8]) -> Result<Self> {
+        let mut pack_context = PackageContext::new();
+        pack_context.decode_sections(bytes)?;
+        Ok(pack_context)
+    }
+
+    /// 同 [`PackageContext::try_from_bytes_unverified`]，从文件读取 `.scrate` 数据
+    pub fn try_from_path_unverified<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::try_from_bytes_unverified(&read_scrate(path.as_ref())?)
+    }
+
+    /// 只读取包信息和依赖表，不校验指纹和签名，也不把 crate 二进制段读入内存
+    pub fn read_info<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let crate_package = CratePackage::decode_header_only_from(reader)?.crate_package;
+        let mut pack_context = PackageContext::new();
+        let mut str_table = StringTable::with_max_str_len(pack_context.max_string_len);
+        str_table.read_bytes_with(crate_package.string_table.arr.as_slice(), crate_package.crate_header.endianness())?;
+        check_string_refs(&str_table, &crate_package.string_refs()?)?;
+        pack_context.pack_info(&crate_package, &str_table)?;
+        pack_context.read_deps(&crate_package, &str_table)?;
+        Ok(pack_context)
+    }
+
+    /// 同 [`PackageContext::read_info`]，从文件读取 `.scrate` 数据
+    pub fn read_info_from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file =
+            std::fs::File::open(path).map_err(|_e| crate::error::CrateSpecError::FileNotFound(path.to_path_buf()))?;
+        Self::read_info(&mut file)
+    }
+}
+
+/// 各数据段引用的偏移量必须都是字符串表中某个字符串的起始位置，
+/// 否则字符串表或数据段已损坏，在读取各字段之前报错
+fn check_string_refs(str_table: &StringTable, refs: &HashSet<u32>) -> Result<()> {
+    let dangling = str_table.dangling_offsets(refs);
+    if dangling.is_empty() {
+        return Ok(());
+    }
+    Err(crate::error::CrateSpecError::DecodeError(tr_format!(
+        "数据段引用的字符串偏移量 {:?} 不是字符串表中字符串的起始位置，字符串表或数据段已损坏",
+        "string offsets {:?} referenced by the sections do not start a string table entry; the string table or the sections are corrupted",
+        dangling
+    )))
+}
+
+fn read_scrate(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|_e| crate::error::CrateSpecError::FileNotFound(path.to_path_buf()))
 }
 
 #[test]
 fn test_encode_decode() {
-    use crate::utils::context::{PackageInfo, SrcTypePath, SIGTYPE};
+    use crate::utils::context::{DepKind, PackageInfo, SrcTypePath, SIGTYPE};
+    use crate::utils::testing::{signer, root_ca_bins};
     fn pack_info() -> PackageInfo {
         PackageInfo {
             name: "rust-crate".to_string(),
@@ -216,6 +893,7 @@ fn test_encode_decode() {
             ver_req: "1.0.0".to_string(),
             src: SrcTypePath::CratesIo,
             src_platform: "ALL".to_string(),
+            kind: DepKind::Normal,
             dump: true,
         }
     }
@@ -226,6 +904,7 @@ fn test_encode_decode() {
             ver_req: ">=0.8.0".to_string(),
             src: SrcTypePath::Git("http://git.com".to_string()),
             src_platform: "windows".to_string(),
+            kind: DepKind::Build,
             dump: true,
         }
     }
@@ -235,31 +914,19 @@ fn test_encode_decode() {
         vec![0u8; 100]
     }
 
-    fn sign() -> PKCS {
-        let mut pkcs1 = PKCS::new();
-        pkcs1.load_from_file_writer(
-            "test/cert.pem".to_string(),
-            "test/key.pem".to_string(),
-            ["test/root-ca.pem".to_string()].to_vec(),
-        );
-        pkcs1
-    }
-
     let mut package_context = PackageContext::new();
 
     package_context.pack_info = pack_info();
     package_context.dep_infos.push(dep_info1());
     package_context.dep_infos.push(dep_info2());
     package_context.crate_binary.bytes = crate_binary();
-    package_context.add_sig(sign(), SIGTYPE::CRATEBIN);
-    package_context.add_sig(sign(), SIGTYPE::FILE);
+    package_context.add_sig(signer(), SIGTYPE::CRATEBIN);
+    package_context.add_sig(signer(), SIGTYPE::FILE);
 
-    let (_crate_package, _str_table, bin) = package_context.encode_to_crate_package();
+    let (_crate_package, _str_table, bin) = package_context.encode_to_crate_package().unwrap();
 
     let mut package_context_new = PackageContext::new();
-    package_context_new.set_root_cas_bin(PKCS::root_ca_bins(
-        ["test/root-ca.pem".to_string()].to_vec(),
-    ));
+    package_context_new.set_root_cas_bin(root_ca_bins());
     let (_crate_package_new, _str_table) = package_context_new
         .decode_from_crate_package(bin.as_slice())
         .unwrap();
@@ -269,3 +936,778 @@ fn test_encode_decode() {
     assert_eq!(dep_info2(), package_context_new.dep_infos[1]);
     assert_eq!(crate_binary(), package_context_new.crate_binary.bytes);
 }
+
+#[test]
+fn test_dump_sections() {
+    use crate::utils::context::{PackageInfo, SIGTYPE};
+    use crate::utils::testing::{TestDir, signer};
+    use std::fs;
+
+    let mut package_context = PackageContext::new();
+    package_context.pack_info = PackageInfo::new(
+        "dump-crate".to_string(),
+        "0.1.0".to_string(),
+        "MIT".to_string(),
+        vec![],
+    );
+    package_context.add_crate_bin(vec![7u8; 50]);
+    let pkcs = signer();
+    package_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (crate_package, _str_table, mut bin) = package_context.encode_to_crate_package().unwrap();
+    // 破坏指纹：导出不依赖校验
+    let last = bin.len() - 1;
+    bin[last] ^= 0xff;
+
+    let tmp = TestDir::new("dump-sections");
+    let dir = tmp.path();
+    let paths = dump_sections(&bin, dir).unwrap();
+
+    let size_of = |name: &str| fs::metadata(dir.join(name)).unwrap().len() as u32;
+    let entries = &crate_package.section_index.entries.arr;
+    assert_eq!(paths.len(), 1 + entries.len());
+    assert_eq!(size_of("stringtable.bin"), crate_package.crate_header.strtable_size);
+    assert_eq!(size_of("package.bin"), entries[0].sh_size);
+    assert_eq!(size_of("deptable.bin"), entries[1].sh_size);
+    assert_eq!(size_of("cratebin.bin"), entries[2].sh_size);
+    assert_eq!(size_of("sig0.bin"), entries[3].sh_size);
+    assert!(fs::read(dir.join("cratebin.bin")).unwrap().ends_with(&[7u8; 50]));
+}
+
+#[test]
+fn test_try_from_bytes_and_path() {
+    use crate::utils::context::SIGTYPE;
+    use crate::utils::testing::{TestDir, signer, root_ca_bins};
+
+    let _guard = crate::utils::pkcs::DEFAULT_ROOT_CAS_TEST_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    let pkcs = signer();
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("try-from".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_dep_info("serde".to_string(), "1.0".to_string(), crate::utils::context::SrcTypePath::CratesIo, "".to_string());
+    pack_context.add_crate_bin(vec![7u8; 16]);
+    pack_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let root_cas = root_ca_bins();
+    let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+    assert_eq!(decoded.pack_info, pack_context.pack_info);
+    assert_eq!(decoded.dep_infos, pack_context.dep_infos);
+    assert!(decoded.sigs[0].trust_anchor.is_some());
+
+    // 没有根 CA 时验签失败，不验证的变体照常解码
+    assert!(PackageContext::try_from_bytes(&bin, &[]).is_err());
+    let unverified = PackageContext::try_from_bytes_unverified(&bin).unwrap();
+    assert_eq!(unverified.crate_binary, pack_context.crate_binary);
+    assert_eq!(unverified.sigs.len(), 1);
+    assert!(unverified.sigs[0].trust_anchor.is_none());
+
+    let dir = TestDir::new("try-from");
+    let path = dir.write("try-from.scrate", &bin);
+    assert_eq!(PackageContext::try_from_path(&path, &root_cas).unwrap().pack_info, pack_context.pack_info);
+    assert_eq!(PackageContext::try_from_path_unverified(&path).unwrap().pack_info, pack_context.pack_info);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(
+        PackageContext::try_from_path(&path, &root_cas),
+        Err(crate::error::CrateSpecError::FileNotFound(_))
+    ));
+
+    // 指纹错误时两种变体都拒绝
+    let mut tampered = bin.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0xff;
+    assert!(PackageContext::try_from_bytes_unverified(&tampered).is_err());
+}
+
+#[test]
+fn test_clone_decoded_context_and_reencode() {
+    use crate::utils::context::SIGTYPE;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("clone".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_dep_info("serde".to_string(), "1.0".to_string(), crate::utils::context::SrcTypePath::CratesIo, "".to_string());
+    pack_context.add_crate_bin(vec![3u8; 32]);
+    pack_context.add_sig(signer(), SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let root_cas = root_ca_bins();
+    let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+
+    // 克隆后去掉原签名、换一个 FILE 签名重新编码，原上下文不受影响
+    let mut resigned = decoded.clone();
+    resigned.sigs.clear();
+    resigned.add_sig(signer(), SIGTYPE::FILE);
+    let (_, _, rebin) = resigned.encode_to_crate_package().unwrap();
+    assert_eq!(decoded.sigs.len(), 1);
+    assert_eq!(decoded.sigs[0].typ, SIGTYPE::CRATEBIN.as_u32());
+
+    let redecoded = PackageContext::try_from_bytes(&rebin, &root_cas).unwrap();
+    assert_eq!(redecoded.pack_info, decoded.pack_info);
+    assert_eq!(redecoded.dep_infos, decoded.dep_infos);
+    assert_eq!(redecoded.crate_binary, decoded.crate_binary);
+    assert_eq!(redecoded.sigs.len(), 1);
+    assert_eq!(redecoded.sigs[0].typ, SIGTYPE::FILE.as_u32());
+}
+
+#[test]
+fn test_check_unreferenced_strings() {
+    use crate::utils::package::FINGERPRINT_LEN;
+    use crate::utils::package::gen_bincode::encode2vec_by_bincode;
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("hidden".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec!["a".to_string()]);
+    ctx.build_metadata.insert("k".to_string(), "v".to_string());
+    ctx.add_crate_bin(vec![0u8; 8]);
+    let (mut crate_package, mut str_table, bin) = ctx.encode_to_crate_package().unwrap();
+    let decoded = PackageContext::try_from_bytes_unverified(&bin).unwrap();
+    assert!(decoded.unreferenced_strings.is_empty());
+    decoded.check_unreferenced_strings().unwrap();
+
+    // 向字符串表注入一个不被引用的字符串；数据段偏移量相对 ds_offset，无需调整
+    let off = str_table.insert_str("smuggled".to_string());
+    crate_package.set_string_table(&str_table);
+    crate_package.set_crate_header(0);
+    let mut bin = encode2vec_by_bincode(&crate_package);
+    let fp_start = bin.len() - FINGERPRINT_LEN;
+    let fingerprint = PKCS::new().gen_digest_256(&bin[..fp_start]).unwrap();
+    bin[fp_start..].copy_from_slice(&fingerprint);
+
+    let decoded = PackageContext::try_from_bytes_unverified(&bin).unwrap();
+    assert_eq!(decoded.pack_info.name, "hidden");
+    assert_eq!(decoded.unreferenced_strings, [(off, "smuggled".to_string())]);
+    let err = decoded.check_unreferenced_strings().unwrap_err();
+    assert!(err.to_string().contains("smuggled"), "{}", err);
+}
+
+#[test]
+fn test_dangling_string_refs_rejected() {
+    use crate::utils::package::FINGERPRINT_LEN;
+    use crate::utils::package::gen_bincode::encode2vec_by_bincode;
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("dangling".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![0u8; 8]);
+    let (mut crate_package, _, _) = ctx.encode_to_crate_package().unwrap();
+
+    // 把许可证的偏移量改为指向包名字符串的中间
+    let bad_off = match &mut crate_package.data_sections.col.arr[0] {
+        DataSection::PackageSection(ps) => {
+            ps.pkg_license = ps.pkg_name + 1;
+            ps.pkg_license
+        }
+        _ => unreachable!(),
+    };
+    let mut bin = encode2vec_by_bincode(&crate_package);
+    let fp_start = bin.len() - FINGERPRINT_LEN;
+    let fingerprint = PKCS::new().gen_digest_256(&bin[..fp_start]).unwrap();
+    bin[fp_start..].copy_from_slice(&fingerprint);
+
+    match PackageContext::try_from_bytes_unverified(&bin) {
+        Err(crate::error::CrateSpecError::DecodeError(msg)) => {
+            assert!(msg.contains(&bad_off.to_string()), "{}", msg)
+        }
+        other => panic!("应报 DecodeError，实际 {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_producer_round_trip() {
+    use crate::utils::package::FINGERPRINT_LEN;
+    use crate::utils::context::Producer;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let pkcs = signer();
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("stamped".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![0u8; 8]);
+    ctx.set_producer("release-pipeline/2.1".to_string(), Some("ci-job-4711".to_string()));
+    ctx.add_sig(pkcs, SIGTYPE::FILE);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+
+    let root_cas = root_ca_bins();
+    let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+    let producer = Producer {
+        tool: "release-pipeline/2.1".to_string(),
+        operator: Some("ci-job-4711".to_string()),
+    };
+    assert_eq!(decoded.producer(), Some(producer));
+    assert!(decoded.metadata_text().contains("ci-job-4711"));
+
+    // 构建元数据在 FILE 签名覆盖范围内：改写操作者（并修正指纹）后验签失败
+    let mut tampered = bin.clone();
+    let pos = tampered.windows(11).position(|w| w == b"ci-job-4711").unwrap();
+    tampered[pos + 10] = b'2';
+    let fp_start = tampered.len() - FINGERPRINT_LEN;
+    let fingerprint = PKCS::new().gen_digest_256(&tampered[..fp_start]).unwrap();
+    tampered[fp_start..].copy_from_slice(&fingerprint);
+    assert!(PackageContext::try_from_bytes_unverified(&tampered).unwrap().producer().unwrap().operator.unwrap().ends_with('2'));
+    assert!(PackageContext::try_from_bytes(&tampered, &root_cas).is_err());
+
+    // 不设置操作者时清除旧值
+    ctx.set_producer("other-tool".to_string(), None);
+    assert_eq!(ctx.producer(), Some(Producer { tool: "other-tool".to_string(), operator: None }));
+}
+
+#[test]
+fn test_pruned_binary_round_trip() {
+    use crate::utils::package::FINGERPRINT_LEN;
+    use crate::utils::incremental::IncrementalDecoder;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let pkcs = signer();
+    let crate_bytes = vec![7u8; 4096];
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("pruned".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(crate_bytes.clone());
+    let full_digest = ctx.crate_digest();
+    ctx.prune_binary();
+    ctx.prune_binary();
+    assert_eq!(ctx.crate_binary.bytes, full_digest);
+    ctx.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (crate_package, _, bin) = ctx.encode_to_crate_package().unwrap();
+    assert!(crate_package.crate_header.binary_pruned());
+    assert!(bin.len() < crate_bytes.len());
+
+    let root_cas = root_ca_bins();
+    let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+    assert!(decoded.binary_pruned);
+    assert_eq!(decoded.crate_binary.bytes, full_digest);
+    assert_eq!(decoded.crate_digest(), openssl::sha::sha256(&crate_bytes).to_vec());
+    assert_eq!(decoded.to_index_entry()["cksum"], digest_to_hex_string(&full_digest));
+    assert!(decoded.require_crate_binary().is_err());
+    assert!(decoded.cross_validate().is_err());
+
+    // 流式解码同样用段内的摘要验签
+    let mut context = PackageContext::new();
+    context.root_cas = root_cas.clone();
+    let mut decoder = IncrementalDecoder::new(context);
+    for chunk in bin.chunks(7) {
+        decoder.update(chunk).unwrap();
+    }
+    assert!(decoder.finish().unwrap().binary_pruned);
+
+    // 签名覆盖摘要：改写摘要（并修正指纹）后验签失败
+    let mut tampered = bin.clone();
+    let pos = tampered.windows(full_digest.len()).position(|w| w == full_digest.as_slice()).unwrap();
+    tampered[pos] ^= 0xff;
+    let fp_start = tampered.len() - FINGERPRINT_LEN;
+    let fingerprint = PKCS::new().gen_digest_256(&tampered[..fp_start]).unwrap();
+    tampered[fp_start..].copy_from_slice(&fingerprint);
+    assert!(PackageContext::try_from_bytes(&tampered, &root_cas).is_err());
+}
+
+#[test]
+fn test_default_root_cas() {
+    use crate::utils::pkcs::{set_default_root_cas, DEFAULT_ROOT_CAS_TEST_LOCK};
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let _guard = DEFAULT_ROOT_CAS_TEST_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    let pkcs = signer();
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("default-ca".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![1u8; 8]);
+    ctx.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+    let root_cas = root_ca_bins();
+    let other_cas = PKCS::root_ca_bins(["test/chain/root-ca.pem".to_string()].to_vec()).unwrap();
+
+    // 未传根 CA 时使用默认值（包括流式解码）
+    set_default_root_cas(root_cas.clone());
+    let decoded = PackageContext::try_from_bytes(&bin, &[]).unwrap();
+    assert!(matches!(decoded.sigs[0].trust_anchor, Some(crate::utils::pkcs::TrustAnchor::Root { index: 0, .. })));
+    let mut decoder = crate::utils::incremental::IncrementalDecoder::new(PackageContext::new());
+    decoder.update(&bin).unwrap();
+    assert!(decoder.finish().is_ok());
+
+    // 显式传入的根 CA 优先于默认值
+    assert!(PackageContext::try_from_bytes(&bin, &other_cas).is_err());
+    set_default_root_cas(other_cas);
+    assert!(PackageContext::try_from_bytes(&bin, &[]).is_err());
+    assert!(PackageContext::try_from_bytes(&bin, &root_cas).is_ok());
+
+    set_default_root_cas(vec![]);
+    assert!(PackageContext::try_from_bytes(&bin, &[]).is_err());
+}
+
+#[test]
+fn test_section_align_round_trip() {
+    use crate::utils::incremental::IncrementalDecoder;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("aligned".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![9u8; 100]);
+    ctx.section_align = 4096;
+    ctx.add_sig(signer(), SIGTYPE::CRATEBIN);
+    ctx.add_sig(signer(), SIGTYPE::FILE);
+    let (crate_package, _, bin) = ctx.encode_to_crate_package().unwrap();
+    assert_eq!(crate_package.crate_header.section_align(), 4096);
+    assert!(!crate_package.crate_header.binary_pruned());
+
+    // 每个数据段（包括 crate 二进制段）都从 4096 的整数倍处开始
+    let ds_offset = crate_package.crate_header.ds_offset as usize;
+    for entry in crate_package.section_index.entries.arr.iter() {
+        assert_eq!((ds_offset + entry.sh_offset as usize) % 4096, 0);
+    }
+    let entry = &crate_package.section_index.entries.arr[2];
+    let start = ds_offset + entry.sh_offset as usize;
+    assert_eq!(&bin[start..start + entry.sh_size as usize], [9u8; 100].as_slice());
+
+    let root_cas = root_ca_bins();
+    let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+    assert_eq!(decoded.section_align, 4096);
+    assert_eq!(decoded.crate_binary, ctx.crate_binary);
+
+    let mut context = PackageContext::new();
+    context.root_cas = root_cas;
+    let mut decoder = IncrementalDecoder::new(context);
+    decoder.update(&bin).unwrap();
+    assert_eq!(decoder.finish().unwrap().sigs.len(), 2);
+
+    ctx.section_align = 3;
+    assert!(matches!(ctx.encode_to_crate_package(), Err(crate::error::CrateSpecError::ValidationError(_))));
+}
+
+#[test]
+fn test_decode_rejects_invalid_version() {
+    use crate::utils::package::FINGERPRINT_LEN;
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("semver".to_string(), "1.2.3-rc.1".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+    assert_eq!(PackageContext::try_from_bytes_unverified(&bin).unwrap().pack_info.version, "1.2.3-rc.1");
+
+    // 在字符串表中把版本改成等长的其他内容，并重算指纹
+    let forge = |version: &[u8]| {
+        let mut bin = bin.clone();
+        let pos = bin.windows(10).position(|w| w == b"1.2.3-rc.1").unwrap();
+        bin[pos..pos + 10].copy_from_slice(version);
+        let fp_start = bin.len() - FINGERPRINT_LEN;
+        let fingerprint = PKCS::new().gen_digest_256(&bin[..fp_start]).unwrap();
+        bin[fp_start..].copy_from_slice(&fingerprint);
+        PackageContext::try_from_bytes_unverified(&bin).unwrap_err()
+    };
+    for version in [b"../../evil", b"not-semver", b"1.2.3/rc.1"] {
+        let err = forge(version);
+        assert!(matches!(err, crate::error::CrateSpecError::DecodeError(ref msg) if msg.contains("版本")), "{}", err);
+    }
+}
+
+#[test]
+fn test_decode_rejects_invalid_name() {
+    use crate::utils::package::FINGERPRINT_LEN;
+    // 编码时直接拒绝
+    for name in ["../../evil", "a/b", "a.b", "1abc", ""] {
+        let mut ctx = PackageContext::new();
+        ctx.set_package_info(name.to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+        ctx.add_crate_bin(vec![0u8; 8]);
+        assert!(matches!(ctx.encode_to_crate_package(), Err(crate::error::CrateSpecError::ValidationError(_))), "{}", name);
+    }
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("evil_name0".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+
+    // 在字符串表中把包名改成等长的路径，并重算指纹：解码时拒绝，不会写到输出目录之外
+    for name in [b"../../evil", b"..\\..\\evil", b"evil/../.."] {
+        let mut bin = bin.clone();
+        let pos = bin.windows(10).position(|w| w == b"evil_name0").unwrap();
+        bin[pos..pos + 10].copy_from_slice(name);
+        let fp_start = bin.len() - FINGERPRINT_LEN;
+        let fingerprint = PKCS::new().gen_digest_256(&bin[..fp_start]).unwrap();
+        bin[fp_start..].copy_from_slice(&fingerprint);
+        let err = PackageContext::try_from_bytes_unverified(&bin).unwrap_err();
+        assert!(matches!(err, crate::error::CrateSpecError::DecodeError(ref msg) if msg.contains("包名")), "{}", err);
+    }
+}
+
+#[test]
+fn test_require_signature() {
+    use crate::utils::package::FINGERPRINT_LEN;
+    use crate::utils::context::SIGTYPE;
+    use crate::utils::package::gen_bincode::encode2vec_by_bincode;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("unsigned".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+
+    // 默认不要求签名：解码成功，没有签名
+    let root_cas = root_ca_bins();
+    assert!(PackageContext::try_from_bytes(&bin, &root_cas).unwrap().sigs.is_empty());
+
+    let mut strict = PackageContext::new();
+    strict.set_root_cas_bin(root_cas.clone());
+    strict.require_signature = true;
+    let err = strict.decode_from_crate_package(&bin).err().unwrap();
+    assert!(matches!(err, crate::error::CrateSpecError::SignatureError(ref msg) if msg.contains("没有签名")), "{}", err);
+
+    // 签名段之后的数据段不受 FILE 签名保护，解码时拒绝
+    let pkcs = signer();
+    ctx.add_sig(pkcs, SIGTYPE::FILE);
+    ctx.build_metadata.insert("rustc".to_string(), "rustc 1.70.0".to_string());
+    let (mut crate_package, _, bin) = ctx.encode_to_crate_package().unwrap();
+    let strict = |require_sig_types: Vec<SIGTYPE>| {
+        let mut strict = PackageContext::new();
+        strict.set_root_cas_bin(root_cas.clone());
+        strict.require_signature = true;
+        strict.require_sig_types = require_sig_types;
+        strict.decode_from_crate_package(&bin).map(|_| ())
+    };
+    strict(vec![]).unwrap();
+    strict(vec![SIGTYPE::FILE]).unwrap();
+    // 要求的签名类型缺失时报错，列出所有缺少的类型
+    let err = strict(vec![SIGTYPE::FILE, SIGTYPE::CRATEBIN, SIGTYPE::NETWORK, SIGTYPE::CRATEBIN]).unwrap_err();
+    assert!(
+        matches!(err, crate::error::CrateSpecError::SignatureError(ref msg) if msg.contains("缺少 cratebin、network 类型")),
+        "{}",
+        err
+    );
+    assert_eq!("CrateBin".parse::<SIGTYPE>().unwrap(), SIGTYPE::CRATEBIN);
+    assert!("detached".parse::<SIGTYPE>().is_err());
+
+    crate_package.data_sections.col.arr.swap(3, 4);
+    crate_package.set_section_index();
+    let mut forged = encode2vec_by_bincode(&crate_package);
+    let fp_start = forged.len() - FINGERPRINT_LEN;
+    let fingerprint = PKCS::new().gen_digest_256(&forged[..fp_start]).unwrap();
+    forged[fp_start..].copy_from_slice(&fingerprint);
+    let err = PackageContext::try_from_bytes_unverified(&forged).unwrap_err();
+    assert!(err.to_string().contains("签名段之后"), "{}", err);
+}
+
+#[test]
+fn test_sha384_sha512_digests() {
+    use crate::utils::context::SIGTYPE;
+    use crate::utils::incremental::IncrementalDecoder;
+    use crate::utils::pkcs::DigestAlgo;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("digests".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![9u8; 100]);
+    ctx.fingerprint_digest = DigestAlgo::Sha512;
+    ctx.add_sig_with_digest(signer(), SIGTYPE::FILE, DigestAlgo::Sha384);
+    ctx.add_sig_with_digest(signer(), SIGTYPE::CRATEBIN, DigestAlgo::Sha512);
+    let (crate_package, _, bin) = ctx.encode_to_crate_package().unwrap();
+    assert_eq!(crate_package.crate_header.fingerprint_digest().unwrap(), DigestAlgo::Sha512);
+    assert_eq!(crate_package.finger_print.arr.len(), 64);
+
+    let root_cas = root_ca_bins();
+    let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+    assert_eq!(decoded.fingerprint_digest, DigestAlgo::Sha512);
+    let digests: Vec<_> = decoded.sigs.iter().map(|sig| sig.digest).collect();
+    assert_eq!(digests, [DigestAlgo::Sha384, DigestAlgo::Sha512]);
+
+    let mut verifier = PackageContext::new();
+    verifier.set_root_cas_bin(root_cas.clone());
+    let mut decoder = IncrementalDecoder::new(verifier);
+    for chunk in bin.chunks(17) {
+        decoder.update(chunk).unwrap();
+    }
+    assert_eq!(decoder.finish().unwrap().sigs.len(), 2);
+
+    let mut explainer = PackageContext::new();
+    explainer.root_cas = root_cas.clone();
+    let report = explainer.explain(&bin);
+    assert!(report.passed(), "{}", report);
+    assert!(report.to_string().contains("sha512 指纹"), "{}", report);
+
+    // 改动 crate 二进制后 SHA-512 指纹不符
+    let mut tampered = bin.clone();
+    let pos = tampered.windows(100).position(|w| w == [9u8; 100]).unwrap();
+    tampered[pos] ^= 0xff;
+    assert!(PackageContext::try_from_bytes(&tampered, &root_cas).is_err());
+
+    // 裁剪包只保存 SHA256 摘要，CRATEBIN 签名不能换用其他算法
+    let mut pruned = PackageContext::new();
+    pruned.set_package_info("digests".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pruned.add_crate_bin(vec![9u8; 100]);
+    pruned.prune_binary();
+    pruned.add_sig_with_digest(signer(), SIGTYPE::CRATEBIN, DigestAlgo::Sha384);
+    let err = pruned.encode_to_crate_package().err().unwrap();
+    assert!(err.to_string().contains("sha384"), "{}", err);
+}
+
+#[test]
+fn test_duplicate_deps_rejected() {
+    use crate::utils::context::{DepKind, SrcTypePath};
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("dup".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_dep_info("libc".to_string(), "0.2".to_string(), SrcTypePath::CratesIo, "cfg(unix)".to_string());
+    pack_context.add_dep_info("libc".to_string(), "0.2".to_string(), SrcTypePath::CratesIo, "cfg(windows)".to_string());
+    pack_context.add_crate_bin(vec![1u8; 16]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    // 同名但平台不同的依赖是合法的
+    assert_eq!(PackageContext::try_from_bytes_unverified(&bin).unwrap().dep_num(), 2);
+    // 同名同平台但类别不同（如同时是开发依赖）也是合法的
+    pack_context.add_dep_info_with_kind("libc".to_string(), "0.2".to_string(), SrcTypePath::CratesIo, "cfg(unix)".to_string(), DepKind::Dev);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    assert_eq!(PackageContext::try_from_bytes_unverified(&bin).unwrap().dep_num(), 3);
+
+    // 依赖表中同名同平台的条目出现两次
+    pack_context.add_dep_info("libc".to_string(), "0.3".to_string(), SrcTypePath::CratesIo, "cfg(unix)".to_string());
+    let (crate_package, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    assert_eq!(crate_package.dep_table_section().unwrap().entries.arr.len(), 4);
+    match PackageContext::try_from_bytes_unverified(&bin) {
+        Err(crate::error::CrateSpecError::DecodeError(msg)) => assert!(msg.contains("libc"), "{}", msg),
+        other => panic!("应报 DecodeError，实际 {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_big_endian_round_trip() {
+    use crate::utils::context::{SrcTypePath, SIGTYPE};
+    use crate::utils::incremental::IncrementalDecoder;
+    use crate::utils::package::Endianness;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let pkcs = signer();
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("big-endian".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec!["a".to_string()]);
+    ctx.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "".to_string());
+    ctx.add_crate_bin(vec![7u8; 300]);
+    ctx.endianness = Endianness::Big;
+    ctx.add_sig(pkcs, SIGTYPE::FILE);
+    let (crate_package, str_table, bin) = ctx.encode_to_crate_package().unwrap();
+
+    // 字符串表长度前缀和段索引字段按大端存储，其余部分不变
+    let header = &crate_package.crate_header;
+    assert_eq!(header.endianness(), Endianness::Big);
+    let strtable = &bin[header.strtable_offset as usize..(header.strtable_offset + header.strtable_size) as usize];
+    assert_eq!(strtable, str_table.to_bytes_with(Endianness::Big).as_slice());
+    let name_off = str_table.off_by_str(&"big-endian".to_string()).unwrap() as usize;
+    assert_eq!(strtable[name_off..name_off + 4], 10u32.to_be_bytes());
+    let entry = &crate_package.section_index.entries.arr[1];
+    let si = header.si_offset as usize + 9;
+    assert_eq!(bin[si + 1..si + 5], entry.sh_offset.to_be_bytes());
+    assert_eq!(bin[si + 5..si + 9], entry.sh_size.to_be_bytes());
+
+    let root_cas = root_ca_bins();
+    let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+    assert_eq!(decoded.endianness, Endianness::Big);
+    assert_eq!(decoded.pack_info, ctx.pack_info);
+    assert_eq!(decoded.dep_infos, ctx.dep_infos);
+    assert_eq!(decoded.crate_binary.bytes, vec![7u8; 300]);
+
+    let mut verifier = PackageContext::new();
+    verifier.set_root_cas_bin(root_cas.clone());
+    let mut decoder = IncrementalDecoder::new(verifier);
+    decoder.update(&bin).unwrap();
+    assert_eq!(decoder.finish().unwrap().pack_info.name, "big-endian");
+    let mut verifier = PackageContext::new();
+    verifier.set_root_cas_bin(root_cas);
+    verifier.decode_streaming(&mut std::io::Cursor::new(&bin)).unwrap();
+    assert_eq!(verifier.dep_infos, ctx.dep_infos);
+
+    // 默认仍为小端，与之前的文件格式一致
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("little-endian".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![7u8; 8]);
+    let (crate_package, str_table, _) = ctx.encode_to_crate_package().unwrap();
+    assert_eq!(crate_package.crate_header.flags, 0);
+    assert_eq!(crate_package.string_table.arr, str_table.to_bytes());
+}
+
+#[test]
+fn test_header_version_and_flags() {
+    use crate::utils::package::gen_bincode::encode2vec_by_bincode;
+    use crate::utils::package::{CrateHeader, CRATE_VERSION, LEGACY_CRATE_VERSION};
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("versioned".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![7u8; 8]);
+    ctx.section_align = 16;
+    let (crate_package, _, bin) = ctx.encode_to_crate_package().unwrap();
+    let header = &crate_package.crate_header;
+    assert_eq!(header.c_version, CRATE_VERSION);
+    assert_eq!(header.section_align(), 16);
+    assert_eq!(PackageContext::try_from_bytes_unverified(&bin).unwrap().section_align, 16);
+
+    // 旧版文件头没有 flags 字段，按所有标志位为 0 解析
+    let mut legacy = CrateHeader::new();
+    legacy.c_version = LEGACY_CRATE_VERSION;
+    legacy.strtable_size = 4;
+    legacy.ds_offset = 42;
+    let legacy_bin = encode2vec_by_bincode(&legacy);
+    assert_eq!(legacy_bin.len() + 4, CrateHeader::new().size());
+    let (decoded, _): (CrateHeader, usize) = bincode::decode_from_slice(&legacy_bin, BINCODE_CONFIG).unwrap();
+    assert_eq!((decoded.flags, decoded.strtable_size, decoded.ds_offset), (0, 4, 42));
+
+    // 未知的版本号和标志位都被拒绝
+    let version_at = MAGIC_NUMBER_LEN;
+    let mut unknown_version = bin.clone();
+    unknown_version[version_at] = CRATE_VERSION + 1;
+    let err = PackageContext::try_from_bytes_unverified(&unknown_version).unwrap_err();
+    assert!(matches!(err, crate::error::CrateSpecError::DecodeError(ref msg) if msg.contains("unsupported crate version")), "{}", err);
+    assert!(CratePackage::peek_header(&unknown_version).is_err());
+
+    let mut unknown_flags = bin;
+    unknown_flags[version_at + 2] |= 0x04;
+    let err = PackageContext::try_from_bytes_unverified(&unknown_flags).unwrap_err();
+    assert!(matches!(err, crate::error::CrateSpecError::DecodeError(ref msg) if msg.contains("unknown header flags")), "{}", err);
+    assert!(CratePackage::peek_header(&unknown_flags).is_err());
+}
+
+#[test]
+fn test_verify_all_reports_every_signature() {
+    use crate::utils::context::SIGTYPE;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("audit".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![3u8; 32]);
+    for typ in [SIGTYPE::CRATEBIN, SIGTYPE::FILE, SIGTYPE::CRATEBIN] {
+        let pkcs = signer();
+        pack_context.add_sig(pkcs, typ);
+    }
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let mut decoded = PackageContext::new();
+    decoded.root_cas = root_ca_bins();
+    let (crate_package, _) = decoded.decode_sections(&bin).unwrap();
+    let results = decoded.verify_all(&crate_package, &bin).unwrap();
+    assert!(results.iter().all(|r| r.ok && r.trust_anchor.is_some()));
+
+    // 损坏第 1 个签名：其余签名仍逐个给出结果
+    let last = decoded.sigs[1].bin.len() - 1;
+    decoded.sigs[1].bin[last] ^= 0xff;
+    let results = decoded.verify_all(&crate_package, &bin).unwrap();
+    assert_eq!(results.iter().map(|r| r.ok).collect::<Vec<_>>(), [true, false, true]);
+    assert_eq!(results[1].typ, Some(SIGTYPE::FILE));
+    assert!(results[1].reason.is_some() && results[1].trust_anchor.is_none());
+
+    let err = decoded.check_all_sigs(&crate_package, &bin).unwrap_err().to_string();
+    assert!(err.contains("3 个签名中 1 个验证失败") && err.contains("签名 #1 (FILE)"), "{}", err);
+}
+
+#[test]
+fn test_parallel_sig_checks_aggregate_failures() {
+    use crate::utils::context::SIGTYPE;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    assert_eq!(parallel_map(100, |i| i * 2), (0..100).map(|i| i * 2).collect::<Vec<_>>());
+    assert!(parallel_map(0, |i| i).is_empty());
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("parallel".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![4u8; 32]);
+    for _ in 0..4 {
+        let pkcs = signer();
+        pack_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    }
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let mut decoded = PackageContext::new();
+    decoded.root_cas = root_ca_bins();
+    let (crate_package, _) = decoded.decode_sections(&bin).unwrap();
+    let mut good = decoded.clone();
+    good.check_sigs(&crate_package, &bin).unwrap();
+    assert!(good.sigs.iter().all(|sig| sig.trust_anchor.is_some()));
+
+    // 两个签名损坏：两个失败都出现在错误中
+    for no in [0, 2] {
+        let last = decoded.sigs[no].bin.len() - 1;
+        decoded.sigs[no].bin[last] ^= 0xff;
+    }
+    let err = decoded.check_sigs(&crate_package, &bin).unwrap_err().to_string();
+    assert!(err.contains("2 个本地签名验证失败") && err.contains("签名 #0") && err.contains("签名 #2"), "{}", err);
+    assert!(!err.contains("签名 #1") && !err.contains("签名 #3"), "{}", err);
+}
+
+#[test]
+fn test_network_sig_failures_aggregated() {
+    use crate::network::{mock_server, PkiClient};
+    use std::sync::Arc;
+
+    let items: Vec<VerifyItem> = (0..3)
+        .map(|i| VerifyItem {
+            base_config: BaseConfig { algo: "sm2".to_string(), kms: String::new(), flow: "sign".to_string() },
+            pub_key: format!("pub{}", i),
+            digest: format!("digest{}", i),
+            signature: format!("sig{}", i),
+        })
+        .collect();
+    let (base_url, server) = mock_server(vec![
+        r#"{"results":[{"result":"FAIL","error":"bad sig"},{"result":"OK"},{"result":"FAIL","error":"revoked"}]}"#.to_string(),
+    ]);
+    let mut ctx = PackageContext::new();
+    ctx.network_client = Some(Arc::new(
+        PkiClient::new(base_url, 0, 0).unwrap().with_batch_verify_path(Some("/v1/verify/digests".to_string())),
+    ));
+    let err = ctx.check_network_sigs(&items).unwrap_err().to_string();
+    server.join().unwrap();
+    assert!(err.contains("2 个网络签名验证失败"), "{}", err);
+    assert!(err.contains("网络签名 #0: bad sig") && err.contains("网络签名 #2: revoked"), "{}", err);
+    assert!(!err.contains("#1"), "{}", err);
+}
+
+#[test]
+fn test_compressed_crate_binary_round_trip() {
+    use crate::utils::context::SIGTYPE;
+    use crate::utils::incremental::IncrementalDecoder;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let pkcs = signer();
+    let root_cas = root_ca_bins();
+    let crate_bin: Vec<u8> = (0..100_000u32).map(|i| (i % 13) as u8).collect();
+
+    for kind in [CompressionKind::Gzip, CompressionKind::Zstd] {
+        let mut ctx = PackageContext::new();
+        ctx.set_package_info("compressed".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+        ctx.add_crate_bin(crate_bin.clone());
+        ctx.compression = kind;
+        ctx.add_sig(pkcs.clone(), SIGTYPE::FILE);
+        ctx.add_sig(pkcs.clone(), SIGTYPE::CRATEBIN);
+        let (mut crate_package, _, bin) = ctx.encode_to_crate_package().unwrap();
+        assert_eq!(crate_package.crate_header.compression().unwrap(), kind);
+        assert_eq!(crate_package.compression().unwrap(), (kind, crate_bin.len() as u64));
+        assert!(bin.len() < crate_bin.len());
+        // 压缩方式记录在文件头标志中，与压缩段必须同时存在
+        crate_package.crate_header.set_compression(CompressionKind::None);
+        assert!(crate_package.compression().is_err());
+
+        // 指纹覆盖压缩后的数据，重新编码得到相同的文件
+        let decoded = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+        assert_eq!(decoded.compression, kind);
+        assert_eq!(decoded.crate_binary.bytes, crate_bin);
+        let mut reencoded = decoded.clone();
+        reencoded.sigs.clear();
+        ctx.sigs.clear();
+        assert_eq!(reencoded.encode_to_crate_package().unwrap().2, ctx.encode_to_crate_package().unwrap().2);
+
+        let mut verifier = PackageContext::new();
+        verifier.set_root_cas_bin(root_cas.clone());
+        let mut decoder = IncrementalDecoder::new(verifier);
+        for chunk in bin.chunks(4096) {
+            decoder.update(chunk).unwrap();
+        }
+        assert_eq!(decoder.finish().unwrap().crate_binary.bytes, crate_bin);
+
+        let mut verifier = PackageContext::new();
+        verifier.set_root_cas_bin(root_cas.clone());
+        let mut reader = std::io::Cursor::new(&bin);
+        let header_only = verifier.decode_streaming(&mut reader).unwrap();
+        let mut out = vec![];
+        header_only.copy_crate_binary(&mut reader, &mut out).unwrap();
+        assert_eq!(out, crate_bin);
+    }
+
+    // 不压缩时不写入压缩段，与之前的文件格式一致
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("plain".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(crate_bin.clone());
+    let (mut crate_package, _, _) = ctx.encode_to_crate_package().unwrap();
+    assert!(crate_package.compression_section().unwrap().is_none());
+    assert_eq!(crate_package.crate_header.flags, 0);
+    crate_package.crate_header.set_compression(CompressionKind::Gzip);
+    assert!(crate_package.compression().is_err());
+
+    // 裁剪后的二进制不能再压缩
+    ctx.prune_binary();
+    ctx.compression = CompressionKind::Zstd;
+    assert!(ctx.encode_to_crate_package().is_err());
+}
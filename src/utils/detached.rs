@@ -0,0 +1,174 @@
+//! 分离签名（`.scrate.sig` 旁路文件）
+//!
+//! 包体是不含签名段的 .scrate 文件，旁路文件只保存签名段以及它们覆盖区域的摘要。
+//! 签名区域与内嵌签名相同，即 [`PackageContext::binary_before_sig`] 的结果。
+//!
+//! 旁路文件格式：`CSSIG` 魔数 + 32 字节 SHA256 摘要 + u32 签名个数（小端）+ 依次排列的签名段。
+use crate::error::{CrateSpecError, Result};
+use crate::utils::context::{PackageContext, SigInfo};
+use crate::utils::package::gen_bincode::{create_bincode_slice_decoder, encode2vec_by_bincode};
+use crate::utils::package::{CratePackage, SigStructureSection};
+use crate::utils::pkcs::PKCS;
+use crate::locale::tr;
+use crate::tr_format;
+use bincode::Decode;
+
+/// 旁路签名文件的扩展名，追加在 .scrate 文件名之后
+pub const DETACHED_SIG_EXT: &str = "sig";
+
+const DETACHED_SIG_MAGIC: [u8; 5] = *b"CSSIG";
+const SIG_NUM_BYTES: usize = 4;
+/// 旁路文件中签名区域摘要的长度（SHA256），与签名本身所用的摘要算法无关
+const DETACHED_DIGEST_LEN: usize = 32;
+
+/// 旁路签名文件内容
+#[derive(Debug)]
+pub struct DetachedSignature {
+    /// 签名区域（binary_before_sig）的 SHA256 摘要
+    pub digest: Vec<u8>,
+    pub sigs: Vec<SigStructureSection>,
+}
+
+impl DetachedSignature {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = DETACHED_SIG_MAGIC.to_vec();
+        bytes.extend(&self.digest);
+        bytes.extend((self.sigs.len() as u32).to_le_bytes());
+        for sig in self.sigs.iter() {
+            bytes.extend(encode2vec_by_bincode(sig));
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let head_len = DETACHED_SIG_MAGIC.len() + DETACHED_DIGEST_LEN + SIG_NUM_BYTES;
+        if bytes.len() < head_len || !bytes.starts_with(&DETACHED_SIG_MAGIC) {
+            return Err(CrateSpecError::DecodeError(tr("不是有效的分离签名文件", "not a valid detached signature file").to_string()));
+        }
+        let digest = bytes[DETACHED_SIG_MAGIC.len()..DETACHED_SIG_MAGIC.len() + DETACHED_DIGEST_LEN].to_vec();
+        let mut num_bytes = [0u8; SIG_NUM_BYTES];
+        num_bytes.copy_from_slice(&bytes[head_len - SIG_NUM_BYTES..head_len]);
+        let sig_num = u32::from_le_bytes(num_bytes) as usize;
+
+        let body = &bytes[head_len..];
+        let mut decoder = create_bincode_slice_decoder(body);
+        let mut sigs = vec![];
+        let mut consumed = 0;
+        for _ in 0..sig_num {
+            let sig = <SigStructureSection as Decode<()>>::decode(&mut decoder)
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("分离签名文件中的签名段无效: {}", "invalid signature section in the detached signature file: {}", e)))?;
+            consumed += sig.size();
+            sigs.push(sig);
+        }
+        if consumed != body.len() {
+            return Err(CrateSpecError::DecodeError(tr("分离签名文件长度与签名个数不符", "the detached signature file length does not match its signature count").to_string()));
+        }
+        Ok(Self { digest, sigs })
+    }
+}
+
+impl PackageContext {
+    /// 编码为不含签名段的包体和旁路签名文件，返回 (包体, 旁路签名)
+    pub fn encode_detached(&mut self) -> Result<(Vec<u8>, Vec<u8>)> {
+        if self.sigs.is_empty() {
+            return Err(CrateSpecError::ValidationError(tr("分离签名至少需要一个签名", "a detached signature needs at least one signature").to_string()));
+        }
+        let sigs = std::mem::take(&mut self.sigs);
+        let encoded = self.encode_to_crate_package();
+        self.sigs = sigs;
+        let (crate_package, _str_table, body) = encoded?;
+
+        self.calc_sigs(&crate_package, Some(&body))?;
+        let digest = PKCS::new().gen_digest_256(&self.binary_before_sig(&crate_package, &body)?)?;
+        let sigs = self
+            .sigs
+            .iter()
+            .map(|siginfo| {
+                let mut sig = SigStructureSection::new();
+                siginfo.write_to_sig_structure_section(&mut sig);
+                sig
+            })
+            .collect();
+        Ok((body, DetachedSignature { digest, sigs }.to_bytes()))
+    }
+
+    /// 解码包体，并用旁路签名文件验证
+    pub fn decode_detached(&mut self, body: &[u8], sig_bytes: &[u8]) -> Result<CratePackage> {
+        let detached = DetachedSignature::from_bytes(sig_bytes)?;
+        if detached.sigs.is_empty() {
+            return Err(CrateSpecError::SignatureError(tr("分离签名文件中没有签名", "the detached signature file has no signatures").to_string()));
+        }
+        // 包体不含签名，签名在取出旁路文件中的签名后统一验证
+        let (crate_package, _str_table) = self.decode_sections(body)?;
+        if crate_package.section_index.sig_num() != 0 {
+            return Err(CrateSpecError::DecodeError(tr("包体中已包含签名段，不是分离签名的包体", "the package body already contains signature sections, it is not a detached package body").to_string()));
+        }
+
+        let digest = PKCS::new().gen_digest_256(&self.binary_before_sig(&crate_package, body)?)?;
+        if !PKCS::digest_eq(&digest, &detached.digest) {
+            return Err(CrateSpecError::SignatureError(tr("分离签名与包体不匹配", "the detached signature does not match the package body").to_string()));
+        }
+        for sig in detached.sigs.iter() {
+            let mut sig_info = SigInfo::new();
+            sig_info.read_from_sig_structure_section(sig)?;
+            self.sigs.push(sig_info);
+        }
+        self.check_sigs(&crate_package, body)?;
+        Ok(crate_package)
+    }
+}
+
+#[test]
+fn test_detached_signature_round_trip() {
+    use crate::utils::context::{PackageInfo, SIGTYPE};
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let root_cas = root_ca_bins();
+
+    let mut pack_context = PackageContext::new();
+    pack_context.pack_info = PackageInfo::new(
+        "detached".to_string(),
+        "0.1.0".to_string(),
+        "MIT".to_string(),
+        vec![],
+    );
+    pack_context.add_crate_bin(vec![3u8; 40]);
+    pack_context.add_sig(signer(), SIGTYPE::CRATEBIN);
+    pack_context.add_sig(signer(), SIGTYPE::FILE);
+    let (body, sig_bytes) = pack_context.encode_detached().unwrap();
+
+    let mut decoded = PackageContext::new();
+    decoded.set_root_cas_bin(root_cas.clone());
+    decoded.require_signature = true;
+    decoded.decode_detached(&body, &sig_bytes).unwrap();
+    assert_eq!(decoded.pack_info, pack_context.pack_info);
+    assert_eq!(decoded.crate_binary, pack_context.crate_binary);
+    assert_eq!(decoded.sigs.len(), 2);
+
+    // 没有旁路文件时包体本身不含签名
+    let mut plain = PackageContext::new();
+    let (crate_package, _) = plain.decode_from_crate_package(&body).unwrap();
+    assert_eq!(crate_package.section_index.sig_num(), 0);
+
+    // 旁路文件与其它包体不匹配
+    let mut other = PackageContext::new();
+    other.pack_info = PackageInfo::new(
+        "detached".to_string(),
+        "0.2.0".to_string(),
+        "MIT".to_string(),
+        vec![],
+    );
+    other.add_crate_bin(vec![3u8; 40]);
+    other.add_sig(signer(), SIGTYPE::CRATEBIN);
+    let (other_body, _) = other.encode_detached().unwrap();
+    let mut decoded = PackageContext::new();
+    decoded.set_root_cas_bin(root_cas);
+    assert!(matches!(
+        decoded.decode_detached(&other_body, &sig_bytes),
+        Err(CrateSpecError::SignatureError(_))
+    ));
+
+    // 损坏的旁路文件
+    assert!(DetachedSignature::from_bytes(&sig_bytes[..sig_bytes.len() - 1]).is_err());
+    assert!(DetachedSignature::from_bytes(b"CRATE").is_err());
+}
@@ -1,13 +1,19 @@
-use crate::utils::context::{PackageContext, StringTable, NOT_SIG_NUM, SIGTYPE};
+use crate::utils::context::{PackageContext, StringTable, SIGTYPE};
 use crate::utils::package::{
-    datasection_type, CrateBinarySection, CratePackage, DataSection, DataSectionCollectionType,
-    DepTableEntry, DepTableSection, LenArrayType, Off, PackageSection, RawArrayType,
-    SectionIndexEntry, SigStructureSection, Size, CRATE_VERSION, FINGERPRINT_LEN, MAGIC_NUMBER,
+    datasection_type, BuildMetaEntry, BuildMetaSection, CompressionSection, CrateBinarySection, CratePackage, DataSection, DataSectionCollectionType,
+    DepTableEntry, DepTableSection, Endianness, FileHashEntry, FileHashSection, LenArrayType, Off, PackageSection, RawArrayType,
+    SectionIndexEntry, SigStructureSection, Size, CRATE_VERSION, MAGIC_NUMBER,
+    MAX_SECTION_ALIGN, PRUNED_DIGEST_LEN, is_valid_section_align,
 };
-use crate::error::Result;
+use crate::error::{CrateSpecError, Result};
+use crate::locale::tr;
+use crate::tr_format;
 
+use crate::utils::compression::CompressionKind;
 use crate::utils::package::gen_bincode::{encode2vec_by_bincode, encode_size_by_bincode};
-use crate::utils::pkcs::PKCS;
+use crate::utils::pkcs::{DigestAlgo, PKCS};
+use crate::utils::timings;
+use std::time::Instant;
 use crate::network::{NetworkSignature, digest_to_hex_string};
 
 impl CratePackage {
@@ -25,8 +31,17 @@ impl CratePackage {
         }
     }
 
+    /// 按头部记录的字节序写入字符串表
     pub fn set_string_table(&mut self, str_table: &StringTable) {
-        self.string_table = RawArrayType::from_vec(str_table.to_bytes());
+        self.string_table = RawArrayType::from_vec(str_table.to_bytes_with(self.crate_header.endianness()));
+    }
+
+    /// 在头部记录字节序，并按其重写字符串表和段索引，须在 [`CratePackage::set_crate_header`] 之后调用；
+    /// 各部分长度不变，偏移量无需重新计算
+    pub fn set_endianness(&mut self, endianness: Endianness, str_table: &StringTable) {
+        self.crate_header.set_endianness(endianness);
+        self.section_index.endianness = endianness;
+        self.set_string_table(str_table);
     }
 
     pub fn set_crate_header(&mut self, fake_num: usize) {
@@ -42,12 +57,26 @@ impl CratePackage {
         self.crate_header.ds_offset = self.crate_header.si_offset + self.crate_header.si_size;
     }
 
+    /// 在头部记录数据段对齐并按 `ds_offset` 重新计算段偏移量，须在 [`CratePackage::set_crate_header`] 之后调用
+    pub fn set_section_align(&mut self, align: usize) {
+        self.crate_header.set_section_align(align);
+        self.data_sections.base = self.crate_header.ds_offset as usize;
+        self.data_sections.align = align;
+        self.set_section_index();
+    }
+
     pub fn set_magic_numer(&mut self) {
         self.magic_number = MAGIC_NUMBER;
     }
 
     pub fn set_finger_print(&mut self, fp: Vec<u8>) {
-        self.finger_print.copy_from_slice(fp.as_slice());
+        self.finger_print = RawArrayType::from_vec(fp);
+    }
+
+    /// 在头部记录指纹摘要算法，并按其长度预留指纹，须在 [`CratePackage::set_crate_header`] 之后调用
+    pub fn set_fingerprint_digest(&mut self, algo: DigestAlgo) {
+        self.crate_header.set_fingerprint_digest(algo);
+        self.finger_print = RawArrayType::from_vec(vec![0; algo.output_len()]);
     }
 }
 
@@ -57,7 +86,7 @@ impl PackageContext {
         &self,
         dsc: &mut DataSectionCollectionType,
         str_table: &mut StringTable,
-    ) {
+    ) -> Result<()> {
         let mut package_section = PackageSection::new();
         self.write_to_package_section(&mut package_section, str_table);
         dsc.col
@@ -71,10 +100,33 @@ impl PackageContext {
             .push(DataSection::DepTableSection(dep_table_section));
 
         let mut binary_section = CrateBinarySection::new();
-        self.write_to_crate_binary_section(&mut binary_section);
+        self.write_to_crate_binary_section(&mut binary_section)?;
         dsc.col
             .arr
             .push(DataSection::CrateBinarySection(binary_section));
+
+        if !self.build_metadata.is_empty() {
+            let mut build_meta_section = BuildMetaSection::new();
+            self.write_to_build_meta_section(&mut build_meta_section, str_table);
+            dsc.col
+                .arr
+                .push(DataSection::BuildMetaSection(build_meta_section));
+        }
+
+        if !self.file_hashes.is_empty() {
+            let mut file_hash_section = FileHashSection::new();
+            self.write_to_file_hash_section(&mut file_hash_section, str_table);
+            dsc.col
+                .arr
+                .push(DataSection::FileHashSection(file_hash_section));
+        }
+
+        if self.compression != CompressionKind::None {
+            dsc.col.arr.push(DataSection::CompressionSection(CompressionSection {
+                raw_size: self.crate_binary.bytes.len() as u64,
+            }));
+        }
+        Ok(())
     }
 
     pub fn write_to_data_section_collection_sig(&self, dsc: &mut DataSectionCollectionType) {
@@ -100,8 +152,32 @@ impl PackageContext {
         dts.entries = LenArrayType::from_vec(entries);
     }
 
-    fn write_to_crate_binary_section(&self, cbs: &mut CrateBinarySection) {
-        self.crate_binary.write_to_crate_binary_section(cbs);
+    fn write_to_build_meta_section(&self, bms: &mut BuildMetaSection, str_table: &mut StringTable) {
+        let entries = self
+            .build_metadata
+            .iter()
+            .map(|(key, value)| BuildMetaEntry {
+                key: str_table.insert_str(key.clone()),
+                value: str_table.insert_str(value.clone()),
+            })
+            .collect();
+        bms.entries = LenArrayType::from_vec(entries);
+    }
+
+    fn write_to_file_hash_section(&self, fhs: &mut FileHashSection, str_table: &mut StringTable) {
+        let entries = self
+            .file_hashes
+            .iter()
+            .map(|file| FileHashEntry {
+                path: str_table.insert_str(file.path.clone()),
+                sha256: file.sha256,
+            })
+            .collect();
+        fhs.entries = LenArrayType::from_vec(entries);
+    }
+
+    fn write_to_crate_binary_section(&self, cbs: &mut CrateBinarySection) -> Result<()> {
+        self.crate_binary.write_to_crate_binary_section(cbs, self.compression)
     }
     fn set_sigs(&self, crate_package: &mut CratePackage, non_sig_num: usize) {
         crate_package.data_sections.col.arr.truncate(non_sig_num);
@@ -109,11 +185,11 @@ impl PackageContext {
         self.write_to_data_section_collection_sig(&mut crate_package.data_sections);
     }
 
-    fn set_pack_dep_bin(&self, crate_package: &mut CratePackage, str_table: &mut StringTable) {
+    fn set_pack_dep_bin(&self, crate_package: &mut CratePackage, str_table: &mut StringTable) -> Result<()> {
         self.write_to_data_section_collection_without_sig(
             &mut crate_package.data_sections,
             str_table,
-        );
+        )
     }
 
     /// 计算签名，接受预序列化的二进制数据以避免重复序列化
@@ -121,7 +197,7 @@ impl PackageContext {
     /// # Arguments
     /// * `crate_package` - CratePackage 结构体引用
     /// * `pre_serialized_bin` - 预序列化的完整二进制数据（可选，如果为 None 则内部序列化）
-    fn calc_sigs(&mut self, crate_package: &CratePackage, pre_serialized_bin: Option<&[u8]>) -> Result<()> {
+    pub(crate) fn calc_sigs(&mut self, crate_package: &CratePackage, pre_serialized_bin: Option<&[u8]>) -> Result<()> {
         // 使用预序列化的数据或进行序列化
         let bin_all = if let Some(bin) = pre_serialized_bin {
             bin.to_vec()
@@ -130,22 +206,23 @@ impl PackageContext {
         };
 
         // binary slice before signature section
-        let bin_all = self.binary_before_sig(crate_package, bin_all.as_slice());
+        let bin_all = self.binary_before_sig(crate_package, bin_all.as_slice())?;
 
-        // binary slice of crate binary section 
-        let bin_crate = crate_package.crate_binary_section()?.bin.arr.as_slice();
+        // crate binary before compression: CRATEBIN and NETWORK signatures always cover the .crate itself
+        let bin_crate = self.crate_binary.bytes.as_slice();
 
-        for siginfo in self.sigs.iter_mut() {
+        for (no, siginfo) in self.sigs.iter_mut().enumerate() {
+            let sig_start = Instant::now();
             match siginfo.typ {
                 typ if typ == SIGTYPE::FILE.as_u32() => {
                     // 本地签名：FILE 类型
-                    let digest = siginfo.pkcs.gen_digest_256(bin_all.as_slice())?;
+                    let digest = siginfo.pkcs.gen_digest(siginfo.digest, bin_all.as_slice())?;
                     siginfo.bin = siginfo.pkcs.encode_pkcs_bin(digest.as_slice())?;
                     siginfo.size = siginfo.bin.len();
                 }
                 typ if typ == SIGTYPE::CRATEBIN.as_u32() => {
-                    // 本地签名：CRATEBIN 类型
-                    let digest = siginfo.pkcs.gen_digest_256(bin_crate)?;
+                    // 本地签名：CRATEBIN 类型（已裁剪时段内容即为 SHA256 摘要，自洽检查保证此时算法为 SHA256）
+                    let digest = if self.binary_pruned { bin_crate.to_vec() } else { siginfo.pkcs.gen_digest(siginfo.digest, bin_crate)? };
                     siginfo.bin = siginfo.pkcs.encode_pkcs_bin(digest.as_slice())?;
                     siginfo.size = siginfo.bin.len();
                 }
@@ -153,53 +230,32 @@ impl PackageContext {
                     // 网络签名：NETWORK 类型（对应 CRATEBIN，只对 crate binary 签名）
                     // 从 PackageContext 获取 PkiClient 和 KeyPair
                     let pki_client = self.network_client.as_ref()
-                        .ok_or_else(|| crate::error::CrateSpecError::Other("网络签名需要设置 network_client".to_string()))?;
+                        .ok_or_else(|| crate::error::CrateSpecError::Other(tr("网络签名需要设置 network_client", "network signatures require network_client").to_string()))?;
                     let keypair = self.network_keypair.as_ref()
-                        .ok_or_else(|| crate::error::CrateSpecError::Other("网络签名需要设置 network_keypair".to_string()))?;
-                    
-                    // 计算摘要（网络签名统一使用 CRATEBIN 类型，只对 crate binary 签名）
-                    let digest = siginfo.pkcs.gen_digest_256(bin_crate)?;
-                    
-                    // 转换为十六进制字符串
-                    let digest_hex = digest_to_hex_string(&digest);
+                        .ok_or_else(|| crate::error::CrateSpecError::Other(tr("网络签名需要设置 network_keypair", "network signatures require network_keypair").to_string()))?;
                     
-                    // 调用 PKI 平台签名接口
-                    let (signature, _cert) = pki_client.sign_digest(
-                        &keypair.priv_key,
-                        &digest_hex,
-                        &keypair.base_config,
-                    ).map_err(|e| crate::error::CrateSpecError::PkiError(e))?;
-                    
-                    // 将公钥、签名、算法信息封装为 NetworkSignature
-                    let network_sig = NetworkSignature {
-                        pub_key: keypair.pub_key.clone(),
-                        signature,
-                        algo: keypair.base_config.algo.clone(),
-                        flow: keypair.base_config.flow.clone(),
-                        kms: if keypair.base_config.kms.is_empty() {
-                            None
-                        } else {
-                            Some(keypair.base_config.kms.clone())
-                        },
-                        key_id: if keypair.key_id.is_empty() {
-                            None
-                        } else {
-                            Some(keypair.key_id.clone())
-                        },
-                    };
+                    // 网络签名统一使用 CRATEBIN 类型，只对 crate binary 签名；
+                    // 按算法发送 SHA256 十六进制摘要或原始内容（Ed25519），将公钥、签名、算法信息封装为 NetworkSignature
+                    // 已裁剪时只有摘要可签，需要原始内容的算法会返回错误
+                    let network_sig = if self.binary_pruned {
+                        NetworkSignature::sign(pki_client, keypair, &digest_to_hex_string(bin_crate))
+                    } else {
+                        NetworkSignature::sign_content(pki_client, keypair, bin_crate)
+                    }
+                    .map_err(crate::error::CrateSpecError::PkiError)?;
                     
                     // 序列化 NetworkSignature
-                    let encoded = bincode::encode_to_vec(&network_sig, bincode::config::standard())
-                        .map_err(|e| crate::error::CrateSpecError::EncodeError(format!("无法序列化网络签名: {}", e)))?;
+                    let encoded = network_sig.to_bytes().map_err(crate::error::CrateSpecError::EncodeError)?;
                     
                     siginfo.bin = encoded;
                     siginfo.size = siginfo.bin.len();
                     siginfo.pub_key = Some(keypair.pub_key.clone());
                 }
                 _ => {
-                    return Err(crate::error::CrateSpecError::Other(format!("不支持的签名类型: {}", siginfo.typ)));
+                    return Err(crate::error::CrateSpecError::Other(tr_format!("不支持的签名类型: {}", "unsupported signature type: {}", siginfo.typ)));
                 }
             }
+            timings::record(format!("{} #{} (type {})", timings::label_sign(), no, siginfo.typ), sig_start.elapsed());
         }
         Ok(())
     }
@@ -216,8 +272,88 @@ impl PackageContext {
         } else {
             encode2vec_by_bincode(crate_package)
         };
-        // 计算除末尾指纹外的所有数据的 SHA256
-        PKCS::new().gen_digest_256(&bin_all[..bin_all.len() - FINGERPRINT_LEN])
+        // 按头部记录的算法计算除末尾指纹外的所有数据的摘要
+        PKCS::new().gen_digest(self.fingerprint_digest, &bin_all[..bin_all.len() - self.fingerprint_digest.output_len()])
+    }
+
+    /// 编码前检查上下文是否自洽：
+    /// 包名非空且符合 cargo 的包名规则、版本为合法的 semver 版本、依赖名非空、网络签名已设置 network_client 和 network_keypair
+    pub fn verify_self_consistency(&self) -> Result<()> {
+        if self.pack_info.name.is_empty() {
+            return Err(CrateSpecError::ValidationError(tr("包名不能为空", "the package name must not be empty").to_string()));
+        }
+        if self.pack_info.version.is_empty() {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "包 {} 的版本不能为空",
+                "the version of package {} must not be empty",
+                self.pack_info.name
+            )));
+        }
+        self.pack_info.check_name().map_err(CrateSpecError::ValidationError)?;
+        self.pack_info.check_version().map_err(|e| {
+            CrateSpecError::ValidationError(tr_format!("包 {}: {}", "package {}: {}", self.pack_info.name, e))
+        })?;
+        // 解码时字符串表按 max_string_len 限制单个字符串，超出的构建元数据（如许可证文本）在这里拒绝
+        if let Some((key, value)) = self.build_metadata.iter().find(|(_, value)| value.len() > self.max_string_len) {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "构建元数据 {} 长 {} 字节，超过字符串长度上限 {}",
+                "build metadata {} is {} bytes long, exceeding the string length limit of {}",
+                key, value.len(), self.max_string_len
+            )));
+        }
+        if let Some(no) = self.dep_infos.iter().position(|dep| dep.name.is_empty()) {
+            return Err(CrateSpecError::ValidationError(tr_format!("第 {} 个依赖的名称为空", "dependency {} has an empty name", no)));
+        }
+        if !is_valid_section_align(self.section_align) {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "数据段对齐必须是不超过 {} 的 2 的幂，实际为 {}",
+                "the section alignment must be a power of two no greater than {}, got {}",
+                MAX_SECTION_ALIGN, self.section_align
+            )));
+        }
+        if let Some(no) = self.sigs.iter().position(|sig| {
+            sig.digest != DigestAlgo::Sha256
+                && (sig.typ == SIGTYPE::NETWORK.as_u32() || (self.binary_pruned && sig.typ == SIGTYPE::CRATEBIN.as_u32()))
+        }) {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "第 {} 个签名不能使用 {} 摘要：网络签名和裁剪包的 CRATEBIN 签名只支持 sha256",
+                "signature {} cannot use a {} digest: network signatures and CRATEBIN signatures of pruned packages only support sha256",
+                no, self.sigs[no].digest
+            )));
+        }
+        if self.binary_pruned && self.compression != CompressionKind::None {
+            return Err(CrateSpecError::ValidationError(
+                tr("已裁剪的 crate 二进制只有摘要，不能再压缩", "a pruned crate binary only holds a digest and cannot be compressed").to_string(),
+            ));
+        }
+        if self.binary_pruned && self.crate_binary.bytes.len() != PRUNED_DIGEST_LEN {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "已裁剪的 crate 二进制应为 {} 字节的 SHA256 摘要，实际 {} 字节",
+                "a pruned crate binary must be a {}-byte SHA256 digest, found {} bytes",
+                PRUNED_DIGEST_LEN,
+                self.crate_binary.bytes.len()
+            )));
+        }
+        for (no, siginfo) in self.sigs.iter().enumerate() {
+            if siginfo.typ != SIGTYPE::NETWORK.as_u32() {
+                continue;
+            }
+            if self.network_client.is_none() {
+                return Err(CrateSpecError::ValidationError(tr_format!(
+                    "第 {} 个签名为网络签名，但未设置 network_client",
+                    "signature {} is a network signature but network_client is not set",
+                    no
+                )));
+            }
+            if self.network_keypair.is_none() {
+                return Err(CrateSpecError::ValidationError(tr_format!(
+                    "第 {} 个签名为网络签名，但未设置 network_keypair",
+                    "signature {} is a network signature but network_keypair is not set",
+                    no
+                )));
+            }
+        }
+        Ok(())
     }
 
     //1 before sig
@@ -225,16 +361,16 @@ impl PackageContext {
         &self,
         str_table: &mut StringTable,
         crate_package: &mut CratePackage,
-    ) {
+    ) -> Result<()> {
         crate_package.set_magic_numer();
 
         // Package contexts info (package, dep, crate binary) are written
         // to CratePackage data sections without signature section
-        self.set_pack_dep_bin(crate_package, str_table);
+        self.set_pack_dep_bin(crate_package, str_table)?;
 
         // since siginfo's bin and size are not calculated yet, we need to set fake signature section at first.
         // only make signature section's placeholder.
-        self.set_sigs(crate_package, NOT_SIG_NUM);
+        self.set_sigs(crate_package, self.non_sig_section_num());
 
         // we have constructed data sections, so let's set section index and string table.
         // since signature section is not calculated yet, so here the signature section's index is
@@ -242,6 +378,13 @@ impl PackageContext {
         crate_package.set_section_index();
         crate_package.set_string_table(str_table);
         crate_package.set_crate_header(0);
+        crate_package.crate_header.set_binary_pruned(self.binary_pruned);
+        crate_package.crate_header.set_compression(self.compression);
+        crate_package.set_fingerprint_digest(self.fingerprint_digest);
+        crate_package.set_endianness(self.endianness, str_table);
+        // ds_offset 确定后才能计算对齐后的段偏移量；填充在签名范围内，签名和验签看到的内容一致
+        crate_package.set_section_align(self.section_align);
+        Ok(())
     }
 
     //2 sig
@@ -251,7 +394,7 @@ impl PackageContext {
         self.calc_sigs(crate_package, pre_serialized_bin)?;
     
         // Set real signature section into each CratePackage's SigStructureSection
-        self.set_sigs(crate_package, NOT_SIG_NUM);
+        self.set_sigs(crate_package, self.non_sig_section_num());
         Ok(())
     }
 
@@ -272,19 +415,23 @@ impl PackageContext {
     /// 优化策略：
     /// 1. 签名前序列化一次（用于签名计算）
     /// 2. 签名后、指纹前序列化一次（用于指纹计算和最终输出）
-    /// 3. 设置指纹后直接修改序列化结果的最后32字节，避免第三次序列化
+    /// 3. 设置指纹后直接修改序列化结果末尾的指纹字节，避免第三次序列化
     /// 
     /// 相比原来的实现，序列化次数从3次减少到2次（减少33%）
     pub fn encode_to_crate_package(&mut self) -> Result<(CratePackage, StringTable, Vec<u8>)> {
+        self.verify_self_consistency()?;
+
         let mut crate_package = CratePackage::new();
         let mut str_table = StringTable::new();
         
         // 阶段1：签名前准备
-        self.encode_to_crate_package_before_sig(&mut str_table, &mut crate_package);
+        self.encode_to_crate_package_before_sig(&mut str_table, &mut crate_package)?;
         
         // 阶段2：计算签名
         // 先序列化一次（用于签名计算）
-        let bin_before_sig = encode2vec_by_bincode(&crate_package);
+        let bin_before_sig = timings::measure(tr_format!("{}（签名前）", "{} (before signing)", timings::label_encode()), || {
+            encode2vec_by_bincode(&crate_package)
+        });
         // 使用预序列化的数据进行签名计算
         self.encode_sig_to_crate_package(&mut crate_package, Some(&bin_before_sig))?;
         
@@ -293,7 +440,9 @@ impl PackageContext {
         
         // 阶段4：签名后序列化（用于指纹计算和最终输出）
         // 段索引已更新，需要重新序列化
-        let mut bin_after_sig = encode2vec_by_bincode(&crate_package);
+        let mut bin_after_sig = timings::measure(tr_format!("{}（签名后）", "{} (after signing)", timings::label_encode()), || {
+            encode2vec_by_bincode(&crate_package)
+        });
         
         // 阶段5：计算指纹并直接修改序列化结果的最后32字节
         // 避免第三次完整序列化，只需更新指纹部分
@@ -301,9 +450,101 @@ impl PackageContext {
         // 更新 crate_package 中的指纹字段（保持一致性，虽然不会再用到）
         crate_package.set_finger_print(fingerprint.clone());
         // 直接修改序列化结果的最后32字节，避免重新序列化整个结构
-        let fp_start = bin_after_sig.len() - FINGERPRINT_LEN;
+        let fp_start = bin_after_sig.len() - fingerprint.len();
         bin_after_sig[fp_start..].copy_from_slice(&fingerprint);
         
         Ok((crate_package, str_table, bin_after_sig))
     }
 }
+
+#[test]
+fn test_verify_self_consistency() {
+    use crate::network::{BaseConfig, KeyPair, PkiClient};
+    use crate::utils::context::{DepInfo, PackageInfo};
+    use std::sync::Arc;
+
+    fn context() -> PackageContext {
+        let mut pack_context = PackageContext::new();
+        pack_context.pack_info = PackageInfo::new(
+            "consistent".to_string(),
+            "0.1.0".to_string(),
+            "MIT".to_string(),
+            vec![],
+        );
+        pack_context.add_crate_bin(vec![0u8; 8]);
+        pack_context
+    }
+    fn assert_invalid(pack_context: &mut PackageContext, msg: &str) {
+        match pack_context.encode_to_crate_package() {
+            Err(CrateSpecError::ValidationError(e)) => assert!(e.contains(msg), "{}", e),
+            other => panic!("expected ValidationError, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    assert!(context().verify_self_consistency().is_ok());
+
+    let mut pack_context = context();
+    pack_context.pack_info.name = "".to_string();
+    assert_invalid(&mut pack_context, "包名");
+
+    let mut pack_context = context();
+    pack_context.pack_info.version = "".to_string();
+    assert_invalid(&mut pack_context, "版本");
+
+    let mut pack_context = context();
+    pack_context.pack_info.version = "../0.1.0".to_string();
+    assert_invalid(&mut pack_context, "路径分隔符");
+
+    let mut pack_context = context();
+    pack_context.pack_info.version = "0.1".to_string();
+    assert_invalid(&mut pack_context, "semver");
+
+    let mut pack_context = context();
+    pack_context.dep_infos.push(DepInfo::default());
+    assert_invalid(&mut pack_context, "依赖的名称");
+
+    let mut pack_context = context();
+    pack_context.add_sig(PKCS::new(), SIGTYPE::NETWORK);
+    assert_invalid(&mut pack_context, "network_client");
+
+    let mut pack_context = context();
+    pack_context.add_sig(PKCS::new(), SIGTYPE::NETWORK);
+    pack_context.network_client =
+        Some(Arc::new(PkiClient::new("http://127.0.0.1:1".to_string(), 0, 0).unwrap()));
+    assert_invalid(&mut pack_context, "network_keypair");
+
+    pack_context.network_keypair = Some(Arc::new(KeyPair {
+        pub_key: String::new(),
+        priv_key: String::new(),
+        key_id: String::new(),
+        base_config: BaseConfig {
+            algo: String::new(),
+            flow: String::new(),
+            kms: String::new(),
+        },
+        fetched_at: None,
+        ttl_secs: None,
+    }));
+    assert!(pack_context.verify_self_consistency().is_ok());
+}
+
+#[test]
+fn test_binary_before_sig_sig_num_mismatch() {
+    use crate::utils::testing::signer;
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("mismatch".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    pack_context.add_sig(signer(), SIGTYPE::CRATEBIN);
+    let (crate_package, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    assert!(pack_context.binary_before_sig(&crate_package, &bin).is_ok());
+
+    // 段索引中只有 1 个签名段，上下文中却有 2 个签名：返回错误而不是 panic
+    pack_context.add_sig(signer(), SIGTYPE::FILE);
+    let err = pack_context.binary_before_sig(&crate_package, &bin).unwrap_err();
+    assert!(
+        matches!(err, crate::error::CrateSpecError::EncodeError(ref msg) if msg.contains("期望 2") && msg.contains("实际 1")),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,95 @@
+//! 通过 OpenSSL engine 加载私钥（例如 PKCS#11 HSM），私钥本身不会离开 engine
+use crate::error::{CrateSpecError, Result};
+use crate::tr_format;
+use foreign_types::ForeignType;
+use openssl::error::ErrorStack;
+use openssl::pkey::{PKey, Private};
+use openssl_sys::{ENGINE, EVP_PKEY};
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+
+// openssl-sys 没有导出 ENGINE 相关接口，这里只声明用到的部分
+extern "C" {
+    fn ENGINE_load_builtin_engines();
+    fn ENGINE_by_id(id: *const c_char) -> *mut ENGINE;
+    fn ENGINE_init(e: *mut ENGINE) -> c_int;
+    fn ENGINE_finish(e: *mut ENGINE) -> c_int;
+    fn ENGINE_free(e: *mut ENGINE) -> c_int;
+    fn ENGINE_load_private_key(
+        e: *mut ENGINE,
+        key_id: *const c_char,
+        ui_method: *mut c_void,
+        callback_data: *mut c_void,
+    ) -> *mut EVP_PKEY;
+}
+
+/// 从指定 engine 加载私钥句柄
+///
+/// `engine_id` 为 engine 名称（如 `pkcs11`），`key_id` 为 engine 能识别的
+/// 密钥标识（如 `pkcs11:token=...;object=...` 形式的 URI）。
+pub fn load_private_key(engine_id: &str, key_id: &str) -> Result<PKey<Private>> {
+    openssl::init();
+    let engine_id_c = CString::new(engine_id)
+        .map_err(|e| CrateSpecError::ValidationError(tr_format!("无效的 engine id: {}", "invalid engine id: {}", e)))?;
+    let key_id_c = CString::new(key_id)
+        .map_err(|e| CrateSpecError::ValidationError(tr_format!("无效的 engine 密钥标识: {}", "invalid engine key id: {}", e)))?;
+
+    unsafe {
+        ENGINE_load_builtin_engines();
+        let engine = ENGINE_by_id(engine_id_c.as_ptr());
+        if engine.is_null() {
+            return Err(CrateSpecError::SignatureError(tr_format!(
+                "无法加载 OpenSSL engine {}: {}",
+                "cannot load OpenSSL engine {}: {}",
+                engine_id,
+                ErrorStack::get()
+            )));
+        }
+        if ENGINE_init(engine) != 1 {
+            let err = ErrorStack::get();
+            ENGINE_free(engine);
+            return Err(CrateSpecError::SignatureError(tr_format!(
+                "无法初始化 OpenSSL engine {}: {}",
+                "cannot initialize OpenSSL engine {}: {}",
+                engine_id, err
+            )));
+        }
+        let pkey = ENGINE_load_private_key(engine, key_id_c.as_ptr(), ptr::null_mut(), ptr::null_mut());
+        let err = ErrorStack::get();
+        // EVP_PKEY 自身持有 engine 引用，这里可以释放本地引用
+        ENGINE_finish(engine);
+        ENGINE_free(engine);
+        if pkey.is_null() {
+            return Err(CrateSpecError::SignatureError(tr_format!(
+                "无法从 engine {} 加载私钥 {}: {}",
+                "cannot load private key {1} from engine {0}: {2}",
+                engine_id, key_id, err
+            )));
+        }
+        Ok(PKey::from_ptr(pkey))
+    }
+}
+
+#[test]
+fn test_engine_unknown_id() {
+    assert!(load_private_key("crate-spec-no-such-engine", "key").is_err());
+}
+
+/// 需要 soft-HSM 环境，默认忽略。设置 CRATE_SPEC_TEST_ENGINE_ID、CRATE_SPEC_TEST_ENGINE_KEY
+/// 和与该密钥匹配的证书 CRATE_SPEC_TEST_ENGINE_CERT 后用
+/// `cargo test --features engine -- --ignored test_engine_sign_with_softhsm` 执行
+#[test]
+#[ignore = "需要 soft-HSM 环境和 CRATE_SPEC_TEST_ENGINE_* 环境变量"]
+fn test_engine_sign_with_softhsm() {
+    use crate::utils::pkcs::{EngineKey, PKCS};
+    let var = |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("未设置 {}", name));
+    let engine_id = var("CRATE_SPEC_TEST_ENGINE_ID");
+    let key_id = var("CRATE_SPEC_TEST_ENGINE_KEY");
+    let cert_path = var("CRATE_SPEC_TEST_ENGINE_CERT");
+    let mut pkcs = PKCS::new();
+    pkcs.load_from_engine_writer(cert_path, EngineKey { engine_id, key_id }, vec![])
+        .unwrap();
+    let digest = pkcs.gen_digest_256(b"crate-spec").unwrap();
+    assert!(pkcs.encode_pkcs_bin(digest.as_slice()).is_ok());
+}
@@ -0,0 +1,286 @@
+//! 逐步验证并生成说明树（`--explain`）
+//!
+//! 依次执行解码时的检查：文件头 → 指纹 → 数据段 → 签名要求和各签名（签名者、验证结果、签名时间），
+//! 记录每一步检查了什么、结果如何。与解码不同，某一步失败后只要后续步骤仍有意义就继续：
+//! 指纹不一致时照常验证签名，便于判断被改动的是哪一部分；结构无法解析时跳过依赖它的步骤。
+use crate::locale::tr;
+use crate::tr_format;
+use crate::utils::context::{PackageContext, SIGTYPE};
+use crate::utils::decode::SigVerifyResult;
+use crate::utils::package::CratePackage;
+use crate::utils::pkcs::PKCS;
+use std::fmt::{Display, Formatter};
+
+/// 单个步骤的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepStatus {
+    Pass,
+    Fail,
+    /// 前置步骤失败或条件不满足，未执行
+    Skip,
+}
+
+impl StepStatus {
+    fn label(self) -> &'static str {
+        match self {
+            StepStatus::Pass => tr("通过", "pass"),
+            StepStatus::Fail => tr("失败", "FAIL"),
+            StepStatus::Skip => tr("跳过", "skip"),
+        }
+    }
+}
+
+/// 说明树的节点：步骤名、结果、说明和子步骤
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainStep {
+    pub name: String,
+    pub status: StepStatus,
+    pub detail: String,
+    pub children: Vec<ExplainStep>,
+}
+
+impl ExplainStep {
+    fn new(name: &str, status: StepStatus, detail: impl Into<String>) -> Self {
+        ExplainStep { name: name.to_string(), status, detail: detail.into(), children: vec![] }
+    }
+
+    fn from_result<T>(name: &str, result: crate::error::Result<T>, ok: impl FnOnce(T) -> String) -> Self {
+        match result {
+            Ok(value) => Self::new(name, StepStatus::Pass, ok(value)),
+            Err(e) => Self::new(name, StepStatus::Fail, e.to_string()),
+        }
+    }
+
+    /// 子步骤中有失败时本步骤失败
+    fn group(name: &str, detail: impl Into<String>, children: Vec<ExplainStep>) -> Self {
+        let status = if children.iter().any(|c| c.status == StepStatus::Fail) { StepStatus::Fail } else { StepStatus::Pass };
+        ExplainStep { name: name.to_string(), status, detail: detail.into(), children }
+    }
+
+    /// 第一个失败的叶子步骤，返回从本节点开始的路径
+    fn first_failure(&self) -> Option<Vec<&str>> {
+        if self.status != StepStatus::Fail {
+            return None;
+        }
+        let mut path = vec![self.name.as_str()];
+        if let Some(rest) = self.children.iter().find_map(|c| c.first_failure()) {
+            path.extend(rest);
+        }
+        Some(path)
+    }
+
+    fn fmt_tree(&self, f: &mut Formatter<'_>, prefix: &str, last: bool) -> std::fmt::Result {
+        let (branch, indent) = if last { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+        write!(f, "{}{}[{}] {}", prefix, branch, self.status.label(), self.name)?;
+        if !self.detail.is_empty() {
+            write!(f, ": {}", self.detail)?;
+        }
+        writeln!(f)?;
+        let child_prefix = format!("{}{}", prefix, indent);
+        for (i, child) in self.children.iter().enumerate() {
+            child.fmt_tree(f, &child_prefix, i + 1 == self.children.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// 整个验证过程的说明树
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub steps: Vec<ExplainStep>,
+}
+
+impl Explanation {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.status != StepStatus::Fail)
+    }
+
+    /// 第一个失败步骤的路径，如 `签名 > 签名 #0 (CRATEBIN) > 摘要`
+    pub fn first_failure(&self) -> Option<String> {
+        self.steps.iter().find_map(|step| step.first_failure()).map(|path| path.join(" > "))
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            step.fmt_tree(f, "", i + 1 == self.steps.len())?;
+        }
+        Ok(())
+    }
+}
+
+fn skipped(names: &[&str], reason: &str) -> Vec<ExplainStep> {
+    names.iter().map(|name| ExplainStep::new(name, StepStatus::Skip, reason)).collect()
+}
+
+impl PackageContext {
+    /// 逐步验证 `.scrate` 数据并返回说明树，不会因某一步失败而提前返回
+    ///
+    /// 各步骤调用解码时的同一组检查：文件头、指纹、[`PackageContext::read_sections`]（段顺序、包名、
+    /// 版本号等）、签名要求和 [`PackageContext::verify_all`]（证书链、摘要、密钥强度、网络验签），
+    /// 另外检查每个本地签名的签名时间。使用上下文中的根 CA（为空时使用进程级默认根 CA）、证书链深度、
+    /// 密钥强度要求、签名要求和 PKI 客户端。
+    pub fn explain(&mut self, bin: &[u8]) -> Explanation {
+        let names = [tr("文件头", "header"), tr("指纹", "fingerprint"), tr("数据段", "sections"), tr("签名", "signatures")];
+        let mut steps = vec![];
+
+        let algo = match CratePackage::peek_header(bin).and_then(|header| header.fingerprint_digest()) {
+            Ok(algo) => {
+                steps.push(ExplainStep::new(names[0], StepStatus::Pass, tr_format!("{} 指纹", "{} fingerprint", algo)));
+                algo
+            }
+            Err(e) => {
+                steps.push(ExplainStep::new(names[0], StepStatus::Fail, e.to_string()));
+                steps.extend(skipped(&names[1..], tr("文件头无法解析", "header unreadable")));
+                return Explanation { steps };
+            }
+        };
+
+        // 指纹不一致时继续检查，签名结果可以说明改动发生在哪一部分
+        steps.push(match self.check_fingerprint(bin) {
+            Ok(true) => ExplainStep::new(names[1], StepStatus::Pass, algo.to_string()),
+            Ok(false) => ExplainStep::new(
+                names[1],
+                StepStatus::Fail,
+                tr("记录值与计算值不一致，文件内容被改动", "the recorded value does not match the computed one; the file was modified"),
+            ),
+            Err(e) => ExplainStep::new(names[1], StepStatus::Fail, e.to_string()),
+        });
+
+        let crate_package = match self.read_sections(bin) {
+            Ok((crate_package, _)) => {
+                steps.push(ExplainStep::new(
+                    names[2],
+                    StepStatus::Pass,
+                    tr_format!(
+                        "{}-{}，{} 个依赖，crate 二进制 {} 字节{}",
+                        "{}-{}, {} dependencies, crate binary {} bytes{}",
+                        self.pack_info.name,
+                        self.pack_info.version,
+                        self.dep_infos.len(),
+                        self.crate_binary.bytes.len(),
+                        if self.binary_pruned { tr("（已裁剪，只含摘要）", " (pruned, digest only)") } else { "" }
+                    ),
+                ));
+                crate_package
+            }
+            Err(e) => {
+                steps.push(ExplainStep::new(names[2], StepStatus::Fail, e.to_string()));
+                steps.extend(skipped(&names[3..], tr("数据段无法解析", "sections unreadable")));
+                return Explanation { steps };
+            }
+        };
+
+        steps.push(self.explain_sigs(names[3], &crate_package, bin));
+        Explanation { steps }
+    }
+
+    fn explain_sigs(&self, name: &str, crate_package: &CratePackage, bin: &[u8]) -> ExplainStep {
+        let requirement_name = tr("签名要求", "requirements");
+        let mut children = vec![if self.require_signature && self.sigs.is_empty() {
+            ExplainStep::new(requirement_name, StepStatus::Fail, tr("包中没有签名", "the package has no signatures"))
+        } else if !self.require_signature && self.require_sig_types.is_empty() {
+            ExplainStep::new(requirement_name, StepStatus::Skip, tr("未设置要求", "none set"))
+        } else {
+            ExplainStep::from_result(requirement_name, self.check_sig_types(&self.require_sig_types), |_| String::new())
+        }];
+
+        match self.verify_all(crate_package, bin) {
+            Ok(results) => children.extend(results.iter().map(|result| self.explain_sig(result))),
+            Err(e) => children.push(ExplainStep::new(tr("验证", "verify"), StepStatus::Fail, e.to_string())),
+        }
+        let detail = match self.sigs.len() {
+            0 => tr("包中没有签名", "the package has no signatures").to_string(),
+            n => tr_format!("{} 个", "{}", n),
+        };
+        ExplainStep::group(name, detail, children)
+    }
+
+    /// 单个签名的说明：验证结果取自 [`PackageContext::verify_all`]，本地签名另外列出签名者和签名时间
+    fn explain_sig(&self, result: &SigVerifyResult) -> ExplainStep {
+        let typ = result.typ.map_or_else(|| format!("type {}", self.sigs[result.index].typ), |typ| format!("{:?}", typ));
+        let name = format!("{} #{} ({})", tr("签名", "signature"), result.index, typ);
+        let siginfo = &self.sigs[result.index];
+        let local = matches!(result.typ, Some(SIGTYPE::FILE | SIGTYPE::CRATEBIN));
+
+        let mut children = vec![];
+        if local {
+            children.push(ExplainStep::from_result(tr("签名者", "signer"), PKCS::signer_subject(&siginfo.bin), |s| s));
+        }
+        let verify_name = tr("验证", "verify");
+        children.push(match (&result.reason, &result.trust_anchor) {
+            (Some(reason), _) => ExplainStep::new(verify_name, StepStatus::Fail, reason.clone()),
+            (None, Some(anchor)) => ExplainStep::new(verify_name, StepStatus::Pass, anchor.to_string()),
+            (None, None) => ExplainStep::new(verify_name, StepStatus::Pass, tr("PKI 平台验签通过", "verified by the PKI platform")),
+        });
+        if local {
+            let time_name = tr("签名时间", "signing time");
+            children.push(match PKCS::verify_signing_time(&siginfo.bin) {
+                Ok(Some(time)) => ExplainStep::new(time_name, StepStatus::Pass, time),
+                Ok(None) => ExplainStep::new(time_name, StepStatus::Skip, tr("签名中没有记录签名时间", "no signing time recorded")),
+                Err(e) => ExplainStep::new(time_name, StepStatus::Fail, e.to_string()),
+            });
+        }
+        ExplainStep::group(&name, "", children)
+    }
+}
+
+#[test]
+fn test_explain_tampered_file() {
+    use crate::utils::pkcs::KeyStrengthPolicy;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let _guard = crate::utils::pkcs::DEFAULT_ROOT_CAS_TEST_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    let pkcs = signer();
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("explain".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![5u8; 64]);
+    pack_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    let root_cas = root_ca_bins();
+    let context = || {
+        let mut ctx = PackageContext::new();
+        ctx.root_cas = root_cas.clone();
+        ctx
+    };
+
+    let good = context().explain(&bin);
+    assert!(good.passed(), "{}", good);
+    assert_eq!(good.first_failure(), None);
+    assert_eq!(good.steps.len(), 4);
+    let text = good.to_string();
+    assert!(text.contains("[跳过] 签名要求") && text.contains("[通过] 签名时间"), "{}", text);
+
+    // 改动 crate 二进制：指纹和该签名都验证失败，失败原因与解码时相同
+    let mut tampered = bin.clone();
+    let pos = tampered.windows(64).position(|w| w == [5u8; 64]).unwrap();
+    tampered[pos] ^= 0xff;
+    let report = context().explain(&tampered);
+    assert!(!report.passed());
+    assert_eq!(report.first_failure().as_deref(), Some("指纹"));
+    let text = report.to_string();
+    assert!(text.contains("[失败] 指纹: 记录值"), "{}", text);
+    assert!(text.contains("[失败] 验证: ") && text.contains("本地签名验证失败"), "{}", text);
+
+    // 签名要求和密钥强度与解码使用同一组检查
+    let mut strict = context();
+    strict.require_sig_types = vec![SIGTYPE::NETWORK];
+    strict.key_policy = KeyStrengthPolicy { min_rsa_bits: Some(16384), ..Default::default() };
+    let report = strict.explain(&bin);
+    assert_eq!(report.first_failure().as_deref(), Some("签名 > 签名要求"));
+    let text = report.to_string();
+    assert!(text.contains("NETWORK") || text.contains("network"), "{}", text);
+    assert!(text.contains("[失败] 签名 #0 (CRATEBIN)"), "{}", text);
+    let mut strict = context();
+    strict.key_policy = KeyStrengthPolicy { min_rsa_bits: Some(16384), ..Default::default() };
+    let mut decoded = strict.clone();
+    let err = decoded.decode_from_crate_package(&bin).err().unwrap();
+    let report = strict.explain(&bin);
+    assert_eq!(report.first_failure().as_deref(), Some("签名 > 签名 #0 (CRATEBIN) > 验证"));
+    assert!(report.to_string().contains(&err.to_string()), "{}\n{}", report, err);
+
+    let garbage = context().explain(b"not a scrate");
+    assert_eq!(garbage.first_failure().as_deref(), Some("文件头"));
+    assert!(garbage.steps[1..].iter().all(|step| step.status == StepStatus::Skip));
+}
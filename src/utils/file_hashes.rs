@@ -0,0 +1,216 @@
+//! `.crate` 内逐文件的 SHA256 清单
+//!
+//! 打包时（`--file-hashes`）解开 `.crate` 的 tar 包，对每个普通文件计算 SHA256，写入可选的文件哈希段。
+//! 该段位于签名段之前，受 FILE 签名保护。解码后可用 [`PackageContext::check_file_hashes`]
+//! 核对已解压的文件，精确指出哪些文件被修改、缺失或多出。
+use crate::error::{CrateSpecError, Result};
+use crate::utils::context::{CrateBinary, PackageContext};
+use crate::utils::package::FILE_HASH_LEN;
+use crate::locale::tr;
+use crate::tr_format;
+use flate2::read::GzDecoder;
+use openssl::sha::Sha256;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path};
+use tar::Archive;
+
+/// `.crate` 中一个文件的路径（tar 内路径，以 `/` 分隔，含 `<name>-<version>/` 前缀）及其 SHA256
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHash {
+    pub path: String,
+    pub sha256: [u8; FILE_HASH_LEN],
+}
+
+/// 以 `/` 连接的相对路径；含 `..`、根目录等非普通组成部分时返回 None
+fn normal_path(path: &Path) -> Option<String> {
+    path.components()
+        .map(|c| match c {
+            Component::Normal(part) => part.to_str().map(str::to_string),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|parts| parts.join("/"))
+}
+
+fn sha256_of<R: Read>(mut reader: R) -> std::io::Result<[u8; FILE_HASH_LEN]> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+/// `dir` 下所有普通文件相对 `dir` 的路径
+fn files_under(dir: &Path, prefix: &str, out: &mut BTreeSet<String>) -> Result<()> {
+    for entry in fs::read_dir(dir).map_err(CrateSpecError::Io)? {
+        let entry = entry.map_err(CrateSpecError::Io)?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let rel = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+        let file_type = entry.file_type().map_err(CrateSpecError::Io)?;
+        if file_type.is_dir() {
+            files_under(&entry.path(), &rel, out)?;
+        } else if file_type.is_file() {
+            out.insert(rel);
+        }
+    }
+    Ok(())
+}
+
+impl CrateBinary {
+    /// 按 tar 中的顺序计算每个普通文件的 SHA256
+    pub fn file_hashes(&self) -> Result<Vec<FileHash>> {
+        let mut archive = Archive::new(GzDecoder::new(self.bytes.as_slice()));
+        let entries = archive.entries()
+            .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制不是有效的 tar 包: {}", "the crate binary is not a valid tar archive: {}", e)))?;
+        let mut hashes = vec![];
+        for entry in entries {
+            let entry = entry
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 tar 条目损坏: {}", "corrupted tar entry in the crate binary: {}", e)))?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制 tar 条目路径无效: {}", "invalid tar entry path in the crate binary: {}", e)))?;
+            let path = normal_path(&path).ok_or_else(|| {
+                CrateSpecError::DecodeError(tr_format!("crate 二进制 tar 条目路径不安全: {}", "unsafe tar entry path in the crate binary: {}", path.display()))
+            })?;
+            let sha256 = sha256_of(entry)
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("读取 {} 失败: {}", "failed to read {}: {}", path, e)))?;
+            hashes.push(FileHash { path, sha256 });
+        }
+        Ok(hashes)
+    }
+}
+
+impl PackageContext {
+    /// 计算 crate 二进制中每个文件的 SHA256，编码时写入文件哈希段；须在裁剪二进制之前调用
+    pub fn record_file_hashes(&mut self) -> Result<()> {
+        self.require_crate_binary()?;
+        self.file_hashes = self.crate_binary.file_hashes()?;
+        Ok(())
+    }
+
+    /// 按文件哈希清单核对 `dir`（`.crate` 解压到的目录，其下为 `<name>-<version>/...`），返回核对的文件数
+    ///
+    /// 清单中各顶层目录下有清单外的文件也视为不一致；任何不一致都会在错误中逐项列出。
+    /// 未裁剪的包先核对清单与 crate 二进制一致：CRATEBIN/网络签名不覆盖文件哈希段，
+    /// 这样清单和已验签的 crate 二进制同样可信。
+    pub fn check_file_hashes(&self, dir: &Path) -> Result<usize> {
+        if self.file_hashes.is_empty() {
+            return Err(CrateSpecError::ValidationError(
+                tr("包中没有文件哈希清单，打包时需使用 --file-hashes", "the package has no file hash manifest; pack it with --file-hashes").to_string(),
+            ));
+        }
+        if !self.binary_pruned && self.crate_binary.file_hashes()? != self.file_hashes {
+            return Err(CrateSpecError::ValidationError(tr("文件哈希清单与 crate 二进制的内容不符", "the file hash manifest does not match the crate binary contents").to_string()));
+        }
+        let (mut modified, mut missing) = (vec![], vec![]);
+        let mut roots = BTreeSet::new();
+        for file in self.file_hashes.iter() {
+            let rel = normal_path(Path::new(&file.path)).ok_or_else(|| {
+                CrateSpecError::ValidationError(tr_format!("文件哈希清单中的路径不安全: {}", "unsafe path in the file hash manifest: {}", file.path))
+            })?;
+            roots.insert(rel.split('/').next().unwrap_or_default().to_string());
+            match fs::File::open(dir.join(&rel)) {
+                Ok(f) => {
+                    if sha256_of(f).map_err(CrateSpecError::Io)? != file.sha256 {
+                        modified.push(rel);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => missing.push(rel),
+                Err(e) => return Err(CrateSpecError::Io(e)),
+            }
+        }
+
+        let listed: HashSet<&str> = self.file_hashes.iter().map(|file| file.path.as_str()).collect();
+        let mut extra = vec![];
+        for root in roots.iter() {
+            let root_dir = dir.join(root);
+            if !root_dir.is_dir() {
+                continue;
+            }
+            let mut found = BTreeSet::new();
+            files_under(&root_dir, root, &mut found)?;
+            extra.extend(found.into_iter().filter(|path| !listed.contains(path.as_str())));
+        }
+
+        let problems: Vec<String> = [(tr("已修改", "modified"), modified), (tr("缺失", "missing"), missing), (tr("多出", "extra"), extra)]
+            .into_iter()
+            .filter(|(_, paths)| !paths.is_empty())
+            .map(|(kind, paths)| format!("{}: {}", kind, paths.join(", ")))
+            .collect();
+        if !problems.is_empty() {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "{} 与文件哈希清单不一致（{}）",
+                "{} does not match the file hash manifest ({})",
+                dir.display(),
+                problems.join("；")
+            )));
+        }
+        Ok(self.file_hashes.len())
+    }
+}
+
+#[test]
+fn test_file_hashes_round_trip() {
+    use crate::utils::testing::TestDir;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let files: [(&str, &[u8]); 3] = [
+        ("demo-0.1.0/Cargo.toml", b"[package]\nname = \"demo\"\nversion = \"0.1.0\"\n"),
+        ("demo-0.1.0/src/lib.rs", b"pub mod util;\n"),
+        ("demo-0.1.0/src/util.rs", b"pub fn answer() -> u32 { 42 }\n"),
+    ];
+    let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+    for (path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content).unwrap();
+    }
+    let crate_bytes = builder.into_inner().unwrap().finish().unwrap();
+
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("demo".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(crate_bytes.clone());
+    ctx.record_file_hashes().unwrap();
+    assert_eq!(ctx.file_hashes.len(), 3);
+    assert_eq!(ctx.file_hashes[2].path, "demo-0.1.0/src/util.rs");
+    assert_eq!(ctx.file_hashes[2].sha256, openssl::sha::sha256(files[2].1));
+
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+    let decoded = PackageContext::try_from_bytes_unverified(&bin).unwrap();
+    assert_eq!(decoded.file_hashes, ctx.file_hashes);
+    assert!(decoded.unreferenced_strings.is_empty());
+    assert!(decoded.metadata_text().contains("  demo-0.1.0/src/lib.rs\n"));
+
+    // 解压后逐文件核对
+    let dir = TestDir::new("file-hashes");
+    Archive::new(GzDecoder::new(crate_bytes.as_slice())).unpack(dir.path()).unwrap();
+    assert_eq!(decoded.check_file_hashes(dir.path()).unwrap(), 3);
+
+    dir.write("demo-0.1.0/src/util.rs", "pub fn answer() -> u32 { 41 }\n");
+    fs::remove_file(dir.join("demo-0.1.0/src/lib.rs")).unwrap();
+    dir.write("demo-0.1.0/src/backdoor.rs", "");
+    let err = decoded.check_file_hashes(dir.path()).unwrap_err().to_string();
+    assert!(err.contains("已修改: demo-0.1.0/src/util.rs"), "{}", err);
+    assert!(err.contains("缺失: demo-0.1.0/src/lib.rs"), "{}", err);
+    assert!(err.contains("多出: demo-0.1.0/src/backdoor.rs"), "{}", err);
+
+    // 被替换的清单与 crate 二进制不符
+    let mut forged = decoded.clone();
+    forged.file_hashes[2].sha256 = openssl::sha::sha256(b"pub fn answer() -> u32 { 41 }\n");
+    let err = forged.check_file_hashes(dir.path()).unwrap_err().to_string();
+    assert!(err.contains("不符"), "{}", err);
+
+    // 没有清单的包无法核对
+    assert!(PackageContext::new().check_file_hashes(dir.path()).is_err());
+}
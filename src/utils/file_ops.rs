@@ -1,12 +1,26 @@
 use crate::error::{Result, CrateSpecError};
+use crate::locale::tr;
+use crate::tr_format;
 use std::fs;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+/// 表示从标准输入读取的输入路径
+pub const STDIN_PATH: &str = "-";
+
+/// 从流（如标准输入）读取 .scrate 数据时允许的最大字节数
+pub const MAX_STREAM_INPUT_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// 输入路径是否表示标准输入
+pub fn is_stdin_path(input: &str) -> bool {
+    input == STDIN_PATH
+}
+
 /// 验证输入文件是否存在
 pub fn validate_input_file(input: &str) -> Result<PathBuf> {
     let path = PathBuf::from_str(input)
-        .map_err(|e| CrateSpecError::ValidationError(format!("无效的输入路径: {}", e)))?;
+        .map_err(|e| CrateSpecError::ValidationError(tr_format!("无效的输入路径: {}", "invalid input path: {}", e)))?;
     if !path.exists() {
         return Err(CrateSpecError::FileNotFound(path));
     }
@@ -16,25 +30,111 @@ pub fn validate_input_file(input: &str) -> Result<PathBuf> {
 /// 确保输出目录存在，如果不存在则创建
 pub fn ensure_output_dir(output: &str) -> Result<PathBuf> {
     let path = PathBuf::from_str(output)
-        .map_err(|e| CrateSpecError::ValidationError(format!("无效的输出路径: {}", e)))?;
+        .map_err(|e| CrateSpecError::ValidationError(tr_format!("无效的输出路径: {}", "invalid output path: {}", e)))?;
     fs::create_dir_all(&path)
-        .map_err(|e| CrateSpecError::Io(e))?;
+        .map_err(CrateSpecError::Io)?;
     Ok(path)
 }
 
+/// 输出文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionStrategy {
+    /// 直接覆盖（默认）
+    #[default]
+    Overwrite,
+    /// 报错，不写入
+    Refuse,
+    /// 在扩展名前追加 `(1)`、`(2)` ... 直到文件名未被占用
+    Suffix,
+}
+
+impl FromStr for CollisionStrategy {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "overwrite" => Ok(CollisionStrategy::Overwrite),
+            "refuse" => Ok(CollisionStrategy::Refuse),
+            "suffix" => Ok(CollisionStrategy::Suffix),
+            _ => Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的冲突处理方式: {}，必须是 'overwrite'、'refuse' 或 'suffix'",
+                "invalid conflict handling: {}, must be 'overwrite', 'refuse' or 'suffix'",
+                s
+            ))),
+        }
+    }
+}
+
+/// 按冲突处理方式确定实际的输出路径
+///
+/// `companion_ext` 为随主文件一起写出的旁路文件扩展名（如分离签名的 `sig`），
+/// 主文件或旁路文件任一存在即视为冲突。
+pub fn resolve_output_path(path: &Path, strategy: CollisionStrategy, companion_ext: Option<&str>) -> Result<PathBuf> {
+    let taken = |p: &Path| {
+        p.exists()
+            || companion_ext.is_some_and(|ext| {
+                let mut companion = p.as_os_str().to_os_string();
+                companion.push(format!(".{}", ext));
+                Path::new(&companion).exists()
+            })
+    };
+    if !taken(path) {
+        return Ok(path.to_path_buf());
+    }
+    match strategy {
+        CollisionStrategy::Overwrite => Ok(path.to_path_buf()),
+        CollisionStrategy::Refuse => Err(CrateSpecError::ValidationError(tr_format!(
+            "输出文件已存在: {}",
+            "output file already exists: {}",
+            path.display()
+        ))),
+        CollisionStrategy::Suffix => {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let ext = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+            (1u32..)
+                .map(|n| path.with_file_name(format!("{}({}){}", stem, n, ext)))
+                .find(|candidate| !taken(candidate))
+                .ok_or_else(|| CrateSpecError::Other(tr("无法生成不冲突的输出文件名", "cannot find a non-colliding output file name").to_string()))
+        }
+    }
+}
+
 /// 写入二进制文件
 pub fn write_file(path: &Path, content: &[u8]) -> Result<()> {
     fs::write(path, content)
-        .map_err(|e| CrateSpecError::Io(e))?;
-    println!("文件已输出到: {}", path.display());
+        .map_err(CrateSpecError::Io)?;
+    println!("{}", tr_format!("文件已输出到: {}", "wrote {}", path.display()));
+    Ok(())
+}
+
+/// 先写入同目录下的临时文件，再重命名到 `path`
+///
+/// 写入或重命名失败时只删除本次创建的临时文件，`path` 处原有的文件保持不变。
+pub fn replace_file(path: &Path, content: &[u8]) -> Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        CrateSpecError::ValidationError(tr_format!("无效的输出路径: {}", "invalid output path: {}", path.display()))
+    })?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+    // create_new 保证不会覆盖或在失败时删除别人的文件
+    let mut tmp = fs::OpenOptions::new().write(true).create_new(true).open(&tmp_path)?;
+    let written = tmp.write_all(content).and_then(|_| tmp.sync_all());
+    drop(tmp);
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(CrateSpecError::Io(e));
+    }
+    println!("{}", tr_format!("文件已输出到: {}", "wrote {}", path.display()));
     Ok(())
 }
 
 /// 写入文本文件
 pub fn write_text_file(path: &Path, content: &str) -> Result<()> {
     fs::write(path, content)
-        .map_err(|e| CrateSpecError::Io(e))?;
-    println!("文件已输出到: {}", path.display());
+        .map_err(CrateSpecError::Io)?;
+    println!("{}", tr_format!("文件已输出到: {}", "wrote {}", path.display()));
     Ok(())
 }
 
@@ -50,3 +150,84 @@ pub fn read_file(path: &Path) -> Result<Vec<u8>> {
         })
 }
 
+/// 从流中读取全部内容，超过 `limit` 字节时返回错误
+pub fn read_stream<R: Read>(reader: R, limit: u64) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut buf)?;
+    if buf.len() as u64 > limit {
+        return Err(CrateSpecError::ValidationError(tr_format!(
+            "输入数据超过大小上限 {} 字节",
+            "the input exceeds the size limit of {} bytes",
+            limit
+        )));
+    }
+    Ok(buf)
+}
+
+/// 从标准输入读取全部内容
+pub fn read_stdin() -> Result<Vec<u8>> {
+    read_stream(io::stdin().lock(), MAX_STREAM_INPUT_BYTES)
+}
+
+#[test]
+fn test_read_stream_limit() {
+    let data = vec![7u8; 16];
+    assert_eq!(read_stream(data.as_slice(), 16).unwrap(), data);
+    assert!(read_stream(data.as_slice(), 15).is_err());
+}
+
+#[test]
+fn test_replace_file_keeps_existing_on_failure() {
+    use crate::utils::testing::TestDir;
+    let dir = TestDir::new("replace-file");
+    let path = dir.write("demo.crate", "old");
+
+    replace_file(&path, b"new").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"new");
+
+    // 目标是目录时重命名失败：临时文件被删除，目录原样保留
+    let blocked = dir.join("blocked.crate");
+    fs::create_dir(&blocked).unwrap();
+    assert!(replace_file(&blocked, b"new").is_err());
+    assert!(blocked.is_dir());
+
+    // 临时文件无法创建时，已有的目标文件不被删除
+    let tmp = dir.join(&format!(".demo.crate.{}.tmp", std::process::id()));
+    fs::create_dir(&tmp).unwrap();
+    assert!(replace_file(&path, b"newer").is_err());
+    assert_eq!(fs::read(&path).unwrap(), b"new");
+    assert!(tmp.is_dir());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+}
+
+#[test]
+fn test_resolve_output_path_strategies() {
+    use crate::utils::testing::TestDir;
+    let dir = TestDir::new("collision");
+    let path = dir.join("demo-0.1.0.scrate");
+
+    // 不冲突时三种方式都使用原路径
+    for strategy in [CollisionStrategy::Overwrite, CollisionStrategy::Refuse, CollisionStrategy::Suffix] {
+        assert_eq!(resolve_output_path(&path, strategy, None).unwrap(), path);
+    }
+
+    fs::write(&path, b"old").unwrap();
+    assert_eq!(resolve_output_path(&path, CollisionStrategy::Overwrite, None).unwrap(), path);
+    assert!(matches!(
+        resolve_output_path(&path, CollisionStrategy::Refuse, None),
+        Err(CrateSpecError::ValidationError(_))
+    ));
+    let first = dir.join("demo-0.1.0(1).scrate");
+    assert_eq!(resolve_output_path(&path, CollisionStrategy::Suffix, None).unwrap(), first);
+
+    // 已有 (1) 的旁路签名文件时跳到 (2)
+    fs::write(dir.join("demo-0.1.0(1).scrate.sig"), b"old").unwrap();
+    assert_eq!(
+        resolve_output_path(&path, CollisionStrategy::Suffix, Some("sig")).unwrap(),
+        dir.join("demo-0.1.0(2).scrate")
+    );
+    assert_eq!(resolve_output_path(&path, CollisionStrategy::Suffix, None).unwrap(), first);
+
+    assert_eq!("SUFFIX".parse::<CollisionStrategy>().unwrap(), CollisionStrategy::Suffix);
+    assert!("rename".parse::<CollisionStrategy>().is_err());
+}
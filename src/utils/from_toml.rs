@@ -1,14 +1,28 @@
-use crate::utils::context::{DepInfo, PackageContext, SrcTypePath};
+use crate::utils::context::{DepInfo, DepKind, PackageContext, SrcTypePath};
+use crate::utils::policy::{DepSourceAction, DepSourcePolicy};
 use crate::error::{Result, CrateSpecError};
+use crate::locale::tr;
+use crate::tr_format;
 use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
-use toml::Table;
+use toml::{Table, Value};
+
+/// 宽松模式下缺少 `name` 时使用的占位包名
+pub const PLACEHOLDER_NAME: &str = "unknown";
+/// 宽松模式下缺少 `version` 时使用的占位版本，与 cargo 对未写版本的清单的默认值一致
+pub const PLACEHOLDER_VERSION: &str = "0.0.0";
 
 #[derive(Default)]
 pub struct CrateToml {
     t: Table,
+    lenient: bool,
+    dep_source_policy: DepSourcePolicy,
+    warnings: Vec<String>,
+    manifest_dir: Option<PathBuf>,
+    /// 工作区根清单的 `[workspace.dependencies]`，首次遇到 `workspace = true` 时读取
+    workspace_deps: Option<Table>,
 }
 
 impl CrateToml {
@@ -16,77 +30,213 @@ impl CrateToml {
         let path_buf = Path::new(path.as_str());
         let f = fs::read(path_buf)
             .map_err(|_e| CrateSpecError::FileNotFound(path_buf.to_path_buf()))?;
-        CrateToml::from_vec(f)
+        let mut toml = CrateToml::from_vec(f)?;
+        toml.manifest_dir = path_buf.parent().map(Path::to_path_buf);
+        Ok(toml)
     }
 
     pub fn from_vec(st_vec: Vec<u8>) -> Result<Self> {
         let st = String::from_utf8(st_vec)
-            .map_err(|e| CrateSpecError::ParseError(format!("UTF-8 解码失败: {}", e)))?;
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("UTF-8 解码失败: {}", "invalid UTF-8: {}", e)))?;
         CrateToml::from_string(&st)
     }
 
+    /// 解析清单内容，开头的 UTF-8 BOM 会被忽略
     pub fn from_string(st: &str) -> Result<Self> {
+        let st = st.strip_prefix('\u{feff}').unwrap_or(st);
         Ok(CrateToml {
             t: Table::from_str(st)
-                .map_err(|e| CrateSpecError::ParseError(format!("TOML 解析失败: {}", e)))?,
+                .map_err(|e| CrateSpecError::ParseError(tr_format!("TOML 解析失败: {}", "TOML parsing failed: {}", e)))?,
+            ..Default::default()
         })
     }
+
+    /// `[package]` 中的包名和版本，缺失或不是字符串时返回 None
+    pub fn name_and_version(&self) -> Option<(&str, &str)> {
+        let package = self.t.get("package")?.as_table()?;
+        Some((package.get("name")?.as_str()?, package.get("version")?.as_str()?))
+    }
+
+    /// 宽松模式：缺少 `name`/`version` 时使用占位值并记录警告，而不是报错
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// 写入依赖表时检查依赖来源的策略
+    pub fn set_dep_source_policy(&mut self, policy: DepSourcePolicy) {
+        self.dep_source_policy = policy;
+    }
+
+    /// `license-file` 等相对路径所基于的清单目录；`from_file` 自动设为清单所在目录，
+    /// 未设置时相对当前工作目录
+    pub fn set_manifest_dir(&mut self, dir: PathBuf) {
+        self.manifest_dir = Some(dir);
+    }
+
+    /// 读取清单时产生的警告（宽松模式的占位值、被依赖源策略去掉的依赖）
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// 读取必填的字符串字段，宽松模式下缺失时返回占位值
+    fn required_str(&mut self, package: &Table, key: &str, placeholder: &str) -> Result<String> {
+        match package.get(key) {
+            Some(val) => Ok(val.as_str()
+                .ok_or_else(|| CrateSpecError::ParseError(tr_format!("'{}' 字段格式错误", "malformed '{}' field", key)))?
+                .to_string()),
+            None if self.lenient => {
+                self.warnings.push(tr_format!("缺少 '{}' 字段，使用占位值 {}", "missing '{}' field, using placeholder {}", key, placeholder));
+                Ok(placeholder.to_string())
+            }
+            None => Err(CrateSpecError::ParseError(tr_format!("缺少 '{}' 字段", "missing '{}' field", key))),
+        }
+    }
+}
+
+/// 相对路径按字面逐级解析后是否始终停留在起始目录内；绝对路径和经 `..` 跳出的路径返回 false
+fn stays_within(path: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
 }
 
 impl CrateToml {
+    /// 按 cargo package 生成内层 Cargo.toml 的方式规范化依赖声明：
+    /// `workspace = true` 换成工作区中的声明并合并成员自己的键，同时写了 `version` 的依赖去掉 `path`。
+    ///
+    /// 打包时读取原始清单，交叉校验时读取 cargo 规范化后的清单，两边由此得到相同的依赖表。
+    fn normalize_dep(&mut self, name: &str, val: &Value) -> Result<Value> {
+        let Some(attri_map) = val.as_table() else {
+            return Ok(val.clone());
+        };
+        let mut normalized = match attri_map.get("workspace").and_then(Value::as_bool) {
+            Some(true) => match self.workspace_dependencies()?.get(name) {
+                Some(Value::String(version)) => Table::from_iter([("version".to_string(), Value::String(version.clone()))]),
+                Some(Value::Table(inherited)) => inherited.clone(),
+                // 工作区中找不到该依赖：保留原样，含不支持的 `workspace` 键而不写入依赖表
+                _ => return Ok(val.clone()),
+            },
+            _ => Table::new(),
+        };
+        for (key, value) in attri_map.iter().filter(|(key, _)| key.as_str() != "workspace") {
+            match (normalized.get_mut(key), value) {
+                (Some(Value::Array(features)), Value::Array(extra)) if key == "features" => features.extend(extra.iter().cloned()),
+                _ => {
+                    normalized.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        if normalized.contains_key("version") {
+            normalized.remove("path");
+        }
+        Ok(Value::Table(normalized))
+    }
+
+    /// 从清单目录向上找到第一个含 `[workspace]` 的 Cargo.toml，返回其中的 `[workspace.dependencies]`
+    fn workspace_dependencies(&mut self) -> Result<&Table> {
+        if self.workspace_deps.is_none() {
+            let mut deps = Table::new();
+            let dirs = self.manifest_dir.iter().flat_map(|dir| dir.ancestors());
+            for dir in dirs {
+                let Ok(text) = fs::read_to_string(dir.join("Cargo.toml")) else {
+                    continue;
+                };
+                let root = CrateToml::from_string(&text)?;
+                if let Some(workspace) = root.t.get("workspace").and_then(Value::as_table) {
+                    if let Some(table) = workspace.get("dependencies") {
+                        deps = table
+                            .as_table()
+                            .ok_or_else(|| CrateSpecError::ParseError(tr("[workspace.dependencies] 段格式错误", "malformed [workspace.dependencies] table").to_string()))?
+                            .clone();
+                    }
+                    break;
+                }
+            }
+            self.workspace_deps = Some(deps);
+        }
+        Ok(self.workspace_deps.get_or_insert_with(Table::new))
+    }
+
     fn write_package_info_to_package_context(
-        &self,
+        &mut self,
         package_context: &mut PackageContext,
         package: &Table,
     ) -> Result<()> {
-        let name = package["name"].as_str()
-            .ok_or_else(|| CrateSpecError::ParseError("缺少 'name' 字段".to_string()))?
-            .to_string();
-        let version = package["version"].as_str()
-            .ok_or_else(|| CrateSpecError::ParseError("缺少 'version' 字段".to_string()))?
-            .to_string();
+        let name = self.required_str(package, "name", PLACEHOLDER_NAME)?;
+        let version = self.required_str(package, "version", PLACEHOLDER_VERSION)?;
         let mut license = "".to_string();
         let mut authors = Vec::<String>::new();
         if package.contains_key("license") {
             license = package["license"].as_str()
-                .ok_or_else(|| CrateSpecError::ParseError("'license' 字段格式错误".to_string()))?
+                .ok_or_else(|| CrateSpecError::ParseError(tr("'license' 字段格式错误", "malformed 'license' field").to_string()))?
                 .to_string();
         }
         if package.contains_key("authors") {
             authors = package["authors"]
                 .as_array()
-                .ok_or_else(|| CrateSpecError::ParseError("'authors' 字段格式错误".to_string()))?
+                .ok_or_else(|| CrateSpecError::ParseError(tr("'authors' 字段格式错误", "malformed 'authors' field").to_string()))?
                 .iter()
                 .map(|x| x.as_str()
-                    .ok_or_else(|| CrateSpecError::ParseError("'authors' 数组元素格式错误".to_string()))
+                    .ok_or_else(|| CrateSpecError::ParseError(tr("'authors' 数组元素格式错误", "malformed 'authors' array element").to_string()))
                     .map(|s| s.to_string()))
                 .collect::<Result<Vec<String>>>()?;
         }
+        if package.contains_key("license-file") {
+            let file = package["license-file"].as_str()
+                .ok_or_else(|| CrateSpecError::ParseError(tr("'license-file' 字段格式错误", "malformed 'license-file' field").to_string()))?
+                .to_string();
+            if !stays_within(Path::new(&file)) {
+                return Err(CrateSpecError::ParseError(tr_format!(
+                    "'license-file' 路径 {} 不在清单目录内",
+                    "'license-file' path {} is outside the manifest directory",
+                    file
+                )));
+            }
+            let path = match &self.manifest_dir {
+                Some(dir) => dir.join(&file),
+                None => PathBuf::from(&file),
+            };
+            let text = fs::read_to_string(&path)
+                .map_err(|_e| CrateSpecError::FileNotFound(path.clone()))?;
+            package_context.set_license_file(file, text);
+        }
         package_context.set_package_info(name, version, license, authors);
         Ok(())
     }
 
     fn write_dep_info_to_package_context(
-        &self,
+        &mut self,
         package_context: &mut PackageContext,
         deps: &Table,
         platform: String,
+        kind: DepKind,
     ) -> Result<Vec<String>> {
         let mut irresolve_depinfos = vec![];
         for dep in deps.iter() {
             let mut dep_info = DepInfo {
                 src_platform: platform.to_string(),
                 name: dep.0.to_string(),
+                kind,
                 ..Default::default()
             };
-            let val = dep.1;
+            let val = &self.normalize_dep(dep.0, dep.1)?;
             if val.is_str() {
                 dep_info.ver_req = val.as_str()
-                    .ok_or_else(|| CrateSpecError::ParseError("依赖版本格式错误".to_string()))?
+                    .ok_or_else(|| CrateSpecError::ParseError(tr("依赖版本格式错误", "malformed dependency version").to_string()))?
                     .to_string();
             } else {
                 let attri_map = val.as_table()
-                    .ok_or_else(|| CrateSpecError::ParseError("依赖配置格式错误".to_string()))?;
+                    .ok_or_else(|| CrateSpecError::ParseError(tr("依赖配置格式错误", "malformed dependency entry").to_string()))?;
                 let allow_keys = HashSet::from([
                     "version".to_string(),
                     "git".to_string(),
@@ -99,26 +249,43 @@ impl CrateToml {
                 }
                 if attri_map.contains_key("version") {
                     dep_info.ver_req = attri_map["version"].as_str()
-                        .ok_or_else(|| CrateSpecError::ParseError("'version' 字段格式错误".to_string()))?
+                        .ok_or_else(|| CrateSpecError::ParseError(tr("'version' 字段格式错误", "malformed 'version' field").to_string()))?
                         .to_string();
                 }
                 if attri_map.contains_key("git") {
                     dep_info.src = SrcTypePath::Git(attri_map["git"].as_str()
-                        .ok_or_else(|| CrateSpecError::ParseError("'git' 字段格式错误".to_string()))?
+                        .ok_or_else(|| CrateSpecError::ParseError(tr("'git' 字段格式错误", "malformed 'git' field").to_string()))?
                         .to_string());
                 }
                 if attri_map.contains_key("registry") {
                     dep_info.src = SrcTypePath::Registry(attri_map["registry"].as_str()
-                        .ok_or_else(|| CrateSpecError::ParseError("'registry' 字段格式错误".to_string()))?
+                        .ok_or_else(|| CrateSpecError::ParseError(tr("'registry' 字段格式错误", "malformed 'registry' field").to_string()))?
                         .to_string());
                 }
             }
             if dep_info.dump {
-                package_context.add_dep_info(
+                if let Err(reason) = self.dep_source_policy.check(&dep_info.src) {
+                    let message = tr_format!(
+                        "依赖 {} 的来源 {} 不符合依赖源策略: {}",
+                        "source {1} of dependency {0} violates the dependency source policy: {2}",
+                        dep_info.name,
+                        dep_info.src.to_canonical_string(),
+                        reason
+                    );
+                    match self.dep_source_policy.on_violation {
+                        DepSourceAction::Error => return Err(CrateSpecError::ValidationError(message)),
+                        DepSourceAction::Drop => {
+                            self.warnings.push(tr_format!("{}，未写入依赖表", "{}; left out of the dependency table", message));
+                            continue;
+                        }
+                    }
+                }
+                package_context.add_dep_info_with_kind(
                     dep_info.name,
                     dep_info.ver_req,
                     dep_info.src,
                     dep_info.src_platform,
+                    dep_info.kind,
                 );
             } else {
                 irresolve_depinfos.push(dep_info.name);
@@ -129,35 +296,65 @@ impl CrateToml {
 
     // write package info and dependency info to package context at current
     pub fn write_info_to_package_context(
-        &self,
+        &mut self,
         package_context: &mut PackageContext,
     ) -> Result<Vec<String>> {
         if !self.t.contains_key("package") {
-            return Err(CrateSpecError::ParseError("缺少 [package] 段".to_string()));
+            return Err(CrateSpecError::ParseError(tr("缺少 [package] 段", "missing [package] table").to_string()));
         }
-        self.write_package_info_to_package_context(
-            package_context,
-            self.t.get("package")
-                .ok_or_else(|| CrateSpecError::ParseError("缺少 [package] 段".to_string()))?
+        let package = self.t.get("package")
+            .ok_or_else(|| CrateSpecError::ParseError(tr("缺少 [package] 段", "missing [package] table").to_string()))?
+            .as_table()
+            .ok_or_else(|| CrateSpecError::ParseError(tr("[package] 段格式错误", "malformed [package] table").to_string()))?
+            .clone();
+        self.write_package_info_to_package_context(package_context, &package)?;
+        if !self.t.contains_key("dependencies") {
+            return Err(CrateSpecError::ParseError(tr("缺少 [dependencies] 段", "missing [dependencies] table").to_string()));
+        }
+        let root = self.t.clone();
+        let mut excluded_crate = self.write_dep_tables_to_package_context(package_context, &root, "", "")?;
+        // [target.'<triple 或 cfg 表达式>'.dependencies] 等平台相关的依赖，平台记录在 src_platform 中
+        if let Some(targets) = root.get("target") {
+            let targets = targets
                 .as_table()
-                .ok_or_else(|| CrateSpecError::ParseError("[package] 段格式错误".to_string()))?,
-        )?;
-        //FIXME current platform is not considered, we only consider [dependencies], see https://course.rs/cargo/reference/specify-deps.html#build-dependencies
-        let excluded_crate = self.write_dep_info_to_package_context(
-            package_context,
-            self.t.get("dependencies")
-                .ok_or_else(|| CrateSpecError::ParseError("缺少 [dependencies] 段".to_string()))?
+                .ok_or_else(|| CrateSpecError::ParseError(tr("[target] 段格式错误", "malformed [target] table").to_string()))?;
+            for (platform, target) in targets.iter() {
+                let prefix = format!("target.'{}'.", platform);
+                let target = target
+                    .as_table()
+                    .ok_or_else(|| CrateSpecError::ParseError(tr_format!("[target.'{}'] 段格式错误", "malformed [target.'{}'] table", platform)))?;
+                excluded_crate.extend(self.write_dep_tables_to_package_context(package_context, target, platform, &prefix)?);
+            }
+        }
+        Ok(excluded_crate)
+    }
+
+    /// 读取 `parent` 下的 `[dependencies]`、`[dev-dependencies]` 和 `[build-dependencies]`（均可省略），
+    /// `prefix` 为报错时段名的前缀
+    fn write_dep_tables_to_package_context(
+        &mut self,
+        package_context: &mut PackageContext,
+        parent: &Table,
+        platform: &str,
+        prefix: &str,
+    ) -> Result<Vec<String>> {
+        let mut excluded_crate = vec![];
+        for kind in [DepKind::Normal, DepKind::Dev, DepKind::Build] {
+            let Some(deps) = parent.get(kind.table_name()) else {
+                continue;
+            };
+            let deps = deps
                 .as_table()
-                .ok_or_else(|| CrateSpecError::ParseError("[dependencies] 段格式错误".to_string()))?,
-            "".to_string(),
-        )?;
+                .ok_or_else(|| CrateSpecError::ParseError(tr_format!("[{}{}] 段格式错误", "malformed [{}{}] table", prefix, kind.table_name())))?;
+            excluded_crate.extend(self.write_dep_info_to_package_context(package_context, deps, platform.to_string(), kind)?);
+        }
         Ok(excluded_crate)
     }
 }
 
 #[test]
 fn test_toml() {
-    let toml = CrateToml::from_file("test/test.toml".to_string());
+    let mut toml = CrateToml::from_file("test/test.toml".to_string()).unwrap();
     let mut pack_context = PackageContext::new();
     println!(
         "{:?}",
@@ -165,3 +362,267 @@ fn test_toml() {
     );
     println!("{:#?}", pack_context);
 }
+
+#[test]
+fn test_lenient_toml_missing_version() {
+    let manifest = "[package]\nname = \"partial\"\n\n[dependencies]\nserde = \"1\"\n";
+
+    let mut strict = CrateToml::from_string(manifest).unwrap();
+    let err = strict.write_info_to_package_context(&mut PackageContext::new()).unwrap_err();
+    assert!(err.to_string().contains("version"));
+
+    let mut lenient = CrateToml::from_string(manifest).unwrap();
+    lenient.set_lenient(true);
+    let mut pack_context = PackageContext::new();
+    lenient.write_info_to_package_context(&mut pack_context).unwrap();
+    assert_eq!(pack_context.pack_info.name, "partial");
+    assert_eq!(pack_context.pack_info.version, PLACEHOLDER_VERSION);
+    assert_eq!(pack_context.dep_infos.len(), 1);
+    assert_eq!(lenient.warnings().len(), 1);
+    assert!(lenient.warnings()[0].contains("version"));
+
+    // 打包后可以正常解码
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    let mut decoded = PackageContext::new();
+    decoded.decode_from_crate_package(&bin).unwrap();
+    assert_eq!(decoded.pack_info.version, PLACEHOLDER_VERSION);
+}
+
+#[test]
+fn test_dep_source_policy_at_pack_time() {
+    let manifest = "[package]\nname = \"policy\"\nversion = \"0.1.0\"\n\n[dependencies]\n\
+        serde = \"1\"\n\
+        tool = { git = \"https://git.blocked.example/tool.git\" }\n";
+
+    // 禁止的 git 主机：默认报错并指出依赖名
+    let mut toml = CrateToml::from_string(manifest).unwrap();
+    toml.set_dep_source_policy(DepSourcePolicy {
+        denied_dep_hosts: vec!["blocked.example".to_string()],
+        ..Default::default()
+    });
+    let err = toml.write_info_to_package_context(&mut PackageContext::new()).unwrap_err();
+    assert!(matches!(&err, CrateSpecError::ValidationError(msg) if msg.contains("tool")));
+
+    // drop 模式下去掉该依赖并记录警告
+    let mut toml = CrateToml::from_string(manifest).unwrap();
+    toml.set_dep_source_policy(DepSourcePolicy {
+        denied_dep_hosts: vec!["blocked.example".to_string()],
+        on_violation: DepSourceAction::Drop,
+        ..Default::default()
+    });
+    let mut pack_context = PackageContext::new();
+    toml.write_info_to_package_context(&mut pack_context).unwrap();
+    assert_eq!(pack_context.dep_infos.len(), 1);
+    assert_eq!(pack_context.dep_infos[0].name, "serde");
+    assert!(toml.warnings()[0].contains("tool"));
+
+    // 只允许 crates.io
+    let crates_io_only = DepSourcePolicy {
+        allowed_dep_sources: vec!["crates-io".parse().unwrap()],
+        ..Default::default()
+    };
+    let mut toml = CrateToml::from_string("[package]\nname = \"ok\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n").unwrap();
+    toml.set_dep_source_policy(crates_io_only.clone());
+    let mut pack_context = PackageContext::new();
+    toml.write_info_to_package_context(&mut pack_context).unwrap();
+    assert_eq!(pack_context.dep_infos.len(), 1);
+
+    let mut toml = CrateToml::from_string(manifest).unwrap();
+    toml.set_dep_source_policy(crates_io_only);
+    assert!(toml.write_info_to_package_context(&mut PackageContext::new()).is_err());
+}
+
+#[test]
+fn test_manifest_with_bom() {
+    let manifest = "\u{feff}[package]\nname = \"bom\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n";
+    for mut toml in [
+        CrateToml::from_string(manifest).unwrap(),
+        CrateToml::from_vec(manifest.as_bytes().to_vec()).unwrap(),
+    ] {
+        let mut pack_context = PackageContext::new();
+        toml.write_info_to_package_context(&mut pack_context).unwrap();
+        assert_eq!(pack_context.pack_info.name, "bom");
+        assert_eq!(pack_context.dep_infos.len(), 1);
+    }
+}
+
+#[test]
+fn test_license_file() {
+    use crate::utils::context::{BUILD_META_LICENSE_FILE, BUILD_META_LICENSE_TEXT, DEFAULT_MAX_STRING_LEN};
+    use crate::utils::testing::TestDir;
+
+    let dir = TestDir::new("license-file");
+    let license = "Custom License\n\nPermission is granted.\n";
+    dir.write("pkg/LICENSE.txt", license);
+    let manifest = dir.write(
+        "pkg/Cargo.toml",
+        "[package]\nname = \"licensed\"\nversion = \"0.1.0\"\nlicense-file = \"LICENSE.txt\"\n\n[dependencies]\n",
+    );
+
+    // 路径相对清单所在目录，而非当前工作目录
+    let mut toml = CrateToml::from_file(manifest.to_str().unwrap().to_string()).unwrap();
+    let mut pack_context = PackageContext::new();
+    toml.write_info_to_package_context(&mut pack_context).unwrap();
+    assert_eq!(pack_context.pack_info.license, "");
+    assert_eq!(pack_context.build_metadata[BUILD_META_LICENSE_FILE], "LICENSE.txt");
+    assert_eq!(pack_context.license_text(), Some(license));
+
+    // 解码后仍可取得许可证文本
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    let mut decoded = PackageContext::new();
+    decoded.decode_from_crate_package(&bin).unwrap();
+    assert_eq!(decoded.license_text(), Some(license));
+    assert!(decoded.metadata_text().contains("    Permission is granted."));
+
+    // 引用的文件不存在时报错
+    let mut missing = CrateToml::from_string(
+        "[package]\nname = \"licensed\"\nversion = \"0.1.0\"\nlicense-file = \"NOPE\"\n\n[dependencies]\n",
+    ).unwrap();
+    missing.set_manifest_dir(dir.join("pkg"));
+    let err = missing.write_info_to_package_context(&mut PackageContext::new()).unwrap_err();
+    assert!(matches!(err, CrateSpecError::FileNotFound(_)));
+
+    // 不能借 license-file 读取清单目录之外的文件
+    dir.write("secret.txt", "outside");
+    let outside = dir.join("secret.txt");
+    for file in ["../secret.txt", "sub/../../secret.txt", outside.to_str().unwrap()] {
+        let mut escaping = CrateToml::from_string(&format!(
+            "[package]\nname = \"licensed\"\nversion = \"0.1.0\"\nlicense-file = {:?}\n\n[dependencies]\n",
+            file
+        )).unwrap();
+        escaping.set_manifest_dir(dir.join("pkg"));
+        let err = escaping.write_info_to_package_context(&mut PackageContext::new()).unwrap_err();
+        assert!(matches!(&err, CrateSpecError::ParseError(msg) if msg.contains("license-file")), "{}: {}", file, err);
+    }
+    dir.write("pkg/sub/README", "");
+    let mut nested = CrateToml::from_string(
+        "[package]\nname = \"licensed\"\nversion = \"0.1.0\"\nlicense-file = \"sub/../LICENSE.txt\"\n\n[dependencies]\n",
+    ).unwrap();
+    nested.set_manifest_dir(dir.join("pkg"));
+    nested.write_info_to_package_context(&mut PackageContext::new()).unwrap();
+
+    // 许可证文本超出字符串表的长度上限时，编码失败而不是生成无法解码的包
+    let mut oversized = PackageContext::new();
+    oversized.set_package_info("licensed".to_string(), "0.1.0".to_string(), String::new(), vec![]);
+    oversized.set_license_file("LICENSE.txt".to_string(), "x".repeat(DEFAULT_MAX_STRING_LEN + 1));
+    oversized.add_crate_bin(vec![0u8; 8]);
+    let err = oversized.encode_to_crate_package().err().unwrap();
+    assert!(matches!(&err, CrateSpecError::ValidationError(msg) if msg.contains(BUILD_META_LICENSE_TEXT)), "{}", err);
+}
+
+#[test]
+fn test_path_and_workspace_dependencies() {
+    use crate::utils::testing::TestDir;
+
+    let dir = TestDir::new("workspace-deps");
+    dir.write(
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"a\"]\n\n[workspace.dependencies]\nserde = \"1.0\"\nbdep = { path = \"b\", version = \"0.2\" }\n",
+    );
+    let manifest = dir.write(
+        "a/Cargo.toml",
+        r#"
+[package]
+name = "a"
+version = "0.1.0"
+
+[dependencies]
+serde = { workspace = true }
+bdep = { workspace = true }
+cdep = { path = "../c", version = "0.3.0" }
+missing = { workspace = true }
+local = { path = "../local" }
+"#,
+    );
+    let mut toml = CrateToml::from_file(manifest.to_str().unwrap().to_string()).unwrap();
+    let mut pack_context = PackageContext::new();
+    let excluded = toml.write_info_to_package_context(&mut pack_context).unwrap();
+    assert_eq!(excluded, ["local", "missing"]);
+    let deps: Vec<_> = pack_context.dep_entries().collect();
+    assert_eq!(
+        deps,
+        [
+            ("bdep", "0.2", "crates.io".to_string()),
+            ("cdep", "0.3.0", "crates.io".to_string()),
+            ("serde", "1.0", "crates.io".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_dev_and_build_dependencies() {
+    let manifest = r#"
+[package]
+name = "kinds"
+version = "0.1.0"
+
+[dependencies]
+serde = "1"
+
+[dev-dependencies]
+serde = "1"
+proptest = { version = "1", registry = "internal" }
+local = { path = "../local" }
+
+[build-dependencies]
+cc = "1.0"
+"#;
+    let mut toml = CrateToml::from_string(manifest).unwrap();
+    let mut pack_context = PackageContext::new();
+    let excluded = toml.write_info_to_package_context(&mut pack_context).unwrap();
+    assert_eq!(excluded, vec!["local".to_string()]);
+    let kinds: Vec<(&str, DepKind)> = pack_context.dep_infos.iter().map(|d| (d.name.as_str(), d.kind)).collect();
+    assert_eq!(
+        kinds,
+        [("serde", DepKind::Normal), ("proptest", DepKind::Dev), ("serde", DepKind::Dev), ("cc", DepKind::Build)]
+    );
+    assert_eq!(pack_context.dep_infos[1].src, SrcTypePath::Registry("internal".to_string()));
+    assert!(pack_context.dep_infos.iter().all(|d| d.src_platform.is_empty()));
+
+    // 依赖类别经依赖表往返后保持不变
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    let decoded = PackageContext::try_from_bytes_unverified(&bin).unwrap();
+    assert_eq!(decoded.dep_infos, pack_context.dep_infos);
+
+    // 开发、构建依赖段可以省略，格式错误时报错
+    let invalid = "[package]\nname = \"a\"\nversion = \"0.1.0\"\n[dependencies]\n[dev-dependencies]\n[build-dependencies]\n";
+    assert!(CrateToml::from_string(invalid).unwrap().write_info_to_package_context(&mut PackageContext::new()).is_ok());
+    let invalid = "build-dependencies = 1\n[package]\nname = \"a\"\nversion = \"0.1.0\"\n[dependencies]\n";
+    assert!(CrateToml::from_string(invalid).unwrap().write_info_to_package_context(&mut PackageContext::new()).is_err());
+}
+
+#[test]
+fn test_target_dependencies() {
+    let mut toml = CrateToml::from_file("test/target.toml".to_string()).unwrap();
+    let mut pack_context = PackageContext::new();
+    assert!(toml.write_info_to_package_context(&mut pack_context).unwrap().is_empty());
+    let deps: Vec<(&str, &str, DepKind)> = pack_context
+        .dep_infos
+        .iter()
+        .map(|d| (d.name.as_str(), d.src_platform.as_str(), d.kind))
+        .collect();
+    assert_eq!(
+        deps,
+        [
+            ("log", "", DepKind::Normal),
+            ("libc", "cfg(unix)", DepKind::Normal),
+            ("winapi", "cfg(windows)", DepKind::Normal),
+            ("cc", "x86_64-pc-windows-msvc", DepKind::Build),
+        ]
+    );
+    let winapi = pack_context.dep_infos.iter().find(|d| d.name == "winapi").unwrap();
+    assert_eq!(winapi.src_platform, "cfg(windows)");
+    assert_eq!(winapi.src, SrcTypePath::Registry("internal".to_string()));
+
+    // 平台经依赖表往返后保持不变
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    assert_eq!(PackageContext::try_from_bytes_unverified(&bin).unwrap().dep_infos, pack_context.dep_infos);
+
+    let invalid = "[package]\nname = \"a\"\nversion = \"0.1.0\"\n[dependencies]\n[target]\nunix = 1\n";
+    let err = CrateToml::from_string(invalid).unwrap().write_info_to_package_context(&mut PackageContext::new()).unwrap_err();
+    assert!(err.to_string().contains("[target.'unix']"), "{}", err);
+}
@@ -0,0 +1,350 @@
+//! 增量解码：按块接收 `.scrate` 数据，边接收边计算指纹、FILE 签名区域和 crate 二进制段的摘要，
+//! 数据结束时（[`IncrementalDecoder::finish`]）只需比对摘要并读取各数据段。
+//! 签名区域和 crate 二进制段边接收边计算的是 SHA256 摘要，使用其他摘要算法的签名在 `finish` 时补算。
+//!
+//! 头部和段索引一旦到齐就会解析，魔数错误、段布局不合法或数据超出文件长度会在 `update` 时立即报错。
+//! 各数据段仍在 `finish` 时从缓存的完整数据中读取。
+use crate::error::{CrateSpecError, Result};
+use crate::utils::compression::CompressionKind;
+use crate::utils::context::{PackageContext, DATASECTIONTYPE};
+use crate::utils::decode::SigDigests;
+use crate::utils::package::gen_bincode::{create_bincode_slice_decoder, encode_size_by_bincode, BINCODE_CONFIG};
+use crate::utils::package::{CrateHeader, SectionIndex, MAGIC_NUMBER, MAGIC_NUMBER_LEN};
+use crate::utils::pkcs::{DigestAlgo, PKCS};
+use crate::locale::tr;
+use crate::tr_format;
+use openssl::hash::Hasher;
+use openssl::sha::Sha256;
+use std::ops::Range;
+
+/// 由头部和段索引得到的文件布局
+#[derive(Debug)]
+struct Layout {
+    /// 整个文件的长度（含指纹）
+    total_len: usize,
+    /// 头部记录的指纹摘要算法，决定末尾指纹的长度
+    fingerprint_digest: DigestAlgo,
+    /// crate 二进制段在文件中的范围
+    crate_bin: Range<usize>,
+    /// FILE 签名覆盖区域的结束位置，没有非签名段时为 None
+    signable_end: Option<usize>,
+    /// 计算 FILE 签名摘要时置零的段索引部分
+    zeroed: Range<usize>,
+}
+
+/// 按块接收 `.scrate` 数据的解码器
+///
+/// ```
+/// use crate_spec::utils::context::{PackageContext, SIGTYPE};
+/// use crate_spec::utils::incremental::IncrementalDecoder;
+/// use crate_spec::utils::pkcs::PKCS;
+///
+/// let mut pkcs = PKCS::new();
+/// pkcs.load_from_file_writer(
+///     "test/cert.pem".to_string(),
+///     "test/key.pem".to_string(),
+///     vec!["test/root-ca.pem".to_string()],
+/// ).unwrap();
+/// let mut ctx = PackageContext::new();
+/// ctx.set_package_info("demo".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+/// ctx.add_crate_bin(vec![0u8; 8]);
+/// ctx.add_sig(pkcs, SIGTYPE::CRATEBIN);
+/// let (_, _, bytes) = ctx.encode_to_crate_package().unwrap();
+///
+/// let mut verifier = PackageContext::new();
+/// verifier.set_root_cas_bin(PKCS::root_ca_bins(vec!["test/root-ca.pem".to_string()]).unwrap());
+/// let mut decoder = IncrementalDecoder::new(verifier);
+/// for chunk in bytes.chunks(16) {
+///     decoder.update(chunk).unwrap();
+/// }
+/// let decoded = decoder.finish().unwrap();
+/// assert_eq!(decoded.pack_info.name, "demo");
+/// ```
+pub struct IncrementalDecoder {
+    context: PackageContext,
+    buf: Vec<u8>,
+    layout: Option<Layout>,
+    /// 头部到齐、得知指纹摘要算法后才创建
+    fingerprint_hasher: Option<Hasher>,
+    fingerprint_pos: usize,
+    crate_hasher: Sha256,
+    crate_pos: usize,
+    file_hasher: Sha256,
+    file_pos: usize,
+}
+
+impl IncrementalDecoder {
+    /// `context` 提供验签所需的根 CA、证书链深度和网络客户端等设置，解码结果写入其中
+    pub fn new(context: PackageContext) -> Self {
+        Self {
+            context,
+            buf: vec![],
+            layout: None,
+            fingerprint_hasher: None,
+            fingerprint_pos: 0,
+            crate_hasher: Sha256::new(),
+            crate_pos: 0,
+            file_hasher: Sha256::new(),
+            file_pos: 0,
+        }
+    }
+
+    /// 已接收的字节数
+    pub fn received(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// 段索引解析后得到的文件总长度
+    pub fn expected_len(&self) -> Option<usize> {
+        self.layout.as_ref().map(|layout| layout.total_len)
+    }
+
+    /// 接收下一块数据
+    pub fn update(&mut self, chunk: &[u8]) -> Result<()> {
+        if let Some(layout) = &self.layout {
+            if self.buf.len() + chunk.len() > layout.total_len {
+                return Err(CrateSpecError::DecodeError(tr_format!(
+                    "数据长度超过文件总长度 {} 字节",
+                    "the data exceeds the total file length of {} bytes",
+                    layout.total_len
+                )));
+            }
+        }
+        self.buf.extend_from_slice(chunk);
+        if self.layout.is_none() {
+            self.layout = self.parse_layout()?;
+            if let Some(layout) = &self.layout {
+                if self.buf.len() > layout.total_len {
+                    return Err(CrateSpecError::DecodeError(tr_format!(
+                        "数据长度超过文件总长度 {} 字节",
+                        "the data exceeds the total file length of {} bytes",
+                        layout.total_len
+                    )));
+                }
+            }
+        }
+        self.advance_hashers()
+    }
+
+    /// 数据接收完毕：校验指纹和签名并读取各数据段
+    pub fn finish(mut self) -> Result<PackageContext> {
+        let layout = self
+            .layout
+            .take()
+            .ok_or_else(|| CrateSpecError::DecodeError(tr("数据不完整: 缺少头部或段索引", "incomplete data: the header or section index is missing").to_string()))?;
+        if self.buf.len() != layout.total_len {
+            return Err(CrateSpecError::DecodeError(tr_format!(
+                "数据不完整: 已接收 {} 字节，应为 {} 字节",
+                "incomplete data: received {} bytes, expected {} bytes",
+                self.buf.len(),
+                layout.total_len
+            )));
+        }
+        let fingerprint = match self.fingerprint_hasher.as_mut() {
+            Some(hasher) => hasher.finish().map_err(|e| CrateSpecError::Other(tr_format!("计算指纹失败: {}", "failed to compute fingerprint: {}", e)))?,
+            None => return Err(CrateSpecError::DecodeError(tr("数据不完整: 缺少头部或段索引", "incomplete data: the header or section index is missing").to_string())),
+        };
+        if !PKCS::digest_eq(&fingerprint, &self.buf[layout.total_len - layout.fingerprint_digest.output_len()..]) {
+            return Err(CrateSpecError::DecodeError("fingerprint not right".to_string()));
+        }
+        let mut context = self.context;
+        let (crate_package, _) = context.read_sections(&self.buf)?;
+        let mut digests = SigDigests::default();
+        if layout.signable_end.is_some() {
+            digests.file.insert(DigestAlgo::Sha256, self.file_hasher.finish().to_vec());
+        }
+        // 已裁剪时 crate 二进制段本身就是摘要，压缩时签名针对解压后的 .crate，都由 complete_sig_digests 计算
+        if !context.binary_pruned && context.compression == CompressionKind::None {
+            digests.crate_bin.insert(DigestAlgo::Sha256, self.crate_hasher.finish().to_vec());
+        }
+        context.complete_sig_digests(&mut digests, || context.binary_before_sig(&crate_package, &self.buf))?;
+        context.check_sigs_with_digests(&digests)?;
+        Ok(context)
+    }
+
+    /// 头部和段索引到齐后计算文件布局，数据不足时返回 None
+    fn parse_layout(&self) -> Result<Option<Layout>> {
+        let header_end = MAGIC_NUMBER_LEN + encode_size_by_bincode(&CrateHeader::new());
+        if self.buf.len() < MAGIC_NUMBER_LEN {
+            return Ok(None);
+        }
+        if self.buf[..MAGIC_NUMBER_LEN] != MAGIC_NUMBER {
+            return Err(CrateSpecError::DecodeError("magic not right!".to_string()));
+        }
+        if self.buf.len() < header_end {
+            return Ok(None);
+        }
+        let (header, _): (CrateHeader, usize) =
+            bincode::decode_from_slice(&self.buf[MAGIC_NUMBER_LEN..header_end], BINCODE_CONFIG)
+                .map_err(|e| CrateSpecError::DecodeError(tr_format!("无法解析文件头: {}", "cannot parse the header: {}", e)))?;
+        let si_range = header.si_offset as usize..header.si_offset as usize + header.si_size as usize;
+        if si_range.start < header_end || header.ds_offset < header.si_offset + header.si_size {
+            return Err(CrateSpecError::DecodeError("file format not right! - si".to_string()));
+        }
+        if self.buf.len() < si_range.end {
+            return Ok(None);
+        }
+        let section_index = SectionIndex::decode(
+            &mut create_bincode_slice_decoder(&self.buf[si_range.clone()]),
+            header.si_num as usize,
+            header.endianness(),
+        )
+        .map_err(|e| CrateSpecError::DecodeError(tr_format!("无法解析段索引: {}", "cannot parse the section index: {}", e)))?;
+        if section_index.size() != header.si_size as usize {
+            return Err(CrateSpecError::DecodeError("file format not right! - si".to_string()));
+        }
+
+        let ds_offset = header.ds_offset as usize;
+        let entries = &section_index.entries.arr;
+        let ds_size = entries
+            .iter()
+            .map(|entry| entry.sh_offset as usize + entry.sh_size as usize)
+            .max()
+            .unwrap_or(0);
+        let crate_bin = entries
+            .iter()
+            .find(|entry| entry.sh_type == DATASECTIONTYPE::CRATEBIN.as_u8())
+            .map(|entry| ds_offset + entry.sh_offset as usize..ds_offset + (entry.sh_offset + entry.sh_size) as usize)
+            .ok_or_else(|| CrateSpecError::DecodeError(tr("缺少 crate 二进制段", "the crate binary section is missing").to_string()))?;
+        let signable_end = (section_index.none_sig_num() > 0)
+            .then(|| ds_offset + section_index.datasection_size_without_sig());
+        let fingerprint_digest = header.fingerprint_digest()?;
+        Ok(Some(Layout {
+            total_len: ds_offset + ds_size + fingerprint_digest.output_len(),
+            fingerprint_digest,
+            crate_bin,
+            signable_end,
+            zeroed: si_range.start + section_index.none_sig_size()..si_range.end,
+        }))
+    }
+
+    /// 把新到达的数据送入各摘要；布局确定前先缓存，指纹不计入末尾的指纹本身
+    fn advance_hashers(&mut self) -> Result<()> {
+        let Some(layout) = &self.layout else {
+            return Ok(());
+        };
+        let hasher = match self.fingerprint_hasher.as_mut() {
+            Some(hasher) => hasher,
+            None => {
+                let algo = layout.fingerprint_digest;
+                let hasher = Hasher::new(algo.message_digest())
+                    .map_err(|e| CrateSpecError::Other(tr_format!("创建 {} 摘要失败: {}", "failed to create {} digest: {}", algo, e)))?;
+                self.fingerprint_hasher.insert(hasher)
+            }
+        };
+        let fingerprint_end = self.buf.len().min(layout.total_len - layout.fingerprint_digest.output_len());
+        if self.fingerprint_pos < fingerprint_end {
+            hasher
+                .update(&self.buf[self.fingerprint_pos..fingerprint_end])
+                .map_err(|e| CrateSpecError::Other(tr_format!("计算指纹失败: {}", "failed to compute fingerprint: {}", e)))?;
+            self.fingerprint_pos = fingerprint_end;
+        }
+
+        let crate_start = self.crate_pos.max(layout.crate_bin.start);
+        let crate_end = self.buf.len().min(layout.crate_bin.end);
+        if crate_start < crate_end {
+            self.crate_hasher.update(&self.buf[crate_start..crate_end]);
+            self.crate_pos = crate_end;
+        }
+
+        let Some(signable_end) = layout.signable_end else {
+            return Ok(());
+        };
+        let file_end = self.buf.len().min(signable_end);
+        while self.file_pos < file_end {
+            let pos = self.file_pos;
+            let next = if layout.zeroed.contains(&pos) {
+                self.file_hasher.update(&vec![0u8; file_end.min(layout.zeroed.end) - pos]);
+                file_end.min(layout.zeroed.end)
+            } else {
+                let next = if pos < layout.zeroed.start { file_end.min(layout.zeroed.start) } else { file_end };
+                self.file_hasher.update(&self.buf[pos..next]);
+                next
+            };
+            self.file_pos = next;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn signed_package() -> (PackageContext, Vec<u8>) {
+    use crate::utils::context::{SrcTypePath, SIGTYPE};
+    use crate::utils::testing::signer;
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("incremental".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "".to_string());
+    pack_context.add_crate_bin((0..5000u32).map(|i| (i % 251) as u8).collect());
+    pack_context.add_sig(signer(), SIGTYPE::FILE);
+    pack_context.add_sig(signer(), SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    (pack_context, bin)
+}
+
+#[cfg(test)]
+fn verifier() -> PackageContext {
+    use crate::utils::testing::root_ca_bins;
+
+    let mut context = PackageContext::new();
+    context.set_root_cas_bin(root_ca_bins());
+    context
+}
+
+#[cfg(test)]
+fn decode_in_chunks(bin: &[u8], chunk_size: usize) -> Result<PackageContext> {
+    let mut decoder = IncrementalDecoder::new(verifier());
+    for chunk in bin.chunks(chunk_size) {
+        decoder.update(chunk)?;
+    }
+    decoder.finish()
+}
+
+#[test]
+fn test_incremental_matches_one_shot() {
+    use crate::utils::testing::root_ca_bins;
+
+    let (_, bin) = signed_package();
+    let root_cas = root_ca_bins();
+    let one_shot = PackageContext::try_from_bytes(&bin, &root_cas).unwrap();
+
+    for chunk_size in [1, 7, 64, 4096, bin.len()] {
+        let decoded = decode_in_chunks(&bin, chunk_size).unwrap();
+        assert_eq!(decoded.pack_info, one_shot.pack_info);
+        assert_eq!(decoded.dep_infos, one_shot.dep_infos);
+        assert_eq!(decoded.crate_binary, one_shot.crate_binary);
+        assert_eq!(decoded.sigs.len(), 2);
+        assert!(decoded.sigs.iter().all(|sig| sig.trust_anchor.is_some()));
+    }
+}
+
+#[test]
+fn test_incremental_rejects_bad_input() {
+    let (_, bin) = signed_package();
+
+    // 魔数错误在第一块就报错
+    let mut decoder = IncrementalDecoder::new(verifier());
+    assert!(decoder.update(b"NOPE!").is_err());
+
+    // 头部解析后即可得知文件长度，多余的数据立即报错
+    let mut decoder = IncrementalDecoder::new(verifier());
+    decoder.update(&bin[..1024]).unwrap();
+    assert_eq!(decoder.expected_len(), Some(bin.len()));
+    assert!(decoder.update(&[&bin[1024..], b"x".as_slice()].concat()).is_err());
+
+    // 数据不完整
+    let mut decoder = IncrementalDecoder::new(verifier());
+    decoder.update(&bin[..bin.len() - 1]).unwrap();
+    assert!(decoder.finish().is_err());
+
+    // crate 二进制段被篡改：指纹不符
+    let mut tampered = bin.clone();
+    let middle = bin.len() / 2;
+    tampered[middle] ^= 0xff;
+    assert!(matches!(decode_in_chunks(&tampered, 64), Err(CrateSpecError::DecodeError(_))));
+
+    // 没有根 CA 时验签失败
+    let mut decoder = IncrementalDecoder::new(PackageContext::new());
+    decoder.update(&bin).unwrap();
+    assert!(decoder.finish().is_err());
+}
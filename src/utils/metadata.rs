@@ -0,0 +1,457 @@
+//! 解码输出的元数据文件格式（`--output-metadata-format`）
+//!
+//! - `txt`：原有的可读文本
+//! - `json`：`package` + `dependencies` 数组（有构建元数据时另有 `build`，有文件哈希时另有 `files`，
+//!   有签名时另有 `signatures`），字段名固定
+//! - `toml`：仿照 Cargo.toml 的 `[package]` / `[dependencies]` 结构，便于重新导入；
+//!   开发和构建依赖写在 `[dev-dependencies]` / `[build-dependencies]`，构建元数据写在 `[package.metadata.build]`
+//! - `index`：crates.io 索引条目（`name`/`vers`/`deps`/`cksum`/`features`），供 registry 镜像导入
+use crate::error::{CrateSpecError, Result};
+use crate::network::digest_to_hex_string;
+use crate::utils::context::{DepInfo, DepKind, PackageContext, PackageInfo, SigInfoReport, SrcTypePath, DEFAULT_PLATFORM};
+use crate::tr_format;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataFormat {
+    #[default]
+    Txt,
+    Json,
+    Toml,
+    Index,
+}
+
+impl MetadataFormat {
+    /// 输出文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            MetadataFormat::Txt => "txt",
+            MetadataFormat::Json => "json",
+            MetadataFormat::Toml => "toml",
+            MetadataFormat::Index => "index.json",
+        }
+    }
+}
+
+impl FromStr for MetadataFormat {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "txt" => Ok(MetadataFormat::Txt),
+            "json" => Ok(MetadataFormat::Json),
+            "toml" => Ok(MetadataFormat::Toml),
+            "index" => Ok(MetadataFormat::Index),
+            _ => Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的元数据格式: {}，必须是 'txt'、'json'、'toml' 或 'index'",
+                "invalid metadata format: {}, must be 'txt', 'json', 'toml' or 'index'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PackageMetadata<'a> {
+    name: &'a str,
+    version: &'a str,
+    license: &'a str,
+    authors: &'a [String],
+}
+
+/// TOML 输出用，字段顺序即输出顺序；空的 license/authors 不输出
+#[derive(Serialize)]
+struct CargoPackage<'a> {
+    name: &'a str,
+    version: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    license: &'a str,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    authors: &'a [String],
+    /// 构建元数据写在 `[package.metadata.build]`，cargo 会忽略该段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<CargoPackageMetadata<'a>>,
+}
+
+#[derive(Serialize)]
+struct CargoPackageMetadata<'a> {
+    build: &'a BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct DependencyMetadata<'a> {
+    name: &'a str,
+    version_req: &'a str,
+    source: String,
+    platform: &'a str,
+    kind: DepKind,
+}
+
+#[derive(Serialize)]
+struct FileMetadata<'a> {
+    path: &'a str,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct Metadata<'a> {
+    package: PackageMetadata<'a>,
+    dependencies: Vec<DependencyMetadata<'a>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    build: &'a BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<FileMetadata<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    signatures: Vec<SigInfoReport>,
+}
+
+/// `--info` 输出的包信息和依赖表，依赖来源保留类型和路径
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageInfoReport {
+    pub package: PackageInfo,
+    pub dependencies: Vec<DepInfo>,
+}
+
+#[derive(Serialize, Default)]
+struct TargetDependencies {
+    #[serde(skip_serializing_if = "Table::is_empty")]
+    dependencies: Table,
+    #[serde(rename = "dev-dependencies", skip_serializing_if = "Table::is_empty")]
+    dev_dependencies: Table,
+    #[serde(rename = "build-dependencies", skip_serializing_if = "Table::is_empty")]
+    build_dependencies: Table,
+}
+
+impl TargetDependencies {
+    fn table_mut(&mut self, kind: DepKind) -> &mut Table {
+        match kind {
+            DepKind::Normal => &mut self.dependencies,
+            DepKind::Dev => &mut self.dev_dependencies,
+            DepKind::Build => &mut self.build_dependencies,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CargoManifest<'a> {
+    package: CargoPackage<'a>,
+    dependencies: Table,
+    #[serde(rename = "dev-dependencies", skip_serializing_if = "Table::is_empty")]
+    dev_dependencies: Table,
+    #[serde(rename = "build-dependencies", skip_serializing_if = "Table::is_empty")]
+    build_dependencies: Table,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    target: BTreeMap<String, TargetDependencies>,
+}
+
+impl PackageContext {
+    /// 按指定格式输出包信息和依赖表
+    pub fn metadata_as(&self, format: MetadataFormat) -> Result<String> {
+        match format {
+            MetadataFormat::Txt => Ok(self.metadata_text()),
+            MetadataFormat::Json => serde_json::to_string_pretty(&self.metadata()?)
+                .map(|mut text| {
+                    text.push('\n');
+                    text
+                })
+                .map_err(|e| CrateSpecError::EncodeError(tr_format!("元数据 JSON 序列化失败: {}", "failed to serialize the metadata as JSON: {}", e))),
+            MetadataFormat::Toml => toml::to_string(&self.metadata_toml())
+                .map(|text| text.trim_start().to_string())
+                .map_err(|e| CrateSpecError::EncodeError(tr_format!("元数据 TOML 序列化失败: {}", "failed to serialize the metadata as TOML: {}", e))),
+            MetadataFormat::Index => Ok(format!("{}\n", self.to_index_entry())),
+        }
+    }
+
+    /// 包信息和依赖表
+    pub fn info_report(&self) -> PackageInfoReport {
+        PackageInfoReport { package: self.pack_info.clone(), dependencies: self.dep_infos.clone() }
+    }
+
+    /// [`PackageContext::info_report`] 的 JSON 文本
+    pub fn info_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.info_report())
+            .map(|mut text| {
+                text.push('\n');
+                text
+            })
+            .map_err(|e| CrateSpecError::EncodeError(tr_format!("包信息 JSON 序列化失败: {}", "failed to serialize the package info as JSON: {}", e)))
+    }
+
+    /// crates.io 索引格式的条目，`cksum` 为 crate 二进制的 SHA256
+    ///
+    /// `kind` 为依赖类别（`normal`/`dev`/`build`）；features 等未记录的信息取默认值。
+    /// 依赖来源：crates.io 依赖的 `registry` 为 null，其他 registry/url 依赖写入 `registry`，
+    /// git 依赖写入索引格式之外的 `git` 字段，p2p 依赖以规范化来源字符串写入 `registry`。
+    pub fn to_index_entry(&self) -> serde_json::Value {
+        let deps: Vec<serde_json::Value> = self
+            .dep_infos
+            .iter()
+            .map(|dep| {
+                let platform = dep.src_platform.trim();
+                let mut entry = json!({
+                    "name": dep.name,
+                    "req": dep.ver_req,
+                    "features": [],
+                    "optional": false,
+                    "default_features": true,
+                    "target": (!platform.is_empty() && platform != DEFAULT_PLATFORM).then_some(platform),
+                    "kind": dep.kind.to_string(),
+                    "registry": null,
+                });
+                match &dep.src {
+                    SrcTypePath::CratesIo => {}
+                    SrcTypePath::Registry(registry) | SrcTypePath::Url(registry) => {
+                        entry["registry"] = json!(registry);
+                    }
+                    SrcTypePath::Git(url) => {
+                        entry["git"] = json!(url);
+                    }
+                    SrcTypePath::P2p(_) => {
+                        entry["registry"] = json!(dep.src.to_canonical_string());
+                    }
+                }
+                entry
+            })
+            .collect();
+        json!({
+            "name": self.pack_info.name,
+            "vers": self.pack_info.version,
+            "deps": deps,
+            "cksum": digest_to_hex_string(&self.crate_digest()),
+            "features": {},
+            "yanked": false,
+            "links": null,
+        })
+    }
+
+    fn metadata(&self) -> Result<Metadata<'_>> {
+        Ok(Metadata {
+            package: PackageMetadata {
+                name: &self.pack_info.name,
+                version: &self.pack_info.version,
+                license: &self.pack_info.license,
+                authors: &self.pack_info.authors,
+            },
+            dependencies: self
+                .dep_infos
+                .iter()
+                .map(|dep| DependencyMetadata {
+                    name: &dep.name,
+                    version_req: &dep.ver_req,
+                    source: dep.src.to_canonical_string(),
+                    platform: &dep.src_platform,
+                    kind: dep.kind,
+                })
+                .collect(),
+            build: &self.build_metadata,
+            files: self
+                .file_hashes
+                .iter()
+                .map(|file| FileMetadata { path: &file.path, sha256: digest_to_hex_string(&file.sha256) })
+                .collect(),
+            signatures: self.sigs.iter().map(|sig| sig.report(false)).collect::<Result<_>>()?,
+        })
+    }
+
+    /// 重建 Cargo.toml 形式的 `[package]` 与 `[dependencies]`（及开发、构建依赖），
+    /// 带平台的依赖放在 `[target.'<platform>'.dependencies]` 下
+    fn metadata_toml(&self) -> CargoManifest<'_> {
+        let mut untargeted = TargetDependencies::default();
+        let mut targets: BTreeMap<String, TargetDependencies> = BTreeMap::new();
+        for dep in self.dep_infos.iter() {
+            let deps = if dep.src_platform.is_empty() {
+                &mut untargeted
+            } else {
+                targets.entry(dep.src_platform.clone()).or_default()
+            };
+            deps.table_mut(dep.kind).insert(dep.name.clone(), dep_toml_value(&dep.ver_req, &dep.src));
+        }
+
+        CargoManifest {
+            package: CargoPackage {
+                name: &self.pack_info.name,
+                version: &self.pack_info.version,
+                license: &self.pack_info.license,
+                authors: &self.pack_info.authors,
+                metadata: (!self.build_metadata.is_empty())
+                    .then_some(CargoPackageMetadata { build: &self.build_metadata }),
+            },
+            dependencies: untargeted.dependencies,
+            dev_dependencies: untargeted.dev_dependencies,
+            build_dependencies: untargeted.build_dependencies,
+            target: targets,
+        }
+    }
+}
+
+/// 单个依赖在 Cargo.toml 中的写法：crates.io 依赖写成版本字符串，其余写成表
+fn dep_toml_value(ver_req: &str, src: &SrcTypePath) -> Value {
+    if let SrcTypePath::CratesIo = src {
+        return Value::from(ver_req);
+    }
+    let mut table = Table::new();
+    if !ver_req.is_empty() {
+        table.insert("version".to_string(), Value::from(ver_req));
+    }
+    match src {
+        SrcTypePath::Git(url) => {
+            table.insert("git".to_string(), Value::from(url.as_str()));
+        }
+        SrcTypePath::Registry(name) => {
+            table.insert("registry".to_string(), Value::from(name.as_str()));
+        }
+        // Cargo.toml 中没有对应写法，保留规范化来源字符串
+        SrcTypePath::Url(_) | SrcTypePath::P2p(_) => {
+            table.insert("source".to_string(), Value::from(src.to_canonical_string()));
+        }
+        SrcTypePath::CratesIo => unreachable!(),
+    }
+    Value::Table(table)
+}
+
+#[test]
+fn test_metadata_formats() {
+    use crate::utils::from_toml::CrateToml;
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info(
+        "demo".to_string(),
+        "1.2.3".to_string(),
+        "MIT".to_string(),
+        vec!["alice".to_string()],
+    );
+    pack_context.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "".to_string());
+    pack_context.add_dep_info(
+        "tool".to_string(),
+        "0.3".to_string(),
+        SrcTypePath::Git("https://example.com/tool.git".to_string()),
+        "".to_string(),
+    );
+    pack_context.add_dep_info(
+        "winapi".to_string(),
+        "0.3".to_string(),
+        SrcTypePath::Registry("internal".to_string()),
+        "cfg(windows)".to_string(),
+    );
+    pack_context.add_dep_info_with_kind("cc".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "".to_string(), DepKind::Build);
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    let mut decoded = PackageContext::new();
+    decoded.decode_from_crate_package(&bin).unwrap();
+
+    let txt = decoded.metadata_as(MetadataFormat::Txt).unwrap();
+    assert_eq!(txt, decoded.metadata_text());
+
+    let json: serde_json::Value =
+        serde_json::from_str(&decoded.metadata_as(MetadataFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["package"]["name"], "demo");
+    assert_eq!(json["package"]["authors"][0], "alice");
+    assert_eq!(json["dependencies"].as_array().unwrap().len(), 4);
+    assert_eq!(json["dependencies"][0]["kind"], "normal");
+    assert_eq!(json["dependencies"][3]["kind"], "build");
+    assert_eq!(json["dependencies"][1]["source"], "git+https://example.com/tool.git");
+    assert_eq!(json["dependencies"][2]["platform"], "cfg(windows)");
+
+    let toml_text = decoded.metadata_as(MetadataFormat::Toml).unwrap();
+    let doc: Table = toml_text.parse().unwrap();
+    assert_eq!(doc["package"]["version"].as_str(), Some("1.2.3"));
+    assert_eq!(doc["dependencies"]["serde"].as_str(), Some("1.0"));
+    assert_eq!(doc["build-dependencies"]["cc"].as_str(), Some("1.0"));
+    assert!(doc.get("dev-dependencies").is_none());
+    assert_eq!(doc["dependencies"]["tool"]["git"].as_str(), Some("https://example.com/tool.git"));
+    assert_eq!(
+        doc["target"]["cfg(windows)"]["dependencies"]["winapi"]["registry"].as_str(),
+        Some("internal")
+    );
+    // 生成的 [package] / [dependencies] / [target] 可以被重新读回（url、p2p 来源除外）
+    let mut reparsed = PackageContext::new();
+    CrateToml::from_string(&toml_text)
+        .unwrap()
+        .write_info_to_package_context(&mut reparsed)
+        .unwrap();
+    assert_eq!(reparsed.pack_info, decoded.pack_info);
+    assert!(reparsed.metadata_eq(&decoded));
+
+    assert_eq!("JSON".parse::<MetadataFormat>().unwrap(), MetadataFormat::Json);
+    assert!("yaml".parse::<MetadataFormat>().is_err());
+}
+
+#[test]
+fn test_info_json() {
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("demo".to_string(), "1.2.3".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "".to_string());
+    pack_context.add_dep_info(
+        "tool".to_string(),
+        "0.3".to_string(),
+        SrcTypePath::Git("https://example.com/tool.git".to_string()),
+        "cfg(unix)".to_string(),
+    );
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    // 未签名的包也能读取，不需要根 CA
+    let info = PackageContext::read_info(&mut std::io::Cursor::new(&bin)).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&info.info_json().unwrap()).unwrap();
+    assert_eq!(json["package"]["name"], "demo");
+    assert_eq!(json["dependencies"][0]["src"], serde_json::json!({ "kind": "crates-io" }));
+    assert_eq!(json["dependencies"][1]["src"]["kind"], "git");
+    assert_eq!(json["dependencies"][1]["src"]["path"], "https://example.com/tool.git");
+    assert_eq!(json["dependencies"][1]["src_platform"], "cfg(unix)");
+    assert!(json["dependencies"][1].get("dump").is_none());
+    let report: PackageInfoReport = serde_json::from_value(json).unwrap();
+    assert_eq!(report, info.info_report());
+}
+
+#[test]
+fn test_to_index_entry() {
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("demo".to_string(), "1.2.3".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_dep_info("serde".to_string(), "^1.0".to_string(), SrcTypePath::CratesIo, "".to_string());
+    pack_context.add_dep_info(
+        "tool".to_string(),
+        "0.3".to_string(),
+        SrcTypePath::Git("https://example.com/tool.git".to_string()),
+        "".to_string(),
+    );
+    pack_context.add_dep_info(
+        "winapi".to_string(),
+        "0.3".to_string(),
+        SrcTypePath::Registry("https://index.example.com".to_string()),
+        "cfg(windows)".to_string(),
+    );
+    pack_context.add_crate_bin(b"crate".to_vec());
+
+    let expected = json!({
+        "name": "demo",
+        "vers": "1.2.3",
+        "deps": [
+            {
+                "name": "serde", "req": "^1.0", "features": [], "optional": false, "default_features": true,
+                "target": null, "kind": "normal", "registry": null
+            },
+            {
+                "name": "tool", "req": "0.3", "features": [], "optional": false, "default_features": true,
+                "target": null, "kind": "normal", "registry": null, "git": "https://example.com/tool.git"
+            },
+            {
+                "name": "winapi", "req": "0.3", "features": [], "optional": false, "default_features": true,
+                "target": "cfg(windows)", "kind": "normal", "registry": "https://index.example.com"
+            }
+        ],
+        // sha256("crate")
+        "cksum": "f5fe331d2367a7a67ee20bd579c77b929ae49439d8b0d8e9c3b98609797b6b69",
+        "features": {},
+        "yanked": false,
+        "links": null
+    });
+    assert_eq!(pack_context.to_index_entry(), expected);
+
+    let line = pack_context.metadata_as(MetadataFormat::Index).unwrap();
+    assert_eq!(line.lines().count(), 1);
+    assert_eq!("index".parse::<MetadataFormat>().unwrap().extension(), "index.json");
+}
@@ -1,7 +1,24 @@
+pub mod batch;
+pub mod compression;
 pub mod context;
 pub mod decode;
+pub mod detached;
 pub mod encode;
+#[cfg(feature = "engine")]
+pub mod engine;
+pub mod explain;
+pub mod file_hashes;
 pub mod file_ops;
 pub mod from_toml;
+pub mod incremental;
+pub mod metadata;
+pub mod pack;
 pub mod package;
 pub mod pkcs;
+pub mod policy;
+pub mod progress;
+pub mod storage;
+pub mod streaming;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timings;
@@ -0,0 +1,959 @@
+use crate::utils::context::{PackageContext, BUILD_META_CARGO_VERSION, BUILD_META_RUSTC_VERSION, SIGTYPE};
+use crate::utils::from_toml::CrateToml;
+use crate::utils::pkcs::PKCS;
+use crate::utils::policy::DepSourcePolicy;
+use crate::utils::progress::{self, EncodeStage, ProgressSink};
+use crate::utils::timings;
+use crate::error::{Result, CrateSpecError};
+use crate::locale::tr;
+use crate::tr_format;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// 轮询子进程是否结束的间隔
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 读取子进程的一个输出管道，放在单独线程中避免管道写满导致子进程阻塞
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// 执行命令并返回标准输出；设置 `timeout` 时超时会终止子进程并返回 [`CrateSpecError::CommandFailed`]
+///
+/// Unix 下设置 `timeout` 时子进程自成一个进程组，超时时连同它启动的 rustc 等进程一起终止。
+/// 此时子进程不在终端的前台进程组中，不会随 Ctrl-C 一起收到 SIGINT。
+fn run_cmd(cmd: &str, args: Vec<&str>, cur_dir: Option<&PathBuf>, timeout: Option<Duration>) -> Result<String> {
+    let mut command = Command::new(cmd);
+    if !args.is_empty() {
+        command.args(args);
+    }
+    if let Some(cd) = cur_dir {
+        command.current_dir(cd);
+    }
+    #[cfg(unix)]
+    if timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CrateSpecError::CommandFailed(tr_format!("无法启动命令 {}: {}", "cannot start command {}: {}", cmd, e)))?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = timeout.map(|t| (Instant::now() + t, t));
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some((deadline, t)) = deadline {
+            if Instant::now() >= deadline {
+                kill_process_group(&mut child)?;
+                child.wait()?;
+                return Err(CrateSpecError::CommandFailed(tr_format!(
+                    "命令 {} 超过 {} 秒未完成，已终止",
+                    "command {} did not finish within {} seconds and was killed",
+                    cmd,
+                    t.as_secs_f64()
+                )));
+            }
+        }
+        thread::sleep(WAIT_POLL_INTERVAL);
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if status.success() {
+        Ok(String::from_utf8_lossy(&stdout).to_string())
+    } else {
+        Err(CrateSpecError::CommandFailed(tr_format!(
+            "命令 {} 执行失败: {}",
+            "command {} failed: {}",
+            cmd,
+            String::from_utf8_lossy(&stderr)
+        )))
+    }
+}
+
+/// 终止以 `child` 为组长的进程组；非 Unix 平台只能终止子进程本身
+fn kill_process_group(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        // 进程组号即组长的 pid
+        let pgid = libc::pid_t::try_from(child.id())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: kill 只发送信号，不涉及内存安全
+        if unsafe { libc::kill(-pgid, libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+    }
+    child.kill()
+}
+
+/// cargo 识别的构建输出目录环境变量
+const CARGO_TARGET_DIR_ENV: &str = "CARGO_TARGET_DIR";
+
+/// 确定 cargo package 输出 `.crate` 的目录：`<target_dir>/package`
+///
+/// 优先级：显式指定的目录 > CARGO_TARGET_DIR > `<crate_path>/target`。
+/// 相对路径按 cargo 的规则相对于 crate 目录（cargo 的工作目录）解析。
+fn package_dir<F>(crate_path: &Path, target_dir: Option<&str>, env_lookup: F) -> PathBuf
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let target_dir = target_dir
+        .map(PathBuf::from)
+        .or_else(|| env_lookup(CARGO_TARGET_DIR_ENV).filter(|v| !v.is_empty()).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("target"));
+    crate_path.join(target_dir).join("package")
+}
+
+/// `.crate` 文件名的默认模式，`{name}`/`{version}` 为占位符
+pub const DEFAULT_CRATE_FILE_PATTERN: &str = "{name}-{version}.crate";
+
+/// 按模式生成 `.crate` 文件名
+fn crate_file_name(pattern: &str, name: &str, version: &str) -> String {
+    pattern.replace("{name}", name).replace("{version}", version)
+}
+
+/// package 目录中各 `.crate` 文件的修改时间，目录不存在时为空
+fn crate_mtimes(package_dir: &Path) -> BTreeMap<PathBuf, SystemTime> {
+    let Ok(entries) = fs::read_dir(package_dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension() == Some("crate".as_ref()))
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+
+/// 在 package 目录中查找唯一一个属于 `name` 的 `.crate` 文件
+///
+/// 候选文件名须为 `<name>-<以数字开头的版本>.crate`，这样 `foo` 不会匹配到 `foo-bar-0.1.0.crate`。
+/// 没有或有多个候选时返回错误。
+fn find_crate_file(package_dir: &Path, name: &str) -> Result<PathBuf> {
+    let prefix = format!("{}-", name);
+    let entries = fs::read_dir(package_dir).map_err(|_e| CrateSpecError::FileNotFound(package_dir.to_path_buf()))?;
+    let mut candidates = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let is_candidate = path.is_file()
+            && path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .and_then(|f| f.strip_prefix(&prefix))
+                .and_then(|rest| rest.strip_suffix(".crate"))
+                .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()));
+        if is_candidate {
+            candidates.push(path);
+        }
+    }
+    candidates.sort();
+    match candidates.len() {
+        1 => Ok(candidates.remove(0)),
+        0 => Err(CrateSpecError::ValidationError(tr_format!(
+            "{} 中没有找到 {} 的 .crate 文件",
+            "no .crate file for {1} found in {0}",
+            package_dir.display(),
+            name
+        ))),
+        _ => Err(CrateSpecError::ValidationError(tr_format!(
+            "{} 中有多个 {} 的 .crate 文件，请用 --crate-file-pattern 指定: {}",
+            "{} has several .crate files for {}, pick one with --crate-file-pattern: {}",
+            package_dir.display(),
+            name,
+            candidates
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// 打包选项
+#[derive(Debug, Clone, Default)]
+pub struct PackOptions {
+    /// 对应 cargo 的 `--target-dir`
+    pub target_dir: Option<String>,
+    /// Cargo.toml 缺少 name/version 时使用占位值
+    pub lenient_toml: bool,
+    /// cargo package 超时时间，超时后终止 cargo 进程
+    pub timeout: Option<Duration>,
+    /// 在构建元数据段中记录打包所用的 cargo/rustc 版本
+    pub record_toolchain: bool,
+    /// 输入为 git 仓库地址时检出的 tag/分支
+    pub git_ref: Option<String>,
+    /// 打印未写入依赖表的依赖
+    pub report_dropped_deps: bool,
+    /// 打包过程中出现警告（占位值、未写入的依赖）时返回错误
+    pub fail_on_warning: bool,
+    /// 依赖源策略，默认允许所有来源
+    pub dep_source_policy: DepSourcePolicy,
+    /// package 目录中 `.crate` 文件名的模式，默认 [`DEFAULT_CRATE_FILE_PATTERN`]
+    pub crate_file_pattern: Option<String>,
+    /// 记录 `.crate` 中每个文件的 SHA256（文件哈希段）
+    pub file_hashes: bool,
+    /// 只读取 Cargo.toml 中的包信息和依赖，不执行 cargo package，也不读取 `.crate`（用于预演）
+    pub metadata_only: bool,
+    /// 进入各编码阶段时的回调（预演时不调用），同时接收打包警告和 cargo package 的输出
+    pub progress: Option<Arc<dyn ProgressSink>>,
+}
+
+/// 输入是否为 git 仓库地址（而不是本地 crate 目录）
+pub fn is_git_url(input: &str) -> bool {
+    ["git://", "ssh://", "git@", "file://"].iter().any(|prefix| input.starts_with(prefix))
+        || ((input.starts_with("https://") || input.starts_with("http://")) && input.ends_with(".git"))
+}
+
+/// 浅克隆到临时目录的 git 仓库，drop 时删除该目录（出错返回时同样会清理）
+struct GitCheckout {
+    dir: PathBuf,
+    /// 删除临时目录失败时的警告交给它
+    progress: Option<Arc<dyn ProgressSink>>,
+}
+
+impl GitCheckout {
+    /// `git clone --depth 1 [--branch <git_ref>] <url> <临时目录>`
+    fn clone(url: &str, git_ref: Option<&str>, timeout: Option<Duration>, progress: Option<Arc<dyn ProgressSink>>) -> Result<Self> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let checkout = GitCheckout {
+            dir: env::temp_dir().join(format!("crate-spec-git-{}-{}", std::process::id(), nanos)),
+            progress,
+        };
+        let dir = checkout
+            .dir
+            .to_str()
+            .ok_or_else(|| CrateSpecError::Other(tr("无法将路径转换为字符串", "path is not valid UTF-8").to_string()))?;
+        let mut args = vec!["clone", "--depth", "1"];
+        if let Some(git_ref) = git_ref {
+            args.extend(["--branch", git_ref]);
+        }
+        args.extend(["--", url, dir]);
+        run_cmd("git", args, None, timeout)?;
+        Ok(checkout)
+    }
+}
+
+impl Drop for GitCheckout {
+    fn drop(&mut self) {
+        if self.dir.exists() {
+            if let Err(e) = fs::remove_dir_all(&self.dir) {
+                let warning = tr_format!("无法删除临时目录 {}: {}", "cannot remove temporary directory {}: {}", self.dir.display(), e);
+                progress::warn(self.progress.as_deref(), &warning);
+            }
+        }
+    }
+}
+
+/// 在 crate 目录下执行 `<tool> --version`（遵循 rust-toolchain 文件），工具不可用时给出警告并返回 None
+fn tool_version(tool: &str, crate_path: &PathBuf, timeout: Option<Duration>, progress: Option<&dyn ProgressSink>) -> Option<String> {
+    match run_cmd(tool, vec!["--version"], Some(crate_path), timeout) {
+        Ok(version) => Some(version.trim().to_string()),
+        Err(e) => {
+            progress::warn(progress, &tr_format!("无法获取 {} 版本，构建元数据中不记录: {}", "cannot get the {} version, leaving it out of the build metadata: {}", tool, e));
+            None
+        }
+    }
+}
+
+/// 打包所用工具链的版本，键为 BUILD_META_CARGO_VERSION / BUILD_META_RUSTC_VERSION
+fn toolchain_versions(crate_path: &PathBuf, timeout: Option<Duration>, progress: Option<&dyn ProgressSink>) -> BTreeMap<String, String> {
+    [(BUILD_META_CARGO_VERSION, "cargo"), (BUILD_META_RUSTC_VERSION, "rustc")]
+        .into_iter()
+        .filter_map(|(key, tool)| Some((key.to_string(), tool_version(tool, crate_path, timeout, progress)?)))
+        .collect()
+}
+
+struct Packing {
+    pack_context: PackageContext,
+    crate_path: PathBuf,
+    options: PackOptions,
+    /// 注入的 `.crate` 内容，设置时不执行 cargo package，也不从 target/package 读取
+    crate_bytes: Option<Vec<u8>>,
+    /// 执行 cargo package 之前 package 目录中已有的 `.crate` 及其修改时间，用于识别未重新生成的旧文件
+    crates_before_package: Option<BTreeMap<PathBuf, SystemTime>>,
+}
+
+impl Packing {
+    fn new(crate_path: &str, options: PackOptions) -> Result<Self> {
+        Ok(Packing {
+            pack_context: PackageContext::new(),
+            crate_path: PathBuf::from_str(crate_path)
+                .map_err(|e| CrateSpecError::ValidationError(tr_format!("无效的路径: {}", "invalid path: {}", e)))?,
+            options,
+            crate_bytes: None,
+            crates_before_package: None,
+        })
+    }
+
+    /// 使用给定的 Cargo.toml 和 `.crate` 内容打包，crate 目录取 Cargo.toml 所在目录
+    fn with_crate_bytes(manifest_path: &str, bytes: Vec<u8>, options: PackOptions) -> Result<Self> {
+        let manifest_path = Path::new(manifest_path);
+        if manifest_path.file_name() != Some("Cargo.toml".as_ref()) {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "清单路径必须指向 Cargo.toml: {}",
+                "the manifest path must point to a Cargo.toml: {}",
+                manifest_path.display()
+            )));
+        }
+        let crate_path = match manifest_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        Ok(Packing {
+            pack_context: PackageContext::new(),
+            crate_path,
+            options,
+            crate_bytes: Some(bytes),
+            crates_before_package: None,
+        })
+    }
+
+    /// 执行 cargo package 命令
+    /// 
+    /// 性能优化说明：
+    /// - 当前使用 `cargo package --allow-dirty`，会执行完整的验证步骤
+    /// - 如需提升性能，可以添加 `--no-verify` 选项：
+    ///   ```rust,ignore
+    ///   ["package", "--allow-dirty", "--no-verify"].to_vec()
+    ///   ```
+    /// 
+    /// `--no-verify` 选项说明：
+    /// - 跳过编译验证（`cargo build`）和测试（`cargo test`）
+    /// - 可以显著提升打包速度（通常节省 80-95% 时间）
+    /// - 适用于：项目已编译、CI/CD 环境、快速迭代场景
+    /// - 不适用于：需要确保代码可编译的生产环境
+    /// 
+    /// 注意：当前实现不使用 `--no-verify`，以确保代码质量。
+    /// 如需使用，请根据实际场景修改上述代码。
+    fn cmd_cargo_package(&self) -> Result<()> {
+        let mut args = ["package", "--allow-dirty"].to_vec();
+        if let Some(target_dir) = &self.options.target_dir {
+            args.extend(["--target-dir", target_dir.as_str()]);
+        }
+        let res = timings::measure(timings::label_cargo_package(), || {
+            run_cmd("cargo", args, Some(&self.crate_path), self.options.timeout)
+        })?;
+        if let Some(progress) = &self.options.progress {
+            progress.on_cargo_output(&res);
+        }
+        Ok(())
+    }
+
+    // read .crate file and parse toml file, then 
+    // we can get the package info and dependency info
+    // and then we can add the crate binary to the pack_context
+    // read .crate file and parse toml file, then 
+    // we can get the package info and dependency info
+    // and then we can add the crate binary to the pack_context
+    // read .crate file and parse toml file, then 
+    // we can get the package info and dependency info
+    // and then we can add the crate binary to the pack_context
+    fn read_crate(&mut self) -> Result<()> {
+        //parse crate toml file
+        let mut toml_path = self.crate_path.clone();
+        toml_path.push("Cargo.toml");
+        let toml_path = fs::canonicalize(&toml_path)
+            .map_err(|_e| CrateSpecError::FileNotFound(toml_path.clone()))?;
+        let toml_path_str = toml_path.to_str()
+            .ok_or_else(|| CrateSpecError::Other(tr("无法将路径转换为字符串", "path is not valid UTF-8").to_string()))?;
+        let mut toml = timings::measure(timings::label_parse_toml(), || {
+            CrateToml::from_file(toml_path_str.to_string())
+        })?;
+        toml.set_lenient(self.options.lenient_toml);
+        toml.set_dep_source_policy(self.options.dep_source_policy.clone());
+        self.pack_context.dropped_deps = toml.write_info_to_package_context(&mut self.pack_context)?;
+        let progress = self.options.progress.as_deref();
+        for warning in toml.warnings() {
+            progress::warn(progress, warning);
+        }
+        let dropped: Vec<String> = self
+            .pack_context
+            .dropped_deps
+            .iter()
+            .map(|name| tr_format!("依赖 {} 含不支持的键，未写入依赖表", "dependency {} has unsupported keys and was left out of the dependency table", name))
+            .collect();
+        if self.options.report_dropped_deps {
+            for warning in dropped.iter() {
+                progress::warn(progress, warning);
+            }
+        }
+        if self.options.fail_on_warning && !(toml.warnings().is_empty() && dropped.is_empty()) {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "打包产生警告（--fail-on-warning）: {}",
+                "packing produced warnings (--fail-on-warning): {}",
+                [toml.warnings(), dropped.as_slice()].concat().join("; ")
+            )));
+        }
+
+        if self.options.metadata_only {
+            return Ok(());
+        }
+
+        //injected crate binary
+        if let Some(bin) = self.crate_bytes.take() {
+            self.pack_context.add_crate_bin(bin);
+            return Ok(());
+        }
+
+        //read crate binary, falling back to scanning the package dir
+        let crate_bin_file = crate_file_name(
+            self.options.crate_file_pattern.as_deref().unwrap_or(DEFAULT_CRATE_FILE_PATTERN),
+            &self.pack_context.pack_info.name,
+            &self.pack_context.pack_info.version,
+        );
+        let package_dir = self.package_dir();
+        let mut crate_bin_path = package_dir.join(&crate_bin_file);
+        if !crate_bin_path.is_file() {
+            crate_bin_path = find_crate_file(&package_dir, &self.pack_context.pack_info.name)?;
+            let warning = tr_format!("未找到 {}，使用 {}", "{} not found, using {}", crate_bin_file, crate_bin_path.display());
+            progress::warn(self.options.progress.as_deref(), &warning);
+        }
+        // 同一版本的旧 .crate 可能是上一次构建留下的，cargo package 没有重写它时不能签名
+        if let Some(before) = &self.crates_before_package {
+            let modified = fs::metadata(&crate_bin_path).and_then(|m| m.modified()).ok();
+            if modified.is_some() && before.get(&crate_bin_path) == modified.as_ref() {
+                return Err(CrateSpecError::EncodeError(tr_format!(
+                    "cargo package 没有重新生成 {}，拒绝签名之前构建留下的文件；请删除该文件或检查 --crate-file-pattern",
+                    "cargo package did not regenerate {}, refusing to sign a file left by an earlier build; delete it or check --crate-file-pattern",
+                    crate_bin_path.display()
+                )));
+            }
+        }
+        let crate_bin_path = fs::canonicalize(&crate_bin_path)
+            .map_err(|_e| CrateSpecError::FileNotFound(crate_bin_path.clone()))?;
+        let bin = timings::measure(timings::label_read_binary(), || fs::read(&crate_bin_path))
+            .map_err(CrateSpecError::Io)?;
+
+        //write to pack_context
+        self.pack_context.add_crate_bin(bin);
+        Ok(())
+    }
+
+    /// cargo package 输出 `.crate` 的目录
+    fn package_dir(&self) -> PathBuf {
+        package_dir(&self.crate_path, self.options.target_dir.as_deref(), |k| env::var_os(k))
+    }
+
+    fn pack_context(mut self) -> Result<PackageContext> {
+        let progress = self.options.progress.clone().filter(|_| !self.options.metadata_only);
+        if self.crate_bytes.is_none() && !self.options.metadata_only {
+            progress::report(progress.as_deref(), EncodeStage::Packaging);
+            self.crates_before_package = Some(crate_mtimes(&self.package_dir()));
+            self.cmd_cargo_package()?;
+        }
+        progress::report(progress.as_deref(), EncodeStage::ReadingCrate);
+        self.read_crate()?;
+        if self.options.metadata_only {
+            return Ok(self.pack_context);
+        }
+        if self.options.record_toolchain {
+            let versions = toolchain_versions(&self.crate_path, self.options.timeout, self.options.progress.as_deref());
+            self.pack_context.build_metadata.extend(versions);
+        }
+        if self.options.file_hashes {
+            self.pack_context.record_file_hashes()?;
+        }
+        Ok(self.pack_context)
+    }
+}
+
+/// 打包 `path` 处的 crate；`path` 为 git 仓库地址时先浅克隆到临时目录，打包后删除
+pub fn pack_context(path: &str, options: PackOptions) -> Result<PackageContext> {
+    if !is_git_url(path) {
+        if options.git_ref.is_some() {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "--git-ref 只能用于 git 仓库地址输入: {}",
+                "--git-ref can only be used with a git repository URL input: {}",
+                path
+            )));
+        }
+        return Packing::new(path, options)?.pack_context();
+    }
+    let checkout = GitCheckout::clone(path, options.git_ref.as_deref(), options.timeout, options.progress.clone())?;
+    let checkout_path = checkout
+        .dir
+        .to_str()
+        .ok_or_else(|| CrateSpecError::Other(tr("无法将路径转换为字符串", "path is not valid UTF-8").to_string()))?;
+    Packing::new(checkout_path, options)?.pack_context()
+}
+
+/// 用 `cargo metadata` 列出 `root` 处工作区的成员 crate 目录（按 cargo 给出的顺序）及工作区的构建输出目录
+fn workspace_members(root: &Path, timeout: Option<Duration>) -> Result<(Vec<PathBuf>, PathBuf)> {
+    let output = run_cmd(
+        "cargo",
+        vec!["metadata", "--no-deps", "--format-version", "1"],
+        Some(&root.to_path_buf()),
+        timeout,
+    )?;
+    let metadata: serde_json::Value = serde_json::from_str(&output)
+        .map_err(|e| CrateSpecError::ParseError(tr_format!("无法解析 cargo metadata 输出: {}", "cannot parse the cargo metadata output: {}", e)))?;
+    let invalid = |field: &str| CrateSpecError::ParseError(tr_format!("cargo metadata 输出缺少 {}", "cargo metadata output is missing {}", field));
+    let members = metadata["workspace_members"].as_array().ok_or_else(|| invalid("workspace_members"))?;
+    let packages = metadata["packages"].as_array().ok_or_else(|| invalid("packages"))?;
+    let member_dirs = members
+        .iter()
+        .map(|id| {
+            let manifest = packages
+                .iter()
+                .find(|package| package["id"] == *id)
+                .and_then(|package| package["manifest_path"].as_str())
+                .ok_or_else(|| invalid(&tr_format!("成员 {} 的 manifest_path", "the manifest_path of member {}", id)))?;
+            Path::new(manifest)
+                .parent()
+                .map(Path::to_path_buf)
+                .ok_or_else(|| invalid(&tr_format!("成员 {} 的 crate 目录", "the crate directory of member {}", id)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let target_dir = metadata["target_directory"].as_str().ok_or_else(|| invalid("target_directory"))?;
+    Ok((member_dirs, PathBuf::from(target_dir)))
+}
+
+/// 逐个打包 `root` 处工作区的所有成员，返回每个成员的上下文
+fn pack_workspace_dir(root: &str, options: PackOptions) -> Result<Vec<PackageContext>> {
+    let (members, target_dir) = workspace_members(Path::new(root), options.timeout)?;
+    if members.is_empty() {
+        return Err(CrateSpecError::ValidationError(tr_format!("{} 处的工作区没有成员", "the workspace at {} has no members", root)));
+    }
+    // 成员共用工作区的构建输出目录，.crate 不在成员自己的 target 下
+    let options = PackOptions {
+        target_dir: options.target_dir.clone().or_else(|| target_dir.to_str().map(str::to_string)),
+        ..options
+    };
+    members
+        .iter()
+        .map(|member| {
+            let member = member
+                .to_str()
+                .ok_or_else(|| CrateSpecError::Other(tr("无法将路径转换为字符串", "path is not valid UTF-8").to_string()))?;
+            Packing::new(member, options.clone())?.pack_context()
+        })
+        .collect()
+}
+
+/// 打包 `root` 处 Cargo 工作区的每个成员，按 `cargo metadata` 的成员顺序返回各自的上下文
+///
+/// 每个成员都在自己的目录中执行 cargo package，与 [`pack_context`] 相同；`root` 为 git 仓库地址时
+/// 先浅克隆到临时目录。`root` 不是工作区而是单个 crate 时只返回该 crate。任一成员打包失败即返回错误。
+pub fn pack_workspace(root: &str, options: PackOptions) -> Result<Vec<PackageContext>> {
+    if !is_git_url(root) {
+        if options.git_ref.is_some() {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "--git-ref 只能用于 git 仓库地址输入: {}",
+                "--git-ref can only be used with a git repository URL input: {}",
+                root
+            )));
+        }
+        return pack_workspace_dir(root, options);
+    }
+    let checkout = GitCheckout::clone(root, options.git_ref.as_deref(), options.timeout, options.progress.clone())?;
+    let checkout_path = checkout
+        .dir
+        .to_str()
+        .ok_or_else(|| CrateSpecError::Other(tr("无法将路径转换为字符串", "path is not valid UTF-8").to_string()))?;
+    pack_workspace_dir(checkout_path, options)
+}
+
+/// 用给定的 Cargo.toml 和 `.crate` 内容打包，不执行 cargo package（用于测试或自行生成 `.crate` 的场景）
+pub fn pack_context_from_bytes(manifest_path: &str, crate_bytes: Vec<u8>, options: PackOptions) -> Result<PackageContext> {
+    Packing::with_crate_bytes(manifest_path, crate_bytes, options)?.pack_context()
+}
+
+/// 用给定的 Cargo.toml 和已生成的 `.crate` 文件打包，不执行 cargo package
+///
+/// 用于 CI 中已经产出 `.crate` 的场景。`.crate` 内 Cargo.toml 的包名和版本必须与 `manifest_path` 一致，
+/// 避免误签其他包。
+pub fn pack_context_from_crate_file(manifest_path: &str, crate_path: &str, options: PackOptions) -> Result<PackageContext> {
+    let crate_path_buf = Path::new(crate_path);
+    let bytes = fs::read(crate_path_buf)
+        .map_err(|_e| CrateSpecError::FileNotFound(crate_path_buf.to_path_buf()))?;
+    let pack = pack_context_from_bytes(manifest_path, bytes, options)?;
+
+    let toml = CrateToml::from_vec(pack.crate_binary.cargo_toml()?)?;
+    let expected = (pack.pack_info.name.as_str(), pack.pack_info.version.as_str());
+    match toml.name_and_version() {
+        Some(actual) if actual == expected => Ok(pack),
+        actual => Err(CrateSpecError::ValidationError(tr_format!(
+            "{} 中的包为 {}，与 {} 中的 {}-{} 不符",
+            "the package in {0} is {1}, which does not match {3}-{4} in {2}",
+            crate_path,
+            actual.map_or(tr("未知", "unknown").to_string(), |(name, version)| format!("{}-{}", name, version)),
+            manifest_path,
+            expected.0,
+            expected.1
+        ))),
+    }
+}
+
+/// 打包 `dir` 处的 crate，用 `signer` 对 crate 二进制签名，返回 `.scrate` 文件内容
+///
+/// ```no_run
+/// use crate_spec::utils::pkcs::PKCS;
+///
+/// let mut signer = PKCS::new();
+/// signer.load_from_file_writer(
+///     "test/cert.pem".to_string(),
+///     "test/key.pem".to_string(),
+///     vec!["test/root-ca.pem".to_string()],
+/// ).unwrap();
+/// let scrate = crate_spec::encode_local("path/to/crate", signer).unwrap();
+/// std::fs::write("demo.scrate", scrate).unwrap();
+/// ```
+pub fn encode_local(dir: &str, signer: PKCS) -> Result<Vec<u8>> {
+    encode_local_with_options(dir, signer, PackOptions::default())
+}
+
+/// 同 [`encode_local`]，可指定打包选项
+pub fn encode_local_with_options(dir: &str, signer: PKCS, options: PackOptions) -> Result<Vec<u8>> {
+    let progress = options.progress.clone();
+    let mut pack_context = pack_context(dir, options)?;
+    progress::report(progress.as_deref(), EncodeStage::Signing);
+    pack_context.add_sig(signer, SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package()?;
+    Ok(bin)
+}
+
+pub fn pack_name(pack: &PackageContext) -> String {
+    format!("{}-{}.scrate", pack.pack_info.name, pack.pack_info.version)
+}
+
+#[test]
+fn test_cmd_cargo_package() {
+    use crate::utils::testing::TestDir;
+    let dir = TestDir::new("cargo-package");
+    let crate_path = dir.fixture_crate("fixture", "0.1.0");
+    let pac = pack_context(&crate_path, PackOptions::default()).unwrap();
+    assert_eq!(pac.pack_info.name, "fixture");
+    assert_eq!(pac.pack_info.version, "0.1.0");
+    assert!(!pac.crate_binary.bytes.is_empty());
+}
+
+#[test]
+fn test_package_dir_target_dir() {
+    let crate_path = Path::new("/work/demo");
+    let no_env = |_: &str| None;
+    let custom_env = |k: &str| (k == CARGO_TARGET_DIR_ENV).then(|| OsString::from("/tmp/custom-target"));
+
+    assert_eq!(package_dir(crate_path, None, no_env), PathBuf::from("/work/demo/target/package"));
+    assert_eq!(package_dir(crate_path, None, custom_env), PathBuf::from("/tmp/custom-target/package"));
+    assert_eq!(
+        package_dir(crate_path, None, |_: &str| Some(OsString::from("out"))),
+        PathBuf::from("/work/demo/out/package")
+    );
+    assert_eq!(
+        package_dir(crate_path, None, |_: &str| Some(OsString::new())),
+        PathBuf::from("/work/demo/target/package")
+    );
+    // 显式指定优先于环境变量
+    assert_eq!(
+        package_dir(crate_path, Some("/opt/target"), custom_env),
+        PathBuf::from("/opt/target/package")
+    );
+}
+
+#[test]
+fn test_run_cmd_timeout() {
+    let start = Instant::now();
+    let res = run_cmd("sleep", vec!["5"], None, Some(Duration::from_millis(200)));
+    assert!(matches!(res, Err(CrateSpecError::CommandFailed(ref msg)) if msg.contains("超过")));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // 超时时子进程启动的后台进程一并终止
+    #[cfg(unix)]
+    {
+        use crate::utils::testing::TestDir;
+        let dir = TestDir::new("run-cmd-timeout");
+        let pid_file = dir.join_str("pid");
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file);
+        assert!(run_cmd("sh", vec!["-c", &script], None, Some(Duration::from_millis(500))).is_err());
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        // SIGKILL 送达后进程不一定立即退出；退出后可能还未被回收，此时状态为 Z
+        let stat = || std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).unwrap_or_default();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !(stat().is_empty() || stat().contains(") Z ")) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        let stat = stat();
+        assert!(stat.is_empty() || stat.contains(") Z "), "{}", stat);
+    }
+
+    // 在时限内完成的命令照常返回输出
+    let out = run_cmd("echo", vec!["done"], None, Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(out.trim(), "done");
+    assert!(matches!(run_cmd("false", vec![], None, None), Err(CrateSpecError::CommandFailed(_))));
+}
+
+#[test]
+fn test_toolchain_versions_round_trip() {
+    let crate_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let versions = toolchain_versions(&crate_path, Some(Duration::from_secs(60)), None);
+    assert!(versions[BUILD_META_CARGO_VERSION].starts_with("cargo "));
+    assert!(versions[BUILD_META_RUSTC_VERSION].starts_with("rustc "));
+    assert_eq!(tool_version("crate-spec-no-such-tool", &crate_path, None, None), None);
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("toolchain".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![0u8; 8]);
+    pack_context.build_metadata = versions.clone();
+    let (crate_package, _, bin) = pack_context.encode_to_crate_package().unwrap();
+    assert!(crate_package.build_meta_section().unwrap().is_some());
+
+    let mut decoded = PackageContext::new();
+    decoded.decode_from_crate_package(&bin).unwrap();
+    assert_eq!(decoded.build_metadata, versions);
+    assert!(decoded.metadata_text().contains(&versions[BUILD_META_RUSTC_VERSION]));
+
+    // 未记录时不写入构建元数据段
+    pack_context.build_metadata.clear();
+    let (crate_package, _, _) = pack_context.encode_to_crate_package().unwrap();
+    assert!(crate_package.build_meta_section().unwrap().is_none());
+}
+
+#[test]
+fn test_pack_from_git_url() {
+    use crate::utils::testing::TestDir;
+    assert!(is_git_url("https://example.com/demo.git"));
+    assert!(is_git_url("git@example.com:demo.git"));
+    assert!(!is_git_url("https://example.com/demo"));
+    assert!(!is_git_url("test/example"));
+
+    // 用本地 bare 仓库代替远程仓库，避免依赖网络
+    let root = TestDir::new("git-test");
+    let work = PathBuf::from(root.fixture_crate("gitdemo", "0.1.0"));
+    let git = |args: Vec<&str>, dir: &PathBuf| {
+        run_cmd("git", [vec!["-c", "user.name=t", "-c", "user.email=t@t"], args].concat(), Some(dir), None).unwrap()
+    };
+    git(vec!["init", "-q"], &work);
+    git(vec!["add", "."], &work);
+    git(vec!["commit", "-q", "-m", "init"], &work);
+    git(vec!["tag", "v0.1.0"], &work);
+    // 没有 Cargo.toml 的分支，克隆成功但打包失败
+    git(vec!["checkout", "-q", "-b", "broken"], &work);
+    git(vec!["rm", "-q", "Cargo.toml"], &work);
+    git(vec!["commit", "-q", "-m", "broken"], &work);
+    git(vec!["checkout", "-q", "-"], &work);
+    git(vec!["clone", "-q", "--bare", "gitdemo", "demo.git"], &root.path().to_path_buf());
+    let url = format!("file://{}", root.join("demo.git").display());
+
+    let options = PackOptions { git_ref: Some("v0.1.0".to_string()), ..Default::default() };
+    let pack = pack_context(&url, options).unwrap();
+    assert_eq!(pack.pack_info.name, "gitdemo");
+    assert!(!pack.crate_binary.bytes.is_empty());
+
+    // 打包失败时同样删除临时目录（测试中只有这里克隆仓库）
+    let prefix = format!("crate-spec-git-{}-", std::process::id());
+    let checkouts = || {
+        fs::read_dir(env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .count()
+    };
+    let before = checkouts();
+    for git_ref in ["no-such-ref", "broken"] {
+        let options = PackOptions { git_ref: Some(git_ref.to_string()), ..Default::default() };
+        assert!(pack_context(&url, options).is_err(), "{}", git_ref);
+        assert_eq!(checkouts(), before, "{}", git_ref);
+    }
+
+    let checkout_err = GitCheckout::clone(&url, Some("no-such-ref"), None, None);
+    assert!(matches!(checkout_err, Err(CrateSpecError::CommandFailed(_))));
+    let checkout = GitCheckout::clone(&url, None, None, None).unwrap();
+    let dir = checkout.dir.clone();
+    assert!(dir.join("Cargo.toml").exists());
+    drop(checkout);
+    assert!(!dir.exists());
+
+    let options = PackOptions { git_ref: Some("v0.1.0".to_string()), ..Default::default() };
+    assert!(matches!(pack_context("test/example", options), Err(CrateSpecError::ValidationError(_))));
+}
+
+#[test]
+fn test_pack_with_injected_crate_bytes() {
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let bytes = b"not produced by cargo".to_vec();
+    let pack = pack_context_from_bytes(manifest, bytes.clone(), PackOptions::default()).unwrap();
+    assert_eq!(pack.pack_info.name, env!("CARGO_PKG_NAME"));
+    assert_eq!(pack.pack_info.version, env!("CARGO_PKG_VERSION"));
+    assert!(pack.deps().iter().any(|dep| dep.name == "openssl"));
+    assert_eq!(pack.crate_binary.bytes, bytes);
+
+    assert!(matches!(
+        pack_context_from_bytes("test/test.toml", bytes, PackOptions::default()),
+        Err(CrateSpecError::ValidationError(_))
+    ));
+}
+
+#[test]
+fn test_pack_from_crate_file() {
+    use crate::utils::testing::TestDir;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let dir = TestDir::new("pack-crate-file");
+    let crate_dir = dir.fixture_crate("prebuilt", "0.3.1");
+    let manifest = format!("{}/Cargo.toml", crate_dir);
+    let tarball = |name: &str, version: &str| {
+        let content = format!("[package]\nname = \"{}\"\nversion = \"{}\"\n", name, version);
+        let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{}-{}/Cargo.toml", name, version), content.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    };
+
+    let bytes = tarball("prebuilt", "0.3.1");
+    let crate_file = dir.write("prebuilt-0.3.1.crate", &bytes);
+    let pack = pack_context_from_crate_file(&manifest, crate_file.to_str().unwrap(), PackOptions::default()).unwrap();
+    assert_eq!(pack.pack_info.name, "prebuilt");
+    assert_eq!(pack.crate_binary.bytes, bytes);
+
+    // 其他版本的 .crate 与清单不符
+    let other = dir.write("prebuilt-0.3.0.crate", tarball("prebuilt", "0.3.0"));
+    let err = pack_context_from_crate_file(&manifest, other.to_str().unwrap(), PackOptions::default()).unwrap_err();
+    assert!(err.to_string().contains("包为 prebuilt-0.3.0"), "{}", err);
+
+    assert!(matches!(
+        pack_context_from_crate_file(&manifest, &dir.join_str("missing.crate"), PackOptions::default()),
+        Err(CrateSpecError::FileNotFound(_))
+    ));
+}
+
+#[test]
+fn test_pack_reports_dropped_deps() {
+    use crate::utils::testing::TestDir;
+    let dir = TestDir::new("dropped-deps");
+    let manifest = dir.write(
+        "Cargo.toml",
+        "[package]\nname = \"dropped\"\nversion = \"0.1.0\"\n\n[dependencies]\n\
+         serde = \"1\"\nlocal = { path = \"../local\" }\n",
+    );
+    let manifest = manifest.to_str().unwrap();
+
+    let pack = pack_context_from_bytes(manifest, vec![0u8; 8], PackOptions::default()).unwrap();
+    assert_eq!(pack.dropped_deps, ["local"]);
+    assert_eq!(pack.deps().len(), 1);
+
+    let options = PackOptions { report_dropped_deps: true, fail_on_warning: true, ..Default::default() };
+    let err = pack_context_from_bytes(manifest, vec![0u8; 8], options).unwrap_err();
+    assert!(matches!(err, CrateSpecError::ValidationError(ref msg) if msg.contains("local")), "{}", err);
+
+    // 警告交给调用方的回调，而不是由库直接输出
+    #[derive(Default)]
+    struct Warnings(std::sync::Mutex<Vec<String>>);
+    impl ProgressSink for Warnings {
+        fn on_stage(&self, _stage: EncodeStage) {}
+        fn on_warning(&self, warning: &str) {
+            self.0.lock().unwrap().push(warning.to_string());
+        }
+    }
+    let warnings = Arc::new(Warnings::default());
+    let options = PackOptions { report_dropped_deps: true, progress: Some(warnings.clone()), ..Default::default() };
+    pack_context_from_bytes(manifest, vec![0u8; 8], options).unwrap();
+    let warnings = warnings.0.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("local"), "{:?}", warnings);
+}
+
+#[test]
+fn test_find_crate_file_fallback() {
+    use crate::utils::testing::TestDir;
+    assert_eq!(crate_file_name(DEFAULT_CRATE_FILE_PATTERN, "demo", "0.1.0"), "demo-0.1.0.crate");
+    assert_eq!(crate_file_name("{name}_v{version}.crate", "demo", "1.0.0-rc.1"), "demo_v1.0.0-rc.1.crate");
+
+    let tmp = TestDir::new("find-crate");
+    let dir = tmp.path();
+    assert!(matches!(find_crate_file(dir, "demo"), Err(CrateSpecError::ValidationError(_))));
+
+    // 名称前缀相同的其他 crate 和非 .crate 文件不算候选
+    fs::write(dir.join("demo-extra-0.1.0.crate"), b"").unwrap();
+    fs::write(dir.join("demo-0.1.0.txt"), b"").unwrap();
+    fs::write(dir.join("demo-0.1.0-alpha+build.5.crate"), b"").unwrap();
+    assert_eq!(find_crate_file(dir, "demo").unwrap(), dir.join("demo-0.1.0-alpha+build.5.crate"));
+
+    fs::write(dir.join("demo-0.2.0.crate"), b"").unwrap();
+    let err = find_crate_file(dir, "demo").unwrap_err();
+    assert!(matches!(err, CrateSpecError::ValidationError(ref msg) if msg.contains("demo-0.2.0.crate")), "{}", err);
+    assert!(matches!(find_crate_file(&tmp.join("missing"), "demo"), Err(CrateSpecError::FileNotFound(_))));
+}
+
+#[test]
+fn test_pack_rejects_stale_crate_file() {
+    use crate::utils::testing::TestDir;
+    let dir = TestDir::new("stale-crate");
+    let crate_path = dir.fixture_crate("fixture", "0.3.0");
+    let package_dir = Path::new(&crate_path).join("target/package");
+    fs::create_dir_all(&package_dir).unwrap();
+    // cargo package 不会生成这个文件名，模式指向它时读到的是旧文件
+    fs::write(package_dir.join("fixture-0.3.0-old.crate"), b"stale").unwrap();
+    let options = |pattern: Option<&str>| PackOptions {
+        crate_file_pattern: pattern.map(str::to_string),
+        ..Default::default()
+    };
+
+    let err = pack_context(&crate_path, options(Some("{name}-{version}-old.crate"))).unwrap_err();
+    assert!(matches!(err, CrateSpecError::EncodeError(ref msg) if msg.contains("fixture-0.3.0-old.crate")), "{}", err);
+
+    // 同一版本重复打包时 cargo 会重写 .crate，不算旧文件
+    pack_context(&crate_path, options(None)).unwrap();
+    let pack = pack_context(&crate_path, options(None)).unwrap();
+    assert!(!pack.crate_binary.bytes.is_empty());
+}
+
+#[test]
+fn test_pack_workspace() {
+    use crate::utils::testing::TestDir;
+    let dir = TestDir::new("workspace");
+    dir.write("Cargo.toml", "[workspace]\nmembers = [\"alpha\", \"beta\"]\nresolver = \"2\"\n");
+    for name in ["alpha", "beta"] {
+        dir.write(
+            &format!("{}/Cargo.toml", name),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"0.2.0\"\nedition = \"2021\"\nlicense = \"MIT\"\n\
+                 description = \"crate-spec test fixture\"\n\n[dependencies]\n",
+                name
+            ),
+        );
+        dir.write(&format!("{}/src/lib.rs", name), "pub fn fixture() {}\n");
+    }
+
+    let packs = pack_workspace(&dir.join_str(""), PackOptions::default()).unwrap();
+    let names: Vec<String> = packs.iter().map(pack_name).collect();
+    assert_eq!(names, ["alpha-0.2.0.scrate", "beta-0.2.0.scrate"]);
+    assert!(packs.iter().all(|pack| !pack.crate_binary.bytes.is_empty()));
+    // 成员的 .crate 在工作区的 target 目录下
+    assert!(dir.join("target/package/alpha-0.2.0.crate").is_file());
+
+    // 单个 crate 视为只有一个成员
+    let single = dir.fixture_crate("single", "0.1.0");
+    assert_eq!(pack_workspace(&single, PackOptions::default()).unwrap().len(), 1);
+    assert!(pack_workspace(&dir.join_str("missing"), PackOptions::default()).is_err());
+}
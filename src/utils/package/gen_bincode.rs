@@ -2,16 +2,17 @@ use bincode;
 use bincode::config::{legacy, Configuration, Fixint, LittleEndian, NoLimit};
 use bincode::de::read::{Reader, SliceReader};
 use bincode::de::DecoderImpl;
+use bincode::enc::write::Writer;
 use bincode::enc::Encoder;
 use bincode::{enc, BorrowDecode, Decode, Encode};
 
 use bincode::error::{DecodeError, EncodeError};
 
 use crate::utils::package::{
-    CrateBinarySection, CrateHeader, CratePackage, DataSection, DataSectionCollectionType,
-    DepTableSection, FingerPrintType, LenArrayType, MagicNumberType, PackageSection, RawArrayType,
-    SectionIndex, SectionIndexEntry, SigStructureSection, Size, Type, Uchar, FINGERPRINT_LEN,
-    MAGIC_NUMBER,
+    BuildMetaSection, CompressionSection, CrateBinarySection, CrateHeader, CratePackage, DataSection, DataSectionCollectionType, Endianness,
+    DepTableSection, FileHashSection, FingerPrintType, LenArrayType, MagicNumberType, PackageSection, RawArrayType,
+    SectionIndex, SectionIndexEntry, SigStructureSection, Size, Type, Uchar, HeaderFlags,
+    CRATE_VERSION, HEADER_KNOWN_FLAGS, LEGACY_CRATE_VERSION, MAGIC_NUMBER,
 };
 
 pub const BINCODE_CONFIG: Configuration<LittleEndian, Fixint, NoLimit> = legacy();
@@ -147,11 +148,71 @@ impl Encode for DataSection {
             DataSection::DepTableSection(x) => x.encode(encoder)?,
             DataSection::CrateBinarySection(x) => x.encode(encoder)?,
             DataSection::SigStructureSection(x) => x.encode(encoder)?, //_ => {panic!("section type error")}
+            DataSection::BuildMetaSection(x) => x.encode(encoder)?,
+            DataSection::FileHashSection(x) => x.encode(encoder)?,
+            DataSection::CompressionSection(x) => x.encode(encoder)?,
         }
         Ok(())
     }
 }
 
+//DataSectionCollectionType Encode, with zero padding before each section
+impl Encode for DataSectionCollectionType {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        let mut written = 0;
+        for (section, (size, offset)) in self.col.arr.iter().zip(self.encode_size_offset()) {
+            encoder.writer().write(&vec![0; offset - written])?;
+            section.encode(encoder)?;
+            written = offset + size;
+        }
+        Ok(())
+    }
+}
+
+//CrateHeader Encode, `flags` only exists from CRATE_VERSION on
+impl Encode for CrateHeader {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.c_version.encode(encoder)?;
+        if self.c_version != LEGACY_CRATE_VERSION {
+            self.flags.encode(encoder)?;
+        }
+        self.strtable_size.encode(encoder)?;
+        self.strtable_offset.encode(encoder)?;
+        self.si_size.encode(encoder)?;
+        self.si_offset.encode(encoder)?;
+        self.si_num.encode(encoder)?;
+        self.ds_offset.encode(encoder)
+    }
+}
+
+//CrateHeader Decode, rejects unknown versions and flags
+impl Decode<()> for CrateHeader {
+    fn decode<D: bincode::de::Decoder<Context = ()>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let c_version: Uchar = Decode::decode(decoder)?;
+        let flags: HeaderFlags = match c_version {
+            LEGACY_CRATE_VERSION => 0,
+            CRATE_VERSION => Decode::decode(decoder)?,
+            _ => return Err(DecodeError::OtherString(format!("unsupported crate version {}", c_version))),
+        };
+        if flags & !HEADER_KNOWN_FLAGS != 0 {
+            return Err(DecodeError::OtherString(format!(
+                "unknown header flags {:#x}",
+                flags & !HEADER_KNOWN_FLAGS
+            )));
+        }
+        Ok(Self {
+            c_version,
+            flags,
+            strtable_size: Decode::decode(decoder)?,
+            strtable_offset: Decode::decode(decoder)?,
+            si_size: Decode::decode(decoder)?,
+            si_offset: Decode::decode(decoder)?,
+            si_num: Decode::decode(decoder)?,
+            ds_offset: Decode::decode(decoder)?,
+        })
+    }
+}
+
 impl Decode<()> for SigStructureSection {
     fn decode<D: bincode::de::Decoder<Context = ()>>(decoder: &mut D) -> Result<Self, DecodeError> {
         let sigstruct_size: Size = Decode::decode(decoder)?;
@@ -190,11 +251,12 @@ impl CratePackage {
     }
 
     pub fn decode_from_slice(bin: &[u8]) -> Result<CratePackage, String> {
-        return match Self::decode(&mut create_bincode_slice_decoder(bin), bin) {
+        match Self::decode(&mut create_bincode_slice_decoder(bin), bin) {
             Ok(t) => Ok(t),
             Err(DecodeError::Other(s)) => Err(s.to_string()),
+            Err(DecodeError::OtherString(s)) => Err(s),
             Err(_) => Err("file format not right! - others".to_string()),
-        };
+        }
     }
 
     pub fn decode<D: bincode::de::Decoder<Context = ()>>(decoder: &mut D, bin: &[u8]) -> Result<Self, DecodeError> {
@@ -225,6 +287,7 @@ impl CratePackage {
         let section_index: SectionIndex = SectionIndex::decode(
             &mut create_bincode_slice_decoder(section_index_bin),
             crate_header.si_num as usize,
+            crate_header.endianness(),
         )?;
 
         let mut enum_size_off_in_bytes = vec![];
@@ -246,13 +309,14 @@ impl CratePackage {
             enum_size_off_in_bytes,
         )?;
 
-        early_return!(
-            bin[bin.len() - FINGERPRINT_LEN..].len() == FINGERPRINT_LEN,
-            "file format not right! - fingerprint"
-        );
-        let fingerprint_bin = &bin[bin.len() - FINGERPRINT_LEN..];
+        let fingerprint_len = crate_header
+            .fingerprint_digest()
+            .map_err(|_| DecodeError::Other("file format not right! - fingerprint digest"))?
+            .output_len();
+        early_return!(bin.len() >= fingerprint_len, "file format not right! - fingerprint");
+        let fingerprint_bin = &bin[bin.len() - fingerprint_len..];
         let finger_print: FingerPrintType =
-            <FingerPrintType as Decode<()>>::decode(&mut create_bincode_slice_decoder(fingerprint_bin))?;
+            FingerPrintType::decode(&mut create_bincode_slice_decoder(fingerprint_bin), fingerprint_len)?;
 
         Ok(Self {
             magic_number,
@@ -265,12 +329,31 @@ impl CratePackage {
     }
 }
 
+///SectionIndex Encode, sh_offset/sh_size follow `endianness` (the encoder is little endian)
+impl Encode for SectionIndex {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        for entry in self.entries.arr.iter() {
+            entry.sh_type.encode(encoder)?;
+            self.endianness.swap_from_little(entry.sh_offset).encode(encoder)?;
+            self.endianness.swap_from_little(entry.sh_size).encode(encoder)?;
+        }
+        Ok(())
+    }
+}
+
 ///SectionIndex Decode
 impl SectionIndex {
-    pub fn decode<D: bincode::de::Decoder<Context = ()>>(decoder: &mut D, elem_num: usize) -> Result<Self, DecodeError> {
-        Ok(Self {
-            entries: RawArrayType::<SectionIndexEntry>::decode(decoder, elem_num)?,
-        })
+    pub fn decode<D: bincode::de::Decoder<Context = ()>>(
+        decoder: &mut D,
+        elem_num: usize,
+        endianness: Endianness,
+    ) -> Result<Self, DecodeError> {
+        let mut entries = RawArrayType::<SectionIndexEntry>::decode(decoder, elem_num)?;
+        for entry in entries.arr.iter_mut() {
+            entry.sh_offset = endianness.swap_from_little(entry.sh_offset);
+            entry.sh_size = endianness.swap_from_little(entry.sh_size);
+        }
+        Ok(Self { entries, endianness })
     }
 }
 
@@ -317,6 +400,27 @@ impl DataSectionCollectionType {
                         .arr
                         .push(DataSection::SigStructureSection(sig_structure));
                 }
+                5 => {
+                    let build_meta: BuildMetaSection = <BuildMetaSection as Decode<()>>::decode(decoder)?;
+                    raw_col
+                        .col
+                        .arr
+                        .push(DataSection::BuildMetaSection(build_meta));
+                }
+                6 => {
+                    let file_hashes: FileHashSection = <FileHashSection as Decode<()>>::decode(decoder)?;
+                    raw_col
+                        .col
+                        .arr
+                        .push(DataSection::FileHashSection(file_hashes));
+                }
+                7 => {
+                    let compression: CompressionSection = <CompressionSection as Decode<()>>::decode(decoder)?;
+                    raw_col
+                        .col
+                        .arr
+                        .push(DataSection::CompressionSection(compression));
+                }
                 _ => return Err(DecodeError::Other("file format not right!")),
             }
             consume_size += size;
@@ -324,11 +428,13 @@ impl DataSectionCollectionType {
         Ok(raw_col)
     }
 
+    /// (size, offset) of each section, offsets are relative to `base` and padded to `align`
     pub fn encode_size_offset(&self) -> Vec<(usize, usize)> {
         let mut v = vec![];
         let mut offset: usize = 0;
         self.col.arr.iter().for_each(|x| {
             let size = encode_size_by_bincode(x);
+            offset = align_up(self.base + offset, self.align) - self.base;
             v.push((size, offset));
             offset += size;
         });
@@ -342,6 +448,11 @@ impl DataSectionCollectionType {
     }
 }
 
+/// round `off` up to a multiple of `align` (a power of two)
+pub fn align_up(off: usize, align: usize) -> usize {
+    (off + align - 1) & !(align - 1)
+}
+
 //CrateBinarySection decode
 impl CrateBinarySection {
     pub fn decode<D: bincode::de::Decoder<Context = ()>>(decoder: &mut D, size_in_bytes: usize) -> Result<Self, DecodeError> {
@@ -450,6 +561,24 @@ impl CrateBinarySection {
     }
 }
 
+impl BuildMetaSection {
+    pub fn size(&self) -> usize {
+        encode_size_by_bincode(self)
+    }
+}
+
+impl FileHashSection {
+    pub fn size(&self) -> usize {
+        encode_size_by_bincode(self)
+    }
+}
+
+impl CompressionSection {
+    pub fn size(&self) -> usize {
+        encode_size_by_bincode(self)
+    }
+}
+
 impl SigStructureSection {
     pub fn size(&self) -> usize {
         encode_size_by_bincode(self)
@@ -2,6 +2,9 @@
 pub mod bin;
 pub mod gen_bincode;
 
+use crate::utils::compression::CompressionKind;
+use crate::utils::pkcs::DigestAlgo;
+use crate::tr_format;
 use bincode::{Decode, Encode};
 
 //Types used in CratePackage
@@ -52,7 +55,7 @@ impl<T> Default for LenArrayType<T> {
 }
 
 impl<T: Clone> LenArrayType<T> {
-    pub fn copy_from_vec(v: &Vec<T>) -> Self {
+    pub fn copy_from_vec(v: &[T]) -> Self {
         let mut len_array = Self::new();
         len_array.arr = v.to_vec();
         len_array.len = v.len() as Size;
@@ -89,20 +92,30 @@ impl<T> Default for RawArrayType<T> {
 }
 
 /// DataSectionCollectionType is a collection of DataSection which is an enum type.
-/// First 3 elements are fixed, and the last elements are about signature section.
+/// First 3 elements are fixed, followed by the optional build metadata section,
+/// and the last elements are about signature section.
 /// 
-/// auto encode
+/// Each section starts at a file offset (`base` + its offset) that is a multiple
+/// of `align`; the gaps are filled with zero bytes.
+///
+/// custom encode
 /// self decode
 /// collections(array whose elem is enum)
-#[derive(Encode, Debug)]
+#[derive(Debug)]
 pub struct DataSectionCollectionType {
     pub col: RawArrayType<DataSection>,
+    /// file offset of the first data section byte (`ds_offset`), not encoded
+    pub base: usize,
+    /// section alignment in bytes, 1 means no padding, not encoded
+    pub align: usize,
 }
 
 impl DataSectionCollectionType {
     pub fn new() -> Self {
         Self {
             col: RawArrayType::new(),
+            base: 0,
+            align: 1,
         }
     }
 }
@@ -133,11 +146,49 @@ pub const MAGIC_NUMBER_LEN: usize = 5;
 pub type MagicNumberType = [Uchar; MAGIC_NUMBER_LEN];
 
 pub const MAGIC_NUMBER: MagicNumberType = [0x43, 0x52, 0x41, 0x54, 0x45];
+/// SHA256 指纹（默认）的长度；实际长度由头部记录的指纹摘要算法决定，见 [`CrateHeader::fingerprint_digest`]
 pub const FINGERPRINT_LEN: usize = 32;
 
-pub type FingerPrintType = [Uchar; FINGERPRINT_LEN];
+pub type FingerPrintType = RawArrayType<Uchar>;
+
+/// 当前写入的文件格式版本：文件头在版本号之后多一个 [`CrateHeader::flags`] 字段
+pub const CRATE_VERSION: Uchar = 1;
+
+/// 没有 `flags` 字段的旧版文件头，解码时按所有标志位为 0 处理
+pub const LEGACY_CRATE_VERSION: Uchar = 0;
+
+/// 头部标志位类型
+pub type HeaderFlags = u32;
+
+/// 头部标志的第 7 位：crate 二进制段只保存 `.crate` 的 SHA256 摘要而不是完整内容
+pub const HEADER_FLAG_PRUNED_BINARY: HeaderFlags = 0x80;
+
+/// 裁剪后 crate 二进制段的长度（SHA256 摘要）
+pub const PRUNED_DIGEST_LEN: usize = 32;
+
+///length of the SHA256 stored for each file in the file hash section
+pub const FILE_HASH_LEN: usize = 32;
+
+/// 头部标志的第 0~1 位：指纹所用摘要算法的编号（见 [`DigestAlgo::as_u8`]），0 为 SHA256
+pub const HEADER_DIGEST_MASK: HeaderFlags = 0x03;
 
-pub const CRATE_VERSION: Uchar = 0;
+/// 头部标志的第 2 位：字符串表长度前缀和段索引字段按大端存储（见 [`Endianness`]）
+pub const HEADER_FLAG_BIG_ENDIAN: HeaderFlags = 0x04;
+
+/// 头部标志的第 3~6 位：数据段对齐字节数的以 2 为底的对数（0 表示不对齐）
+pub const HEADER_ALIGN_MASK: HeaderFlags = 0x78;
+const HEADER_ALIGN_SHIFT: u32 = 3;
+
+/// 头部标志的第 8~9 位：crate 二进制段压缩方式的编号（见 [`CompressionKind::as_u8`]），0 为不压缩
+pub const HEADER_COMPRESSION_MASK: HeaderFlags = 0x300;
+const HEADER_COMPRESSION_SHIFT: u32 = 8;
+
+/// 本版本认识的全部头部标志位，解码时其余位必须为 0
+pub const HEADER_KNOWN_FLAGS: HeaderFlags =
+    HEADER_DIGEST_MASK | HEADER_FLAG_BIG_ENDIAN | HEADER_ALIGN_MASK | HEADER_FLAG_PRUNED_BINARY | HEADER_COMPRESSION_MASK;
+
+/// 数据段对齐的最大值（32 KiB，头部中对数占 4 位）
+pub const MAX_SECTION_ALIGN: usize = 1 << (HEADER_ALIGN_MASK >> HEADER_ALIGN_SHIFT);
 
 /// CratePackage is the top-level package structure.
 /// This structure contains all the information of a crate package, and will
@@ -160,7 +211,7 @@ impl CratePackage {
             string_table: RawArrayType::new(),
             section_index: SectionIndex::new(),
             data_sections: DataSectionCollectionType::new(),
-            finger_print: [0; FINGERPRINT_LEN],
+            finger_print: RawArrayType::from_vec(vec![0; FINGERPRINT_LEN]),
         }
     }
 }
@@ -171,12 +222,14 @@ impl Default for CratePackage {
     }
 }
 
-//auto encode
-//auto decode
+//custom encode
+//custom decode
 ///crate header structure
-#[derive(Encode, Decode, Debug)]
+#[derive(Debug)]
 pub struct CrateHeader {
     pub c_version: Uchar,
+    /// 格式标志（摘要算法、字节序、对齐、裁剪、压缩），只在 [`CRATE_VERSION`] 及以后的文件头中编码
+    pub flags: HeaderFlags,
     pub strtable_size: Size,
     pub strtable_offset: Off,
     pub si_size: Size,
@@ -190,7 +243,8 @@ pub struct CrateHeader {
 impl CrateHeader {
     pub fn new() -> Self {
         Self {
-            c_version: Default::default(),
+            c_version: CRATE_VERSION,
+            flags: Default::default(),
             strtable_size: Default::default(),
             strtable_offset: Default::default(),
             si_num: Default::default(),
@@ -203,24 +257,142 @@ impl CrateHeader {
     }
 }
 
+impl CrateHeader {
+    /// crate 二进制段是否只保存摘要引用
+    pub fn binary_pruned(&self) -> bool {
+        self.flags & HEADER_FLAG_PRUNED_BINARY != 0
+    }
+
+    pub fn set_binary_pruned(&mut self, pruned: bool) {
+        if pruned {
+            self.flags |= HEADER_FLAG_PRUNED_BINARY;
+        } else {
+            self.flags &= !HEADER_FLAG_PRUNED_BINARY;
+        }
+    }
+
+    /// 数据段对齐字节数，未对齐时为 1
+    pub fn section_align(&self) -> usize {
+        1 << ((self.flags & HEADER_ALIGN_MASK) >> HEADER_ALIGN_SHIFT)
+    }
+
+    /// `align` 须为不超过 [`MAX_SECTION_ALIGN`] 的 2 的幂
+    pub fn set_section_align(&mut self, align: usize) {
+        debug_assert!(is_valid_section_align(align));
+        let shift = align.trailing_zeros() as HeaderFlags;
+        self.flags = (self.flags & !HEADER_ALIGN_MASK) | ((shift << HEADER_ALIGN_SHIFT) & HEADER_ALIGN_MASK);
+    }
+
+    /// 指纹所用的摘要算法
+    pub fn fingerprint_digest(&self) -> crate::error::Result<DigestAlgo> {
+        DigestAlgo::from_u8((self.flags & HEADER_DIGEST_MASK) as Uchar)
+    }
+
+    pub fn set_fingerprint_digest(&mut self, algo: DigestAlgo) {
+        self.flags = (self.flags & !HEADER_DIGEST_MASK) | (algo.as_u8() as HeaderFlags & HEADER_DIGEST_MASK);
+    }
+
+    /// crate 二进制段的压缩方式
+    pub fn compression(&self) -> crate::error::Result<CompressionKind> {
+        CompressionKind::from_u8(((self.flags & HEADER_COMPRESSION_MASK) >> HEADER_COMPRESSION_SHIFT) as Uchar)
+    }
+
+    pub fn set_compression(&mut self, kind: CompressionKind) {
+        self.flags = (self.flags & !HEADER_COMPRESSION_MASK)
+            | (((kind.as_u8() as HeaderFlags) << HEADER_COMPRESSION_SHIFT) & HEADER_COMPRESSION_MASK);
+    }
+
+    /// 字符串表长度前缀和段索引字段的字节序
+    pub fn endianness(&self) -> Endianness {
+        if self.flags & HEADER_FLAG_BIG_ENDIAN != 0 {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        match endianness {
+            Endianness::Little => self.flags &= !HEADER_FLAG_BIG_ENDIAN,
+            Endianness::Big => self.flags |= HEADER_FLAG_BIG_ENDIAN,
+        }
+    }
+}
+
+/// 字符串表长度前缀和段索引中 u32 字段的字节序，默认小端；文件头和其他数据段总是小端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    pub fn u32_to_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big => value.to_be_bytes(),
+        }
+    }
+
+    pub fn u32_from_bytes(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        }
+    }
+
+    /// 按小端编解码的值与本字节序的值互相转换：大端时交换字节
+    pub(crate) fn swap_from_little(self, value: u32) -> u32 {
+        match self {
+            Endianness::Little => value,
+            Endianness::Big => value.swap_bytes(),
+        }
+    }
+}
+
+impl std::str::FromStr for Endianness {
+    type Err = crate::error::CrateSpecError;
+
+    fn from_str(s: &str) -> crate::error::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "little" | "le" => Ok(Endianness::Little),
+            "big" | "be" => Ok(Endianness::Big),
+            _ => Err(crate::error::CrateSpecError::ValidationError(tr_format!(
+                "无效的字节序: {}，必须是 'little' 或 'big'",
+                "invalid byte order: {}, must be 'little' or 'big'",
+                s
+            ))),
+        }
+    }
+}
+
+/// 对齐值是否可以记录在头部：2 的幂且不超过 [`MAX_SECTION_ALIGN`]
+pub fn is_valid_section_align(align: usize) -> bool {
+    align.is_power_of_two() && align <= MAX_SECTION_ALIGN
+}
+
 impl Default for CrateHeader {
     fn default() -> Self {
         Self::new()
     }
 }
 
-//auto encode
+//custom encode
 //self decode
 ///section index structure
-#[derive(Encode, Debug)]
+#[derive(Debug)]
 pub struct SectionIndex {
     pub entries: RawArrayType<SectionIndexEntry>,
+    /// byte order of sh_offset/sh_size, recorded in the crate header, not encoded
+    pub endianness: Endianness,
 }
 
 impl SectionIndex {
     pub fn new() -> Self {
         Self {
             entries: RawArrayType::new(),
+            endianness: Endianness::Little,
         }
     }
 
@@ -274,6 +446,12 @@ pub enum DataSection {
     CrateBinarySection(CrateBinarySection),
     //4
     SigStructureSection(SigStructureSection),
+    //5
+    BuildMetaSection(BuildMetaSection),
+    //6
+    FileHashSection(FileHashSection),
+    //7
+    CompressionSection(CompressionSection),
 }
 
 pub fn datasection_type(d: &DataSection) -> Type {
@@ -282,6 +460,9 @@ pub fn datasection_type(d: &DataSection) -> Type {
         DataSection::DepTableSection(_) => 1,
         DataSection::CrateBinarySection(_) => 3,
         DataSection::SigStructureSection(_) => 4,
+        DataSection::BuildMetaSection(_) => 5,
+        DataSection::FileHashSection(_) => 6,
+        DataSection::CompressionSection(_) => 7,
     }
 }
 
@@ -385,6 +566,94 @@ impl Default for DepTableSection {
     }
 }
 
+//auto encode
+//auto decode
+///Build metadata entry structure, a key/value pair of string offsets
+#[derive(Encode, Decode, Debug, Default)]
+pub struct BuildMetaEntry {
+    pub key: StrOff,
+    pub value: StrOff,
+}
+
+//auto encode
+//non-self decode
+///Build metadata section structure (optional, e.g. toolchain versions used for packing)
+#[derive(Encode, Debug)]
+pub struct BuildMetaSection {
+    pub entries: LenArrayType<BuildMetaEntry>,
+}
+
+impl bincode::Decode<()> for BuildMetaSection {
+    fn decode<D: bincode::de::Decoder<Context = ()>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            entries: bincode::Decode::decode(decoder)?,
+        })
+    }
+}
+
+impl BuildMetaSection {
+    pub fn new() -> Self {
+        Self {
+            entries: LenArrayType::new(),
+        }
+    }
+}
+
+impl Default for BuildMetaSection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//auto encode
+//auto decode
+///File hash entry structure, a file path inside the .crate tarball and its SHA256
+#[derive(Encode, Decode, Debug, Default)]
+pub struct FileHashEntry {
+    pub path: StrOff,
+    pub sha256: [Uchar; FILE_HASH_LEN],
+}
+
+//auto encode
+//non-self decode
+///File hash section structure (optional, one entry per regular file in the .crate tarball)
+#[derive(Encode, Debug)]
+pub struct FileHashSection {
+    pub entries: LenArrayType<FileHashEntry>,
+}
+
+impl bincode::Decode<()> for FileHashSection {
+    fn decode<D: bincode::de::Decoder<Context = ()>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            entries: bincode::Decode::decode(decoder)?,
+        })
+    }
+}
+
+impl FileHashSection {
+    pub fn new() -> Self {
+        Self {
+            entries: LenArrayType::new(),
+        }
+    }
+}
+
+impl Default for FileHashSection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//auto encode
+//auto decode
+///Compression section structure (optional, only written when the crate binary section is compressed;
+///the compression kind itself is stored in the header flags)
+#[derive(Encode, Decode, Debug, Default)]
+pub struct CompressionSection {
+    /// length of the uncompressed .crate
+    pub raw_size: u64,
+}
+
 //auto encode
 //non-self decode
 #[derive(Encode, Debug)]
@@ -1,21 +1,454 @@
 use crate::error::{Result, CrateSpecError};
+use crate::locale::tr;
+use crate::tr_format;
+use openssl::asn1::Asn1Time;
 use openssl::hash::{hash, MessageDigest};
-use std::fmt::{Debug, Formatter};
+use std::ffi::c_int;
+use std::fmt::{Debug, Display, Formatter};
 use std::fs;
 use std::path::Path;
+use std::sync::{PoisonError, RwLock};
 
-use openssl::pkcs7::Pkcs7;
+use foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::pkcs7::{Pkcs7, Pkcs7SignerInfo};
 use openssl::pkcs7::Pkcs7Flags;
-use openssl::pkey::PKey;
-use openssl::stack::Stack;
-use openssl::x509::store::X509StoreBuilder;
-use openssl::x509::X509;
+use openssl::pkey::{HasPublic, Id, PKey, PKeyRef, Private};
+use openssl::stack::{Stack, StackRef};
+use openssl_sys as ffi;
+use openssl::memcmp;
+use openssl::x509::store::{X509StoreBuilder, X509StoreRef};
+use openssl::x509::verify::X509VerifyParam;
+use openssl::x509::{X509NameRef, X509Ref, X509StoreContext, X509VerifyResult, X509};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
-#[derive(PartialEq)]
+/// 摘要算法，用于签名内容和指纹
+///
+/// 编号记录在文件中（签名段类型字节的第 4~7 位、头部版本字节的第 0~1 位），0 为 SHA256，
+/// 因此未记录算法的旧文件都按 SHA256 处理。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgo {
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgo {
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            DigestAlgo::Sha256 => 0,
+            DigestAlgo::Sha384 => 1,
+            DigestAlgo::Sha512 => 2,
+        }
+    }
+
+    pub fn from_u8(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(DigestAlgo::Sha256),
+            1 => Ok(DigestAlgo::Sha384),
+            2 => Ok(DigestAlgo::Sha512),
+            _ => Err(CrateSpecError::DecodeError(tr_format!("未知的摘要算法编号: {}", "unknown digest algorithm id: {}", id))),
+        }
+    }
+
+    /// 摘要的字节数
+    pub fn output_len(&self) -> usize {
+        self.message_digest().size()
+    }
+
+    pub fn message_digest(&self) -> MessageDigest {
+        match self {
+            DigestAlgo::Sha256 => MessageDigest::sha256(),
+            DigestAlgo::Sha384 => MessageDigest::sha384(),
+            DigestAlgo::Sha512 => MessageDigest::sha512(),
+        }
+    }
+}
+
+impl Display for DigestAlgo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DigestAlgo::Sha256 => write!(f, "sha256"),
+            DigestAlgo::Sha384 => write!(f, "sha384"),
+            DigestAlgo::Sha512 => write!(f, "sha512"),
+        }
+    }
+}
+
+impl FromStr for DigestAlgo {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(DigestAlgo::Sha256),
+            "sha384" => Ok(DigestAlgo::Sha384),
+            "sha512" => Ok(DigestAlgo::Sha512),
+            _ => Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的摘要算法: {}，必须是 'sha256'、'sha384' 或 'sha512'",
+                "invalid digest algorithm: {}, must be 'sha256', 'sha384' or 'sha512'",
+                s
+            ))),
+        }
+    }
+}
+
+/// 本地签名和验签使用的 PKCS7 标志
+///
+/// `BINARY` 让 OpenSSL 按原始字节签名摘要。没有它时内容被当作文本，换行符会被规范化，
+/// 含 `\n` 或 `\r\n` 字节的摘要无法往返。这改变了签名格式：加入 `BINARY` 之前签出的包，
+/// 摘要含换行字节时会验签失败，其余签名不受影响。
+const PKCS7_FLAGS: Pkcs7Flags = Pkcs7Flags::STREAM.union(Pkcs7Flags::BINARY);
+
+/// 验签时默认允许的证书链深度（中间 CA 证书的最大数量）
+pub const DEFAULT_MAX_CHAIN_DEPTH: u32 = 5;
+
+/// 进程级默认根 CA（PEM 内容），见 [`set_default_root_cas`]
+static DEFAULT_ROOT_CAS: RwLock<Vec<Vec<u8>>> = RwLock::new(Vec::new());
+
+/// 设置进程级默认根 CA（PEM 内容），可在多线程中随时替换
+///
+/// 解码时未显式提供根 CA（`root_cas` 为空）则使用这里的默认值验证本地签名；
+/// 显式提供的根 CA 总是优先。传入空列表等同于清除默认值。
+pub fn set_default_root_cas(root_cas: Vec<Vec<u8>>) {
+    *DEFAULT_ROOT_CAS.write().unwrap_or_else(PoisonError::into_inner) = root_cas;
+}
+
+/// 当前的进程级默认根 CA，未设置时为空
+pub fn default_root_cas() -> Vec<Vec<u8>> {
+    DEFAULT_ROOT_CAS.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// 修改或依赖默认根 CA 的测试需持有此锁，避免并行测试互相影响
+#[cfg(test)]
+pub(crate) static DEFAULT_ROOT_CAS_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// 签名者公钥的强度要求，默认不做限制
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyStrengthPolicy {
+    /// RSA 公钥的最小位数
+    pub min_rsa_bits: Option<u32>,
+    /// 允许的 EC 曲线（OpenSSL 短名称，如 prime256v1、secp384r1、SM2），为空时不限制
+    pub allowed_ec_curves: Vec<String>,
+}
+
+impl KeyStrengthPolicy {
+    /// 是否没有任何要求
+    pub fn is_permissive(&self) -> bool {
+        self.min_rsa_bits.is_none() && self.allowed_ec_curves.is_empty()
+    }
+
+    /// 检查证书公钥，不满足要求时返回 `SignatureError`
+    pub fn check_cert(&self, cert: &X509Ref) -> Result<()> {
+        let pkey = cert
+            .public_key()
+            .map_err(|e| CrateSpecError::SignatureError(tr_format!("无法读取签名者公钥: {}", "cannot read the signer public key: {}", e)))?;
+        if let (Some(min_bits), Ok(_)) = (self.min_rsa_bits, pkey.rsa()) {
+            if pkey.bits() < min_bits {
+                return Err(CrateSpecError::SignatureError(tr_format!(
+                    "签名者 RSA 密钥为 {} 位，低于要求的 {} 位",
+                    "the signer RSA key has {} bits, below the required {} bits",
+                    pkey.bits(),
+                    min_bits
+                )));
+            }
+        }
+        if let (false, Ok(ec_key)) = (self.allowed_ec_curves.is_empty(), pkey.ec_key()) {
+            let curve = ec_key
+                .group()
+                .curve_name()
+                .and_then(|nid| nid.short_name().ok())
+                .unwrap_or(tr("未命名曲线", "unnamed curve"));
+            if !self.allowed_ec_curves.iter().any(|allowed| allowed.eq_ignore_ascii_case(curve)) {
+                return Err(CrateSpecError::SignatureError(tr_format!(
+                    "签名者 EC 密钥的曲线 {} 不在允许列表中: {}",
+                    "the signer EC curve {} is not in the allowed list: {}",
+                    curve,
+                    self.allowed_ec_curves.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 签名者密钥类型（见 [`PKCS::key_algo`]）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyAlgo {
+    Rsa { bits: u32 },
+    /// ECDSA，附带曲线的 OpenSSL 短名称（如 prime256v1）
+    Ec { curve: String },
+    Ed25519,
+    Ed448,
+    /// 其他类型，附带 OpenSSL 的密钥类型编号
+    Other(i32),
+}
+
+impl KeyAlgo {
+    fn of<T: HasPublic>(pkey: &PKeyRef<T>) -> Self {
+        match pkey.id() {
+            Id::RSA => KeyAlgo::Rsa { bits: pkey.bits() },
+            Id::EC => KeyAlgo::Ec {
+                curve: pkey
+                    .ec_key()
+                    .ok()
+                    .and_then(|ec_key| ec_key.group().curve_name())
+                    .and_then(|nid| nid.short_name().ok())
+                    .unwrap_or(tr("未命名曲线", "unnamed curve"))
+                    .to_string(),
+            },
+            Id::ED25519 => KeyAlgo::Ed25519,
+            Id::ED448 => KeyAlgo::Ed448,
+            id => KeyAlgo::Other(id.as_raw()),
+        }
+    }
+
+    /// 本地签名（PKCS#7）能否使用该类型的密钥：OpenSSL 的 PKCS#7 实现不支持 EdDSA
+    pub fn supports_pkcs7(&self) -> bool {
+        !matches!(self, KeyAlgo::Ed25519 | KeyAlgo::Ed448)
+    }
+}
+
+impl Display for KeyAlgo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyAlgo::Rsa { bits } => f.write_str(&tr_format!("RSA {} 位", "RSA {} bits", bits)),
+            KeyAlgo::Ec { curve } => write!(f, "ECDSA {}", curve),
+            KeyAlgo::Ed25519 => write!(f, "Ed25519"),
+            KeyAlgo::Ed448 => write!(f, "Ed448"),
+            KeyAlgo::Other(id) => f.write_str(&tr_format!("未知密钥类型 {}", "unknown key type {}", id)),
+        }
+    }
+}
+
+/// 签名验证通过后，证书链最终锚定的根 CA
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrustAnchor {
+    /// 证书链终止于传入的第 `index` 个根 CA
+    Root { index: usize, subject: String },
+    /// 无法把证书链解析到传入的某个根 CA，附带原因
+    Unresolved(String),
+}
+
+impl Display for TrustAnchor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrustAnchor::Root { index, subject } => f.write_str(&tr_format!("{}（根 CA #{}）", "{} (root CA #{})", subject, index)),
+            TrustAnchor::Unresolved(reason) => f.write_str(&tr_format!("未能确定信任锚: {}", "trust anchor unresolved: {}", reason)),
+        }
+    }
+}
+
+fn name_to_string(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry.data().to_string().unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 签名中携带的证书，以及其中的签名者证书
+///
+/// 只接受恰好一个签名者：`Pkcs7::verify` 会接受每一个签名者，而密钥强度、证书用途和签名时间
+/// 都只针对这里返回的签名者检查，多出的签名者会绕过这些检查。
+fn embedded_certs_and_signer(pkcs7: &Pkcs7) -> std::result::Result<(Stack<X509>, X509), String> {
+    let mut untrusted = Stack::new().map_err(|e| e.to_string())?;
+    if let Some(embedded) = pkcs7.signed().and_then(|signed| signed.certificates()) {
+        for cert in embedded {
+            untrusted.push(cert.to_owned()).map_err(|e| e.to_string())?;
+        }
+    }
+    let signers = pkcs7
+        .signers(&untrusted, PKCS7_FLAGS)
+        .map_err(|e| tr_format!("无法取得签名者证书: {}", "cannot get the signer certificate: {}", e))?;
+    if signers.len() > 1 {
+        return Err(tr_format!("签名中有 {} 个签名者，只支持一个", "the signature has {} signers, only one is supported", signers.len()));
+    }
+    let signer = signers.get(0).ok_or(tr("签名中没有签名者证书", "the signature has no signer certificate"))?.to_owned();
+    Ok((untrusted, signer))
+}
+
+/// 用签名中携带的证书重建签名者的证书链，找出链顶对应的根 CA
+fn trust_anchor(pkcs7: &Pkcs7, store: &X509StoreRef, root_cas: &[X509]) -> std::result::Result<TrustAnchor, String> {
+    let (untrusted, signer) = embedded_certs_and_signer(pkcs7)?;
+
+    let mut ctx = X509StoreContext::new().map_err(|e| e.to_string())?;
+    let top = ctx
+        .init(store, &signer, &untrusted, |ctx| {
+            if !ctx.verify_cert()? {
+                return Ok(Err(tr_format!("证书链验证失败: {}", "certificate chain verification failed: {}", ctx.error())));
+            }
+            Ok(ctx.chain().and_then(|chain| chain.iter().last().map(|cert| cert.to_owned())).ok_or_else(|| tr("证书链为空", "the certificate chain is empty").to_string()))
+        })
+        .map_err(|e| e.to_string())??;
+
+    let top_der = top.to_der().map_err(|e| e.to_string())?;
+    for (index, root_ca) in root_cas.iter().enumerate() {
+        if root_ca.to_der().map_err(|e| e.to_string())? == top_der {
+            return Ok(TrustAnchor::Root { index, subject: name_to_string(root_ca.subject_name()) });
+        }
+    }
+    Err(tr_format!("证书链顶端 {} 不在提供的根 CA 中", "the chain top {} is not among the given root CAs", name_to_string(top.subject_name())))
+}
+
+/// OpenSSL 的 `X509_V_ERR_CERT_CHAIN_TOO_LONG`，openssl crate 没有导出该常量
+const X509_V_ERR_CERT_CHAIN_TOO_LONG: c_int = 22;
+
+/// 用验签时的证书存储单独验证签名者的证书链，返回 OpenSSL 的验证结果
+fn chain_verify_result(pkcs7: &Pkcs7, store: &X509StoreRef) -> std::result::Result<X509VerifyResult, String> {
+    let (untrusted, signer) = embedded_certs_and_signer(pkcs7)?;
+    let mut ctx = X509StoreContext::new().map_err(|e| e.to_string())?;
+    ctx.init(store, &signer, &untrusted, |ctx| {
+        ctx.verify_cert()?;
+        Ok(ctx.error())
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// 取出签名者认证属性中的 signingTime，没有该属性时返回 None
+///
+/// 只读取受签名保护的认证属性（authenticatedAttributes），不在整个 PKCS7 中查找：
+/// 签名中携带的证书不受签名保护，任何人都可以往里加入看起来像签名时间的内容。
+fn signing_time(pkcs7: &Pkcs7) -> std::result::Result<Option<Asn1Time>, String> {
+    if pkcs7.signed().is_none() {
+        return Err(tr("PKCS7 不是 signedData 类型", "the PKCS7 is not of type signedData").to_string());
+    }
+    // SAFETY: 已确认为 signedData，signer info 栈和属性都归 pkcs7 所有，只在本函数内借用
+    unsafe {
+        let infos = ffi::PKCS7_get_signer_info(pkcs7.as_ptr());
+        if infos.is_null() {
+            return Err(tr("签名中没有签名者信息", "the signature has no signer info").to_string());
+        }
+        let info = StackRef::<Pkcs7SignerInfo>::from_ptr(infos)
+            .get(0)
+            .ok_or(tr("签名中没有签名者信息", "the signature has no signer info"))?;
+        let attr = ffi::PKCS7_get_signed_attribute(info.as_ptr(), ffi::NID_pkcs9_signingTime);
+        if attr.is_null() {
+            return Ok(None);
+        }
+        if !matches!((*attr).type_, ffi::V_ASN1_UTCTIME | ffi::V_ASN1_GENERALIZEDTIME) {
+            return Err(tr("签名时间属性格式错误", "malformed signingTime attribute").to_string());
+        }
+        let time = (*attr).value.asn1_string;
+        let len = usize::try_from(ffi::ASN1_STRING_length(time)).map_err(|_| tr("签名时间属性格式错误", "malformed signingTime attribute").to_string())?;
+        let bytes = std::slice::from_raw_parts(ffi::ASN1_STRING_get0_data(time), len);
+        let text = std::str::from_utf8(bytes).map_err(|_| tr("签名时间不是有效的 ASCII", "signingTime is not valid ASCII").to_string())?;
+        Asn1Time::from_str(text)
+            .map(Some)
+            .map_err(|e| tr_format!("无法解析签名时间 {}: {}", "cannot parse signingTime {}: {}", text, e))
+    }
+}
+
+/// 证书的 keyUsage 扩展是否允许数字签名，没有该扩展时返回 None
+///
+/// 由 OpenSSL 解析扩展：扩展格式错误时 OpenSSL 会置 `EXFLAG_INVALID`。
+fn key_usage_allows_signing(cert: &X509Ref) -> std::result::Result<Option<bool>, String> {
+    // SAFETY: cert 在调用期间有效；两个函数只读取（并缓存）OpenSSL 解析出的扩展信息
+    unsafe {
+        let flags = ffi::X509_get_extension_flags(cert.as_ptr());
+        if flags & ffi::EXFLAG_INVALID != 0 {
+            return Err(tr("证书扩展格式错误", "malformed certificate extensions").to_string());
+        }
+        if flags & ffi::EXFLAG_KUSAGE == 0 {
+            return Ok(None);
+        }
+        Ok(Some(ffi::X509_get_key_usage(cert.as_ptr()) & ffi::X509v3_KU_DIGITAL_SIGNATURE != 0))
+    }
+}
+
+/// 检查签名者证书当前在有效期内，且 keyUsage 扩展（如有）允许数字签名
+fn check_signer_cert(signer: &X509Ref) -> Result<()> {
+    let subject = name_to_string(signer.subject_name());
+    let now = Asn1Time::days_from_now(0)
+        .map_err(|e| CrateSpecError::Other(tr_format!("无法获取当前时间: {}", "cannot get the current time: {}", e)))?;
+    if *signer.not_before() > *now {
+        return Err(CrateSpecError::SignatureError(tr_format!(
+            "签名证书 {} 尚未生效，生效时间为 {}",
+            "signing certificate {} is not valid before {}",
+            subject,
+            signer.not_before()
+        )));
+    }
+    if *signer.not_after() < *now {
+        return Err(CrateSpecError::SignatureError(tr_format!(
+            "签名证书 {} 已于 {} 过期",
+            "signing certificate {} expired on {}",
+            subject,
+            signer.not_after()
+        )));
+    }
+    match key_usage_allows_signing(signer).map_err(|e| CrateSpecError::SignatureError(tr_format!("签名证书 {}: {}", "signing certificate {}: {}", subject, e)))? {
+        Some(false) => Err(CrateSpecError::SignatureError(tr_format!(
+            "签名证书 {} 的 keyUsage 不含 digitalSignature，不能用于签名",
+            "the keyUsage of signing certificate {} lacks digitalSignature, so it cannot sign",
+            subject
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// 存放在 OpenSSL engine（如 PKCS#11 HSM）中的私钥
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineKey {
+    pub engine_id: String,
+    pub key_id: String,
+}
+
+impl EngineKey {
+    #[cfg(feature = "engine")]
+    fn load(&self) -> Result<PKey<Private>> {
+        crate::utils::engine::load_private_key(&self.engine_id, &self.key_id)
+    }
+
+    #[cfg(not(feature = "engine"))]
+    fn load(&self) -> Result<PKey<Private>> {
+        Err(CrateSpecError::ConfigError(tr_format!(
+            "使用 engine {} 中的私钥需要以 engine 特性编译",
+            "using a private key from engine {} requires building with the engine feature",
+            self.engine_id
+        )))
+    }
+}
+
+#[derive(Clone, PartialEq)]
 pub struct PKCS {
     cert_bin: Vec<u8>,
     pkey_bin: Vec<u8>,
+    engine_key: Option<EngineKey>,
     root_ca_bins: Vec<Vec<u8>>,
+    /// 签名时嵌入的中间 CA 证书（PEM，每个文件可包含多张证书）
+    chain_bins: Vec<Vec<u8>>,
+}
+
+const PEM_BEGIN: &[u8] = b"-----BEGIN ";
+
+/// 内容中含 PEM 块（`-----BEGIN `）时视为 PEM，否则按 DER 解析
+fn is_pem(bin: &[u8]) -> bool {
+    bin.windows(PEM_BEGIN.len()).any(|w| w == PEM_BEGIN)
+}
+
+/// 签名者证书为 DER 时转换为 PEM，PEM 原样返回
+fn cert_to_pem(bin: Vec<u8>, path: &str) -> Result<Vec<u8>> {
+    if is_pem(&bin) {
+        return Ok(bin);
+    }
+    X509::from_der(&bin)
+        .and_then(|cert| cert.to_pem())
+        .map_err(|e| CrateSpecError::ParseError(tr_format!("证书 {} 既不是 PEM 也不是有效的 DER: {}", "certificate {} is neither PEM nor valid DER: {}", path, e)))
+}
+
+/// 私钥为 DER（PKCS#8 或传统格式）时转换为 PKCS#8 PEM，PEM 原样返回
+fn private_key_to_pem(bin: Vec<u8>, path: &str) -> Result<Vec<u8>> {
+    if is_pem(&bin) {
+        return Ok(bin);
+    }
+    PKey::private_key_from_der(&bin)
+        .and_then(|pkey| pkey.private_key_to_pem_pkcs8())
+        .map_err(|e| CrateSpecError::ParseError(tr_format!("私钥 {} 既不是 PEM 也不是有效的 DER: {}", "private key {} is neither PEM nor valid DER: {}", path, e)))
 }
 
 impl Debug for PKCS {
@@ -29,7 +462,9 @@ impl PKCS {
         Self {
             cert_bin: vec![],
             pkey_bin: vec![],
+            engine_key: None,
             root_ca_bins: vec![],
+            chain_bins: vec![],
         }
     }
     pub fn root_ca_bins(ca_paths: Vec<String>) -> Result<Vec<Vec<u8>>> {
@@ -44,6 +479,7 @@ impl PKCS {
     }
 
     // load certificate, private key and root ca from file.
+    // certificate and private key may be PEM or DER (PKCS#8 or traditional), DER is converted to PEM.
 
     pub fn load_from_file_writer(
         &mut self,
@@ -52,11 +488,36 @@ impl PKCS {
         ca_paths: Vec<String>,
     ) -> Result<()> {
         let cert_path_buf = Path::new(cert_path.as_str());
-        self.cert_bin = fs::read(cert_path_buf)
+        let cert_bin = fs::read(cert_path_buf)
             .map_err(|_e| CrateSpecError::FileNotFound(cert_path_buf.to_path_buf()))?;
+        self.cert_bin = cert_to_pem(cert_bin, &cert_path)?;
         let pkey_path_buf = Path::new(pkey_path.as_str());
-        self.pkey_bin = fs::read(pkey_path_buf)
+        let pkey_bin = fs::read(pkey_path_buf)
             .map_err(|_e| CrateSpecError::FileNotFound(pkey_path_buf.to_path_buf()))?;
+        self.pkey_bin = private_key_to_pem(pkey_bin, &pkey_path)?;
+        self.check_key_algo()?;
+        for ca_path in ca_paths {
+            let ca_path_buf = Path::new(ca_path.as_str());
+            let ca_bin = fs::read(ca_path_buf)
+                .map_err(|_e| CrateSpecError::FileNotFound(ca_path_buf.to_path_buf()))?;
+            self.root_ca_bins.push(ca_bin);
+        }
+        Ok(())
+    }
+
+    /// 从文件加载证书和根 CA，私钥保留在 OpenSSL engine 中，签名时才通过 engine 使用
+    pub fn load_from_engine_writer(
+        &mut self,
+        cert_path: String,
+        engine_key: EngineKey,
+        ca_paths: Vec<String>,
+    ) -> Result<()> {
+        let cert_path_buf = Path::new(cert_path.as_str());
+        let cert_bin = fs::read(cert_path_buf)
+            .map_err(|_e| CrateSpecError::FileNotFound(cert_path_buf.to_path_buf()))?;
+        self.cert_bin = cert_to_pem(cert_bin, &cert_path)?;
+        self.check_key_algo()?;
+        self.engine_key = Some(engine_key);
         for ca_path in ca_paths {
             let ca_path_buf = Path::new(ca_path.as_str());
             let ca_bin = fs::read(ca_path_buf)
@@ -66,6 +527,53 @@ impl PKCS {
         Ok(())
     }
 
+    /// 加载签名者证书的中间 CA 证书，签名时嵌入 PKCS7，验证方只需信任根 CA 即可建立证书链
+    pub fn load_chain_from_files(&mut self, chain_paths: Vec<String>) -> Result<()> {
+        for chain_path in chain_paths {
+            let chain_path_buf = Path::new(chain_path.as_str());
+            let chain_bin = fs::read(chain_path_buf)
+                .map_err(|_e| CrateSpecError::FileNotFound(chain_path_buf.to_path_buf()))?;
+            let certs = X509::stack_from_pem(chain_bin.as_slice())
+                .map_err(|e| CrateSpecError::ParseError(tr_format!("解析中间 CA 证书 {} 失败: {}", "failed to parse intermediate CA certificate {}: {}", chain_path, e)))?;
+            if certs.is_empty() {
+                return Err(CrateSpecError::ParseError(tr_format!("{} 中没有证书", "{} contains no certificates", chain_path)));
+            }
+            self.chain_bins.push(chain_bin);
+        }
+        Ok(())
+    }
+
+    /// 签名者证书公钥的类型，私钥在 engine 中时同样适用
+    pub fn key_algo(&self) -> Result<KeyAlgo> {
+        let cert = X509::from_pem(self.cert_bin.as_slice())
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("解析证书失败: {}", "failed to parse the certificate: {}", e)))?;
+        let pkey = cert
+            .public_key()
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("无法读取证书公钥: {}", "cannot read the certificate public key: {}", e)))?;
+        Ok(KeyAlgo::of(&pkey))
+    }
+
+    /// 加载时拒绝 PKCS#7 无法使用的密钥类型，而不是等到签名时才报 OpenSSL 错误
+    fn check_key_algo(&self) -> Result<()> {
+        let algo = self.key_algo()?;
+        if !algo.supports_pkcs7() {
+            return Err(CrateSpecError::ValidationError(tr_format!(
+                "本地签名使用 PKCS#7，不支持 {} 密钥，请使用 RSA 或 ECDSA 证书，或在网络模式下使用 Ed25519",
+                "local signatures use PKCS#7, which does not support {} keys; use an RSA or ECDSA certificate, or Ed25519 in network mode",
+                algo
+            )));
+        }
+        Ok(())
+    }
+
+    fn private_key(&self) -> Result<PKey<Private>> {
+        match &self.engine_key {
+            Some(engine_key) => engine_key.load(),
+            None => PKey::private_key_from_pem(self.pkey_bin.as_slice())
+                .map_err(|e| CrateSpecError::ParseError(tr_format!("解析私钥失败: {}", "failed to parse the private key: {}", e))),
+        }
+    }
+
     pub fn load_from_file_reader(&mut self, ca_paths: Vec<String>) -> Result<()> {
         for ca_path in ca_paths {
             let ca_path_buf = Path::new(ca_path.as_str());
@@ -77,65 +585,163 @@ impl PKCS {
     }
 
     pub fn encode_pkcs_bin(&self, message: &[u8]) -> Result<Vec<u8>> {
-        //FIXME current we don't support middle certs
         let cert = X509::from_pem(self.cert_bin.as_slice())
-            .map_err(|e| CrateSpecError::ParseError(format!("解析证书失败: {}", e)))?;
-        let certs = Stack::new()
-            .map_err(|e| CrateSpecError::Other(format!("创建证书栈失败: {}", e)))?;
-        let flags = Pkcs7Flags::STREAM;
-        let pkey = PKey::private_key_from_pem(self.pkey_bin.as_slice())
-            .map_err(|e| CrateSpecError::ParseError(format!("解析私钥失败: {}", e)))?;
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("解析证书失败: {}", "failed to parse the certificate: {}", e)))?;
+        let mut certs = Stack::new()
+            .map_err(|e| CrateSpecError::Other(tr_format!("创建证书栈失败: {}", "failed to create certificate stack: {}", e)))?;
+        for chain_bin in self.chain_bins.iter() {
+            let chain = X509::stack_from_pem(chain_bin.as_slice())
+                .map_err(|e| CrateSpecError::ParseError(tr_format!("解析中间 CA 证书失败: {}", "failed to parse the intermediate CA certificate: {}", e)))?;
+            for int_cert in chain {
+                certs.push(int_cert)
+                    .map_err(|e| CrateSpecError::Other(tr_format!("添加中间 CA 证书失败: {}", "failed to add intermediate CA certificate: {}", e)))?;
+            }
+        }
+        let flags = PKCS7_FLAGS;
+        let pkey = self.private_key()?;
         let mut store_builder = X509StoreBuilder::new()
-            .map_err(|e| CrateSpecError::Other(format!("创建证书存储构建器失败: {}", e)))?;
+            .map_err(|e| CrateSpecError::Other(tr_format!("创建证书存储构建器失败: {}", "failed to create certificate store builder: {}", e)))?;
 
         for root_ca_bin in self.root_ca_bins.iter() {
             let root_ca = X509::from_pem(root_ca_bin.as_slice())
-                .map_err(|e| CrateSpecError::ParseError(format!("解析根 CA 证书失败: {}", e)))?;
+                .map_err(|e| CrateSpecError::ParseError(tr_format!("解析根 CA 证书失败: {}", "failed to parse the root CA certificate: {}", e)))?;
             store_builder.add_cert(root_ca)
-                .map_err(|e| CrateSpecError::Other(format!("添加根 CA 证书失败: {}", e)))?;
+                .map_err(|e| CrateSpecError::Other(tr_format!("添加根 CA 证书失败: {}", "failed to add root CA certificate: {}", e)))?;
         }
 
         let _store = store_builder.build();
 
         let pkcs7 = Pkcs7::sign(&cert, &pkey, &certs, message, flags)
-            .map_err(|e| CrateSpecError::SignatureError(format!("PKCS7 签名失败: {}", e)))?;
+            .map_err(|e| CrateSpecError::SignatureError(tr_format!("PKCS7 签名失败: {}", "PKCS7 signing failed: {}", e)))?;
 
         pkcs7.to_smime(message, flags)
-            .map_err(|e| CrateSpecError::SignatureError(format!("生成 S/MIME 数据失败: {}", e)))
+            .map_err(|e| CrateSpecError::SignatureError(tr_format!("生成 S/MIME 数据失败: {}", "failed to produce S/MIME data: {}", e)))
     }
 
-    pub fn decode_pkcs_bin(signed_bin: &[u8], root_ca_bins: &[Vec<u8>]) -> Result<Vec<u8>> {
+    /// 验证 S/MIME 签名并取出签名内容
+    ///
+    /// 签名中嵌入的中间 CA 证书（见 [`PKCS::load_chain_from_files`]）用于建立到根 CA 的证书链，本身不被信任。
+    /// `max_chain_depth` 为证书链中允许出现的中间 CA 证书的最大数量，超过时验签失败。
+    /// 签名者证书不在有效期内，或其 keyUsage 扩展不含 digitalSignature 时返回说明原因的 `SignatureError`。
+    pub fn decode_pkcs_bin(signed_bin: &[u8], root_ca_bins: &[Vec<u8>], max_chain_depth: u32) -> Result<Vec<u8>> {
+        Self::verify_pkcs_bin(signed_bin, root_ca_bins, max_chain_depth).map(|(content, _)| content)
+    }
+
+    /// 同 [`PKCS::decode_pkcs_bin`]，另外返回证书链终止于哪个根 CA
+    pub fn verify_pkcs_bin(
+        signed_bin: &[u8],
+        root_ca_bins: &[Vec<u8>],
+        max_chain_depth: u32,
+    ) -> Result<(Vec<u8>, TrustAnchor)> {
         //FIXME maybe all pkcs section should share same root cas
         let certs = Stack::new()
-            .map_err(|e| CrateSpecError::Other(format!("创建证书栈失败: {}", e)))?;
-        let flags = Pkcs7Flags::STREAM;
+            .map_err(|e| CrateSpecError::Other(tr_format!("创建证书栈失败: {}", "failed to create certificate stack: {}", e)))?;
+        let flags = PKCS7_FLAGS;
         let mut store_builder = X509StoreBuilder::new()
-            .map_err(|e| CrateSpecError::Other(format!("创建证书存储构建器失败: {}", e)))?;
+            .map_err(|e| CrateSpecError::Other(tr_format!("创建证书存储构建器失败: {}", "failed to create certificate store builder: {}", e)))?;
 
+        let mut root_cas = vec![];
         for root_ca_bin in root_ca_bins.iter() {
             let root_ca = X509::from_pem(root_ca_bin.as_slice())
-                .map_err(|e| CrateSpecError::ParseError(format!("解析根 CA 证书失败: {}", e)))?;
-            store_builder.add_cert(root_ca)
-                .map_err(|e| CrateSpecError::Other(format!("添加根 CA 证书失败: {}", e)))?;
+                .map_err(|e| CrateSpecError::ParseError(tr_format!("解析根 CA 证书失败: {}", "failed to parse the root CA certificate: {}", e)))?;
+            store_builder.add_cert(root_ca.clone())
+                .map_err(|e| CrateSpecError::Other(tr_format!("添加根 CA 证书失败: {}", "failed to add root CA certificate: {}", e)))?;
+            root_cas.push(root_ca);
         }
 
+        let depth = i32::try_from(max_chain_depth)
+            .map_err(|_| CrateSpecError::ValidationError(tr_format!("无效的证书链深度: {}", "invalid certificate chain depth: {}", max_chain_depth)))?;
+        let mut verify_param = X509VerifyParam::new()
+            .map_err(|e| CrateSpecError::Other(tr_format!("创建验证参数失败: {}", "failed to create verify parameters: {}", e)))?;
+        verify_param.set_depth(depth);
+        store_builder.set_param(&verify_param)
+            .map_err(|e| CrateSpecError::Other(tr_format!("设置证书链深度失败: {}", "failed to set the chain depth: {}", e)))?;
+
         let store = store_builder.build();
 
         let (pkcs7_decoded, _content) = Pkcs7::from_smime(signed_bin)
-            .map_err(|e| CrateSpecError::ParseError(format!("解析 S/MIME 数据失败: {}", e)))?;
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("解析 S/MIME 数据失败: {}", "failed to parse the S/MIME data: {}", e)))?;
+        // 先单独检查签名者证书，给出比 OpenSSL 证书链错误更明确的原因
+        let (_, signer) = embedded_certs_and_signer(&pkcs7_decoded).map_err(CrateSpecError::SignatureError)?;
+        check_signer_cert(&signer)?;
 
         let mut output = Vec::new();
         pkcs7_decoded
             .verify(&certs, &store, None, Some(&mut output), flags)
-            .map_err(|e| CrateSpecError::SignatureError(format!("PKCS7 验证失败: {}", e)))?;
-        Ok(output)
+            .map_err(|e| {
+                let too_long = chain_verify_result(&pkcs7_decoded, &store)
+                    .is_ok_and(|result| result.as_raw() == X509_V_ERR_CERT_CHAIN_TOO_LONG);
+                if too_long {
+                    CrateSpecError::SignatureError(tr_format!(
+                        "PKCS7 验证失败: 证书链长度超过上限 {}: {}",
+                        "PKCS7 verification failed: the certificate chain is longer than {}: {}",
+                        max_chain_depth, e
+                    ))
+                } else {
+                    CrateSpecError::SignatureError(tr_format!("PKCS7 验证失败: {}", "PKCS7 verification failed: {}", e))
+                }
+            })?;
+        let anchor = trust_anchor(&pkcs7_decoded, &store, &root_cas).unwrap_or_else(TrustAnchor::Unresolved);
+        Ok((output, anchor))
     }
 
-    pub fn gen_digest_256(&self, bin: &[u8]) -> Result<Vec<u8>> {
-        let res = hash(MessageDigest::sha256(), bin)
-            .map_err(|e| CrateSpecError::Other(format!("生成 SHA256 摘要失败: {}", e)))?;
+    /// 检查签名者证书的公钥是否满足强度要求；要求为空时不解析签名直接通过
+    pub fn check_signer_key(signed_bin: &[u8], policy: &KeyStrengthPolicy) -> Result<()> {
+        if policy.is_permissive() {
+            return Ok(());
+        }
+        let (pkcs7, _content) = Pkcs7::from_smime(signed_bin)
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("解析 S/MIME 数据失败: {}", "failed to parse the S/MIME data: {}", e)))?;
+        let (_, signer) = embedded_certs_and_signer(&pkcs7).map_err(CrateSpecError::SignatureError)?;
+        policy.check_cert(&signer)
+    }
+
+    /// 签名者证书的主题，只解析签名，不验证
+    pub fn signer_subject(signed_bin: &[u8]) -> Result<String> {
+        let (pkcs7, _content) = Pkcs7::from_smime(signed_bin)
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("解析 S/MIME 数据失败: {}", "failed to parse the S/MIME data: {}", e)))?;
+        let (_, signer) = embedded_certs_and_signer(&pkcs7).map_err(CrateSpecError::SignatureError)?;
+        Ok(name_to_string(signer.subject_name()))
+    }
+
+    /// 检查签名记录的时间（signingTime 属性）是否落在签名者证书的有效期内
+    ///
+    /// 返回签名时间；签名中没有记录时间时返回 None。只检查时间，不验证签名本身。
+    pub fn verify_signing_time(signed_bin: &[u8]) -> Result<Option<String>> {
+        let (pkcs7, _content) = Pkcs7::from_smime(signed_bin)
+            .map_err(|e| CrateSpecError::ParseError(tr_format!("解析 S/MIME 数据失败: {}", "failed to parse the S/MIME data: {}", e)))?;
+        let Some(time) = signing_time(&pkcs7).map_err(CrateSpecError::SignatureError)? else {
+            return Ok(None);
+        };
+        let (_, signer) = embedded_certs_and_signer(&pkcs7).map_err(CrateSpecError::SignatureError)?;
+        let (not_before, not_after) = (signer.not_before(), signer.not_after());
+        if *time < *not_before || *time > *not_after {
+            return Err(CrateSpecError::SignatureError(tr_format!(
+                "签名时间 {} 不在签名证书 {} 的有效期 [{}, {}] 内",
+                "signing time {} is outside the validity [{2}, {3}] of signing certificate {1}",
+                *time,
+                name_to_string(signer.subject_name()),
+                not_before,
+                not_after
+            )));
+        }
+        Ok(Some((*time).to_string()))
+    }
+
+    /// 以常量时间比较两个摘要，避免逐字节比较带来的时序侧信道；长度不同时直接返回 false
+    pub fn digest_eq(a: &[u8], b: &[u8]) -> bool {
+        a.len() == b.len() && memcmp::eq(a, b)
+    }
+
+    pub fn gen_digest(&self, algo: DigestAlgo, bin: &[u8]) -> Result<Vec<u8>> {
+        let res = hash(algo.message_digest(), bin)
+            .map_err(|e| CrateSpecError::Other(tr_format!("生成 {} 摘要失败: {}", "failed to compute {} digest: {}", algo, e)))?;
         Ok(res.to_vec())
     }
+
+    pub fn gen_digest_256(&self, bin: &[u8]) -> Result<Vec<u8>> {
+        self.gen_digest(DigestAlgo::Sha256, bin)
+    }
 }
 
 impl Default for PKCS {
@@ -143,6 +749,357 @@ impl Default for PKCS {
         Self::new()
     }
 }
+
+#[test]
+fn test_digest_algo() {
+    let pkcs = PKCS::new();
+    for (algo, len) in [(DigestAlgo::Sha256, 32), (DigestAlgo::Sha384, 48), (DigestAlgo::Sha512, 64)] {
+        assert_eq!(algo.output_len(), len);
+        assert_eq!(pkcs.gen_digest(algo, b"crate-spec").unwrap().len(), len);
+        assert_eq!(DigestAlgo::from_u8(algo.as_u8()).unwrap(), algo);
+        assert_eq!(algo.to_string().parse::<DigestAlgo>().unwrap(), algo);
+    }
+    assert_eq!(pkcs.gen_digest_256(b"crate-spec").unwrap(), openssl::sha::sha256(b"crate-spec").to_vec());
+    assert_eq!("SHA-384".parse::<DigestAlgo>().unwrap(), DigestAlgo::Sha384);
+    assert!("md5".parse::<DigestAlgo>().is_err());
+    assert!(DigestAlgo::from_u8(3).is_err());
+}
+
+#[test]
+fn test_pkcs_binary_digest() {
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    // 摘要是任意二进制数据，包含换行符时也必须原样往返
+    let pkcs = signer();
+    let digest = b"\n\r\n\x00digest\r".to_vec();
+    let signed = pkcs.encode_pkcs_bin(digest.as_slice()).unwrap();
+    let root_cas = root_ca_bins();
+    assert_eq!(PKCS::decode_pkcs_bin(signed.as_slice(), &root_cas, DEFAULT_MAX_CHAIN_DEPTH).unwrap(), digest);
+}
+
+#[test]
+fn test_pkcs_der_cert_and_key() {
+    use crate::utils::testing::{TestDir, root_ca_bins};
+
+    let dir = TestDir::new("pkcs-der");
+    let cert = X509::from_pem(&fs::read("test/cert.pem").unwrap()).unwrap();
+    let pkey = PKey::private_key_from_pem(&fs::read("test/key.pem").unwrap()).unwrap();
+    let cert_der = dir.write("cert.der", cert.to_der().unwrap());
+    let pkcs8_der = dir.write("key.pk8", pkey.private_key_to_pkcs8().unwrap());
+    let traditional_der = dir.write("key.der", pkey.private_key_to_der().unwrap());
+    let root_cas = root_ca_bins();
+
+    let certs = ["test/cert.pem".to_string(), cert_der.to_str().unwrap().to_string()];
+    let keys = [
+        "test/key.pem".to_string(),
+        pkcs8_der.to_str().unwrap().to_string(),
+        traditional_der.to_str().unwrap().to_string(),
+    ];
+    for cert_path in certs.iter() {
+        for key_path in keys.iter() {
+            let mut pkcs = PKCS::new();
+            pkcs.load_from_file_writer(cert_path.clone(), key_path.clone(), vec![]).unwrap();
+            let signed = pkcs.encode_pkcs_bin(b"digest").unwrap();
+            let content = PKCS::decode_pkcs_bin(signed.as_slice(), &root_cas, DEFAULT_MAX_CHAIN_DEPTH).unwrap();
+            assert_eq!(content, b"digest", "{} / {}", cert_path, key_path);
+        }
+    }
+
+    // 既不是 PEM 也不是 DER 的文件在加载时报错
+    let garbage = dir.write("garbage.der", b"not a key");
+    let err = PKCS::new()
+        .load_from_file_writer(certs[0].clone(), garbage.to_str().unwrap().to_string(), vec![])
+        .unwrap_err();
+    assert!(err.to_string().contains("garbage.der"), "{}", err);
+}
+
+#[test]
+fn test_pkcs_ec_and_eddsa_keys() {
+    use crate::utils::testing::{TestDir, root_ca_bins};
+    use openssl::x509::X509NameBuilder;
+
+    let mut pkcs = PKCS::new();
+    pkcs.load_from_file_writer(
+        "test/ec/cert.pem".to_string(),
+        "test/ec/key.pem".to_string(),
+        ["test/ec/root-ca.pem".to_string()].to_vec(),
+    ).unwrap();
+    assert_eq!(pkcs.key_algo().unwrap(), KeyAlgo::Ec { curve: "prime256v1".to_string() });
+    let digest = pkcs.gen_digest_256(b"crate-spec").unwrap();
+    let signed = pkcs.encode_pkcs_bin(&digest).unwrap();
+    let root_cas = PKCS::root_ca_bins(["test/ec/root-ca.pem".to_string()].to_vec()).unwrap();
+    assert_eq!(PKCS::decode_pkcs_bin(&signed, &root_cas, DEFAULT_MAX_CHAIN_DEPTH).unwrap(), digest);
+    let rsa_root_cas = root_ca_bins();
+    assert!(PKCS::decode_pkcs_bin(&signed, &rsa_root_cas, DEFAULT_MAX_CHAIN_DEPTH).is_err());
+
+    let mut rsa = PKCS::new();
+    rsa.load_from_file_writer("test/cert.pem".to_string(), "test/key.pem".to_string(), vec![]).unwrap();
+    assert!(matches!(rsa.key_algo().unwrap(), KeyAlgo::Rsa { .. }));
+
+    // OpenSSL 的 PKCS#7 不支持 Ed25519，加载时即报错
+    let pkey = PKey::generate_ed25519().unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "ed25519").unwrap();
+    let name = name.build();
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder.set_pubkey(&pkey).unwrap();
+    builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    builder.sign(&pkey, MessageDigest::null()).unwrap();
+    let dir = TestDir::new("pkcs-ed25519");
+    let cert_path = dir.write("cert.pem", builder.build().to_pem().unwrap());
+    let key_path = dir.write("key.pem", pkey.private_key_to_pem_pkcs8().unwrap());
+    let err = PKCS::new()
+        .load_from_file_writer(cert_path.to_str().unwrap().to_string(), key_path.to_str().unwrap().to_string(), vec![])
+        .unwrap_err();
+    assert!(matches!(&err, CrateSpecError::ValidationError(msg) if msg.contains("Ed25519")), "{}", err);
+}
+
+#[test]
+fn test_signer_cert_validity_and_key_usage() {
+    use openssl::x509::extension::KeyUsage;
+    use openssl::x509::X509NameBuilder;
+
+    // 自签名证书同时作为签名者和根 CA
+    fn signed_with(not_before: Asn1Time, not_after: Asn1Time, key_usage: Option<KeyUsage>) -> Result<Vec<u8>> {
+        let pkey = PKey::private_key_from_pem(&fs::read("test/key.pem").unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "signer-cert").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&pkey).unwrap();
+        builder.set_not_before(&not_before).unwrap();
+        builder.set_not_after(&not_after).unwrap();
+        if let Some(mut key_usage) = key_usage {
+            builder.append_extension(key_usage.critical().build().unwrap()).unwrap();
+        }
+        builder.sign(&pkey, MessageDigest::sha256()).unwrap();
+        let cert = builder.build();
+        let mut pkcs = PKCS::new();
+        pkcs.cert_bin = cert.to_pem().unwrap();
+        pkcs.pkey_bin = pkey.private_key_to_pem_pkcs8().unwrap();
+        let signed = pkcs.encode_pkcs_bin(b"digest").unwrap();
+        PKCS::decode_pkcs_bin(&signed, &[pkcs.cert_bin], DEFAULT_MAX_CHAIN_DEPTH)
+    }
+    let days = |n: i64| Asn1Time::from_unix(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64 + n * 86400).unwrap();
+    let message = |result: Result<Vec<u8>>| match result {
+        Err(CrateSpecError::SignatureError(msg)) => msg,
+        other => panic!("应报 SignatureError，实际 {:?}", other),
+    };
+
+    let mut usage = KeyUsage::new();
+    usage.digital_signature();
+    assert_eq!(signed_with(days(-1), days(1), Some(usage)).unwrap(), b"digest");
+    assert_eq!(signed_with(days(-1), days(1), None).unwrap(), b"digest");
+
+    assert!(message(signed_with(days(-10), days(-1), None)).contains("已于"));
+    assert!(message(signed_with(days(1), days(10), None)).contains("尚未生效"));
+    let mut usage = KeyUsage::new();
+    usage.key_cert_sign();
+    assert!(message(signed_with(days(-1), days(1), Some(usage))).contains("digitalSignature"));
+}
+
+#[test]
+fn test_pkcs_chain_depth_limit() {
+    use crate::utils::testing::root_ca_bins;
+
+    // test/chain 下的证书链为 root -> int1 -> int2 -> leaf，含 2 个中间 CA
+    let cert = X509::from_pem(&fs::read("test/chain/cert.pem").unwrap()).unwrap();
+    let pkey = PKey::private_key_from_pem(&fs::read("test/chain/key.pem").unwrap()).unwrap();
+    let mut certs = Stack::new().unwrap();
+    for int_cert in X509::stack_from_pem(&fs::read("test/chain/intermediates.pem").unwrap()).unwrap() {
+        certs.push(int_cert).unwrap();
+    }
+    let flags = PKCS7_FLAGS;
+    let digest = PKCS::new().gen_digest_256(b"crate-spec").unwrap();
+    let signed = Pkcs7::sign(&cert, &pkey, &certs, &digest, flags)
+        .unwrap()
+        .to_smime(&digest, flags)
+        .unwrap();
+    let root_cas = PKCS::root_ca_bins(["test/chain/root-ca.pem".to_string()].to_vec()).unwrap();
+
+    assert_eq!(PKCS::decode_pkcs_bin(&signed, &root_cas, DEFAULT_MAX_CHAIN_DEPTH).unwrap(), digest);
+    assert_eq!(PKCS::decode_pkcs_bin(&signed, &root_cas, 2).unwrap(), digest);
+    let err = PKCS::decode_pkcs_bin(&signed, &root_cas, 1).unwrap_err();
+    assert!(err.to_string().contains("证书链长度超过上限 1"), "{}", err);
+
+    // 根 CA 不匹配时报告原始的验证错误，而不是链长度超限
+    let other_root = root_ca_bins();
+    let err = PKCS::decode_pkcs_bin(&signed, &other_root, DEFAULT_MAX_CHAIN_DEPTH).unwrap_err();
+    assert!(!err.to_string().contains("证书链长度超过上限"), "{}", err);
+}
+
+#[test]
+fn test_pkcs_embeds_intermediates() {
+    // 签名者证书由中间 CA 签发，验证方只有根 CA
+    let root_cas = PKCS::root_ca_bins(["test/chain/root-ca.pem".to_string()].to_vec()).unwrap();
+    let digest = PKCS::new().gen_digest_256(b"crate-spec").unwrap();
+    let mut pkcs = PKCS::new();
+    pkcs.load_from_file_writer(
+        "test/chain/cert.pem".to_string(),
+        "test/chain/key.pem".to_string(),
+        ["test/chain/root-ca.pem".to_string()].to_vec(),
+    ).unwrap();
+    let signed = pkcs.encode_pkcs_bin(&digest).unwrap();
+    assert!(PKCS::decode_pkcs_bin(&signed, &root_cas, DEFAULT_MAX_CHAIN_DEPTH).is_err());
+
+    pkcs.load_chain_from_files(["test/chain/intermediates.pem".to_string()].to_vec()).unwrap();
+    let signed = pkcs.encode_pkcs_bin(&digest).unwrap();
+    let (content, anchor) = PKCS::verify_pkcs_bin(&signed, &root_cas, DEFAULT_MAX_CHAIN_DEPTH).unwrap();
+    assert_eq!(content, digest);
+    assert!(matches!(anchor, TrustAnchor::Root { index: 0, .. }));
+
+    let err = PKCS::new().load_chain_from_files(["test/chain/key.pem".to_string()].to_vec()).unwrap_err();
+    assert!(err.to_string().contains("test/chain/key.pem"), "{}", err);
+}
+
+#[test]
+fn test_digest_eq() {
+    let digest = PKCS::new().gen_digest_256(b"crate-spec").unwrap();
+    assert!(PKCS::digest_eq(&digest, &digest.clone()));
+    let mut other = digest.clone();
+    other[31] ^= 1;
+    assert!(!PKCS::digest_eq(&digest, &other));
+    assert!(!PKCS::digest_eq(&digest, &digest[..31]));
+    assert!(PKCS::digest_eq(&[], &[]));
+}
+
+#[test]
+fn test_pkcs_trust_anchor() {
+    use crate::utils::testing::signer;
+
+    // 两个候选根 CA，只有 test/root-ca.pem 能锚定 test/cert.pem 的证书链
+    let pkcs = signer();
+    let digest = pkcs.gen_digest_256(b"crate-spec").unwrap();
+    let signed = pkcs.encode_pkcs_bin(&digest).unwrap();
+    let root_cas = PKCS::root_ca_bins(
+        ["test/chain/root-ca.pem".to_string(), "test/root-ca.pem".to_string()].to_vec(),
+    ).unwrap();
+    let (content, anchor) = PKCS::verify_pkcs_bin(&signed, &root_cas, DEFAULT_MAX_CHAIN_DEPTH).unwrap();
+    assert_eq!(content, digest);
+    let expected_subject = name_to_string(X509::from_pem(&root_cas[1]).unwrap().subject_name());
+    assert_eq!(anchor, TrustAnchor::Root { index: 1, subject: expected_subject });
+
+    // 证书链无法解析到任何提供的根 CA 时明确说明
+    let (pkcs7, _) = Pkcs7::from_smime(&signed).unwrap();
+    let store = X509StoreBuilder::new().unwrap().build();
+    let err = trust_anchor(&pkcs7, &store, &[]).unwrap_err();
+    assert!(err.contains("证书链验证失败"), "{}", err);
+}
+
+#[test]
+fn test_verify_signing_time() {
+    use crate::utils::testing::signer;
+
+    let pkcs = signer();
+    let digest = pkcs.gen_digest_256(b"crate-spec").unwrap();
+    let signed = pkcs.encode_pkcs_bin(&digest).unwrap();
+    assert!(PKCS::verify_signing_time(&signed).unwrap().is_some());
+
+    // 把签名时间改成 2000 年，早于证书的 notBefore
+    const SIGNING_TIME_OID: [u8; 11] = [0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x05];
+    let (pkcs7, _) = Pkcs7::from_smime(&signed).unwrap();
+    let mut der = pkcs7.to_der().unwrap();
+    let pos = der.windows(SIGNING_TIME_OID.len()).position(|w| w == SIGNING_TIME_OID).unwrap();
+    let time_start = pos + SIGNING_TIME_OID.len() + 4;
+    assert_eq!(der[time_start - 2], 0x17);
+    der[time_start..time_start + 13].copy_from_slice(b"000101000000Z");
+    let backdated = Pkcs7::from_der(&der)
+        .unwrap()
+        .to_smime(&digest, PKCS7_FLAGS)
+        .unwrap();
+    let err = PKCS::verify_signing_time(&backdated).unwrap_err();
+    assert!(matches!(&err, CrateSpecError::SignatureError(msg) if msg.contains("有效期")), "{}", err);
+}
+
+#[test]
+fn test_reject_multiple_signers() {
+    use openssl::hash::MessageDigest;
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509Builder;
+    use std::os::raw::c_int;
+
+    // openssl-sys 没有导出添加签名者的接口，这里只声明用到的部分
+    extern "C" {
+        fn PKCS7_sign_add_signer(
+            p7: *mut ffi::PKCS7,
+            signcert: *mut ffi::X509,
+            pkey: *mut ffi::EVP_PKEY,
+            md: *const ffi::EVP_MD,
+            flags: c_int,
+        ) -> *mut ffi::PKCS7_SIGNER_INFO;
+        fn PKCS7_final(p7: *mut ffi::PKCS7, data: *mut ffi::BIO, flags: c_int) -> c_int;
+    }
+
+    // 第二个签名者使用 1024 位的弱 RSA 密钥
+    let weak_key = PKey::from_rsa(Rsa::generate(1024).unwrap()).unwrap();
+    let mut builder = X509Builder::new().unwrap();
+    builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    builder.set_pubkey(&weak_key).unwrap();
+    builder.sign(&weak_key, MessageDigest::sha256()).unwrap();
+    let weak_cert = builder.build();
+
+    // 另一个签名者使用 P-256 密钥；signerInfos 按 DER 编码排序，ECDSA 签名较短，排在弱密钥之前
+    let cert = X509::from_pem(&fs::read("test/ec/cert.pem").unwrap()).unwrap();
+    let key = PKey::private_key_from_pem(&fs::read("test/ec/key.pem").unwrap()).unwrap();
+    let digest = PKCS::new().gen_digest_256(b"crate-spec").unwrap();
+    let flags = PKCS7_FLAGS;
+    let pkcs7 = Pkcs7::sign(&weak_cert, &weak_key, &Stack::new().unwrap(), &digest, flags | Pkcs7Flags::PARTIAL).unwrap();
+    // SAFETY: 证书、私钥和内存 BIO 在调用期间都有效，PKCS7_sign_add_signer 自行增加引用计数
+    unsafe {
+        let info = PKCS7_sign_add_signer(pkcs7.as_ptr(), cert.as_ptr(), key.as_ptr(), ffi::EVP_sha256(), flags.bits());
+        assert!(!info.is_null());
+        let bio = ffi::BIO_new_mem_buf(digest.as_ptr().cast(), digest.len() as c_int);
+        assert_eq!(PKCS7_final(pkcs7.as_ptr(), bio, flags.bits()), 1);
+        ffi::BIO_free_all(bio);
+    }
+    let signed = pkcs7.to_smime(&digest, flags).unwrap();
+
+    // 只检查第一个签名者时取到的是 EC 密钥，弱 RSA 密钥的签名者不能借此通过密钥强度检查
+    let policy = KeyStrengthPolicy { min_rsa_bits: Some(2048), ..Default::default() };
+    let err = PKCS::check_signer_key(&signed, &policy).unwrap_err();
+    assert!(err.to_string().contains("2 个签名者"), "{}", err);
+    let root_cas = PKCS::root_ca_bins(["test/ec/root-ca.pem".to_string()].to_vec()).unwrap();
+    assert!(PKCS::decode_pkcs_bin(&signed, &root_cas, DEFAULT_MAX_CHAIN_DEPTH).is_err());
+}
+
+#[test]
+fn test_signing_time_ignores_embedded_certs() {
+    use openssl::asn1::{Asn1Object, Asn1OctetString};
+    use openssl::hash::MessageDigest;
+    use openssl::x509::{X509Builder, X509Extension};
+
+    // 构造一张携带伪造签名时间的证书：私有扩展的内容是 signingTime OID 加上 1999 年的 UTCTime
+    let key = PKey::private_key_from_pem(&fs::read("test/key.pem").unwrap()).unwrap();
+    let mut decoy = [0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x05, 0x31, 0x0f, 0x17, 0x0d].to_vec();
+    decoy.extend_from_slice(b"991231235959Z");
+    let mut builder = X509Builder::new().unwrap();
+    builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    let oid = Asn1Object::from_str("1.3.6.1.4.1.55555.1").unwrap();
+    let contents = Asn1OctetString::new_from_bytes(&decoy).unwrap();
+    builder.append_extension(X509Extension::new_from_der(&oid, false, &contents).unwrap()).unwrap();
+    builder.sign(&key, MessageDigest::sha256()).unwrap();
+    let mut certs = Stack::new().unwrap();
+    certs.push(builder.build()).unwrap();
+
+    // 伪造证书放在签名携带的证书中，位于真正的签名者信息之前
+    let cert = X509::from_pem(&fs::read("test/cert.pem").unwrap()).unwrap();
+    let digest = PKCS::new().gen_digest_256(b"crate-spec").unwrap();
+    let flags = PKCS7_FLAGS;
+    let signed = Pkcs7::sign(&cert, &key, &certs, &digest, flags).unwrap().to_smime(&digest, flags).unwrap();
+
+    let time = PKCS::verify_signing_time(&signed).unwrap().unwrap();
+    assert!(!time.contains("1999"), "{}", time);
+}
 // #[test]
 // fn test_pkcs(){
 //     let mut pkcs = PKCS::new();
@@ -213,3 +1170,72 @@ impl Default for PKCS {
 //     //assert_eq!(&*res, spec);
 //     Ok(())
 // }
+
+#[test]
+fn test_key_strength_policy() {
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509NameBuilder;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    // 自签名证书同时作为签名者和根 CA
+    fn self_signed(pkey: &PKey<Private>) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "key-strength").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(pkey).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(pkey, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+    fn signed_by(pkey: PKey<Private>) -> (Vec<u8>, Vec<u8>) {
+        let cert = self_signed(&pkey);
+        let mut pkcs = PKCS::new();
+        pkcs.cert_bin = cert.to_pem().unwrap();
+        pkcs.pkey_bin = pkey.private_key_to_pem_pkcs8().unwrap();
+        let signed = pkcs.encode_pkcs_bin(&pkcs.gen_digest_256(b"crate-spec").unwrap()).unwrap();
+        (signed, pkcs.cert_bin)
+    }
+
+    let rsa_policy = KeyStrengthPolicy { min_rsa_bits: Some(2048), allowed_ec_curves: vec![] };
+    let (weak, weak_root) = signed_by(PKey::from_rsa(Rsa::generate(1024).unwrap()).unwrap());
+    PKCS::verify_pkcs_bin(&weak, &[weak_root], DEFAULT_MAX_CHAIN_DEPTH).unwrap();
+    assert!(PKCS::check_signer_key(&weak, &KeyStrengthPolicy::default()).is_ok());
+    let err = PKCS::check_signer_key(&weak, &rsa_policy).unwrap_err();
+    assert!(matches!(&err, CrateSpecError::SignatureError(msg) if msg.contains("1024 位")), "{}", err);
+    let (strong, _) = signed_by(PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap());
+    assert!(PKCS::check_signer_key(&strong, &rsa_policy).is_ok());
+
+    // EC 曲线白名单（大小写不敏感），RSA 要求不影响 EC 密钥
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let (ec, _) = signed_by(PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap());
+    assert!(PKCS::check_signer_key(&ec, &rsa_policy).is_ok());
+    let p384_only = KeyStrengthPolicy { min_rsa_bits: None, allowed_ec_curves: vec!["secp384r1".to_string()] };
+    assert!(PKCS::check_signer_key(&ec, &p384_only).unwrap_err().to_string().contains("prime256v1"));
+    let p256 = KeyStrengthPolicy { min_rsa_bits: None, allowed_ec_curves: vec!["PRIME256V1".to_string()] };
+    assert!(PKCS::check_signer_key(&ec, &p256).is_ok());
+    assert!(PKCS::check_signer_key(&strong, &p256).is_ok());
+
+    // 解码时按上下文的要求检查每个本地签名（test/cert.pem 为 2048 位）
+    use crate::utils::context::{PackageContext, SIGTYPE};
+    let pkcs = signer();
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("strength".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![1u8; 8]);
+    ctx.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+    let decode = |min_rsa_bits| {
+        let mut decoded = PackageContext::new();
+        decoded.set_root_cas_bin(root_ca_bins());
+        decoded.key_policy = KeyStrengthPolicy { min_rsa_bits, allowed_ec_curves: vec![] };
+        decoded.decode_from_crate_package(&bin).map(|_| ())
+    };
+    assert!(decode(Some(2048)).is_ok());
+    assert!(decode(Some(4096)).unwrap_err().to_string().contains("签名 #0"));
+}
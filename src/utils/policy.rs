@@ -0,0 +1,368 @@
+//! 发布策略检查（`--compat-check <policy>`）
+//!
+//! 策略文件为 TOML，所有规则均可省略，省略即不限制：
+//!
+//! ```toml
+//! allowed_licenses = ["MIT", "Apache-2.0"]    # license 表达式中的每个许可证都必须在列表中
+//! forbidden_sources = ["git", "url"]          # crates-io / git / url / registry / p2p
+//! required_sig_types = ["cratebin"]           # file / cratebin / network
+//! max_dependencies = 50
+//! ```
+//!
+//! 打包时的依赖源策略见 [`DepSourcePolicy`]，对应配置文件的 `[policy]` 段。
+use crate::error::{CrateSpecError, Result};
+use crate::utils::context::{PackageContext, SrcKind, SrcTypePath, SIGTYPE};
+use crate::locale::tr;
+use crate::tr_format;
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+/// 策略中可要求的签名类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicySigType {
+    File,
+    Cratebin,
+    Network,
+}
+
+impl PolicySigType {
+    fn sig_type(&self) -> SIGTYPE {
+        match self {
+            PolicySigType::File => SIGTYPE::FILE,
+            PolicySigType::Cratebin => SIGTYPE::CRATEBIN,
+            PolicySigType::Network => SIGTYPE::NETWORK,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default)]
+    pub allowed_licenses: Vec<String>,
+    #[serde(default)]
+    pub forbidden_sources: Vec<SrcKind>,
+    #[serde(default)]
+    pub required_sig_types: Vec<PolicySigType>,
+    pub max_dependencies: Option<usize>,
+}
+
+/// 单条违规
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// 违反的规则名，与策略文件中的键一致
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// 策略检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyReport {
+    pub package: String,
+    pub violations: Vec<Violation>,
+}
+
+impl PolicyReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for PolicyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.passed() {
+            return f.write_str(&tr_format!("策略检查通过: {}", "policy check passed: {}", self.package));
+        }
+        f.write_str(&tr_format!(
+            "策略检查未通过: {}，{} 项违规",
+            "policy check failed: {}, {} violation(s)",
+            self.package,
+            self.violations.len()
+        ))?;
+        for violation in self.violations.iter() {
+            write!(f, "\n  [{}] {}", violation.rule, violation.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// 拆分 SPDX 表达式（如 `MIT OR Apache-2.0`、`MIT/Apache-2.0`）中的许可证标识
+fn license_ids(license: &str) -> Vec<&str> {
+    license
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '(' || c == ')')
+        .filter(|id| !id.is_empty() && !matches!(*id, "OR" | "AND" | "WITH"))
+        .collect()
+}
+
+impl Policy {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let path_buf = Path::new(path);
+        let text = fs::read_to_string(path_buf)
+            .map_err(|_e| CrateSpecError::FileNotFound(path_buf.to_path_buf()))?;
+        text.parse()
+    }
+
+    /// 按策略检查已解码的包，返回全部违规而不是遇到第一条就停止
+    pub fn check(&self, pack_context: &PackageContext) -> PolicyReport {
+        let mut violations = vec![];
+        let pack_info = &pack_context.pack_info;
+
+        if !self.allowed_licenses.is_empty() {
+            let ids = license_ids(&pack_info.license);
+            if ids.is_empty() {
+                violations.push(Violation {
+                    rule: "allowed_licenses",
+                    message: tr("包未声明 license", "the package declares no license").to_string(),
+                });
+            }
+            for id in ids {
+                if !self.allowed_licenses.iter().any(|allowed| allowed == id) {
+                    violations.push(Violation {
+                        rule: "allowed_licenses",
+                        message: tr_format!("许可证 {} 不在允许列表中", "license {} is not in the allowed list", id),
+                    });
+                }
+            }
+        }
+
+        for kind in self.forbidden_sources.iter() {
+            for dep in pack_context.deps_by_source(*kind) {
+                violations.push(Violation {
+                    rule: "forbidden_sources",
+                    message: tr_format!("依赖 {} 的来源 {} 被禁止", "source {1} of dependency {0} is forbidden", dep.name, dep.src.to_canonical_string()),
+                });
+            }
+        }
+
+        for required in self.required_sig_types.iter() {
+            let typ = required.sig_type().as_u32();
+            if !pack_context.sigs.iter().any(|sig| sig.typ == typ) {
+                violations.push(Violation {
+                    rule: "required_sig_types",
+                    message: tr_format!("缺少 {:?} 类型的签名", "missing a {:?} signature", required).to_lowercase(),
+                });
+            }
+        }
+
+        if let Some(max) = self.max_dependencies {
+            let count = pack_context.deps().len();
+            if count > max {
+                violations.push(Violation {
+                    rule: "max_dependencies",
+                    message: tr_format!("依赖数量 {} 超过上限 {}", "{} dependencies exceed the limit of {}", count, max),
+                });
+            }
+        }
+
+        PolicyReport {
+            package: format!("{}-{}", pack_info.name, pack_info.version),
+            violations,
+        }
+    }
+}
+
+impl std::str::FromStr for Policy {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        toml::from_str(s).map_err(|e| CrateSpecError::ParseError(tr_format!("策略文件解析失败: {}", "failed to parse the policy file: {}", e)))
+    }
+}
+
+/// 依赖源允许列表中的一项：`crates-io`、`git`、`url`、`registry`、`p2p`，
+/// 或 `git+<host>`、`url+<host>` 只允许指定主机
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepSourceRule {
+    Kind(SrcKind),
+    Host(SrcKind, String),
+}
+
+impl std::str::FromStr for DepSourceRule {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let kind = |name: &str| match name {
+            "crates-io" => Ok(SrcKind::CratesIo),
+            "git" => Ok(SrcKind::Git),
+            "url" => Ok(SrcKind::Url),
+            "registry" => Ok(SrcKind::Registry),
+            "p2p" => Ok(SrcKind::P2p),
+            _ => Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的依赖源: {}，必须是 crates-io、git、url、registry、p2p 或 git+<host>、url+<host>",
+                "invalid dependency source: {}, must be crates-io, git, url, registry, p2p, git+<host> or url+<host>",
+                s
+            ))),
+        };
+        match s.split_once('+') {
+            Some((name @ ("git" | "url"), host)) if !host.is_empty() => {
+                Ok(DepSourceRule::Host(kind(name)?, host.to_ascii_lowercase()))
+            }
+            Some(_) => kind("").map(DepSourceRule::Kind),
+            None => Ok(DepSourceRule::Kind(kind(s)?)),
+        }
+    }
+}
+
+/// 依赖源不符合策略时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepSourceAction {
+    /// 打包失败（默认）
+    #[default]
+    Error,
+    /// 不写入依赖表并给出警告
+    Drop,
+}
+
+impl std::str::FromStr for DepSourceAction {
+    type Err = CrateSpecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(DepSourceAction::Error),
+            "drop" => Ok(DepSourceAction::Drop),
+            _ => Err(CrateSpecError::ValidationError(tr_format!(
+                "无效的依赖源策略处理方式: {}，必须是 'error' 或 'drop'",
+                "invalid dependency source policy action: {}, must be 'error' or 'drop'",
+                s
+            ))),
+        }
+    }
+}
+
+/// 打包时的依赖源策略，默认允许所有来源
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DepSourcePolicy {
+    /// 允许的依赖源，为空时不限制
+    pub allowed_dep_sources: Vec<DepSourceRule>,
+    /// 禁止的 git/url 依赖主机，同时匹配其子域名
+    pub denied_dep_hosts: Vec<String>,
+    pub on_violation: DepSourceAction,
+}
+
+/// 取 git/url 依赖地址中的主机名，支持 `https://host/...`、`ssh://user@host:port/...` 和 `user@host:path`
+fn source_host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next()?;
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    host == pattern || host.ends_with(&format!(".{}", pattern))
+}
+
+impl DepSourcePolicy {
+    /// 检查依赖来源，不允许时返回原因
+    pub fn check(&self, src: &SrcTypePath) -> std::result::Result<(), String> {
+        let host = match src {
+            SrcTypePath::Git(url) | SrcTypePath::Url(url) => source_host(url),
+            _ => None,
+        };
+        if let Some(host) = host.as_deref() {
+            if let Some(denied) = self.denied_dep_hosts.iter().find(|denied| host_matches(host, denied)) {
+                return Err(tr_format!("主机 {} 被 denied_dep_hosts 中的 {} 禁止", "host {} is denied by {} in denied_dep_hosts", host, denied));
+            }
+        }
+        if self.allowed_dep_sources.is_empty() {
+            return Ok(());
+        }
+        let allowed = self.allowed_dep_sources.iter().any(|rule| match rule {
+            DepSourceRule::Kind(kind) => *kind == src.kind(),
+            DepSourceRule::Host(kind, pattern) => {
+                *kind == src.kind() && host.as_deref().is_some_and(|host| host_matches(host, pattern))
+            }
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(tr("不在 allowed_dep_sources 中", "not in allowed_dep_sources").to_string())
+        }
+    }
+}
+
+#[test]
+fn test_policy_check() {
+    use crate::utils::context::SrcTypePath;
+    use crate::utils::pkcs::PKCS;
+
+    let policy: Policy = r#"
+        allowed_licenses = ["MIT", "Apache-2.0"]
+        forbidden_sources = ["git"]
+        required_sig_types = ["cratebin"]
+        max_dependencies = 2
+    "#
+    .parse()
+    .unwrap();
+
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info(
+        "demo".to_string(),
+        "0.1.0".to_string(),
+        "MIT OR Apache-2.0".to_string(),
+        vec![],
+    );
+    pack_context.add_dep_info("serde".to_string(), "1.0".to_string(), SrcTypePath::CratesIo, "".to_string());
+    pack_context.add_sig(PKCS::new(), SIGTYPE::CRATEBIN);
+    let report = policy.check(&pack_context);
+    assert!(report.passed(), "{}", report);
+
+    // 引入 git 依赖后违反 forbidden_sources
+    pack_context.add_dep_info(
+        "tool".to_string(),
+        "0.3".to_string(),
+        SrcTypePath::Git("https://example.com/tool.git".to_string()),
+        "".to_string(),
+    );
+    let report = policy.check(&pack_context);
+    assert!(!report.passed());
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].rule, "forbidden_sources");
+    assert!(report.to_string().contains("git+https://example.com/tool.git"), "{}", report);
+
+    // 其余规则一并报告
+    pack_context.set_package_info("demo".to_string(), "0.1.0".to_string(), "GPL-3.0".to_string(), vec![]);
+    pack_context.sigs.clear();
+    let rules: Vec<_> = policy.check(&pack_context).violations.iter().map(|v| v.rule).collect();
+    assert_eq!(rules, ["allowed_licenses", "forbidden_sources", "required_sig_types"]);
+
+    assert!("unknown_rule = 1".parse::<Policy>().is_err());
+    assert!("forbidden_sources = [\"svn\"]".parse::<Policy>().is_err());
+}
+
+#[test]
+fn test_dep_source_policy() {
+    let git = |url: &str| SrcTypePath::Git(url.to_string());
+
+    // 默认允许所有来源
+    let policy = DepSourcePolicy::default();
+    assert!(policy.check(&git("https://evil.example.com/x.git")).is_ok());
+
+    let policy = DepSourcePolicy {
+        denied_dep_hosts: vec!["example.com".to_string()],
+        ..Default::default()
+    };
+    assert!(policy.check(&git("https://git.Example.com/x.git")).is_err());
+    assert!(policy.check(&git("git@example.com:org/x.git")).is_err());
+    assert!(policy.check(&git("ssh://git@example.com:22/org/x.git")).is_err());
+    assert!(policy.check(&git("https://notexample.com/x.git")).is_ok());
+    assert!(policy.check(&SrcTypePath::CratesIo).is_ok());
+
+    let policy = DepSourcePolicy {
+        allowed_dep_sources: vec!["crates-io".parse().unwrap(), "git+github.com".parse().unwrap()],
+        ..Default::default()
+    };
+    assert!(policy.check(&SrcTypePath::CratesIo).is_ok());
+    assert!(policy.check(&git("https://github.com/a/b")).is_ok());
+    assert!(policy.check(&git("https://gitlab.com/a/b")).is_err());
+    assert!(policy.check(&SrcTypePath::Registry("corp".to_string())).is_err());
+
+    assert!("git+".parse::<DepSourceRule>().is_err());
+    assert!("registry+corp".parse::<DepSourceRule>().is_err());
+    assert!("svn".parse::<DepSourceRule>().is_err());
+    assert_eq!("DROP".parse::<DepSourceAction>().unwrap(), DepSourceAction::Drop);
+}
@@ -0,0 +1,122 @@
+//! 编码进度回调
+//!
+//! 打包和签名可能耗时数分钟，编码流程在进入每个阶段时调用 [`ProgressSink::on_stage`]，
+//! 打包时的警告和 cargo package 的输出也通过它交给调用方，库本身不向终端输出。
+//! CLI 使用 [`ConsoleProgress`] 在 stderr 输出阶段变化和警告，库的调用方可以实现自己的
+//! [`ProgressSink`]（例如更新图形界面），通过 [`PackOptions::progress`](super::pack::PackOptions::progress) 传入。
+use crate::locale::{lang, tr, Lang};
+use std::fmt;
+
+/// 编码流程的阶段，按发生顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeStage {
+    /// 执行 cargo package（使用已有 `.crate` 时跳过）
+    Packaging,
+    /// 解析 Cargo.toml 并读取 `.crate`
+    ReadingCrate,
+    /// 计算签名并编码 `.scrate`
+    Signing,
+    /// 写出 `.scrate`（及分离签名）文件
+    Writing,
+}
+
+impl EncodeStage {
+    /// 阶段序号，从 1 开始
+    pub fn index(self) -> usize {
+        match self {
+            EncodeStage::Packaging => 1,
+            EncodeStage::ReadingCrate => 2,
+            EncodeStage::Signing => 3,
+            EncodeStage::Writing => 4,
+        }
+    }
+
+    /// 阶段总数
+    pub const COUNT: usize = 4;
+}
+
+impl fmt::Display for EncodeStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match (lang(), self) {
+            (Lang::Zh, EncodeStage::Packaging) => "执行 cargo package",
+            (Lang::Zh, EncodeStage::ReadingCrate) => "读取 crate",
+            (Lang::Zh, EncodeStage::Signing) => "签名",
+            (Lang::Zh, EncodeStage::Writing) => "写出文件",
+            (Lang::En, EncodeStage::Packaging) => "running cargo package",
+            (Lang::En, EncodeStage::ReadingCrate) => "reading crate",
+            (Lang::En, EncodeStage::Signing) => "signing",
+            (Lang::En, EncodeStage::Writing) => "writing output",
+        };
+        f.write_str(text)
+    }
+}
+
+/// 接收编码阶段变化的回调，可能在任意线程调用
+pub trait ProgressSink: Send + Sync {
+    /// 进入 `stage` 时调用
+    fn on_stage(&self, stage: EncodeStage);
+
+    /// 打包时出现警告（占位值、未写入依赖表的依赖、无法获取工具链版本等）时调用，预演时同样调用；默认忽略
+    fn on_warning(&self, _warning: &str) {}
+
+    /// cargo package 执行成功后以其标准输出调用；默认忽略
+    fn on_cargo_output(&self, _output: &str) {}
+}
+
+impl fmt::Debug for dyn ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
+
+/// 有进度回调时通知进入 `stage`
+pub fn report(sink: Option<&dyn ProgressSink>, stage: EncodeStage) {
+    if let Some(sink) = sink {
+        sink.on_stage(stage);
+    }
+}
+
+/// 有进度回调时转交打包警告
+pub fn warn(sink: Option<&dyn ProgressSink>, warning: &str) {
+    if let Some(sink) = sink {
+        sink.on_warning(warning);
+    }
+}
+
+/// CLI 默认的进度输出：每进入一个阶段向 stderr 输出一行，如 `[1/4] 执行 cargo package...`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleProgress;
+
+impl ProgressSink for ConsoleProgress {
+    fn on_stage(&self, stage: EncodeStage) {
+        eprintln!("[{}/{}] {}...", stage.index(), EncodeStage::COUNT, stage);
+    }
+
+    fn on_warning(&self, warning: &str) {
+        eprintln!("{}: {}", tr("警告", "warning"), warning);
+    }
+
+    fn on_cargo_output(&self, output: &str) {
+        println!("{}", output);
+    }
+}
+
+#[test]
+fn test_report_stages() {
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<EncodeStage>>);
+    impl ProgressSink for Recorder {
+        fn on_stage(&self, stage: EncodeStage) {
+            self.0.lock().unwrap().push(stage);
+        }
+    }
+
+    let recorder = Recorder::default();
+    report(Some(&recorder), EncodeStage::Signing);
+    report(None, EncodeStage::Writing);
+    report(Some(&recorder), EncodeStage::Writing);
+    assert_eq!(*recorder.0.lock().unwrap(), vec![EncodeStage::Signing, EncodeStage::Writing]);
+    assert_eq!(EncodeStage::Writing.index(), EncodeStage::COUNT);
+}
@@ -0,0 +1,74 @@
+//! 键值存储抽象
+//!
+//! 密钥对等需要持久化的数据通过 [`Storage`] 读写，默认的 [`FsStorage`] 以键为文件路径存到本地文件系统；
+//! 接入 Redis、Vault 等已有基础设施时实现该 trait 即可，无需改动调用方。
+use crate::error::{CrateSpecError, Result};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 键值存储，键的含义由实现决定（文件系统实现中为文件路径）
+pub trait Storage: Send + Sync {
+    /// 读取 `key` 对应的值，不存在时返回 None
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// 写入（覆盖）`key` 对应的值
+    fn put(&self, key: &str, value: &[u8]) -> Result<()>;
+
+    /// 删除 `key`，不存在时不报错
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// 本地文件系统存储：键为文件路径，写入时按需创建父目录，Unix 下文件权限设为仅所有者可读写
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsStorage;
+
+impl Storage for FsStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(key) {
+            Ok(bin) => Ok(Some(bin)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(CrateSpecError::Io(e)),
+        }
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<()> {
+        if let Some(parent) = Path::new(key).parent() {
+            fs::create_dir_all(parent).map_err(CrateSpecError::Io)?;
+        }
+        fs::write(key, value).map_err(CrateSpecError::Io)?;
+        #[cfg(unix)]
+        {
+            use crate::network::KEYPAIR_FILE_MODE;
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(key, fs::Permissions::from_mode(KEYPAIR_FILE_MODE)).map_err(CrateSpecError::Io)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match fs::remove_file(key) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(CrateSpecError::Io(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_fs_storage() {
+    use crate::utils::testing::TestDir;
+
+    let dir = TestDir::new("storage");
+    let key = dir.join_str("nested/keypair.bin");
+    assert_eq!(FsStorage.get(&key).unwrap(), None);
+    FsStorage.put(&key, b"secret").unwrap();
+    assert_eq!(FsStorage.get(&key).unwrap().as_deref(), Some(&b"secret"[..]));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(&key).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+    FsStorage.delete(&key).unwrap();
+    FsStorage.delete(&key).unwrap();
+    assert_eq!(FsStorage.get(&key).unwrap(), None);
+}
@@ -0,0 +1,359 @@
+//! 大文件的流式解码：按偏移量只读取头部、字符串表、段索引、指纹和 crate 二进制段以外的数据段，
+//! crate 二进制段留在文件中，需要时用 [`HeaderOnly::copy_crate_binary`] 按块复制出来。
+//!
+//! [`PackageContext::decode_streaming`] 在此基础上按块读完整个文件一遍，计算指纹和签名摘要并验签，
+//! 内存占用与 crate 二进制段的大小无关。已裁剪的包 crate 二进制段只有 32 字节，仍读入内存。
+//! 压缩的 crate 二进制段边读边解压，签名摘要按解压后的 `.crate` 计算。
+use crate::error::{CrateSpecError, Result};
+use crate::utils::context::{PackageContext, DATASECTIONTYPE, SIGTYPE};
+use crate::utils::decode::SigDigests;
+use crate::utils::package::gen_bincode::{create_bincode_slice_decoder, encode_size_by_bincode};
+use crate::utils::package::{
+    CrateBinarySection, CrateHeader, CratePackage, DataSection, DataSectionCollectionType, RawArrayType, SectionIndex,
+    MAGIC_NUMBER, MAGIC_NUMBER_LEN,
+};
+use crate::utils::pkcs::{DigestAlgo, PKCS};
+use crate::locale::tr;
+use crate::tr_format;
+use openssl::hash::Hasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// 计算摘要时每次读取的字节数
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 只读取元数据的解码结果
+#[derive(Debug)]
+pub struct HeaderOnly {
+    /// 解码后的包结构，crate 二进制段为空（已裁剪时为 SHA256 摘要本身）
+    pub crate_package: CratePackage,
+    /// crate 二进制段在文件中的字节范围
+    pub crate_binary: Range<u64>,
+    /// 整个文件的长度（含指纹）
+    pub file_len: u64,
+}
+
+impl HeaderOnly {
+    /// 把 crate 二进制段从 `reader`（须是解码时的同一文件）复制到 `out`，返回从文件读取的字节数
+    ///
+    /// 压缩的 crate 二进制段解压后写出，`out` 得到的总是 `.crate` 本身。
+    pub fn copy_crate_binary<R: Read + Seek, W: Write>(&self, reader: &mut R, out: &mut W) -> Result<u64> {
+        reader.seek(SeekFrom::Start(self.crate_binary.start))?;
+        let len = self.crate_binary.end - self.crate_binary.start;
+        let mut decoder = self.crate_package.compression()?.0.decoder(out)?;
+        let copied = io::copy(&mut reader.take(len), &mut decoder)?;
+        if copied != len {
+            return Err(CrateSpecError::DecodeError(tr_format!(
+                "crate 二进制段不完整: 读取 {} 字节，应为 {} 字节",
+                "the crate binary section is truncated: read {} bytes, expected {} bytes",
+                copied, len
+            )));
+        }
+        decoder
+            .finish()
+            .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制解压失败: {}", "failed to decompress the crate binary: {}", e)))?;
+        Ok(copied)
+    }
+}
+
+/// 从 `reader` 的 `offset` 处读取 `len` 字节，超出文件长度时报错
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize, what: &str) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => CrateSpecError::DecodeError(tr_format!("文件不完整，缺少{}", "the file is truncated, missing the {}", what)),
+        _ => CrateSpecError::Io(e),
+    })?;
+    Ok(buf)
+}
+
+impl CratePackage {
+    /// 从文件解码除 crate 二进制段外的各部分，不校验指纹和签名
+    ///
+    /// ```no_run
+    /// use crate_spec::utils::package::CratePackage;
+    ///
+    /// let header_only = CratePackage::decode_header_only("demo-0.1.0.scrate").unwrap();
+    /// let mut file = std::fs::File::open("demo-0.1.0.scrate").unwrap();
+    /// let mut out = std::fs::File::create("demo-0.1.0.crate").unwrap();
+    /// header_only.copy_crate_binary(&mut file, &mut out).unwrap();
+    /// ```
+    pub fn decode_header_only<P: AsRef<Path>>(path: P) -> Result<HeaderOnly> {
+        let path = path.as_ref();
+        let mut file = File::open(path).map_err(|_e| CrateSpecError::FileNotFound(path.to_path_buf()))?;
+        Self::decode_header_only_from(&mut file)
+    }
+
+    /// 同 [`CratePackage::decode_header_only`]，从任意可定位的数据源读取
+    pub fn decode_header_only_from<R: Read + Seek>(reader: &mut R) -> Result<HeaderOnly> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        let header_end = MAGIC_NUMBER_LEN + encode_size_by_bincode(&CrateHeader::new());
+        let head = read_at(reader, 0, header_end.min(file_len as usize), tr("文件头", "header"))?;
+        let crate_header = CratePackage::peek_header(&head)?;
+        let fingerprint_len = crate_header.fingerprint_digest()?.output_len() as u64;
+
+        let string_table = read_at(
+            reader,
+            crate_header.strtable_offset as u64,
+            crate_header.strtable_size as usize,
+            tr("字符串表", "string table"),
+        )?;
+        let si_start = crate_header.si_offset as u64;
+        let si_end = si_start + crate_header.si_size as u64;
+        if si_start < header_end as u64 || (crate_header.ds_offset as u64) < si_end {
+            return Err(CrateSpecError::DecodeError("file format not right! - si".to_string()));
+        }
+        let si_bin = read_at(reader, si_start, crate_header.si_size as usize, tr("段索引", "section index"))?;
+        let section_index = SectionIndex::decode(
+            &mut create_bincode_slice_decoder(&si_bin),
+            crate_header.si_num as usize,
+            crate_header.endianness(),
+        )
+            .map_err(|e| CrateSpecError::DecodeError(tr_format!("无法解析段索引: {}", "cannot parse the section index: {}", e)))?;
+        if section_index.size() != crate_header.si_size as usize {
+            return Err(CrateSpecError::DecodeError("file format not right! - si".to_string()));
+        }
+
+        let ds_offset = crate_header.ds_offset as u64;
+        let mut data_sections = DataSectionCollectionType::new();
+        let mut crate_binary = None;
+        let mut ds_end = 0;
+        for entry in section_index.entries.arr.iter() {
+            let (offset, size) = (entry.sh_offset as u64, entry.sh_size as u64);
+            // 与一次性解码相同：各段须按偏移量递增排列且互不重叠
+            if offset < ds_end {
+                return Err(CrateSpecError::DecodeError("file format not right!".to_string()));
+            }
+            ds_end = offset + size;
+            let range = ds_offset + offset..ds_offset + offset + size;
+            if range.end + fingerprint_len > file_len {
+                return Err(CrateSpecError::DecodeError(tr_format!("文件不完整，缺少类型为 {} 的数据段", "the file is truncated, missing the data section of type {}", entry.sh_type)));
+            }
+            if entry.sh_type == DATASECTIONTYPE::CRATEBIN.as_u8() {
+                crate_binary = Some(range.clone());
+                if !crate_header.binary_pruned() {
+                    data_sections.col.arr.push(DataSection::CrateBinarySection(CrateBinarySection::new()));
+                    continue;
+                }
+            }
+            let bin = read_at(reader, range.start, size as usize, tr("数据段", "data section"))?;
+            let mut decoded =
+                DataSectionCollectionType::decode(&mut create_bincode_slice_decoder(&bin), vec![(entry.sh_type as i32, size as usize, 0)])
+                    .map_err(|e| CrateSpecError::DecodeError(tr_format!("无法解析类型为 {} 的数据段: {}", "cannot parse the data section of type {}: {}", entry.sh_type, e)))?;
+            data_sections.col.arr.append(&mut decoded.col.arr);
+        }
+        let crate_binary = crate_binary.ok_or_else(|| CrateSpecError::DecodeError(tr("缺少 crate 二进制段", "the crate binary section is missing").to_string()))?;
+        if ds_offset + ds_end + fingerprint_len != file_len {
+            return Err(CrateSpecError::DecodeError(tr_format!(
+                "文件长度 {} 字节与段索引不符，应为 {} 字节",
+                "the file is {} bytes, which does not match the section index ({} bytes expected)",
+                file_len,
+                ds_offset + ds_end + fingerprint_len
+            )));
+        }
+        let finger_print = read_at(reader, file_len - fingerprint_len, fingerprint_len as usize, tr("指纹", "fingerprint"))?;
+
+        Ok(HeaderOnly {
+            crate_package: CratePackage {
+                magic_number: MAGIC_NUMBER,
+                crate_header,
+                string_table: RawArrayType::from_vec(string_table),
+                section_index,
+                data_sections,
+                finger_print: RawArrayType::from_vec(finger_print),
+            },
+            crate_binary,
+            file_len,
+        })
+    }
+}
+
+/// 按算法分别计算的一组摘要
+struct Hashers(HashMap<DigestAlgo, Hasher>);
+
+impl Hashers {
+    fn new(algos: impl IntoIterator<Item = DigestAlgo>) -> Result<Self> {
+        let mut hashers = HashMap::new();
+        for algo in algos {
+            if let std::collections::hash_map::Entry::Vacant(entry) = hashers.entry(algo) {
+                entry.insert(
+                    Hasher::new(algo.message_digest())
+                        .map_err(|e| CrateSpecError::Other(tr_format!("创建 {} 摘要失败: {}", "failed to create {} digest: {}", algo, e)))?,
+                );
+            }
+        }
+        Ok(Self(hashers))
+    }
+
+    fn update(&mut self, data: &[u8]) -> Result<()> {
+        for hasher in self.0.values_mut() {
+            hasher.update(data).map_err(|e| CrateSpecError::Other(tr_format!("计算摘要失败: {}", "failed to compute digest: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<HashMap<DigestAlgo, Vec<u8>>> {
+        self.0
+            .into_iter()
+            .map(|(algo, mut hasher)| {
+                let digest = hasher.finish().map_err(|e| CrateSpecError::Other(tr_format!("计算摘要失败: {}", "failed to compute digest: {}", e)))?;
+                Ok((algo, digest.to_vec()))
+            })
+            .collect()
+    }
+}
+
+impl Write for Hashers {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `chunk`（位于文件 `pos` 处）与 `range` 重叠的部分
+fn overlap<'a>(chunk: &'a [u8], pos: u64, range: &Range<u64>) -> Option<(u64, &'a [u8])> {
+    let start = pos.max(range.start);
+    let end = (pos + chunk.len() as u64).min(range.end);
+    (start < end).then(|| (start, &chunk[(start - pos) as usize..(end - pos) as usize]))
+}
+
+impl PackageContext {
+    /// 流式解码并验证 `.scrate`：读取元数据和签名，按块计算指纹和签名摘要，不把 crate 二进制段读入内存
+    ///
+    /// 解码后 `crate_binary` 为空（已裁剪的包除外），用返回值的 [`HeaderOnly::copy_crate_binary`] 取出 crate 二进制。
+    pub fn decode_streaming<R: Read + Seek>(&mut self, reader: &mut R) -> Result<HeaderOnly> {
+        let header_only = CratePackage::decode_header_only_from(reader)?;
+        let crate_package = &header_only.crate_package;
+        self.read_package(crate_package)?;
+
+        let mut file_algos = vec![];
+        let mut crate_algos = vec![];
+        for sig in self.sigs.iter() {
+            match sig.typ {
+                typ if typ == SIGTYPE::FILE.as_u32() => file_algos.push(sig.digest),
+                typ if typ == SIGTYPE::NETWORK.as_u32() => crate_algos.push(DigestAlgo::Sha256),
+                _ => crate_algos.push(sig.digest),
+            }
+        }
+        // 已裁剪时 crate 二进制段本身就是摘要，由 complete_sig_digests 取用
+        if self.binary_pruned {
+            crate_algos.clear();
+        }
+        let section_index = &crate_package.section_index;
+        let signable = 0..if section_index.none_sig_num() > 0 {
+            crate_package.crate_header.ds_offset as u64 + section_index.datasection_size_without_sig() as u64
+        } else {
+            file_algos.clear();
+            0
+        };
+        let si_offset = crate_package.crate_header.si_offset as u64;
+        let zeroed = si_offset + section_index.none_sig_size() as u64..si_offset + section_index.size() as u64;
+
+        let mut fingerprint = Hashers::new([self.fingerprint_digest])?;
+        let mut file = Hashers::new(file_algos)?;
+        // 签名针对解压后的 .crate，crate 二进制段先经解压再计算摘要
+        let mut crate_bin = crate_package.compression()?.0.decoder(Hashers::new(crate_algos)?)?;
+        let body_end = header_only.file_len - crate_package.finger_print.arr.len() as u64;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut pos = 0;
+        while pos < body_end {
+            let len = CHUNK_SIZE.min((body_end - pos) as usize);
+            reader.read_exact(&mut chunk[..len])?;
+            let data = &chunk[..len];
+            fingerprint.update(data)?;
+            if let Some((_, part)) = overlap(data, pos, &header_only.crate_binary) {
+                crate_bin
+                    .write_all(part)
+                    .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制解压失败: {}", "failed to decompress the crate binary: {}", e)))?;
+            }
+            if let Some((start, part)) = overlap(data, pos, &signable) {
+                match overlap(part, start, &zeroed) {
+                    Some((zero_start, zeros)) => {
+                        let mut part = part.to_vec();
+                        let from = (zero_start - start) as usize;
+                        part[from..from + zeros.len()].fill(0);
+                        file.update(&part)?;
+                    }
+                    None => file.update(part)?,
+                }
+            }
+            pos += len as u64;
+        }
+
+        let calculated = fingerprint.finish()?.remove(&self.fingerprint_digest).unwrap_or_default();
+        if !PKCS::digest_eq(&calculated, &crate_package.finger_print.arr) {
+            return Err(CrateSpecError::DecodeError("fingerprint not right".to_string()));
+        }
+        let crate_bin = crate_bin
+            .finish()
+            .map_err(|e| CrateSpecError::DecodeError(tr_format!("crate 二进制解压失败: {}", "failed to decompress the crate binary: {}", e)))?;
+        let mut digests = SigDigests { file: file.finish()?, crate_bin: crate_bin.finish()? };
+        self.complete_sig_digests(&mut digests, || {
+            Err(CrateSpecError::Other(tr("流式解码应已计算出 FILE 签名摘要", "streaming decode should have computed the FILE signature digest").to_string()))
+        })?;
+        self.check_sigs_with_digests(&digests)?;
+        Ok(header_only)
+    }
+}
+
+#[test]
+fn test_decode_streaming() {
+    use crate::utils::context::PackageInfo;
+    use std::io::Cursor;
+    use crate::utils::testing::{signer, root_ca_bins};
+
+    let pkcs = signer();
+    let crate_bin: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let mut ctx = PackageContext::new();
+    ctx.pack_info = PackageInfo {
+        name: "demo".to_string(),
+        version: "0.1.0".to_string(),
+        license: "MIT".to_string(),
+        authors: vec!["rust".to_string()],
+    };
+    ctx.add_crate_bin(crate_bin.clone());
+    ctx.add_sig(pkcs.clone(), SIGTYPE::FILE);
+    ctx.add_sig_with_digest(pkcs, SIGTYPE::CRATEBIN, DigestAlgo::Sha384);
+    let (_, _, bytes) = ctx.encode_to_crate_package().unwrap();
+    let root_cas = root_ca_bins();
+
+    let header_only = CratePackage::decode_header_only_from(&mut Cursor::new(&bytes)).unwrap();
+    assert_eq!(header_only.file_len, bytes.len() as u64);
+    let range = header_only.crate_binary.start as usize..header_only.crate_binary.end as usize;
+    assert_eq!(&bytes[range], crate_bin.as_slice());
+    assert!(header_only.crate_package.crate_binary_section().unwrap().bin.arr.is_empty());
+
+    let mut verifier = PackageContext::new();
+    verifier.set_root_cas_bin(root_cas.clone());
+    let mut reader = Cursor::new(&bytes);
+    let header_only = verifier.decode_streaming(&mut reader).unwrap();
+    assert_eq!(verifier.pack_info.name, "demo");
+    assert_eq!(verifier.sigs.len(), 2);
+    let mut out = vec![];
+    assert_eq!(header_only.copy_crate_binary(&mut reader, &mut out).unwrap(), crate_bin.len() as u64);
+    assert_eq!(out, crate_bin);
+
+    // 篡改 crate 二进制段（同时更新指纹）后签名验证失败
+    let mut tampered = bytes.clone();
+    let offset = header_only.crate_binary.start as usize + 1000;
+    tampered[offset] ^= 0xff;
+    let body_len = tampered.len() - DigestAlgo::Sha256.output_len();
+    let fingerprint = PKCS::new().gen_digest(DigestAlgo::Sha256, &tampered[..body_len]).unwrap();
+    tampered[body_len..].copy_from_slice(&fingerprint);
+    let mut verifier = PackageContext::new();
+    verifier.set_root_cas_bin(root_cas.clone());
+    assert!(verifier.decode_streaming(&mut Cursor::new(&tampered)).is_err());
+    assert!(PackageContext::try_from_bytes(&tampered, &root_cas).is_err());
+
+    // 截断的文件在读取元数据时报错
+    let truncated = &bytes[..bytes.len() - 10];
+    assert!(CratePackage::decode_header_only_from(&mut Cursor::new(truncated)).is_err());
+}
@@ -0,0 +1,94 @@
+//! 测试辅助：每次运行独立、drop 时自动删除的临时目录，以及仓库 `test/` 下的签名证书
+//!
+//! 供本 crate 的单元测试使用，也可在下游集成测试中使用（需开启 `testing` feature），避免并行运行的
+//! 测试写入同一固定路径或在仓库中遗留产物。[`TestDir::fixture_crate`] 生成一个无依赖的最小 crate，
+//! 打包测试不必对本仓库执行 `cargo package`。
+//!
+//! ```
+//! use crate_spec::utils::testing::TestDir;
+//!
+//! let dir = TestDir::new("doc");
+//! let path = dir.write("a/b.txt", b"hello");
+//! assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+//! let root = dir.path().to_path_buf();
+//! drop(dir);
+//! assert!(!root.exists());
+//! ```
+use crate::utils::pkcs::PKCS;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// 测试证书、私钥和根 CA，路径相对于本仓库根目录
+pub const CERT: &str = "test/cert.pem";
+pub const KEY: &str = "test/key.pem";
+pub const ROOT_CA: &str = "test/root-ca.pem";
+
+/// 加载测试证书的本地签名者，失败时 panic
+pub fn signer() -> PKCS {
+    let mut pkcs = PKCS::new();
+    pkcs.load_from_file_writer(CERT.to_string(), KEY.to_string(), [ROOT_CA.to_string()].to_vec())
+        .expect("无法加载测试证书");
+    pkcs
+}
+
+/// 测试根 CA 的 DER 编码，可直接传给 `set_root_cas_bin`
+pub fn root_ca_bins() -> Vec<Vec<u8>> {
+    PKCS::root_ca_bins([ROOT_CA.to_string()].to_vec()).expect("无法加载测试根 CA")
+}
+
+/// 临时目录，drop 时连同内容一起删除
+#[derive(Debug)]
+pub struct TestDir {
+    dir: TempDir,
+}
+
+impl TestDir {
+    /// 在系统临时目录下创建 `crate-spec-<label>-XXXXXX` 目录，失败时 panic
+    pub fn new(label: &str) -> Self {
+        let dir = tempfile::Builder::new()
+            .prefix(&format!("crate-spec-{}-", label))
+            .tempdir()
+            .expect("无法创建临时目录");
+        TestDir { dir }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    /// 同 [`TestDir::join`]，返回字符串形式，便于传给接受路径字符串的接口
+    pub fn join_str(&self, name: &str) -> String {
+        self.join(name).to_str().expect("临时目录路径不是有效的 UTF-8").to_string()
+    }
+
+    /// 写入 `name`（按需创建父目录），返回其完整路径
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("无法创建目录");
+        }
+        fs::write(&path, contents).expect("无法写入文件");
+        path
+    }
+
+    /// 在 `<name>/` 下生成一个无依赖的最小 crate，返回 crate 目录（字符串形式）
+    ///
+    /// 清单带空的 `[workspace]`，即使临时目录位于某个工作区内也能独立打包。
+    pub fn fixture_crate(&self, name: &str, version: &str) -> String {
+        self.write(
+            &format!("{}/Cargo.toml", name),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"{}\"\nedition = \"2021\"\nlicense = \"MIT\"\n\
+                 description = \"crate-spec test fixture\"\n\n[dependencies]\n\n[workspace]\n",
+                name, version
+            ),
+        );
+        self.write(&format!("{}/src/lib.rs", name), "pub fn fixture() {}\n");
+        self.join_str(name)
+    }
+}
@@ -0,0 +1,146 @@
+//! 轻量级耗时统计（`--timings`）
+//!
+//! 默认关闭；调用 [`enable`] 后，各阶段通过 [`measure`] / [`record`] 记录耗时，
+//! 最后由 [`take_report`] 汇总输出。记录在进程内共享，并行验签的工作线程中的记录同样计入。
+use crate::locale::tr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+pub fn label_cargo_package() -> &'static str {
+    "cargo package"
+}
+
+pub fn label_parse_toml() -> &'static str {
+    tr("解析 Cargo.toml", "parse Cargo.toml")
+}
+
+pub fn label_read_binary() -> &'static str {
+    tr("读取二进制", "read binary")
+}
+
+pub fn label_sign() -> &'static str {
+    tr("签名", "sign")
+}
+
+pub fn label_encode() -> &'static str {
+    tr("编码序列化", "encode")
+}
+
+pub fn label_decode() -> &'static str {
+    tr("解码与验签", "decode and verify")
+}
+
+pub fn label_pki_sign() -> &'static str {
+    tr("PKI 签名请求", "PKI sign request")
+}
+
+pub fn label_pki_verify() -> &'static str {
+    tr("PKI 验签请求", "PKI verify request")
+}
+
+pub fn label_pki_health() -> &'static str {
+    tr("PKI 健康检查", "PKI health check")
+}
+
+pub fn label_total() -> &'static str {
+    tr("总计", "total")
+}
+
+static TIMINGS: Mutex<Option<Vec<(String, Duration)>>> = Mutex::new(None);
+
+/// 记录只会被追加或整体取出，某个线程持锁时 panic 不会留下不一致的状态
+fn timings() -> MutexGuard<'static, Option<Vec<(String, Duration)>>> {
+    TIMINGS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 开启耗时统计（清空已有记录）
+pub fn enable() {
+    *timings() = Some(Vec::new());
+}
+
+pub fn is_enabled() -> bool {
+    timings().is_some()
+}
+
+/// 记录一个阶段的耗时，未开启时忽略
+pub fn record(label: impl Into<String>, elapsed: Duration) {
+    if let Some(entries) = timings().as_mut() {
+        entries.push((label.into(), elapsed));
+    }
+}
+
+/// 执行 `f` 并以 `label` 记录其耗时
+pub fn measure<T>(label: impl Into<String>, f: impl FnOnce() -> T) -> T {
+    if !is_enabled() {
+        return f();
+    }
+    let start = Instant::now();
+    let res = f();
+    record(label, start.elapsed());
+    res
+}
+
+/// 取出全部记录并关闭统计，未开启时返回 None
+pub fn take_report() -> Option<String> {
+    timings().take().map(|entries| format_report(&entries))
+}
+
+fn format_report(entries: &[(String, Duration)]) -> String {
+    let mut report = format!("{}:\n", tr("耗时统计", "timings"));
+    for (label, elapsed) in entries {
+        report.push_str(&format!(
+            "  {:<32} {:>10.3} ms\n",
+            label,
+            elapsed.as_secs_f64() * 1000.0
+        ));
+    }
+    report
+}
+
+/// 统计状态在进程内共享，开启统计的测试需要依次执行
+#[cfg(test)]
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_timings_report_labels() {
+    use crate::utils::context::{PackageContext, SIGTYPE};
+    use crate::utils::testing::signer;
+
+    let _guard = TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    assert!(take_report().is_none());
+    measure(label_encode(), || ());
+    assert!(!is_enabled());
+
+    enable();
+    let start = Instant::now();
+    let mut pack_context = PackageContext::new();
+    pack_context.pack_info.name = "timings".to_string();
+    pack_context.pack_info.version = "0.1.0".to_string();
+    pack_context.add_crate_bin(vec![0u8; 16]);
+    let pkcs = signer();
+    pack_context.add_sig(pkcs, SIGTYPE::CRATEBIN);
+    pack_context.encode_to_crate_package().unwrap();
+    record(label_total(), start.elapsed());
+
+    let report = take_report().unwrap();
+    assert!(report.contains(&format!("{} #0", label_sign())));
+    assert!(report.contains(label_encode()));
+    assert!(report.contains(label_total()));
+    assert!(report.contains(" ms"));
+    assert!(!is_enabled());
+}
+
+#[test]
+fn test_timings_record_from_worker_threads() {
+    let _guard = TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    enable();
+    std::thread::scope(|scope| {
+        for i in 0..4 {
+            scope.spawn(move || measure(format!("{} #{}", label_pki_verify(), i), || ()));
+        }
+    });
+    let report = take_report().unwrap();
+    for i in 0..4 {
+        assert!(report.contains(&format!("{} #{}", label_pki_verify(), i)), "{}", report);
+    }
+}
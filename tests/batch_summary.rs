@@ -0,0 +1,35 @@
+mod common;
+
+use crate_spec::utils::context::{PackageContext, SIGTYPE};
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_batch_summary_only_over_directory() {
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("summary".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_crate_bin(vec![3u8; 64]);
+    pack_context.add_sig(common::signer(), SIGTYPE::CRATEBIN);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let dir = TestDir::new("batch-summary");
+    let good = dir.write("a-good.scrate", &bin);
+    let mut tampered = bin.clone();
+    let mid = tampered.len() / 2;
+    tampered[mid] ^= 0xff;
+    let bad = dir.write("b-tampered.scrate", &tampered);
+    // 目录中的非 .scrate 文件不参与验证
+    dir.write("notes.txt", b"ignored");
+
+    let output = common::crate_spec()
+        .args(["-d", "--mode", "local", "--cli", "-r", common::ROOT_CA, "--summary-only", "--batch"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", stdout);
+    assert_eq!(lines[0], format!("OK   {}  summary@0.1.0  sigs=1", good.display()));
+    assert!(lines[1].starts_with(&format!("FAIL {}  ", bad.display())), "{}", lines[1]);
+}
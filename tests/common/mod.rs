@@ -0,0 +1,42 @@
+//! 集成测试共用的命令行调用和签名证书
+#![allow(dead_code, unused_imports)]
+
+use std::process::{Command, Output};
+
+pub use crate_spec::utils::testing::{signer, CERT, KEY, ROOT_CA};
+
+/// 以英文输出运行的 crate-spec 命令，断言可以匹配英文错误信息
+pub fn crate_spec() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_crate-spec"));
+    command.env("CRATE_SPEC_LANG", "en");
+    command
+}
+
+/// 用测试证书在本地模式下编码 `crate_path`，输出到 `output` 目录
+pub fn encode_local(crate_path: &str, output: &str, extra: &[&str]) -> Output {
+    crate_spec()
+        .args(["-e", "--cli", "-c", CERT, "-p", KEY, "-r", ROOT_CA])
+        .args(extra)
+        .arg("-o")
+        .arg(output)
+        .arg(crate_path)
+        .output()
+        .unwrap()
+}
+
+/// 以测试根 CA 在本地模式下解码 `input`，输出到 `output` 目录
+pub fn decode_local(input: &str, output: &str, extra: &[&str]) -> Output {
+    crate_spec()
+        .args(["-d", "--cli", "-r", ROOT_CA])
+        .args(extra)
+        .arg("-o")
+        .arg(output)
+        .arg(input)
+        .output()
+        .unwrap()
+}
+
+/// 命令的标准错误输出，用于断言失败时的提示
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
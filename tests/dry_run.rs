@@ -0,0 +1,46 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+use std::path::Path;
+
+#[test]
+fn test_encode_dry_run_writes_nothing() {
+    let dir = TestDir::new("dry-run");
+    let crate_path = dir.fixture_crate("fixture", "0.4.0");
+    let output = dir.join_str("out");
+    let result = common::encode_local(&crate_path, &output, &["--dry-run"]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(stdout.contains("fixture-0.4.0.scrate"), "{}", stdout);
+    // 预演不执行 cargo package，也不创建输出目录
+    assert!(!Path::new(&output).exists());
+    assert!(!Path::new(&crate_path).join("target").exists());
+}
+
+#[test]
+fn test_encode_output_name() {
+    let dir = TestDir::new("output-name");
+    let crate_path = dir.fixture_crate("fixture", "0.4.0");
+    let output = dir.join_str("out");
+    let encode = |name: &str, extra: &[&str]| {
+        common::encode_local(&crate_path, &output, &[&["--output-name", name], extra].concat())
+    };
+
+    let result = encode("fixture-latest.scrate", &["--dry-run"]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    assert!(String::from_utf8_lossy(&result.stdout).contains("fixture-latest.scrate"));
+
+    let result = encode("fixture-latest.scrate", &[]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    assert!(dir.join("out/fixture-latest.scrate").is_file());
+    assert!(!dir.join("out/fixture-0.4.0.scrate").exists());
+
+    // 不允许包含路径分隔符
+    for name in ["../escape.scrate", "nested\\name.scrate", ".."] {
+        let result = encode(name, &[]);
+        assert!(!result.status.success(), "{}", name);
+        assert!(common::stderr(&result).contains("validation error"), "{}", name);
+    }
+    assert!(!dir.join("escape.scrate").exists());
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_dump_sections_cli() {
+    let dir = TestDir::new("dump-sections-cli");
+    let crate_path = dir.fixture_crate("fixture", "0.7.0");
+    let result = common::encode_local(&crate_path, &dir.join_str("signed"), &[]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+
+    // 导出到 TestDir 下，测试结束后连同导出的文件一起删除
+    let result = common::crate_spec()
+        .args(["-d", "--cli", "-r", common::ROOT_CA, "--dump-sections"])
+        .arg(dir.join_str("sections"))
+        .arg(dir.join_str("signed/fixture-0.7.0.scrate"))
+        .output()
+        .unwrap();
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    for name in ["stringtable.bin", "package.bin", "deptable.bin", "cratebin.bin", "sig0.bin"] {
+        assert!(dir.join("sections").join(name).is_file(), "{}", name);
+    }
+}
@@ -0,0 +1,19 @@
+mod common;
+
+use crate_spec::utils::context::PackageContext;
+use crate_spec::utils::pkcs::PKCS;
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_encode_local_round_trip() {
+    let dir = TestDir::new("encode-local");
+    let crate_path = dir.fixture_crate("fixture", "0.3.1");
+    let scrate = crate_spec::encode_local(&crate_path, common::signer()).unwrap();
+
+    let root_cas = PKCS::root_ca_bins([common::ROOT_CA.to_string()].to_vec()).unwrap();
+    let decoded = PackageContext::try_from_bytes(&scrate, &root_cas).unwrap();
+    assert_eq!(decoded.pack_info.name, "fixture");
+    assert_eq!(decoded.pack_info.version, "0.3.1");
+    assert_eq!(decoded.sigs.len(), 1);
+    assert!(!decoded.crate_binary.bytes.is_empty());
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_encode_prints_stages() {
+    let dir = TestDir::new("encode-progress");
+    let crate_path = dir.fixture_crate("fixture", "0.5.0");
+    let output = dir.join_str("out");
+
+    let result = common::encode_local(&crate_path, &output, &[]);
+    let stderr = common::stderr(&result);
+    assert!(result.status.success(), "{}", stderr);
+    let stages = ["[1/4] running cargo package", "[2/4] reading crate", "[3/4] signing", "[4/4] writing output"];
+    let positions: Vec<usize> = stages
+        .iter()
+        .map(|stage| stderr.find(stage).unwrap_or_else(|| panic!("{} 未输出: {}", stage, stderr)))
+        .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", stderr);
+
+    // 预演不进入任何阶段
+    let result = common::encode_local(&crate_path, &output, &["--dry-run"]);
+    assert!(result.status.success());
+    assert!(!common::stderr(&result).contains("[1/4]"));
+}
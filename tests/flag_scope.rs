@@ -0,0 +1,21 @@
+mod common;
+
+#[test]
+fn test_on_collision_requires_encode() {
+    let output = common::crate_spec()
+        .args(["-d", "--cli", "-r", common::ROOT_CA, "--on-collision", "refuse", "-o", "out", "in.scrate"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(common::stderr(&output).contains("--encode"), "{}", common::stderr(&output));
+}
+
+#[test]
+fn test_batch_format_requires_batch() {
+    let output = common::crate_spec()
+        .args(["-d", "--cli", "-r", common::ROOT_CA, "--batch-format", "jsonl", "-o", "out", "in.scrate"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(common::stderr(&output).contains("--batch"), "{}", common::stderr(&output));
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use crate_spec::utils::context::{PackageContext, SrcTypePath};
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_info_prints_json_without_config() {
+    let mut pack_context = PackageContext::new();
+    pack_context.set_package_info("info".to_string(), "0.2.0".to_string(), "MIT".to_string(), vec![]);
+    pack_context.add_dep_info(
+        "helper".to_string(),
+        "1.0".to_string(),
+        SrcTypePath::Registry("internal".to_string()),
+        "".to_string(),
+    );
+    pack_context.add_crate_bin(vec![5u8; 64]);
+    let (_, _, bin) = pack_context.encode_to_crate_package().unwrap();
+
+    let dir = TestDir::new("info-json");
+    let input = dir.write("info-0.2.0.scrate", &bin);
+    // 不提供 --cli 和根 CA：--info 不读取配置，未签名的包也能输出
+    let output = common::crate_spec().args(["-d", "--info"]).arg(&input).output().unwrap();
+    assert!(output.status.success(), "{}", common::stderr(&output));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["package"]["version"], "0.2.0");
+    assert_eq!(json["dependencies"][0]["src"]["kind"], "registry");
+    assert_eq!(json["dependencies"][0]["src"]["path"], "internal");
+}
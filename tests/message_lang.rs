@@ -0,0 +1,38 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+
+/// 输出中是否含有中文字符
+fn has_cjk(text: &str) -> bool {
+    text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c))
+}
+
+#[test]
+fn test_error_details_follow_lang() {
+    let dir = TestDir::new("message-lang");
+    let crate_path = dir.fixture_crate("fixture", "0.6.0");
+    let result = common::encode_local(&crate_path, &dir.join_str("signed"), &[]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+
+    // 只保留开头几个字节，解码时容纳不下文件头
+    let input = dir.join_str("signed/fixture-0.6.0.scrate");
+    let bytes = std::fs::read(&input).unwrap();
+    let truncated = dir.join_str("truncated.scrate");
+    std::fs::write(&truncated, &bytes[..8]).unwrap();
+
+    let result = common::decode_local(&truncated, &dir.join_str("en"), &[]);
+    assert!(!result.status.success());
+    let stderr = common::stderr(&result);
+    assert!(!has_cjk(&stderr), "{}", stderr);
+    assert!(stderr.contains("too short for the header"), "{}", stderr);
+
+    let result = common::crate_spec()
+        .env("CRATE_SPEC_LANG", "zh")
+        .args(["-d", "--cli", "-r", common::ROOT_CA, "-o"])
+        .arg(dir.join_str("zh"))
+        .arg(&truncated)
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    assert!(has_cjk(&common::stderr(&result)), "{}", common::stderr(&result));
+}
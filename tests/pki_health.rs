@@ -0,0 +1,34 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+use std::net::TcpListener;
+
+#[test]
+fn test_network_encode_fails_fast_when_pki_unreachable() {
+    let dir = TestDir::new("pki-health");
+    let crate_path = dir.fixture_crate("fixture", "0.6.0");
+    // 绑定后立即释放端口，连接被拒绝
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let config = dir.write(
+        "config.toml",
+        format!(
+            "[local.decode]\n\n[network.encode]\ninput_path = {:?}\noutput_path = {:?}\n\n\
+             [net]\npki_base_url = \"http://127.0.0.1:{}\"\nalgo = \"sm2\"\nflow = \"sign\"\nkey_pair_path = {:?}\n",
+            crate_path,
+            dir.join_str("out"),
+            port,
+            dir.join_str("keypair.bin"),
+        ),
+    );
+    let result = common::crate_spec()
+        .args(["-e", "--mode", "net", "--config"])
+        .arg(&config)
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    let stderr = common::stderr(&result);
+    assert!(stderr.contains("/v1/health"), "{}", stderr);
+    // 在 cargo package 和获取密钥对之前失败
+    assert!(!dir.join("fixture/target").exists());
+    assert!(!dir.join("keypair.bin").exists());
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_decode_require_sig_type() {
+    let dir = TestDir::new("require-sig-type");
+    let crate_path = dir.fixture_crate("fixture", "0.5.0");
+    let result = common::encode_local(&crate_path, &dir.join_str("signed"), &[]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+
+    let input = dir.join_str("signed/fixture-0.5.0.scrate");
+    let decode = |types: &str, output: &str| {
+        common::decode_local(&input, &dir.join_str(output), &["--require-sig-type", types])
+    };
+
+    // 本地编码只有 CRATEBIN 签名，要求网络签名时失败且不输出文件
+    let result = decode("cratebin,network", "rejected");
+    assert!(!result.status.success());
+    assert!(common::stderr(&result).contains("network"));
+    assert!(!dir.join("rejected").exists());
+
+    let result = decode("cratebin", "accepted");
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    assert!(dir.join("accepted/fixture-0.5.0.crate").is_file());
+
+    assert!(!decode("detached", "invalid").status.success());
+}
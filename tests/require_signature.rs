@@ -0,0 +1,34 @@
+mod common;
+
+use crate_spec::utils::context::PackageContext;
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_decode_unsigned_package() {
+    let dir = TestDir::new("require-signature");
+    let mut ctx = PackageContext::new();
+    ctx.set_package_info("unsigned".to_string(), "0.1.0".to_string(), "MIT".to_string(), vec![]);
+    ctx.add_crate_bin(vec![3u8; 32]);
+    let (_, _, bin) = ctx.encode_to_crate_package().unwrap();
+    let input = dir.write("unsigned-0.1.0.scrate", bin);
+    let input = input.to_str().unwrap();
+
+    // 默认照常解码，只提示没有签名
+    let result = common::decode_local(input, &dir.join_str("accepted"), &[]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    assert!(String::from_utf8_lossy(&result.stdout).contains("no signatures present"));
+    assert!(dir.join("accepted/unsigned-0.1.0.crate").is_file());
+
+    // 要求签名时失败且不输出文件
+    let result = common::decode_local(input, &dir.join_str("rejected"), &["--require-signature"]);
+    assert!(!result.status.success());
+    assert!(common::stderr(&result).contains("no signatures"), "{}", common::stderr(&result));
+    assert!(!dir.join("rejected").exists());
+
+    // 批量验证同样可以要求签名
+    let batch = |extra: &[&str]| {
+        common::crate_spec().args(["-d", "--cli", "-r", common::ROOT_CA, "--batch", input]).args(extra).output().unwrap()
+    };
+    assert!(batch(&[]).status.success());
+    assert!(!batch(&["--require-signature"]).status.success());
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+use std::io::Write;
+use std::process::{Output, Stdio};
+
+/// 以 `input` 为标准输入运行本地解码，`args` 为输入路径之前的额外参数
+fn decode_stdin(dir: &TestDir, output: &str, args: &[&str], input: &[u8]) -> Output {
+    let mut child = common::crate_spec()
+        .args(["-d", "--cli", "-r", common::ROOT_CA])
+        .args(args)
+        .arg("-o")
+        .arg(dir.join_str(output))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // 写完后关闭标准输入，解码端才能读到 EOF
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_decode_from_stdin() {
+    let dir = TestDir::new("stdin-input");
+    let crate_path = dir.fixture_crate("fixture", "0.8.0");
+    let result = common::encode_local(&crate_path, &dir.join_str("signed"), &[]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    let bytes = std::fs::read(dir.join("signed/fixture-0.8.0.scrate")).unwrap();
+
+    // 输入路径为 "-"
+    let result = decode_stdin(&dir, "dash", &["-"], &bytes);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    assert!(dir.join("dash/fixture-0.8.0.crate").is_file());
+
+    // --stdin 与 "-" 相同
+    let result = decode_stdin(&dir, "flag", &["--stdin"], &bytes);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    assert!(dir.join("flag/fixture-0.8.0.crate").is_file());
+
+    // 从标准输入读到被截断的数据时失败，且不输出文件
+    let result = decode_stdin(&dir, "truncated", &["-"], &bytes[..bytes.len() / 2]);
+    assert!(!result.status.success());
+    assert!(!dir.join("truncated/fixture-0.8.0.crate").exists());
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use crate_spec::utils::testing::TestDir;
+
+#[test]
+fn test_encode_workspace_signs_every_member() {
+    let dir = TestDir::new("workspace-cli");
+    dir.write("Cargo.toml", "[workspace]\nmembers = [\"alpha\", \"beta\"]\nresolver = \"2\"\n");
+    for name in ["alpha", "beta"] {
+        dir.write(
+            &format!("{}/Cargo.toml", name),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"0.3.0\"\nedition = \"2021\"\nlicense = \"MIT\"\n\
+                 description = \"crate-spec test fixture\"\n\n[dependencies]\n",
+                name
+            ),
+        );
+        dir.write(&format!("{}/src/lib.rs", name), "pub fn fixture() {}\n");
+    }
+    let result = common::encode_local(&dir.join_str(""), &dir.join_str("out"), &["--workspace"]);
+    assert!(result.status.success(), "{}", common::stderr(&result));
+    assert!(dir.join("out/alpha-0.3.0.scrate").is_file());
+    assert!(dir.join("out/beta-0.3.0.scrate").is_file());
+
+    // 每个成员都能独立校验
+    for name in ["alpha", "beta"] {
+        let input = dir.join_str(&format!("out/{}-0.3.0.scrate", name));
+        let result = common::decode_local(&input, &dir.join_str("decoded"), &[]);
+        assert!(result.status.success(), "{}", common::stderr(&result));
+    }
+}